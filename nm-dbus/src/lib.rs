@@ -1,20 +1,168 @@
+use futures_util::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
 use zbus::proxy;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
 use zbus::{Connection, Result};
 
 #[derive(Debug, Clone)]
 pub struct AccessPoint {
     pub ssid: String,
     pub strength: u8,
-    pub is_secured: bool,
+    pub security: Security,
     pub path: String,
 }
 
+// NM80211ApFlags
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+
+// NM80211ApSecurityFlags, key management part
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+const AP_SEC_KEY_MGMT_EAP_SUITE_B_192: u32 = 0x2000;
+
+/// How an access point asks clients to authenticate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Security {
+    Open,
+    Wep,
+    /// WPA1 with a pre-shared key
+    WpaPsk,
+    Wpa2Psk,
+    Wpa3Sae,
+    /// WPA or WPA2/3 with 802.1X, needing EAP settings rather than a password
+    Enterprise,
+}
+
+impl Security {
+    /// Decode the `Flags`, `WpaFlags` and `RsnFlags` properties of an access
+    /// point, the strongest scheme it offers winning
+    pub fn from_flags(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Self {
+        let key_mgmt = wpa_flags | rsn_flags;
+        if key_mgmt & (AP_SEC_KEY_MGMT_802_1X | AP_SEC_KEY_MGMT_EAP_SUITE_B_192) != 0 {
+            Security::Enterprise
+        } else if rsn_flags & AP_SEC_KEY_MGMT_SAE != 0 {
+            Security::Wpa3Sae
+        } else if rsn_flags & AP_SEC_KEY_MGMT_PSK != 0 {
+            Security::Wpa2Psk
+        } else if wpa_flags & AP_SEC_KEY_MGMT_PSK != 0 {
+            Security::WpaPsk
+        } else if flags & AP_FLAGS_PRIVACY != 0 {
+            Security::Wep
+        } else {
+            Security::Open
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Security::Open => "Open",
+            Security::Wep => "WEP",
+            Security::WpaPsk => "WPA-PSK",
+            Security::Wpa2Psk => "WPA2-PSK",
+            Security::Wpa3Sae => "WPA3-SAE",
+            Security::Enterprise => "802.1X",
+        }
+    }
+
+    pub fn is_secured(self) -> bool {
+        self != Security::Open
+    }
+}
+
+/// Outer EAP method of an enterprise network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EapMethod {
+    #[default]
+    Peap,
+    Ttls,
+}
+
+impl EapMethod {
+    pub const ALL: [EapMethod; 2] = [EapMethod::Peap, EapMethod::Ttls];
+
+    /// Name in NetworkManager's `802-1x.eap` setting
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EapMethod::Peap => "peap",
+            EapMethod::Ttls => "ttls",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EapMethod::Peap => "PEAP",
+            EapMethod::Ttls => "TTLS",
+        }
+    }
+}
+
+/// Inner authentication inside the PEAP or TTLS tunnel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Phase2Auth {
+    #[default]
+    Mschapv2,
+    Gtc,
+    Pap,
+}
+
+impl Phase2Auth {
+    pub const ALL: [Phase2Auth; 3] = [Phase2Auth::Mschapv2, Phase2Auth::Gtc, Phase2Auth::Pap];
+
+    /// Name in NetworkManager's `802-1x.phase2-auth` setting
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase2Auth::Mschapv2 => "mschapv2",
+            Phase2Auth::Gtc => "gtc",
+            Phase2Auth::Pap => "pap",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase2Auth::Mschapv2 => "MSCHAPv2",
+            Phase2Auth::Gtc => "GTC",
+            Phase2Auth::Pap => "PAP",
+        }
+    }
+}
+
+/// Credentials for an 802.1X network
+#[derive(Clone, Default)]
+pub struct EapConfig {
+    pub method: EapMethod,
+    pub phase2: Phase2Auth,
+    pub identity: String,
+    /// Outer identity sent in the clear, empty to send `identity`
+    pub anonymous_identity: String,
+    pub password: String,
+    /// CA certificate to check the server against, the system's when unset
+    pub ca_cert: Option<PathBuf>,
+}
+
+// Keeps the password out of logs
+impl fmt::Debug for EapConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EapConfig")
+            .field("method", &self.method)
+            .field("phase2", &self.phase2)
+            .field("identity", &self.identity)
+            .field("anonymous_identity", &self.anonymous_identity)
+            .field("ca_cert", &self.ca_cert)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ActiveConnection {
     pub ssid: String,
     pub strength: u8,
     pub interface: String,
     pub ip_address: String,
+    /// Object path of the access point connected to
+    pub access_point: String,
 }
 
 // NetworkManager D-Bus proxy
@@ -30,6 +178,49 @@ trait NetworkManager {
     /// Get active connections
     #[zbus(property)]
     fn active_connections(&self) -> Result<Vec<zbus::zvariant::OwnedObjectPath>>;
+
+    /// Save a new connection and activate it on `device`
+    fn add_and_activate_connection(
+        &self,
+        connection: HashMap<&str, HashMap<&str, Value<'_>>>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    /// Activate a saved connection on `device`
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> Result<OwnedObjectPath>;
+}
+
+// NetworkManager Settings proxy
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    /// Saved connection profiles
+    fn list_connections(&self) -> Result<Vec<OwnedObjectPath>>;
+}
+
+/// Settings of a saved connection, by group and key
+type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+// Saved connection proxy
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait SettingsConnection {
+    /// Settings of the profile, without its secrets
+    fn get_settings(&self) -> Result<ConnectionSettings>;
+
+    /// Replace all settings of the profile
+    fn update(&self, properties: HashMap<&str, HashMap<&str, Value<'_>>>) -> Result<()>;
 }
 
 // NetworkManager Device proxy
@@ -88,6 +279,10 @@ trait AccessPointProxy {
     #[zbus(property)]
     fn strength(&self) -> Result<u8>;
 
+    /// Capability flags (0x1 = privacy, i.e. WEP on a network without WPA)
+    #[zbus(property)]
+    fn flags(&self) -> Result<u32>;
+
     /// WPA flags (0 = open network)
     #[zbus(property, name = "WpaFlags")]
     fn wpa_flags(&self) -> Result<u32>;
@@ -134,6 +329,11 @@ impl NetworkManagerClient {
         Ok(Self { connection })
     }
 
+    /// Use an already open system bus connection instead of a new one
+    pub fn with_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
     /// Scan for available WiFi networks
     pub async fn scan_networks(&self) -> Result<Vec<AccessPoint>> {
         let nm_proxy = NetworkManagerProxy::new(&self.connection).await?;
@@ -228,6 +428,7 @@ impl NetworkManagerClient {
                     strength: ap.strength,
                     interface,
                     ip_address,
+                    access_point: ap.path,
                 }));
             }
         }
@@ -235,6 +436,20 @@ impl NetworkManagerClient {
         Ok(None)
     }
 
+    /// Signal strength of the access point at `ap_path` each time it changes,
+    /// starting with the current one
+    pub async fn watch_strength(&self, ap_path: &str) -> Result<impl Stream<Item = u8>> {
+        let ap_proxy = AccessPointProxyProxy::builder(&self.connection)
+            .path(ap_path.to_string())?
+            .build()
+            .await?;
+
+        Ok(ap_proxy
+            .receive_strength_changed()
+            .await
+            .filter_map(|change| async move { change.get().await.ok() }))
+    }
+
     /// Connect to a network (delegates to system authentication)
     pub async fn connect_to_network(&self, ssid: &str) -> Result<()> {
         // Use nmcli for connection (it handles system authentication)
@@ -248,6 +463,89 @@ impl NetworkManagerClient {
         Ok(())
     }
 
+    /// Save an 802.1X connection to `ssid` and connect to it
+    ///
+    /// A profile already saved for `ssid` gets the new sign-in details and
+    /// keeps the rest of its settings. Goes over D-Bus rather than nmcli so
+    /// the password stays off the command line.
+    pub async fn connect_enterprise(&self, ssid: &str, eap: &EapConfig) -> Result<()> {
+        let nm_proxy = NetworkManagerProxy::new(&self.connection).await?;
+        let device = self
+            .wireless_device()
+            .await?
+            .ok_or_else(|| zbus::Error::Failure("No Wi-Fi device found".to_string()))?;
+
+        let mut security = HashMap::new();
+        security.insert("key-mgmt", Value::from("wpa-eap"));
+
+        let mut dot1x = HashMap::new();
+        dot1x.insert("eap", Value::from(vec![eap.method.as_str()]));
+        dot1x.insert("phase2-auth", Value::from(eap.phase2.as_str()));
+        dot1x.insert("identity", Value::from(eap.identity.as_str()));
+        dot1x.insert("password", Value::from(eap.password.as_str()));
+        if !eap.anonymous_identity.is_empty() {
+            dot1x.insert(
+                "anonymous-identity",
+                Value::from(eap.anonymous_identity.as_str()),
+            );
+        }
+        match &eap.ca_cert {
+            // A path is given as a NUL-terminated file:// URI in a byte array
+            Some(path) => {
+                let mut uri = format!("file://{}", path.display()).into_bytes();
+                uri.push(0);
+                dot1x.insert("ca-cert", Value::from(uri));
+            }
+            None => {
+                dot1x.insert("system-ca-certs", Value::from(true));
+            }
+        }
+
+        let any_access_point = ObjectPath::from_static_str_unchecked("/");
+        if let Some((path, saved)) = self.saved_connection(ssid).await? {
+            let mut settings = HashMap::new();
+            for (group, values) in &saved {
+                let values = values
+                    .iter()
+                    .map(|(key, value)| Ok((key.as_str(), Value::from(value.try_clone()?))))
+                    .collect::<Result<HashMap<_, _>>>()?;
+                settings.insert(group.as_str(), values);
+            }
+            settings.insert("802-11-wireless-security", security);
+            settings.insert("802-1x", dot1x);
+
+            let saved_proxy = SettingsConnectionProxy::builder(&self.connection)
+                .path(&path)?
+                .build()
+                .await?;
+            saved_proxy.update(settings).await?;
+            nm_proxy
+                .activate_connection(&path, &device, &any_access_point)
+                .await?;
+            return Ok(());
+        }
+
+        let mut connection = HashMap::new();
+        connection.insert("type", Value::from("802-11-wireless"));
+        connection.insert("id", Value::from(ssid));
+
+        let mut wireless = HashMap::new();
+        wireless.insert("ssid", Value::from(ssid.as_bytes()));
+        wireless.insert("mode", Value::from("infrastructure"));
+
+        let settings = HashMap::from([
+            ("connection", connection),
+            ("802-11-wireless", wireless),
+            ("802-11-wireless-security", security),
+            ("802-1x", dot1x),
+        ]);
+        nm_proxy
+            .add_and_activate_connection(settings, &device, &any_access_point)
+            .await?;
+
+        Ok(())
+    }
+
     /// Disconnect from current network by interface name
     pub async fn disconnect(&self, interface: &str) -> Result<()> {
         let nm_proxy = NetworkManagerProxy::new(&self.connection).await?;
@@ -289,18 +587,63 @@ impl NetworkManagerClient {
 
         let strength = ap_proxy.strength().await?;
 
+        let flags = ap_proxy.flags().await.unwrap_or(0);
         let wpa_flags = ap_proxy.wpa_flags().await.unwrap_or(0);
         let rsn_flags = ap_proxy.rsn_flags().await.unwrap_or(0);
-        let is_secured = wpa_flags != 0 || rsn_flags != 0;
+        let security = Security::from_flags(flags, wpa_flags, rsn_flags);
 
         Ok(AccessPoint {
             ssid,
             strength,
-            is_secured,
+            security,
             path: ap_path.to_string(),
         })
     }
 
+    // Helper: Saved Wi-Fi profile for `ssid`, with its settings
+    async fn saved_connection(
+        &self,
+        ssid: &str,
+    ) -> Result<Option<(OwnedObjectPath, ConnectionSettings)>> {
+        let settings_proxy = SettingsProxy::new(&self.connection).await?;
+
+        for path in settings_proxy.list_connections().await? {
+            let saved_proxy = SettingsConnectionProxy::builder(&self.connection)
+                .path(&path)?
+                .build()
+                .await?;
+            let settings = saved_proxy.get_settings().await?;
+
+            let saved_ssid = settings
+                .get("802-11-wireless")
+                .and_then(|wireless| wireless.get("ssid"))
+                .and_then(|ssid| Vec::<u8>::try_from(ssid.try_clone().ok()?).ok());
+            if saved_ssid.as_deref() == Some(ssid.as_bytes()) {
+                return Ok(Some((path, settings)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Helper: Path of the first Wi-Fi device
+    async fn wireless_device(&self) -> Result<Option<OwnedObjectPath>> {
+        let nm_proxy = NetworkManagerProxy::new(&self.connection).await?;
+
+        for device_path in nm_proxy.get_devices().await? {
+            let device_proxy = DeviceProxy::builder(&self.connection)
+                .path(&device_path)?
+                .build()
+                .await?;
+
+            if device_proxy.device_type().await? == 2 {
+                return Ok(Some(device_path));
+            }
+        }
+
+        Ok(None)
+    }
+
     // Helper: Get IP address from device
     async fn get_ip_address(&self, device_proxy: &DeviceProxy<'_>) -> Result<String> {
         let ip4_config_path = device_proxy.ip4_config().await?;
@@ -0,0 +1,130 @@
+//! Interface for widgets living outside the statusbar crate
+//!
+//! A widget is a relm4 component whose `Init` is read from its own section of
+//! the config file. The bar only sees it through [`Instance`], so widgets with
+//! any message type sit side by side:
+//!
+//! ```ignore
+//! impl statusbar_plugin::Widget for Uptime {
+//!     const NAME: &'static str = "uptime";
+//! }
+//!
+//! pub fn registration() -> statusbar_plugin::Registration {
+//!     statusbar_plugin::register::<Uptime>()
+//! }
+//! ```
+//!
+//! The bar compiles the crate in behind a feature and lists `registration()`
+//! in its plugin registry.
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::prelude::*;
+use serde::de::DeserializeOwned;
+use std::any::Any;
+
+pub use relm4;
+
+/// A widget built outside the bar
+///
+/// Its `Init` must deserialize from the config section and have defaults for
+/// when there is none, and its root must be a GTK widget.
+pub trait Widget: Component<Output = ()> {
+    /// Identifier of the widget, naming its config section, e.g. `[uptime]`
+    const NAME: &'static str;
+
+    /// State reported by `statusbarctl get-state`
+    fn snapshot(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+}
+
+/// A widget type the bar can launch, see [`register`]
+pub struct Registration {
+    pub name: &'static str,
+    launch: fn(Option<toml::Value>) -> Result<Instance, String>,
+}
+
+impl Registration {
+    /// Launch the widget with its config section, or defaults without one
+    pub fn launch(&self, config: Option<toml::Value>) -> Result<Instance, String> {
+        (self.launch)(config)
+    }
+}
+
+/// Make `W` known to the bar
+pub fn register<W>() -> Registration
+where
+    W: Widget,
+    W::Init: DeserializeOwned + Default,
+    W::Root: IsA<gtk::Widget>,
+{
+    Registration {
+        name: W::NAME,
+        launch: launch::<W>,
+    }
+}
+
+fn launch<W>(config: Option<toml::Value>) -> Result<Instance, String>
+where
+    W: Widget,
+    W::Init: DeserializeOwned + Default,
+    W::Root: IsA<gtk::Widget>,
+{
+    let init = match config {
+        Some(config) => config
+            .try_into()
+            .map_err(|e| format!("Invalid [{}] section: {}", W::NAME, e))?,
+        None => W::Init::default(),
+    };
+    Ok(Instance(Box::new(W::builder().launch(init).detach())))
+}
+
+/// A running widget, whatever its message type
+pub struct Instance(Box<dyn Erased>);
+
+impl Instance {
+    /// The widget's root, to be placed in the bar
+    pub fn root(&self) -> gtk::Widget {
+        self.0.root()
+    }
+
+    pub fn snapshot(&self) -> serde_json::Value {
+        self.0.snapshot()
+    }
+
+    /// Send the widget one of its own messages
+    ///
+    /// Fails if `msg` isn't of the widget's input type.
+    pub fn emit<M: 'static>(&self, msg: M) -> Result<(), String> {
+        self.0.emit(Box::new(msg))
+    }
+}
+
+/// The parts of a `Controller<W>` that don't depend on `W`
+trait Erased {
+    fn root(&self) -> gtk::Widget;
+    fn snapshot(&self) -> serde_json::Value;
+    fn emit(&self, msg: Box<dyn Any>) -> Result<(), String>;
+}
+
+impl<W> Erased for Controller<W>
+where
+    W: Widget,
+    W::Root: IsA<gtk::Widget>,
+{
+    fn root(&self) -> gtk::Widget {
+        self.widget().clone().upcast()
+    }
+
+    fn snapshot(&self) -> serde_json::Value {
+        self.model().snapshot()
+    }
+
+    fn emit(&self, msg: Box<dyn Any>) -> Result<(), String> {
+        let msg = msg
+            .downcast::<W::Input>()
+            .map_err(|_| format!("{} doesn't take this message", W::NAME))?;
+        ComponentController::emit(self, *msg);
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Send a command to the running bar, e.g. from a compositor keybinding
+//!
+//! Speaks the plain-text protocol of the control socket, one command per
+//! invocation: `statusbarctl open-popover wifi`, `statusbarctl set dnd on`.
+
+use std::process::ExitCode;
+
+// The bar's own client for its control socket
+#[path = "../services/ipc/client.rs"]
+mod ipc;
+
+const USAGE: &str = "\
+Usage: statusbarctl <command>
+
+Commands:
+  show, hide             Show or hide the bar
+  toggle-visibility      Hide the bar, or show it again
+  reload-config          Re-read the config and relaunch every widget
+  reload-theme           Recompile and reload the theme
+  open-popover <widget>  Open a widget's popover, e.g. wifi or clock
+  cycle-popover          Open the next widget's popover
+  set dnd <on|off>       Hold back notification banners
+  trigger <action>       Run a quick action, e.g. toggle-privacy
+  dump-state             Print the state of every widget as JSON";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match ipc::send(&args.join(" ")) {
+        Ok(reply) => match reply.strip_prefix("error: ") {
+            Some(e) => {
+                eprintln!("{}", e);
+                ExitCode::FAILURE
+            }
+            None => {
+                if reply != "ok" {
+                    println!("{}", reply);
+                }
+                ExitCode::SUCCESS
+            }
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
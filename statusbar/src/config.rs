@@ -0,0 +1,1291 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// User configuration loaded from `$XDG_CONFIG_HOME/statusbar/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub break_reminder: BreakReminderConfig,
+    pub screen_time: ScreenTimeConfig,
+    pub focus: FocusConfig,
+    pub bar: BarConfig,
+    pub theme: ThemeConfig,
+    /// Per-output bars keyed by connector name such as `eDP-1`. Without any,
+    /// a single bar goes wherever the compositor puts it.
+    pub outputs: HashMap<String, OutputConfig>,
+    /// Click and scroll actions keyed by widget name, e.g. `[actions.wifi]`
+    pub actions: HashMap<String, WidgetActions>,
+    pub clock: ClockConfig,
+    pub workspace: WorkspaceConfig,
+    pub window_title: WindowTitleConfig,
+    pub taskbar: TaskbarConfig,
+    pub resources: ResourcesConfig,
+    pub media_player: MediaPlayerConfig,
+    pub status_item: StatusItemConfig,
+    pub privacy: PrivacyConfig,
+    pub weather: WeatherConfig,
+    pub volume: VolumeConfig,
+    pub brightness: BrightnessConfig,
+    pub microphone: MicrophoneConfig,
+    pub keyboard_layout: KeyboardLayoutConfig,
+    pub updates: UpdatesConfig,
+    pub night_light: NightLightConfig,
+    pub custom: CustomConfig,
+    pub lua: LuaConfig,
+    pub failed_units: FailedUnitsConfig,
+    pub diagnostics: DiagnosticsConfig,
+    pub disk_alert: DiskAlertConfig,
+    pub email: EmailConfig,
+    pub vpn: VpnConfig,
+    pub input_method: InputMethodConfig,
+    pub time_tracking: TimeTrackingConfig,
+    pub countdown: CountdownConfig,
+    pub ticker: TickerConfig,
+    pub gamemode: GameModeConfig,
+    pub bluetooth: BluetoothConfig,
+    pub battery: BatteryConfig,
+    pub wifi: WiFiConfig,
+    pub notifications: NotificationsConfig,
+    pub scripts: ScriptSandboxConfig,
+    /// Sections of plugin widgets, keyed by their name
+    #[serde(flatten)]
+    pub plugins: HashMap<String, toml::Value>,
+}
+
+/// Actions run on clicking or scrolling a widget, in place of what the
+/// widget does itself; unset ones keep the widget's own behavior
+///
+/// An action is `popover` to open the widget's popover, `exec <command>` to
+/// run a shell command, `niri <action>` for `niri msg action <action>`, or
+/// any `statusbarctl` command such as `set dnd on` or `trigger toggle-privacy`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WidgetActions {
+    pub left_click: Option<String>,
+    pub middle_click: Option<String>,
+    /// Replaces the widget's context menu
+    pub right_click: Option<String>,
+    pub scroll_up: Option<String>,
+    pub scroll_down: Option<String>,
+}
+
+/// Settings for the break reminder widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BreakReminderConfig {
+    /// Show the widget and track active time
+    pub enabled: bool,
+    /// Continuous active time before a break is due
+    pub interval_minutes: u64,
+    /// Length of a break
+    pub break_seconds: u64,
+    /// Send a desktop notification when a break is due
+    pub notify: bool,
+    /// Show a fullscreen overlay when the reminder is ignored
+    pub overlay: bool,
+    /// How long a due break can be ignored before escalating
+    pub overlay_delay_minutes: u64,
+}
+
+impl Default for BreakReminderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 20,
+            break_seconds: 20,
+            notify: true,
+            overlay: false,
+            overlay_delay_minutes: 5,
+        }
+    }
+}
+
+/// Settings for the screen time widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScreenTimeConfig {
+    /// Show the widget and record per-app focus time
+    pub enabled: bool,
+    /// Number of apps listed in the popover
+    pub max_apps: usize,
+}
+
+impl Default for ScreenTimeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_apps: 10,
+        }
+    }
+}
+
+/// Settings for the focus timer widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FocusConfig {
+    /// Show the widget and watch for distracting apps
+    pub enabled: bool,
+    /// Length of a focus session
+    pub duration_minutes: u64,
+    /// app_ids that count as an interruption during a session
+    pub distracting_apps: Vec<String>,
+    /// What to do when a distracting app gains focus
+    pub action: DistractionAction,
+    /// Workspace name distracting windows are moved to with `action = "move"`
+    pub move_to_workspace: Option<String>,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration_minutes: 25,
+            distracting_apps: Vec::new(),
+            action: DistractionAction::Warn,
+            move_to_workspace: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistractionAction {
+    /// Send a notification
+    Warn,
+    /// Move the window away to `move_to_workspace`
+    Move,
+    /// Minimize the window, or warn where the compositor can't, like niri
+    Minimize,
+}
+
+/// Settings for the clock widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// strftime-style format of the bar label
+    pub format: String,
+    /// Show ISO week numbers in the calendar
+    pub show_week_numbers: bool,
+    /// Location used for today's sunrise/sunset, in degrees (east positive)
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Extra timezones listed above the calendar
+    pub world_clocks: Vec<WorldClock>,
+    pub pomodoro: PomodoroConfig,
+    /// List recent notifications next to the calendar
+    pub show_notifications: bool,
+    /// Pango markup tooltip with `{date}`, `{time}`, `{week}` and `{timezone}`
+    pub tooltip: String,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            format: "%H:%M:%S".to_string(),
+            show_week_numbers: true,
+            latitude: None,
+            longitude: None,
+            world_clocks: Vec::new(),
+            pomodoro: PomodoroConfig::default(),
+            show_notifications: true,
+            tooltip: "<b>{date}</b>\nWeek {week}, {timezone}".to_string(),
+        }
+    }
+}
+
+/// Pomodoro timer in the clock popover
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    /// Offer the timer in the clock popover
+    pub enabled: bool,
+    pub work_minutes: u64,
+    pub short_break_minutes: u64,
+    pub long_break_minutes: u64,
+    /// Work sessions before a long break
+    pub sessions_before_long_break: u32,
+    /// Start a focus session during each work phase
+    pub focus_during_work: bool,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            sessions_before_long_break: 4,
+            focus_during_work: true,
+        }
+    }
+}
+
+/// Settings for the resources widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResourcesConfig {
+    /// Show CPU usage in the bar
+    pub show_cpu: bool,
+    /// Show memory usage in the bar
+    pub show_memory: bool,
+}
+
+impl Default for ResourcesConfig {
+    fn default() -> Self {
+        Self {
+            show_cpu: true,
+            show_memory: true,
+        }
+    }
+}
+
+/// Settings for the workspace widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WorkspaceConfig {
+    /// Annotate workspace buttons with their windows
+    pub badges: WorkspaceBadges,
+    /// Omit workspaces without windows, except the focused one
+    pub hide_empty: bool,
+    /// Blink the buttons of workspaces with urgent windows
+    pub blink_urgent: bool,
+    /// Show a button toggling the compositor's overview, if it has one
+    pub overview_button: bool,
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        Self {
+            badges: WorkspaceBadges::None,
+            hide_empty: false,
+            blink_urgent: false,
+            overview_button: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceBadges {
+    #[default]
+    None,
+    /// Number of windows on the workspace
+    Count,
+    /// Icons of the apps on the workspace
+    Icons,
+}
+
+/// Settings for the window title widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WindowTitleConfig {
+    /// Show the focused app's icon before the title
+    pub show_icon: bool,
+    /// Longest title shown, in characters
+    pub max_length: usize,
+    /// Per-app overrides of `max_length`, by app_id
+    pub app_max_length: HashMap<String, usize>,
+    /// app_ids whose titles are never shown
+    pub hide: Vec<String>,
+    /// Rewrites applied to the title in order
+    pub rules: Vec<TitleRule>,
+    /// Show float, maximize and close buttons for the focused window
+    pub controls: bool,
+}
+
+impl Default for WindowTitleConfig {
+    fn default() -> Self {
+        Self {
+            show_icon: true,
+            max_length: 50,
+            app_max_length: HashMap::new(),
+            hide: Vec::new(),
+            rules: Vec::new(),
+            controls: false,
+        }
+    }
+}
+
+/// A regex rewrite of window titles, e.g.
+/// `{ app_id = "firefox", pattern = ".* — Mozilla Firefox$", replacement = "Firefox" }`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TitleRule {
+    /// Only rewrite titles of this app; all apps when unset
+    pub app_id: Option<String>,
+    pub pattern: String,
+    /// Replacement text, with `$1` or `${name}` for captured groups
+    #[serde(default)]
+    pub replacement: String,
+}
+
+/// Settings for the taskbar widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TaskbarConfig {
+    /// Show a button per window on the focused workspace
+    pub enabled: bool,
+    /// Show window titles next to the icons
+    pub show_titles: bool,
+    /// Longest title shown on a button, in characters
+    pub max_title_length: usize,
+}
+
+impl Default for TaskbarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            show_titles: false,
+            max_title_length: 20,
+        }
+    }
+}
+
+/// Settings for the media player widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MediaPlayerConfig {
+    /// Seconds skipped per Shift+scroll step
+    pub seek_step_seconds: u64,
+}
+
+impl Default for MediaPlayerConfig {
+    fn default() -> Self {
+        Self {
+            seek_step_seconds: 5,
+        }
+    }
+}
+
+/// A named timezone, e.g. `{ name = "Tokyo", timezone = "Asia/Tokyo" }`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorldClock {
+    pub name: String,
+    /// IANA timezone name
+    pub timezone: String,
+}
+
+/// Publish the bar's quick settings as a tray item for other bars
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StatusItemConfig {
+    /// Register a StatusNotifierItem on the session bus
+    pub enabled: bool,
+    /// Keep the widgets and services running without showing the bar
+    pub hide_bar: bool,
+}
+
+/// Settings for the privacy screen and the capture indicators
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Show indicators while the camera, microphone or screen is captured
+    pub enabled: bool,
+    /// Turn the privacy screen on while the screen is being shared
+    pub redact_while_sharing: bool,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            redact_while_sharing: true,
+        }
+    }
+}
+
+/// Settings for the weather widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WeatherConfig {
+    /// Show the widget and fetch conditions from wttr.in
+    pub enabled: bool,
+    /// City or coordinates; empty to locate by IP address
+    pub location: String,
+    /// Minutes between fetches
+    pub interval_minutes: u64,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            location: String::new(),
+            interval_minutes: 30,
+        }
+    }
+}
+
+/// Settings for the volume widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VolumeConfig {
+    /// Show the output volume and the per-app mixer
+    pub enabled: bool,
+    /// Percent changed per scroll step
+    pub step: u32,
+}
+
+impl Default for VolumeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            step: 5,
+        }
+    }
+}
+
+/// Settings for the brightness widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BrightnessConfig {
+    /// Show the backlight level of the displays
+    pub enabled: bool,
+    /// Percent changed per scroll step
+    pub step: u32,
+}
+
+impl Default for BrightnessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            step: 5,
+        }
+    }
+}
+
+/// Settings for the microphone widget
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MicrophoneConfig {
+    /// Show whether the default source is muted or in use
+    pub enabled: bool,
+}
+
+/// Settings for the keyboard layout widget
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeyboardLayoutConfig {
+    /// Show the active layout; clicking switches to the next one
+    pub enabled: bool,
+    /// Short labels keyed by full layout name, e.g. `"English (US)" = "US"`
+    pub labels: HashMap<String, String>,
+}
+
+/// Settings for the updates widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UpdatesConfig {
+    /// Show a badge with the number of pending updates
+    pub enabled: bool,
+    /// Package managers to check
+    pub backends: Vec<UpdateBackend>,
+    /// Minutes between checks
+    pub interval_minutes: u64,
+    /// Shell command run by the popover's update button, e.g.
+    /// `foot -e sudo pacman -Syu`; empty to hide the button
+    pub updater_command: String,
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backends: vec![UpdateBackend::Pacman],
+            interval_minutes: 60,
+            updater_command: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateBackend {
+    /// `checkupdates` from pacman-contrib, which doesn't need root
+    Pacman,
+    Apt,
+    Dnf,
+    Flatpak,
+}
+
+/// Settings for the night light widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NightLightConfig {
+    /// Show a toggle for the color temperature filter
+    pub enabled: bool,
+    /// Program run to filter the screen
+    pub backend: NightLightBackend,
+    /// Temperatures in Kelvin
+    pub day_temperature: u32,
+    pub night_temperature: u32,
+    /// Location used to follow sunset and sunrise, in degrees (east
+    /// positive); without it the night temperature applies all day
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Turn the filter on when the bar starts
+    pub autostart: bool,
+}
+
+impl Default for NightLightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: NightLightBackend::Wlsunset,
+            day_temperature: 6500,
+            night_temperature: 4000,
+            latitude: None,
+            longitude: None,
+            autostart: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NightLightBackend {
+    Wlsunset,
+    Gammastep,
+}
+
+impl NightLightBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            NightLightBackend::Wlsunset => "wlsunset",
+            NightLightBackend::Gammastep => "gammastep",
+        }
+    }
+}
+
+/// Settings for the bluetooth widget
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BluetoothConfig {
+    /// Show the adapter state and the device list
+    pub enabled: bool,
+}
+
+/// Settings for the battery widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BatteryConfig {
+    /// Pango markup tooltip with `{percentage}`, `{status}`, `{power}` and
+    /// `{time}`; lines left empty by unknown values are dropped
+    pub tooltip: String,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            tooltip: "<b>{percentage}%</b>, {status}\n{time}\n{power}".to_string(),
+        }
+    }
+}
+
+/// Settings for the Wi-Fi widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WiFiConfig {
+    /// Pango markup tooltip with `{ssid}`, `{signal}`, `{ip}` and
+    /// `{interface}`
+    pub tooltip: String,
+}
+
+impl Default for WiFiConfig {
+    fn default() -> Self {
+        Self {
+            tooltip: "<b>{ssid}</b>\nSignal {signal}%\n{ip}".to_string(),
+        }
+    }
+}
+
+/// Settings for the notification daemon and the notification center
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Show the bell with the unread count
+    pub enabled: bool,
+    /// Become the desktop's notification daemon and show banners
+    ///
+    /// Only read at startup; without it the history comes from watching
+    /// whichever daemon runs.
+    pub daemon: bool,
+    /// How long banners stay when the sender doesn't say
+    pub banner_seconds: u64,
+    /// Banners shown at once; older ones are hidden first
+    pub max_banners: usize,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            daemon: false,
+            banner_seconds: 5,
+            max_banners: 3,
+        }
+    }
+}
+
+/// Settings for the failed systemd units widget
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FailedUnitsConfig {
+    /// Show failed system and user units
+    pub enabled: bool,
+    /// Unit names never reported, e.g. `fwupd-refresh.service`
+    pub ignore: Vec<String>,
+}
+
+/// Settings for the widget listing failing backends
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiagnosticsConfig {
+    /// Show the widget while a backend keeps failing
+    pub enabled: bool,
+    /// Failures in a row before a backend is listed
+    pub min_failures: u32,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_failures: 3,
+        }
+    }
+}
+
+/// Settings for the disk space alert widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiskAlertConfig {
+    /// Show an alert while a filesystem is nearly full
+    pub enabled: bool,
+    /// Usage in percent at which a filesystem counts as full
+    pub threshold_percent: u8,
+    /// Mount points to watch; empty for all of them
+    pub mount_points: Vec<String>,
+    /// Seconds between checks
+    pub interval_seconds: u64,
+}
+
+impl Default for DiskAlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_percent: 90,
+            mount_points: Vec::new(),
+            interval_seconds: 60,
+        }
+    }
+}
+
+/// Settings for the email widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// Show the unread count of the `[[email.accounts]]`
+    pub enabled: bool,
+    /// Minutes between checks
+    pub interval_minutes: u64,
+    /// Shell command opening the mail client, e.g. `thunderbird`
+    pub mail_client: String,
+    pub accounts: Vec<EmailAccountConfig>,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 5,
+            mail_client: String::new(),
+            accounts: Vec::new(),
+        }
+    }
+}
+
+/// A mailbox read from a local Maildir or over IMAP
+///
+/// The IMAP password is looked up in the keyring under `service=statusbar`
+/// and `account=<name>`; the bar asks for it the first time it is missing.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmailAccountConfig {
+    pub name: String,
+    /// Maildir folder to count instead of asking a server
+    pub maildir: String,
+    /// IMAP server, connected to over TLS
+    pub imap_host: String,
+    pub imap_port: u16,
+    pub username: String,
+    pub mailbox: String,
+}
+
+impl Default for EmailAccountConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            maildir: String::new(),
+            imap_host: String::new(),
+            imap_port: 993,
+            username: String::new(),
+            mailbox: "INBOX".to_string(),
+        }
+    }
+}
+
+/// Settings for the VPN widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VpnConfig {
+    /// Show the VPN state, with connection controls in the popover
+    pub enabled: bool,
+    pub backend: VpnBackend,
+    /// Seconds between status checks
+    pub interval_seconds: u64,
+}
+
+impl Default for VpnConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: VpnBackend::Tailscale,
+            interval_seconds: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VpnBackend {
+    /// tailscaled's local API
+    Tailscale,
+    /// The `mullvad` command line client
+    Mullvad,
+}
+
+impl VpnBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            VpnBackend::Tailscale => "Tailscale",
+            VpnBackend::Mullvad => "Mullvad",
+        }
+    }
+}
+
+/// Settings for the input method widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InputMethodConfig {
+    /// Show the active input method; clicking switches to the next one
+    pub enabled: bool,
+    pub backend: InputMethodBackend,
+    /// Short labels keyed by input method name, e.g. `pinyin = "拼"`
+    pub labels: HashMap<String, String>,
+}
+
+impl Default for InputMethodConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: InputMethodBackend::Fcitx5,
+            labels: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputMethodBackend {
+    Fcitx5,
+    Ibus,
+}
+
+/// Settings for the time tracking widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TimeTrackingConfig {
+    /// Show the running task and its elapsed time
+    pub enabled: bool,
+    /// Where tasks are tracked; the Toggl API token is looked up in the
+    /// keyring under `service statusbar account toggl`, asked for when missing
+    pub backend: TimeTrackingBackend,
+    /// Seconds between checks for changes made elsewhere; Toggl's free plan
+    /// allows 30 requests an hour, so raise this to 300 or more there
+    pub interval_seconds: u64,
+    /// Recent tasks offered in the popover
+    pub max_recent: usize,
+    /// Longest task shown in the bar, in characters
+    pub max_length: usize,
+}
+
+impl Default for TimeTrackingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: TimeTrackingBackend::Timewarrior,
+            interval_seconds: 60,
+            max_recent: 8,
+            max_length: 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeTrackingBackend {
+    /// The `timew` command line client
+    Timewarrior,
+    /// Toggl Track's web API
+    Toggl,
+}
+
+impl TimeTrackingBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeTrackingBackend::Timewarrior => "Timewarrior",
+            TimeTrackingBackend::Toggl => "Toggl",
+        }
+    }
+}
+
+/// Settings for the countdown widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CountdownConfig {
+    /// Show the time left until the next deadline
+    pub enabled: bool,
+    pub targets: Vec<CountdownTarget>,
+    /// Hours a passed deadline stays in the bar
+    pub expired_hours: u64,
+}
+
+impl Default for CountdownConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            targets: Vec::new(),
+            expired_hours: 24,
+        }
+    }
+}
+
+/// A deadline counted down to
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CountdownTarget {
+    /// Shown before the time left, e.g. `release`
+    pub name: String,
+    /// Local time as `2025-06-01 09:00` or `2025-06-01`, or RFC 3339 with
+    /// an offset
+    pub at: String,
+}
+
+/// Settings for the ticker widget
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TickerConfig {
+    /// Cycle through the symbols' prices in the bar
+    pub enabled: bool,
+    pub provider: TickerProvider,
+    /// Ticker symbols like `AAPL` for Yahoo, coin ids like `bitcoin` for
+    /// CoinGecko
+    pub symbols: Vec<String>,
+    /// Currency CoinGecko prices are quoted in
+    pub currency: String,
+    /// Minutes between fetches
+    pub interval_minutes: u64,
+    /// Seconds each symbol stays in the bar
+    pub cycle_seconds: u64,
+}
+
+impl Default for TickerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: TickerProvider::Yahoo,
+            symbols: Vec::new(),
+            currency: "usd".to_string(),
+            interval_minutes: 5,
+            cycle_seconds: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TickerProvider {
+    /// Yahoo Finance, for stocks, funds and indices
+    Yahoo,
+    /// CoinGecko, for cryptocurrencies
+    CoinGecko,
+}
+
+impl TickerProvider {
+    pub fn name(self) -> &'static str {
+        match self {
+            TickerProvider::Yahoo => "Yahoo Finance",
+            TickerProvider::CoinGecko => "CoinGecko",
+        }
+    }
+}
+
+/// Settings for the GameMode indicator
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameModeConfig {
+    /// Light up while GameMode is active or the CPU governor is set to
+    /// `performance`
+    pub enabled: bool,
+}
+
+/// Placement and shape of the bar itself
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BarConfig {
+    /// Screen edge the bar is anchored to; on `left` and `right` widgets stack
+    /// vertically and long labels are shortened
+    pub position: BarPosition,
+    /// What the bar does while a fullscreen window is shown on its output
+    pub fullscreen: FullscreenMode,
+    /// Thickness in pixels at a text scaling factor of 1.0, the width of
+    /// vertical bars
+    pub height: i32,
+    /// Gaps between the bar and the screen edges in pixels
+    pub margin: BarMargin,
+    /// Round the bar's corners by this many pixels, for floating bars
+    pub corner_radius: i32,
+    /// Reserve space for the bar so windows don't go under it
+    pub exclusive_zone: bool,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            position: BarPosition::default(),
+            fullscreen: FullscreenMode::default(),
+            height: 32,
+            margin: BarMargin::default(),
+            corner_radius: 0,
+            exclusive_zone: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BarMargin {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarPosition {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl BarPosition {
+    pub fn is_vertical(self) -> bool {
+        matches!(self, BarPosition::Left | BarPosition::Right)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FullscreenMode {
+    /// Hide the bar until the window leaves fullscreen
+    #[default]
+    Hide,
+    /// Stay above the window without reserving space
+    Overlay,
+    /// Keep the bar as it is
+    Show,
+}
+
+/// Bar settings for one output; outputs without an entry get the full bar
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Show a bar on this output
+    pub enabled: bool,
+    /// Names of the widgets shown, as used by `statusbar msg`; all of them
+    /// when unset. Widgets that notify or reach the network, and plugins,
+    /// run once and show on the first output listing them
+    pub widgets: Option<Vec<String>>,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            widgets: None,
+        }
+    }
+}
+
+/// Settings for the custom script widgets
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CustomConfig {
+    /// Show the `[[custom.scripts]]` blocks
+    pub enabled: bool,
+    pub scripts: Vec<CustomScriptConfig>,
+}
+
+/// One block of the custom widget, rendering what a command prints
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CustomScriptConfig {
+    /// Added as the `custom-<name>` CSS class
+    pub name: String,
+    /// Shell command producing the output
+    pub exec: String,
+    /// Seconds between runs; 0 keeps the command running and shows each
+    /// line it prints
+    pub interval_seconds: u64,
+    pub format: CustomFormat,
+    /// Shown before the text
+    pub icon: String,
+    /// Icons replacing `icon`, keyed by the `alt` field of JSON output
+    pub icons: HashMap<String, String>,
+    /// Shell commands run on clicks and scrolls; the block refreshes after
+    pub on_click: String,
+    pub on_middle_click: String,
+    pub on_scroll_up: String,
+    pub on_scroll_down: String,
+}
+
+impl Default for CustomScriptConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            exec: String::new(),
+            interval_seconds: 10,
+            format: CustomFormat::Plain,
+            icon: String::new(),
+            icons: HashMap::new(),
+            on_click: String::new(),
+            on_middle_click: String::new(),
+            on_scroll_up: String::new(),
+            on_scroll_down: String::new(),
+        }
+    }
+}
+
+/// Settings for the widgets written in Lua
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LuaConfig {
+    /// Load the `[[lua.widgets]]` scripts
+    pub enabled: bool,
+    pub widgets: Vec<LuaWidgetConfig>,
+}
+
+/// One block driven by a Lua script
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LuaWidgetConfig {
+    /// Added as the `lua-<name>` CSS class
+    pub name: String,
+    /// Script file, relative to the directory of the config file
+    pub path: PathBuf,
+}
+
+/// How a custom script's output is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFormat {
+    /// Lines of text, tooltip and CSS class, like i3blocks
+    Plain,
+    /// waybar-style `{"text", "alt", "tooltip", "class"}`
+    Json,
+}
+
+/// Limits applied to commands run by script widgets
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScriptSandboxConfig {
+    /// Kill a script that runs longer than this
+    pub timeout_seconds: u64,
+    /// Output kept per run; the rest is discarded
+    pub max_output_bytes: usize,
+    /// Start scripts with only the variables in `keep_env`
+    pub clean_env: bool,
+    /// Variables passed through when `clean_env` is set
+    pub keep_env: Vec<String>,
+    /// Run scripts under bubblewrap with a read-only filesystem
+    pub bwrap: bool,
+    /// Keep network access inside the bubblewrap sandbox
+    pub allow_network: bool,
+}
+
+impl Default for ScriptSandboxConfig {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 10,
+            max_output_bytes: 64 * 1024,
+            clean_env: true,
+            keep_env: [
+                "PATH",
+                "HOME",
+                "USER",
+                "LANG",
+                "XDG_RUNTIME_DIR",
+                "WAYLAND_DISPLAY",
+                "DBUS_SESSION_BUS_ADDRESS",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            bwrap: false,
+            allow_network: true,
+        }
+    }
+}
+
+/// Appearance overrides; `auto` follows the desktop accessibility settings
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// Switch to the high-contrast palette
+    pub high_contrast: Preference,
+    /// Disable animations and transitions
+    pub reduce_motion: Preference,
+    /// Transitions for widgets appearing and the bar hiding
+    pub animations: AnimationConfig,
+    /// Dark or light palette; `auto` follows the desktop's color scheme
+    pub color_scheme: ColorScheme,
+    /// SCSS variables of `theme/_variables.scss` to override, named without
+    /// the `$`, e.g. `accent = "#e06c75"`, `font-family = "Inter"` or
+    /// `radius-md = "0.5rem"`
+    pub variables: HashMap<String, String>,
+}
+
+/// Opt-in transitions; reduce motion turns them off again
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnimationConfig {
+    pub enabled: bool,
+    pub duration_ms: u32,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration_ms: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+impl ColorScheme {
+    /// Whether to use the light palette, given the desktop's preference
+    pub fn is_light(self, desktop_prefers_light: bool) -> bool {
+        match self {
+            ColorScheme::Auto => desktop_prefers_light,
+            ColorScheme::Dark => false,
+            ColorScheme::Light => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preference {
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+impl Preference {
+    /// Resolve against the value detected from the desktop
+    pub fn resolve(self, detected: bool) -> bool {
+        match self {
+            Preference::Auto => detected,
+            Preference::On => true,
+            Preference::Off => false,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file, falling back to defaults if it is missing or invalid
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Failed to parse {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Current values of one `[section]`, with defaults filled in
+    pub fn section(&self, name: &str) -> Option<toml::Table> {
+        let toml::Value::Table(mut config) = toml::Value::try_from(self).ok()? else {
+            return None;
+        };
+
+        match config.remove(name)? {
+            toml::Value::Table(section) => Some(section),
+            _ => None,
+        }
+    }
+
+    /// Write keys of one `[section]` to the config file, keeping comments and
+    /// everything else as the user wrote it
+    pub fn save_section(name: &str, values: Vec<(String, toml_edit::Value)>) -> Result<(), String> {
+        let path = Self::path().ok_or("No config directory")?;
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+
+        let mut document = contents
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+        let section = document
+            .entry(name)
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(|| format!("`{}` is not a table", name))?;
+        for (key, value) in values {
+            section.insert(&key, toml_edit::Item::Value(value));
+        }
+
+        // Refuse to write something the bar can't load back
+        let contents = document.to_string();
+        toml::from_str::<Config>(&contents).map_err(|e| e.to_string())?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("statusbar").join("config.toml"))
+    }
+}
@@ -0,0 +1,29 @@
+// `--headless-test`: the full widget tree without a Wayland session
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Time given to widgets to receive their initial state before dumping it
+pub const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Switch to headless mode; must be called before any service starts
+pub fn enable() {
+    let _ = ENABLED.set(true);
+}
+
+/// Whether the bar runs under `--headless-test`
+///
+/// Services replace their system connections with fixed mock data then.
+pub fn is_enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+/// Whether to use the built-in mock compositor
+///
+/// Tests running a fake compositor socket set
+/// `STATUSBAR_HEADLESS_COMPOSITOR=live` to have it detected like a real one.
+pub fn mock_compositor() -> bool {
+    is_enabled() && env::var_os("STATUSBAR_HEADLESS_COMPOSITOR").is_none_or(|value| value != "live")
+}
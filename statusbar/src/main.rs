@@ -1,35 +1,246 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::process::Command;
+use std::rc::Rc;
+use std::thread;
 
+use gtk::gdk;
 use gtk::prelude::*;
 use gtk4_layer_shell::{Edge, Layer, LayerShell};
 use relm4::prelude::*;
 
+mod config;
+mod headless;
+mod plugins;
+mod services;
+mod theme;
 mod widgets;
+use config::{
+    BarConfig, BarPosition, ClockConfig, Config, FullscreenMode, OutputConfig, ThemeConfig,
+    WidgetActions,
+};
+use services::bar_service;
+use services::compositor::{self, Event};
+use services::ipc::{self, IpcCommand, IpcResult, Responder};
+use services::portal::{self, PortalSettings};
+use services::privacy;
+use services::status_item::{self, QuickAction};
+use services::{night_light, notifications, power, secrets};
+use widgets::break_reminder::BreakReminderMsg;
+use widgets::clock::{ClockMsg, ClockOutput};
+use widgets::focus_timer::FocusTimerMsg;
+use widgets::motion;
 use widgets::{
-    Battery, Clock, MediaPlayer, Resources, SystemTray, WiFi, WindowTitle, WorkspaceWidget,
+    Battery, Bluetooth, BreakReminder, Brightness, Clock, ContextAction, ContextMenu, Countdown,
+    Custom, Diagnostics, DiskAlert, Email, FailedUnits, FocusTimer, GameMode, InputEvent,
+    InputMethod, KeyboardLayout, LuaWidgets, MediaPlayer, Microphone, NightLight,
+    NotificationCenter, PrivacyIndicator, Resources, ScreenTime, Snapshot, SystemTray, Taskbar,
+    Ticker, TimeTracking, Updates, Volume, Vpn, Weather, WiFi, WindowTitle, WorkspaceWidget,
 };
 
 const APP_ID: &str = "com.github.iceice666.statusbar";
 
+/// Widest a text label grows on a vertical bar, in characters
+const VERTICAL_LABEL_CHARS: i32 = 4;
+
+/// Widgets visited by `cycle-popover`, in bar order
+const POPOVER_CYCLE: &[&str] = &[
+    MediaPlayer::NAME,
+    Resources::NAME,
+    DiskAlert::NAME,
+    WiFi::NAME,
+    Bluetooth::NAME,
+    Volume::NAME,
+    Brightness::NAME,
+    Battery::NAME,
+    BreakReminder::NAME,
+    ScreenTime::NAME,
+    FocusTimer::NAME,
+    TimeTracking::NAME,
+    Countdown::NAME,
+    Weather::NAME,
+    Ticker::NAME,
+    Email::NAME,
+    Vpn::NAME,
+    Updates::NAME,
+    FailedUnits::NAME,
+    Diagnostics::NAME,
+    NotificationCenter::NAME,
+    PrivacyIndicator::NAME,
+    Clock::NAME,
+];
+
+/// Built-in widgets in bar order
+const WIDGETS: &[&str] = &[
+    WorkspaceWidget::NAME,
+    WindowTitle::NAME,
+    Taskbar::NAME,
+    MediaPlayer::NAME,
+    Resources::NAME,
+    DiskAlert::NAME,
+    WiFi::NAME,
+    Bluetooth::NAME,
+    Volume::NAME,
+    Brightness::NAME,
+    NightLight::NAME,
+    Microphone::NAME,
+    KeyboardLayout::NAME,
+    InputMethod::NAME,
+    Battery::NAME,
+    BreakReminder::NAME,
+    ScreenTime::NAME,
+    FocusTimer::NAME,
+    TimeTracking::NAME,
+    Countdown::NAME,
+    Weather::NAME,
+    Ticker::NAME,
+    Email::NAME,
+    Vpn::NAME,
+    Updates::NAME,
+    FailedUnits::NAME,
+    Diagnostics::NAME,
+    NotificationCenter::NAME,
+    PrivacyIndicator::NAME,
+    GameMode::NAME,
+    Custom::NAME,
+    LuaWidgets::NAME,
+    SystemTray::NAME,
+    Clock::NAME,
+];
+
+/// Widgets that notify, keep files or reach the network, run by the primary
+/// bar alone and shown on the first bar whose output lists them, like plugins
+const RUN_ONCE: &[&str] = &[
+    BreakReminder::NAME,
+    ScreenTime::NAME,
+    FocusTimer::NAME,
+    TimeTracking::NAME,
+    Countdown::NAME,
+    Weather::NAME,
+    Ticker::NAME,
+    Email::NAME,
+    Updates::NAME,
+    Custom::NAME,
+    LuaWidgets::NAME,
+];
+
+/// GTK's reference DPI that the text scaling factor multiplies
+const BASE_DPI: f64 = 96.0;
+
+/// Layer shell namespace of the bar windows
+const NAMESPACE: &str = "statusbar";
+
+struct StatusBarInit {
+    config: Config,
+    css_provider: gtk::CssProvider,
+    /// Output the bar is pinned to, the compositor picks one when unset
+    output: Option<gdk::Monitor>,
+    /// Input of the primary bar on the bars it launches, unset on the primary
+    /// bar itself, which serves IPC and other app-wide services
+    primary_bar: Option<relm4::Sender<StatusBarMsg>>,
+}
+
 struct StatusBar {
-    workspace: Controller<WorkspaceWidget>,
-    window_title: Controller<WindowTitle>,
-    media_player: Controller<MediaPlayer>,
-    resources: Controller<Resources>,
-    wifi: Controller<WiFi>,
-    battery: Controller<Battery>,
-    tray: Controller<SystemTray>,
-    clock: Controller<Clock>,
+    window: gtk::ApplicationWindow,
+    css_provider: gtk::CssProvider,
+    theme_config: ThemeConfig,
+    bar_config: BarConfig,
+    /// Output the bar is on, once known
+    output: Option<gdk::Monitor>,
+    /// The bar was hidden for a fullscreen window and comes back with it
+    hidden_for_fullscreen: bool,
+    /// Outputs showing a fullscreen window, as last reported
+    fullscreen_outputs: Vec<String>,
+    workspace: Option<Controller<WorkspaceWidget>>,
+    window_title: Option<Controller<WindowTitle>>,
+    taskbar: Option<Controller<Taskbar>>,
+    media_player: Option<Controller<MediaPlayer>>,
+    resources: Option<Controller<Resources>>,
+    disk_alert: Option<Controller<DiskAlert>>,
+    wifi: Option<Controller<WiFi>>,
+    bluetooth: Option<Controller<Bluetooth>>,
+    volume: Option<Controller<Volume>>,
+    brightness: Option<Controller<Brightness>>,
+    night_light: Option<Controller<NightLight>>,
+    microphone: Option<Controller<Microphone>>,
+    keyboard_layout: Option<Controller<KeyboardLayout>>,
+    input_method: Option<Controller<InputMethod>>,
+    battery: Option<Controller<Battery>>,
+    break_reminder: Option<Controller<BreakReminder>>,
+    screen_time: Option<Controller<ScreenTime>>,
+    focus_timer: Option<Controller<FocusTimer>>,
+    time_tracking: Option<Controller<TimeTracking>>,
+    countdown: Option<Controller<Countdown>>,
+    weather: Option<Controller<Weather>>,
+    ticker: Option<Controller<Ticker>>,
+    email: Option<Controller<Email>>,
+    vpn: Option<Controller<Vpn>>,
+    updates: Option<Controller<Updates>>,
+    failed_units: Option<Controller<FailedUnits>>,
+    diagnostics: Option<Controller<Diagnostics>>,
+    notification_center: Option<Controller<NotificationCenter>>,
+    privacy_indicator: Option<Controller<PrivacyIndicator>>,
+    gamemode: Option<Controller<GameMode>>,
+    custom: Option<Controller<Custom>>,
+    lua: Option<Controller<LuaWidgets>>,
+    tray: Option<Controller<SystemTray>>,
+    clock: Option<Controller<Clock>>,
+    /// Widgets from plugin crates, keyed by name like the built-in ones
+    plugins: Vec<(&'static str, statusbar_plugin::Instance)>,
+    /// Containers holding the root of each widget the bar runs, keyed by
+    /// `ContextMenu::NAME`; they may be shown on another bar
+    slots: HashMap<&'static str, gtk::Box>,
+    /// Where each widget goes in this bar, holding its slot while shown here
+    places: HashMap<&'static str, gtk::Box>,
+    /// Click and scroll actions keyed by widget name, shared with the slots
+    input_actions: Rc<RefCell<HashMap<String, WidgetActions>>>,
+    primary: bool,
+    /// Input of the primary bar, which gets the clock's focus events
+    primary_sender: relm4::Sender<StatusBarMsg>,
+    /// Bars on the other outputs, owned by the primary bar
+    secondary_bars: Vec<Controller<StatusBar>>,
 }
 
 #[derive(Debug)]
 enum StatusBarMsg {
-    // Messages will go here
+    PortalSettingsChanged(PortalSettings),
+    ContextAction(&'static str, ContextAction),
+    /// A click or scroll bound to an action in the config
+    Input(&'static str, InputEvent),
+    Ipc(IpcCommand, Responder),
+    Clock(ClockOutput),
+    QuickAction(QuickAction),
+    /// Reload a widget on this bar alone, sent by the primary bar
+    ReloadWidget(&'static str),
+    /// Outputs were connected or disconnected
+    OutputsChanged,
+    /// Outputs now showing a fullscreen window
+    FullscreenChanged(Vec<String>),
+    /// Print the state for `--headless-test` and quit
+    HeadlessDump,
+}
+
+/// Relaunch a widget controller if the bar still runs it and return its new
+/// root, dropping the controller otherwise
+///
+/// The controller arm comes first, since a call that fails to parse as a
+/// `ty` fragment can't fall through to the next arm.
+macro_rules! relaunch {
+    ($field:expr, $runs:expr, $controller:expr) => {{
+        $field = $runs.then(|| $controller);
+        $field
+            .as_ref()
+            .map(|controller| controller.widget().clone().upcast::<gtk::Widget>())
+    }};
+    ($field:expr, $runs:expr, $widget:ty, $init:expr) => {
+        relaunch!($field, $runs, <$widget>::builder().launch($init).detach())
+    };
 }
 
 #[relm4::component]
 impl SimpleComponent for StatusBar {
-    type Init = ();
+    type Init = StatusBarInit;
     type Input = StatusBarMsg;
     type Output = ();
 
@@ -38,160 +249,1695 @@ impl SimpleComponent for StatusBar {
         #[name = "window"]
         gtk::ApplicationWindow {
             set_css_classes: &["statusbar-window"],
-            set_height_request: 32,
-            set_default_height: 32,
-
-            gtk::Box {
-                set_orientation: gtk::Orientation::Horizontal,
-                set_spacing: 0,
-                set_css_classes: &["statusbar-container"],
 
-                // Left section
+            // Slides the bar in and out with animations on
+            #[local_ref]
+            bar_revealer -> gtk::Revealer {
                 gtk::Box {
-                    set_orientation: gtk::Orientation::Horizontal,
-                    set_spacing: 2,
-                    set_hexpand: true,
-                    set_halign: gtk::Align::Start,
-                    set_css_classes: &["statusbar-left"],
+                    set_orientation: orientation(model.bar_config.position),
+                    set_spacing: 0,
+                    set_css_classes: &["statusbar-container"],
 
-                    #[local_ref]
-                    workspace_widget -> gtk::Box {},
+                    // Left section, the top one on vertical bars
+                    #[name = "left_section"]
+                    gtk::Box {
+                        set_orientation: orientation(model.bar_config.position),
+                        set_spacing: 2,
+                        set_css_classes: &["statusbar-left"],
 
-                    #[local_ref]
-                    window_title_widget -> gtk::Box {},
+                        #[local_ref]
+                        workspace_slot -> gtk::Box {},
 
-                    #[local_ref]
-                    media_player_widget -> gtk::Box {},
-                },
+                        #[local_ref]
+                        window_title_slot -> gtk::Box {},
 
-                // Right section
-                gtk::Box {
-                    set_orientation: gtk::Orientation::Horizontal,
-                    set_spacing: 2,
-                    set_hexpand: true,
-                    set_halign: gtk::Align::End,
-                    set_css_classes: &["statusbar-right"],
+                        #[local_ref]
+                        taskbar_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        media_player_slot -> gtk::Box {},
+                    },
+
+                    // Right section, the bottom one on vertical bars
+                    #[name = "right_section"]
+                    gtk::Box {
+                        set_orientation: orientation(model.bar_config.position),
+                        set_spacing: 2,
+                        set_css_classes: &["statusbar-right"],
+
+                        #[local_ref]
+                        resources_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        disk_alert_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        wifi_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        bluetooth_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        volume_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        brightness_slot -> gtk::Box {},
 
-                    #[local_ref]
-                    resources_widget -> gtk::Box {},
+                        #[local_ref]
+                        night_light_slot -> gtk::Box {},
 
-                    #[local_ref]
-                    wifi_widget -> gtk::Box {},
+                        #[local_ref]
+                        microphone_slot -> gtk::Box {},
 
-                    #[local_ref]
-                    battery_widget -> gtk::Box {},
+                        #[local_ref]
+                        keyboard_layout_slot -> gtk::Box {},
 
-                    #[local_ref]
-                    tray_widget -> gtk::Box {},
+                        #[local_ref]
+                        input_method_slot -> gtk::Box {},
 
-                    #[local_ref]
-                    clock_widget -> gtk::Box {},
+                        #[local_ref]
+                        battery_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        break_reminder_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        screen_time_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        focus_timer_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        time_tracking_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        countdown_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        weather_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        ticker_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        email_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        vpn_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        updates_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        failed_units_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        diagnostics_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        notification_center_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        privacy_indicator_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        gamemode_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        custom_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        lua_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        tray_slot -> gtk::Box {},
+
+                        #[local_ref]
+                        clock_slot -> gtk::Box {},
+                    }
                 }
             }
         }
     }
 
     fn init(
-        _init: Self::Init,
+        init: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        // Initialize layer shell BEFORE window is realized
-        root.init_layer_shell();
+        let StatusBarInit {
+            config,
+            css_provider,
+            output,
+            primary_bar,
+        } = init;
+        let primary = primary_bar.is_none();
+        let primary_sender = primary_bar.unwrap_or_else(|| sender.input_sender().clone());
 
-        // Configure layer shell properties
-        root.set_layer(Layer::Overlay);
-        root.set_namespace(Some("statusbar"));
-        root.auto_exclusive_zone_enable();
-
-        // Anchor to top, left, and right edges (spans full width)
-        root.set_anchor(Edge::Top, true);
-        root.set_anchor(Edge::Left, true);
-        root.set_anchor(Edge::Right, true);
-        root.set_anchor(Edge::Bottom, false);
-
-        // Set margins (0 for now)
-        root.set_margin(Edge::Top, 0);
-        root.set_margin(Edge::Bottom, 0);
-        root.set_margin(Edge::Left, 0);
-        root.set_margin(Edge::Right, 0);
-
-        // Initialize widgets
-        let workspace = WorkspaceWidget::builder().launch(()).detach();
-        let window_title = WindowTitle::builder().launch(()).detach();
-        let media_player = MediaPlayer::builder().launch(()).detach();
-        let resources = Resources::builder().launch(()).detach();
-        let wifi = WiFi::builder().launch(()).detach();
-        let battery = Battery::builder().launch(()).detach();
-        let tray = SystemTray::builder().launch(()).detach();
-        let clock = Clock::builder().launch(()).detach();
-
-        let model = StatusBar {
+        // The primary bar takes the first configured output and launches the
+        // bars on the rest
+        let configured_outputs = if primary && !headless::is_enabled() {
+            bar_outputs(&config)
+        } else {
+            None
+        };
+        let mut output = output;
+        let mut other_outputs = Vec::new();
+        if let Some(mut outputs) = configured_outputs {
+            if outputs.is_empty() {
+                // Every connected output has its bar disabled
+                let window = root.clone();
+                gtk::glib::idle_add_local_once(move || window.set_visible(false));
+            } else {
+                output = Some(outputs.remove(0));
+                other_outputs = outputs;
+            }
+        }
+        if !primary {
+            root.set_application(Some(&relm4::main_application()));
+        }
+
+        // There's no Wayland compositor to talk to in headless mode
+        if !headless::is_enabled() {
+            Self::init_layer_shell(&root, &config.bar, output.as_ref());
+        }
+
+        // Must come before widgets subscribe to the notification history
+        if primary && config.notifications.daemon && !headless::is_enabled() {
+            notifications::serve();
+        }
+
+        // Revealers pick up the duration as they are made
+        motion::configure(&config.theme.animations);
+
+        // Initialize the widgets this bar runs
+        let runs =
+            |name: &str| runs_widget(&config, name, primary, output.as_ref(), &other_outputs);
+        let workspace = runs(WorkspaceWidget::NAME).then(|| {
+            WorkspaceWidget::builder()
+                .launch(config.workspace.clone())
+                .detach()
+        });
+        let window_title = runs(WindowTitle::NAME).then(|| {
+            WindowTitle::builder()
+                .launch(config.window_title.clone())
+                .detach()
+        });
+        let taskbar =
+            runs(Taskbar::NAME).then(|| Taskbar::builder().launch(config.taskbar.clone()).detach());
+        let media_player = runs(MediaPlayer::NAME).then(|| {
+            MediaPlayer::builder()
+                .launch(config.media_player.clone())
+                .detach()
+        });
+        let resources = runs(Resources::NAME).then(|| {
+            Resources::builder()
+                .launch(config.resources.clone())
+                .detach()
+        });
+        let disk_alert = runs(DiskAlert::NAME).then(|| {
+            DiskAlert::builder()
+                .launch(config.disk_alert.clone())
+                .detach()
+        });
+        let wifi = runs(WiFi::NAME).then(|| WiFi::builder().launch(config.wifi.clone()).detach());
+        let bluetooth = runs(Bluetooth::NAME).then(|| {
+            Bluetooth::builder()
+                .launch(config.bluetooth.clone())
+                .detach()
+        });
+        let volume =
+            runs(Volume::NAME).then(|| Volume::builder().launch(config.volume.clone()).detach());
+        let brightness = runs(Brightness::NAME).then(|| {
+            Brightness::builder()
+                .launch(config.brightness.clone())
+                .detach()
+        });
+        let night_light = runs(NightLight::NAME).then(|| {
+            NightLight::builder()
+                .launch(config.night_light.clone())
+                .detach()
+        });
+        let microphone = runs(Microphone::NAME).then(|| {
+            Microphone::builder()
+                .launch(config.microphone.clone())
+                .detach()
+        });
+        let keyboard_layout = runs(KeyboardLayout::NAME).then(|| {
+            KeyboardLayout::builder()
+                .launch(config.keyboard_layout.clone())
+                .detach()
+        });
+        let input_method = runs(InputMethod::NAME).then(|| {
+            InputMethod::builder()
+                .launch(config.input_method.clone())
+                .detach()
+        });
+        let battery =
+            runs(Battery::NAME).then(|| Battery::builder().launch(config.battery.clone()).detach());
+        let break_reminder = runs(BreakReminder::NAME).then(|| {
+            BreakReminder::builder()
+                .launch(config.break_reminder.clone())
+                .detach()
+        });
+        let screen_time = runs(ScreenTime::NAME).then(|| {
+            ScreenTime::builder()
+                .launch(config.screen_time.clone())
+                .detach()
+        });
+        let focus_timer = runs(FocusTimer::NAME)
+            .then(|| FocusTimer::builder().launch(config.focus.clone()).detach());
+        let time_tracking = runs(TimeTracking::NAME).then(|| {
+            TimeTracking::builder()
+                .launch(config.time_tracking.clone())
+                .detach()
+        });
+        let countdown = runs(Countdown::NAME).then(|| {
+            Countdown::builder()
+                .launch(config.countdown.clone())
+                .detach()
+        });
+        let weather =
+            runs(Weather::NAME).then(|| Weather::builder().launch(config.weather.clone()).detach());
+        let ticker =
+            runs(Ticker::NAME).then(|| Ticker::builder().launch(config.ticker.clone()).detach());
+        let email =
+            runs(Email::NAME).then(|| Email::builder().launch(config.email.clone()).detach());
+        let vpn = runs(Vpn::NAME).then(|| Vpn::builder().launch(config.vpn.clone()).detach());
+        let updates =
+            runs(Updates::NAME).then(|| Updates::builder().launch(config.updates.clone()).detach());
+        let failed_units = runs(FailedUnits::NAME).then(|| {
+            FailedUnits::builder()
+                .launch(config.failed_units.clone())
+                .detach()
+        });
+        let diagnostics = runs(Diagnostics::NAME).then(|| {
+            Diagnostics::builder()
+                .launch(config.diagnostics.clone())
+                .detach()
+        });
+        let notification_center = runs(NotificationCenter::NAME).then(|| {
+            NotificationCenter::builder()
+                .launch(config.notifications.clone())
+                .detach()
+        });
+        let privacy_indicator = runs(PrivacyIndicator::NAME).then(|| {
+            PrivacyIndicator::builder()
+                .launch(config.privacy.clone())
+                .detach()
+        });
+        let gamemode = runs(GameMode::NAME)
+            .then(|| GameMode::builder().launch(config.gamemode.clone()).detach());
+        let custom = runs(Custom::NAME).then(|| {
+            Custom::builder()
+                .launch((config.custom.clone(), config.scripts.clone()))
+                .detach()
+        });
+        let lua = runs(LuaWidgets::NAME).then(|| {
+            LuaWidgets::builder()
+                .launch((config.lua.clone(), config.scripts.clone()))
+                .detach()
+        });
+        let tray = runs(SystemTray::NAME).then(|| SystemTray::builder().launch(()).detach());
+        let clock =
+            runs(Clock::NAME).then(|| Self::launch_clock(config.clock.clone(), &primary_sender));
+
+        // Wrap every widget in a slot carrying the shared context menu and
+        // the configured click and scroll actions
+        let input_actions = Rc::new(RefCell::new(config.actions.clone()));
+        let mut slots: HashMap<_, _> = [
+            slot(workspace.as_ref(), &input_actions, &sender),
+            slot(window_title.as_ref(), &input_actions, &sender),
+            slot(taskbar.as_ref(), &input_actions, &sender),
+            slot(media_player.as_ref(), &input_actions, &sender),
+            slot(resources.as_ref(), &input_actions, &sender),
+            slot(disk_alert.as_ref(), &input_actions, &sender),
+            slot(wifi.as_ref(), &input_actions, &sender),
+            slot(bluetooth.as_ref(), &input_actions, &sender),
+            slot(volume.as_ref(), &input_actions, &sender),
+            slot(brightness.as_ref(), &input_actions, &sender),
+            slot(night_light.as_ref(), &input_actions, &sender),
+            slot(microphone.as_ref(), &input_actions, &sender),
+            slot(keyboard_layout.as_ref(), &input_actions, &sender),
+            slot(input_method.as_ref(), &input_actions, &sender),
+            slot(battery.as_ref(), &input_actions, &sender),
+            slot(break_reminder.as_ref(), &input_actions, &sender),
+            slot(screen_time.as_ref(), &input_actions, &sender),
+            slot(focus_timer.as_ref(), &input_actions, &sender),
+            slot(time_tracking.as_ref(), &input_actions, &sender),
+            slot(countdown.as_ref(), &input_actions, &sender),
+            slot(weather.as_ref(), &input_actions, &sender),
+            slot(ticker.as_ref(), &input_actions, &sender),
+            slot(email.as_ref(), &input_actions, &sender),
+            slot(vpn.as_ref(), &input_actions, &sender),
+            slot(updates.as_ref(), &input_actions, &sender),
+            slot(failed_units.as_ref(), &input_actions, &sender),
+            slot(diagnostics.as_ref(), &input_actions, &sender),
+            slot(notification_center.as_ref(), &input_actions, &sender),
+            slot(privacy_indicator.as_ref(), &input_actions, &sender),
+            slot(gamemode.as_ref(), &input_actions, &sender),
+            slot(custom.as_ref(), &input_actions, &sender),
+            slot(lua.as_ref(), &input_actions, &sender),
+            slot(tray.as_ref(), &input_actions, &sender),
+            slot(clock.as_ref(), &input_actions, &sender),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let mut plugins = plugins::launch_all(&config, runs);
+        plugins.retain(|(name, _)| {
+            let clashes = WIDGETS.contains(name);
+            if clashes {
+                eprintln!("Plugin {} has the name of a built-in widget", name);
+            }
+            !clashes
+        });
+        for (name, plugin) in &plugins {
+            let slot = widget_slot(name, &plugin.root(), &input_actions, &sender);
+            slots.insert(*name, slot);
+        }
+
+        // Every bar has a place for every widget, for the ones the primary
+        // bar runs on behalf of the others
+        let places: HashMap<_, _> = WIDGETS
+            .iter()
+            .chain(
+                plugins::names()
+                    .iter()
+                    .filter(|name| !WIDGETS.contains(name)),
+            )
+            .map(|name| (*name, gtk::Box::new(gtk::Orientation::Horizontal, 0)))
+            .collect();
+        for (name, place) in &places {
+            match slots.get(name) {
+                Some(slot) => place.append(slot),
+                None => place.set_visible(false),
+            }
+        }
+
+        let mut model = StatusBar {
+            window: root.clone(),
+            css_provider,
+            theme_config: config.theme.clone(),
+            bar_config: config.bar.clone(),
+            output: output.clone(),
+            hidden_for_fullscreen: false,
+            fullscreen_outputs: Vec::new(),
             workspace,
             window_title,
+            taskbar,
             media_player,
             resources,
+            disk_alert,
             wifi,
+            bluetooth,
+            volume,
+            brightness,
+            night_light,
+            microphone,
+            keyboard_layout,
+            input_method,
             battery,
+            break_reminder,
+            screen_time,
+            focus_timer,
+            time_tracking,
+            countdown,
+            weather,
+            ticker,
+            email,
+            vpn,
+            updates,
+            failed_units,
+            diagnostics,
+            notification_center,
+            privacy_indicator,
+            gamemode,
+            custom,
+            lua,
             tray,
             clock,
+            plugins,
+            slots,
+            places,
+            input_actions,
+            primary,
+            primary_sender,
+            secondary_bars: Vec::new(),
         };
 
-        let workspace_widget = model.workspace.widget();
-        let window_title_widget = model.window_title.widget();
-        let media_player_widget = model.media_player.widget();
-        let resources_widget = model.resources.widget();
-        let wifi_widget = model.wifi.widget();
-        let battery_widget = model.battery.widget();
-        let tray_widget = model.tray.widget();
-        let clock_widget = model.clock.widget();
+        let workspace_slot = &model.places[WorkspaceWidget::NAME];
+        let window_title_slot = &model.places[WindowTitle::NAME];
+        let taskbar_slot = &model.places[Taskbar::NAME];
+        let media_player_slot = &model.places[MediaPlayer::NAME];
+        let resources_slot = &model.places[Resources::NAME];
+        let disk_alert_slot = &model.places[DiskAlert::NAME];
+        let wifi_slot = &model.places[WiFi::NAME];
+        let bluetooth_slot = &model.places[Bluetooth::NAME];
+        let volume_slot = &model.places[Volume::NAME];
+        let brightness_slot = &model.places[Brightness::NAME];
+        let night_light_slot = &model.places[NightLight::NAME];
+        let microphone_slot = &model.places[Microphone::NAME];
+        let keyboard_layout_slot = &model.places[KeyboardLayout::NAME];
+        let input_method_slot = &model.places[InputMethod::NAME];
+        let battery_slot = &model.places[Battery::NAME];
+        let break_reminder_slot = &model.places[BreakReminder::NAME];
+        let screen_time_slot = &model.places[ScreenTime::NAME];
+        let focus_timer_slot = &model.places[FocusTimer::NAME];
+        let time_tracking_slot = &model.places[TimeTracking::NAME];
+        let countdown_slot = &model.places[Countdown::NAME];
+        let weather_slot = &model.places[Weather::NAME];
+        let ticker_slot = &model.places[Ticker::NAME];
+        let email_slot = &model.places[Email::NAME];
+        let vpn_slot = &model.places[Vpn::NAME];
+        let updates_slot = &model.places[Updates::NAME];
+        let failed_units_slot = &model.places[FailedUnits::NAME];
+        let diagnostics_slot = &model.places[Diagnostics::NAME];
+        let notification_center_slot = &model.places[NotificationCenter::NAME];
+        let privacy_indicator_slot = &model.places[PrivacyIndicator::NAME];
+        let gamemode_slot = &model.places[GameMode::NAME];
+        let custom_slot = &model.places[Custom::NAME];
+        let lua_slot = &model.places[LuaWidgets::NAME];
+        let tray_slot = &model.places[SystemTray::NAME];
+        let clock_slot = &model.places[Clock::NAME];
+        let bar_revealer = &motion::revealer(motion::bar_transition(config.bar.position));
         let widgets = view_output!();
 
+        // Plugins follow the built-in status widgets, before the tray
+        let mut previous = model.places[LuaWidgets::NAME].clone();
+        for name in plugins::names() {
+            let Some(place) = model.places.get(name).filter(|_| !WIDGETS.contains(&name)) else {
+                continue;
+            };
+            widgets
+                .right_section
+                .insert_child_after(place, Some(&previous));
+            previous = place.clone();
+        }
+
+        model.set_thickness(model.bar_config.height);
+        if model.bar_config.corner_radius > 0 {
+            root.add_css_class("floating");
+        }
+        let position = model.bar_config.position;
+        align_section(&widgets.left_section, gtk::Align::Start, position);
+        align_section(&widgets.right_section, gtk::Align::End, position);
+        if position.is_vertical() {
+            root.add_css_class("vertical");
+            for slot in model.slots.values() {
+                stack_vertically(slot.upcast_ref());
+            }
+        }
+
+        if primary {
+            model.serve(&config, &root, &sender);
+            model.secondary_bars = launch_secondary_bars(
+                &config,
+                &model.css_provider,
+                other_outputs,
+                &model.primary_sender,
+            );
+            model.place_shared_widgets(&config);
+        } else {
+            root.present();
+        }
+        if !headless::is_enabled() {
+            root.connect_map(|_| refresh_outputs_off());
+            root.connect_unmap(|_| refresh_outputs_off());
+        }
+
+        // Follow font, scaling and accessibility changes from the desktop
+        let mut portal_rx = portal::subscribe();
+        model.apply_accessibility(&portal_rx.borrow());
+        relm4::spawn(async move {
+            while portal_rx.changed().await.is_ok() {
+                let settings = portal_rx.borrow().clone();
+                sender.input(StatusBarMsg::PortalSettingsChanged(settings));
+            }
+        });
+
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, _msg: Self::Input, _sender: ComponentSender<Self>) {
-        // Message handling will go here
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            StatusBarMsg::PortalSettingsChanged(settings) => {
+                self.apply_accessibility(&settings);
+                self.apply_text_scaling(&settings);
+            }
+            StatusBarMsg::ContextAction(name, action) => match action {
+                ContextAction::Hide => {
+                    if let Some(slot) = self.slots.get(name) {
+                        set_slot_visible(slot, false);
+                    }
+                }
+                ContextAction::Settings => {
+                    let sender = self.primary_sender.clone();
+                    widgets::settings::open(name, move || {
+                        sender.emit(StatusBarMsg::ContextAction(name, ContextAction::Reload))
+                    });
+                }
+                // The primary bar reloads the widget on every bar
+                ContextAction::Reload if !self.primary => self
+                    .primary_sender
+                    .emit(StatusBarMsg::ContextAction(name, ContextAction::Reload)),
+                ContextAction::Reload => self.reload_everywhere(&[name], &sender),
+            },
+            StatusBarMsg::ReloadWidget(name) => self.reload_widget(name, &sender),
+            StatusBarMsg::Input(name, event) => {
+                let action = self
+                    .input_actions
+                    .borrow()
+                    .get(name)
+                    .and_then(|actions| event.action(actions))
+                    .map(str::to_string);
+                let Some(action) = action else {
+                    return;
+                };
+                if let Err(e) = self.run_input_action(name, &action, &sender) {
+                    eprintln!("Action '{}' of {}: {}", action, name, e);
+                }
+            }
+            StatusBarMsg::Ipc(command, responder) => {
+                responder.send(self.handle_ipc(command, &sender));
+            }
+            StatusBarMsg::Clock(ClockOutput::FocusStarted { minutes }) => {
+                if let Some(focus_timer) = &self.focus_timer {
+                    focus_timer.emit(FocusTimerMsg::StartSessionFor { minutes });
+                }
+            }
+            StatusBarMsg::Clock(ClockOutput::FocusEnded) => {
+                if let Some(focus_timer) = &self.focus_timer {
+                    focus_timer.emit(FocusTimerMsg::StopSession);
+                }
+            }
+            StatusBarMsg::QuickAction(action) => self.handle_quick_action(action),
+            StatusBarMsg::OutputsChanged => self.outputs_changed(&sender),
+            StatusBarMsg::FullscreenChanged(outputs) => self.fullscreen_changed(outputs),
+            StatusBarMsg::HeadlessDump => {
+                match serde_json::to_string_pretty(&self.dump_state()) {
+                    Ok(state) => println!("{}", state),
+                    Err(e) => eprintln!("Failed to serialize the state: {}", e),
+                }
+                relm4::main_application().quit();
+            }
+        }
     }
 }
 
-/// Compile SCSS to CSS at runtime
-fn compile_scss() -> Result<String, String> {
-    let scss_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
-        .join("theme")
-        .join("style.scss");
+impl StatusBar {
+    /// Start the app-wide services owned by the primary bar
+    fn serve(
+        &self,
+        config: &Config,
+        root: &gtk::ApplicationWindow,
+        sender: &ComponentSender<Self>,
+    ) {
+        if headless::is_enabled() {
+            let dump_sender = sender.clone();
+            gtk::glib::timeout_add_local_once(headless::SETTLE_TIME, move || {
+                dump_sender.input(StatusBarMsg::HeadlessDump)
+            });
+        } else {
+            // Accept commands from `statusbar msg` and keybinds
+            let ipc_sender = sender.clone();
+            ipc::listen(move |command, responder| {
+                ipc_sender.input(StatusBarMsg::Ipc(command, responder))
+            });
+            // The same commands for D-Bus clients
+            let bus_sender = sender.clone();
+            bar_service::serve(move |command, responder| {
+                bus_sender.input(StatusBarMsg::Ipc(command, responder))
+            });
 
-    grass::from_path(&scss_path, &grass::Options::default())
-        .map_err(|e| format!("Failed to compile SCSS:\n{}", e))
-}
+            // Follow outputs being plugged in when bars are placed per output
+            let display = gdk::Display::default().filter(|_| !config.outputs.is_empty());
+            if let Some(display) = display {
+                let outputs_sender = sender.clone();
+                display.monitors().connect_items_changed(move |_, _, _, _| {
+                    outputs_sender.input(StatusBarMsg::OutputsChanged)
+                });
+            }
+            // Bars may be left on an output that's gone
+            if let Some(display) = gdk::Display::default() {
+                display
+                    .monitors()
+                    .connect_items_changed(|_, _, _, _| refresh_outputs_off());
+            }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    gtk::init()?;
+            ask_for_missing_secrets();
+
+            // Passed on to the other bars, so the compositor is asked once
+            if config.bar.fullscreen != FullscreenMode::Show {
+                watch_fullscreen(sender.clone());
+            }
+        }
+
+        // Let another bar host our quick settings
+        if config.status_item.enabled {
+            let quick_sender = sender.clone();
+            status_item::serve(move |action| quick_sender.input(StatusBarMsg::QuickAction(action)));
+        }
+        if config.status_item.hide_bar {
+            // Hide once relm4 has presented the window
+            let window = root.clone();
+            gtk::glib::idle_add_local_once(move || window.set_visible(false));
+        }
+
+        if config.privacy.redact_while_sharing {
+            privacy::redact_while_sharing();
+        }
+    }
+
+    /// Move the bars to the outputs now connected
+    fn outputs_changed(&mut self, sender: &ComponentSender<Self>) {
+        let config = Config::load();
+        let Some(mut outputs) = bar_outputs(&config) else {
+            return;
+        };
+        // Shared widgets shown on the old bars come back to this one
+        for (name, slot) in &self.slots {
+            if is_shared(name) {
+                move_slot(slot, &self.places[name]);
+            }
+        }
+        for bar in self.secondary_bars.drain(..) {
+            bar.widget().destroy();
+        }
+        if outputs.is_empty() {
+            self.window.set_visible(false);
+            return;
+        }
+
+        // Remapping the surface is the only way to move it to another output
+        let output = outputs.remove(0);
+        self.window.set_visible(false);
+        self.window.set_monitor(Some(&output));
+        motion::set_window_visible(self.window.upcast_ref(), true);
+        self.hidden_for_fullscreen = false;
+        self.output = Some(output);
+
+        self.secondary_bars =
+            launch_secondary_bars(&config, &self.css_provider, outputs, &self.primary_sender);
+        for bar in &self.secondary_bars {
+            bar.emit(StatusBarMsg::FullscreenChanged(
+                self.fullscreen_outputs.clone(),
+            ));
+        }
+
+        // Start and stop the widgets the new outputs show or no longer show
+        let changed: Vec<&'static str> = self
+            .places
+            .keys()
+            .copied()
+            .filter(|name| self.runs(&config, name) != self.slots.contains_key(name))
+            .collect();
+        for name in changed {
+            self.reload_widget(name, sender);
+        }
+        self.place_shared_widgets(&config);
+    }
+
+    /// Whether this bar runs the widget `name` on its outputs, see
+    /// `runs_widget`
+    fn runs(&self, config: &Config, name: &str) -> bool {
+        let others: Vec<gdk::Monitor> = self
+            .secondary_bars
+            .iter()
+            .filter_map(|bar| bar.model().output.clone())
+            .collect();
+        runs_widget(config, name, self.primary, self.output.as_ref(), &others)
+    }
+
+    /// Put each shared widget the primary bar runs on the first bar showing it
+    fn place_shared_widgets(&self, config: &Config) {
+        for (name, slot) in &self.slots {
+            if !is_shared(name) {
+                continue;
+            }
+            let place = if output_shows(config, self.output.as_ref(), name) {
+                self.places.get(name).cloned()
+            } else {
+                self.secondary_bars
+                    .iter()
+                    .map(|bar| bar.model())
+                    .find(|bar| output_shows(config, bar.output.as_ref(), name))
+                    .and_then(|bar| bar.places.get(name).cloned())
+            };
+            match place {
+                Some(place) => move_slot(slot, &place),
+                None => take_slot(slot),
+            }
+        }
+    }
+
+    /// Reload widgets on every bar, re-reading the config
+    fn reload_everywhere(&mut self, names: &[&'static str], sender: &ComponentSender<Self>) {
+        for &name in names {
+            self.reload_widget(name, sender);
+            for bar in &self.secondary_bars {
+                bar.emit(StatusBarMsg::ReloadWidget(name));
+            }
+        }
+        self.place_shared_widgets(&Config::load());
+    }
+
+    /// Get out of the way of a fullscreen window on the bar's output
+    fn fullscreen_changed(&mut self, outputs: Vec<String>) {
+        for bar in &self.secondary_bars {
+            bar.emit(StatusBarMsg::FullscreenChanged(outputs.clone()));
+        }
+
+        // Bars placed by the compositor learn their output once mapped
+        if self.output.is_none() {
+            self.output = self
+                .window
+                .surface()
+                .and_then(|surface| gdk::Display::default()?.monitor_at_surface(&surface));
+        }
+        let fullscreen = self
+            .output
+            .as_ref()
+            .and_then(|output| output.connector())
+            .is_some_and(|connector| outputs.iter().any(|name| *name == connector));
 
-    // Compile SCSS to CSS at runtime
-    let css = match compile_scss() {
-        Ok(css) => css,
-        Err(e) => {
+        match self.bar_config.fullscreen {
+            FullscreenMode::Hide => {
+                if fullscreen && self.window.is_visible() {
+                    motion::set_window_visible(self.window.upcast_ref(), false);
+                    self.hidden_for_fullscreen = true;
+                } else if !fullscreen && self.hidden_for_fullscreen {
+                    motion::set_window_visible(self.window.upcast_ref(), true);
+                    self.hidden_for_fullscreen = false;
+                }
+            }
+            FullscreenMode::Overlay => {
+                if fullscreen {
+                    self.window.set_exclusive_zone(0);
+                } else if self.bar_config.exclusive_zone {
+                    self.window.auto_exclusive_zone_enable();
+                }
+            }
+            FullscreenMode::Show => {}
+        }
+        self.fullscreen_outputs = outputs;
+    }
+
+    /// Run an action from the `[actions]` config for a widget
+    fn run_input_action(
+        &mut self,
+        name: &'static str,
+        action: &str,
+        sender: &ComponentSender<Self>,
+    ) -> Result<(), String> {
+        if action == "popover" {
+            let popover = self
+                .slots
+                .get(name)
+                .and_then(|slot| Self::find_popover(slot.upcast_ref()))
+                .ok_or("the widget has no popover")?;
+            if popover.is_visible() {
+                popover.popdown();
+            } else {
+                Self::open_popover(&popover);
+            }
+            return Ok(());
+        }
+
+        let mut command = if let Some(command) = action.strip_prefix("exec ") {
+            let mut command_line = Command::new("sh");
+            command_line.arg("-c").arg(command);
+            command_line
+        } else if let Some(args) = action.strip_prefix("niri ") {
+            let mut command_line = Command::new("niri");
+            command_line
+                .args(["msg", "action"])
+                .args(args.split_whitespace());
+            command_line
+        } else {
+            let command = action.parse::<IpcCommand>()?;
+            return self.handle_ipc(command, sender).map(|_| ());
+        };
+
+        thread::spawn(move || match command.status() {
+            Ok(status) if !status.success() => eprintln!("{:?} exited with {}", command, status),
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to run {:?}: {}", command, e),
+        });
+        Ok(())
+    }
+
+    /// Re-read the config and relaunch a single widget in place
+    fn reload_widget(&mut self, name: &'static str, sender: &ComponentSender<Self>) {
+        let Some(place) = self.places.get(name).cloned() else {
+            return;
+        };
+        let config = Config::load();
+        let runs = self.runs(&config, name);
+        match config.actions.get(name) {
+            Some(actions) => {
+                let actions = actions.clone();
+                self.input_actions
+                    .borrow_mut()
+                    .insert(name.to_string(), actions);
+            }
+            None => {
+                self.input_actions.borrow_mut().remove(name);
+            }
+        }
+
+        let root = match name {
+            WorkspaceWidget::NAME => {
+                relaunch!(self.workspace, runs, WorkspaceWidget, config.workspace)
+            }
+            WindowTitle::NAME => {
+                relaunch!(self.window_title, runs, WindowTitle, config.window_title)
+            }
+            Taskbar::NAME => relaunch!(self.taskbar, runs, Taskbar, config.taskbar),
+            MediaPlayer::NAME => {
+                relaunch!(self.media_player, runs, MediaPlayer, config.media_player)
+            }
+            Resources::NAME => relaunch!(self.resources, runs, Resources, config.resources),
+            DiskAlert::NAME => relaunch!(self.disk_alert, runs, DiskAlert, config.disk_alert),
+            WiFi::NAME => relaunch!(self.wifi, runs, WiFi, config.wifi),
+            Bluetooth::NAME => relaunch!(self.bluetooth, runs, Bluetooth, config.bluetooth),
+            Volume::NAME => relaunch!(self.volume, runs, Volume, config.volume),
+            Brightness::NAME => relaunch!(self.brightness, runs, Brightness, config.brightness),
+            NightLight::NAME => relaunch!(self.night_light, runs, NightLight, config.night_light),
+            Microphone::NAME => relaunch!(self.microphone, runs, Microphone, config.microphone),
+            KeyboardLayout::NAME => {
+                relaunch!(
+                    self.keyboard_layout,
+                    runs,
+                    KeyboardLayout,
+                    config.keyboard_layout
+                )
+            }
+            InputMethod::NAME => {
+                relaunch!(self.input_method, runs, InputMethod, config.input_method)
+            }
+            Battery::NAME => relaunch!(self.battery, runs, Battery, config.battery),
+            BreakReminder::NAME => {
+                relaunch!(
+                    self.break_reminder,
+                    runs,
+                    BreakReminder,
+                    config.break_reminder
+                )
+            }
+            ScreenTime::NAME => relaunch!(self.screen_time, runs, ScreenTime, config.screen_time),
+            FocusTimer::NAME => relaunch!(self.focus_timer, runs, FocusTimer, config.focus),
+            TimeTracking::NAME => {
+                relaunch!(self.time_tracking, runs, TimeTracking, config.time_tracking)
+            }
+            Countdown::NAME => relaunch!(self.countdown, runs, Countdown, config.countdown),
+            Weather::NAME => relaunch!(self.weather, runs, Weather, config.weather),
+            Ticker::NAME => relaunch!(self.ticker, runs, Ticker, config.ticker),
+            Email::NAME => relaunch!(self.email, runs, Email, config.email),
+            Vpn::NAME => relaunch!(self.vpn, runs, Vpn, config.vpn),
+            Updates::NAME => relaunch!(self.updates, runs, Updates, config.updates),
+            FailedUnits::NAME => {
+                relaunch!(self.failed_units, runs, FailedUnits, config.failed_units)
+            }
+            Diagnostics::NAME => relaunch!(self.diagnostics, runs, Diagnostics, config.diagnostics),
+            NotificationCenter::NAME => relaunch!(
+                self.notification_center,
+                runs,
+                NotificationCenter,
+                config.notifications
+            ),
+            PrivacyIndicator::NAME => {
+                relaunch!(
+                    self.privacy_indicator,
+                    runs,
+                    PrivacyIndicator,
+                    config.privacy
+                )
+            }
+            GameMode::NAME => relaunch!(self.gamemode, runs, GameMode, config.gamemode),
+            Custom::NAME => relaunch!(self.custom, runs, Custom, (config.custom, config.scripts)),
+            LuaWidgets::NAME => relaunch!(self.lua, runs, LuaWidgets, (config.lua, config.scripts)),
+            SystemTray::NAME => relaunch!(self.tray, runs, SystemTray, ()),
+            Clock::NAME => relaunch!(
+                self.clock,
+                runs,
+                Self::launch_clock(config.clock, &self.primary_sender)
+            ),
+            _ => self.relaunch_plugin(name, runs, &config),
+        };
+
+        let Some(root) = root else {
+            // The bar no longer runs the widget
+            if let Some(slot) = self.slots.remove(name) {
+                take_slot(&slot);
+            }
+            return;
+        };
+        if self.bar_config.position.is_vertical() {
+            stack_vertically(&root);
+        }
+        match self.slots.get(name) {
+            Some(slot) => {
+                // The context menu is parented to the slot as well and stays
+                let mut child = slot.first_child();
+                while let Some(current) = child {
+                    child = current.next_sibling();
+                    if !current.is::<gtk::PopoverMenu>() {
+                        slot.remove(&current);
+                    }
+                }
+                slot.append(&root);
+            }
+            None => {
+                let slot = widget_slot(name, &root, &self.input_actions, sender);
+                if self.bar_config.position.is_vertical() {
+                    stack_vertically(slot.upcast_ref());
+                }
+                move_slot(&slot, &place);
+                self.slots.insert(name, slot);
+            }
+        }
+    }
+
+    /// Relaunch a plugin widget if the bar still runs it and return its new
+    /// root
+    fn relaunch_plugin(
+        &mut self,
+        name: &'static str,
+        runs: bool,
+        config: &Config,
+    ) -> Option<gtk::Widget> {
+        self.plugins.retain(|(plugin, _)| *plugin != name);
+        if !runs {
+            return None;
+        }
+        match plugins::launch(name, config) {
+            Ok(instance) => {
+                let root = instance.root();
+                self.plugins.push((name, instance));
+                Some(root)
+            }
+            Err(e) => {
+                eprintln!("Failed to relaunch {}: {}", name, e);
+                None
+            }
+        }
+    }
+
+    fn handle_quick_action(&self, action: QuickAction) {
+        match action {
+            QuickAction::TogglePomodoro => {
+                // Each bar showing the clock runs its own, the first one
+                // takes the pomodoro
+                let clock = self
+                    .clock
+                    .as_ref()
+                    .map(Controller::sender)
+                    .cloned()
+                    .or_else(|| {
+                        self.secondary_bars
+                            .iter()
+                            .find_map(|bar| Some(bar.model().clock.as_ref()?.sender().clone()))
+                    });
+                if let Some(clock) = clock {
+                    clock.emit(ClockMsg::TogglePomodoro);
+                }
+            }
+            QuickAction::StartFocus => {
+                if let Some(focus_timer) = &self.focus_timer {
+                    focus_timer.emit(FocusTimerMsg::StartSession);
+                }
+            }
+            QuickAction::StopFocus => {
+                if let Some(focus_timer) = &self.focus_timer {
+                    focus_timer.emit(FocusTimerMsg::StopSession);
+                }
+            }
+            QuickAction::StartBreak => {
+                if let Some(break_reminder) = &self.break_reminder {
+                    break_reminder.emit(BreakReminderMsg::StartBreak);
+                }
+            }
+            QuickAction::TogglePrivacy => privacy::toggle(),
+            QuickAction::OpenSettings => widgets::settings::open_config_file(),
+            QuickAction::ReloadTheme => self.reload_theme(),
+            QuickAction::Quit => relm4::main_application().quit(),
+        }
+    }
+
+    /// Anchor the bar to its configured edge as a layer shell surface
+    fn init_layer_shell(
+        root: &gtk::ApplicationWindow,
+        config: &BarConfig,
+        output: Option<&gdk::Monitor>,
+    ) {
+        // Initialize layer shell BEFORE window is realized
+        root.init_layer_shell();
+        if let Some(output) = output {
+            root.set_monitor(Some(output));
+        }
+
+        // Configure layer shell properties
+        root.set_layer(Layer::Overlay);
+        root.set_namespace(Some(NAMESPACE));
+        if config.exclusive_zone {
+            root.auto_exclusive_zone_enable();
+        }
+
+        // Anchor to every edge but the opposite one to span the bar's edge
+        let opposite = match config.position {
+            BarPosition::Top => Edge::Bottom,
+            BarPosition::Bottom => Edge::Top,
+            BarPosition::Left => Edge::Right,
+            BarPosition::Right => Edge::Left,
+        };
+        for side in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+            root.set_anchor(side, side != opposite);
+        }
+
+        // Gaps to the screen edges, for floating bars
+        root.set_margin(Edge::Top, config.margin.top);
+        root.set_margin(Edge::Bottom, config.margin.bottom);
+        root.set_margin(Edge::Left, config.margin.left);
+        root.set_margin(Edge::Right, config.margin.right);
+    }
+
+    /// The clock drives focus sessions from its pomodoro, with the focus
+    /// timer of the primary bar
+    fn launch_clock(
+        config: ClockConfig,
+        sender: &relm4::Sender<StatusBarMsg>,
+    ) -> Controller<Clock> {
+        Clock::builder()
+            .launch(config)
+            .forward(sender, StatusBarMsg::Clock)
+    }
+
+    fn handle_ipc(&mut self, command: IpcCommand, sender: &ComponentSender<Self>) -> IpcResult {
+        let known_widget = |widget: &str| {
+            self.places
+                .get_key_value(widget)
+                .map(|(name, _)| *name)
+                .ok_or_else(|| format!("Unknown widget '{}'", widget))
+        };
+
+        match command {
+            IpcCommand::CyclePopover => self.cycle_popover(),
+            IpcCommand::DumpState | IpcCommand::GetState { widget: None } => {
+                return Ok(self.dump_state());
+            }
+            IpcCommand::GetState {
+                widget: Some(widget),
+            } => {
+                let name = known_widget(&widget)?;
+                return Ok(self.dump_state()[name].take());
+            }
+            IpcCommand::SetWidgetVisible { widget, visible } => {
+                let name = known_widget(&widget)?;
+                let slot = self
+                    .find_slot(name)
+                    .ok_or_else(|| format!("'{}' isn't on any bar", widget))?;
+                set_slot_visible(&slot, visible);
+            }
+            IpcCommand::ReloadWidget { widget } => {
+                let name = known_widget(&widget)?;
+                self.reload_everywhere(&[name], sender);
+            }
+            IpcCommand::ToggleVisibility => self.set_visible(!self.window.is_visible()),
+            IpcCommand::SetVisible(visible) => self.set_visible(visible),
+            IpcCommand::ReloadConfig => {
+                let names: Vec<&'static str> = self.places.keys().copied().collect();
+                self.reload_everywhere(&names, sender);
+            }
+            IpcCommand::OpenPopover { widget } => {
+                let name = known_widget(&widget)?;
+                let popover = self
+                    .find_slot(name)
+                    .and_then(|slot| Self::find_popover(slot.upcast_ref()))
+                    .ok_or_else(|| format!("'{}' has no popover", widget))?;
+                Self::open_popover(&popover);
+            }
+            IpcCommand::SetDoNotDisturb(enabled) => notifications::set_do_not_disturb(enabled),
+            IpcCommand::Trigger(action) => self.handle_quick_action(action),
+        }
+
+        Ok(serde_json::Value::Null)
+    }
+
+    /// Snapshot of every widget on any bar, keyed by `ContextMenu::NAME`
+    fn dump_state(&self) -> serde_json::Value {
+        let mut widgets = self.own_state();
+        for bar in &self.secondary_bars {
+            for (name, state) in bar.model().own_state() {
+                widgets.entry(name).or_insert(state);
+            }
+        }
+        serde_json::Value::Object(widgets)
+    }
+
+    /// Snapshot of the widgets this bar runs
+    fn own_state(&self) -> serde_json::Map<String, serde_json::Value> {
+        fn entry<W>(controller: Option<&Controller<W>>) -> Option<(String, serde_json::Value)>
+        where
+            W: Component + ContextMenu + Snapshot,
+        {
+            Some((W::NAME.to_string(), controller?.model().snapshot()))
+        }
+
+        [
+            entry(self.workspace.as_ref()),
+            entry(self.window_title.as_ref()),
+            entry(self.taskbar.as_ref()),
+            entry(self.media_player.as_ref()),
+            entry(self.resources.as_ref()),
+            entry(self.disk_alert.as_ref()),
+            entry(self.wifi.as_ref()),
+            entry(self.bluetooth.as_ref()),
+            entry(self.volume.as_ref()),
+            entry(self.brightness.as_ref()),
+            entry(self.night_light.as_ref()),
+            entry(self.microphone.as_ref()),
+            entry(self.keyboard_layout.as_ref()),
+            entry(self.input_method.as_ref()),
+            entry(self.battery.as_ref()),
+            entry(self.break_reminder.as_ref()),
+            entry(self.screen_time.as_ref()),
+            entry(self.focus_timer.as_ref()),
+            entry(self.time_tracking.as_ref()),
+            entry(self.countdown.as_ref()),
+            entry(self.weather.as_ref()),
+            entry(self.ticker.as_ref()),
+            entry(self.email.as_ref()),
+            entry(self.vpn.as_ref()),
+            entry(self.updates.as_ref()),
+            entry(self.failed_units.as_ref()),
+            entry(self.diagnostics.as_ref()),
+            entry(self.notification_center.as_ref()),
+            entry(self.privacy_indicator.as_ref()),
+            entry(self.gamemode.as_ref()),
+            entry(self.custom.as_ref()),
+            entry(self.lua.as_ref()),
+            entry(self.tray.as_ref()),
+            entry(self.clock.as_ref()),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(
+            self.plugins
+                .iter()
+                .map(|(name, plugin)| (name.to_string(), plugin.snapshot())),
+        )
+        .collect()
+    }
+
+    /// Close the open popover and open the next one shown in the bar
+    fn cycle_popover(&self) {
+        let popovers: Vec<gtk::Popover> = POPOVER_CYCLE
+            .iter()
+            .filter_map(|name| self.find_slot(name))
+            .filter_map(|slot| Self::find_popover(slot.upcast_ref()))
+            .collect();
+
+        let open = popovers.iter().position(|popover| popover.is_visible());
+        if let Some(index) = open {
+            popovers[index].popdown();
+        }
+
+        // Click the owning button so widgets refresh just like on a real click
+        let start = open.map_or(0, |index| index + 1);
+        let end = open.unwrap_or(popovers.len());
+        let next = (start..popovers.len())
+            .chain(0..end)
+            .filter_map(|index| popovers[index].parent()?.downcast::<gtk::Button>().ok())
+            .find(|button| button.is_drawable());
+
+        if let Some(button) = next {
+            button.emit_clicked();
+        }
+    }
+
+    /// The slot of `name` on this bar or, from the primary bar, any other
+    fn find_slot(&self, name: &str) -> Option<gtk::Box> {
+        self.slots.get(name).cloned().or_else(|| {
+            self.secondary_bars
+                .iter()
+                .find_map(|bar| bar.model().slots.get(name).cloned())
+        })
+    }
+
+    /// Show or hide the bars on all outputs
+    fn set_visible(&self, visible: bool) {
+        power::set_hidden(!visible);
+        motion::set_window_visible(self.window.upcast_ref(), visible);
+        for bar in &self.secondary_bars {
+            motion::set_window_visible(bar.widget().upcast_ref(), visible);
+        }
+    }
+
+    /// Open a widget's popover unless it already is
+    fn open_popover(popover: &gtk::Popover) {
+        if popover.is_visible() {
+            return;
+        }
+        // Click the owning button so widgets refresh just like on a real click
+        match popover.parent().and_downcast::<gtk::Button>() {
+            Some(button) => button.emit_clicked(),
+            None => popover.popup(),
+        }
+    }
+
+    /// A widget's own popover, skipping the context menu on its slot
+    fn find_popover(widget: &gtk::Widget) -> Option<gtk::Popover> {
+        let mut child = widget.first_child();
+        while let Some(current) = child {
+            if let Some(popover) = current.downcast_ref::<gtk::Popover>() {
+                if !current.is::<gtk::PopoverMenu>() {
+                    return Some(popover.clone());
+                }
+            } else if let Some(popover) = Self::find_popover(&current) {
+                return Some(popover);
+            }
+            child = current.next_sibling();
+        }
+        None
+    }
+
+    fn apply_accessibility(&self, settings: &PortalSettings) {
+        let high_contrast = self
+            .theme_config
+            .high_contrast
+            .resolve(settings.high_contrast);
+        let reduce_motion = self
+            .theme_config
+            .reduce_motion
+            .resolve(settings.reduce_motion);
+
+        Self::set_css_class(&self.window, "high-contrast", high_contrast);
+        Self::set_css_class(&self.window, "reduce-motion", reduce_motion);
+        let animated = motion::enabled() && !reduce_motion;
+        Self::set_css_class(&self.window, "animated", animated);
+
+        // The theme is recompiled with the matching palette as well, the
+        // classes are there for user styles
+        let light = self
+            .theme_config
+            .color_scheme
+            .is_light(settings.prefer_light);
+        Self::set_css_class(&self.window, "light", light);
+        Self::set_css_class(&self.window, "dark", !light);
+
+        if let Some(gtk_settings) = gtk::Settings::default() {
+            gtk_settings.set_gtk_enable_animations(!reduce_motion);
+        }
+    }
+
+    fn apply_text_scaling(&self, settings: &PortalSettings) {
+        let scale = settings.text_scaling_factor;
+
+        if let Some(gtk_settings) = gtk::Settings::default() {
+            gtk_settings.set_gtk_xft_dpi((BASE_DPI * scale * 1024.0).round() as i32);
+            if let Some(font_name) = &settings.font_name {
+                gtk_settings.set_gtk_font_name(Some(font_name));
+            }
+        }
+
+        self.set_thickness((self.bar_config.height as f64 * scale).round() as i32);
+
+        // Recompile so rem-based sizes pick up the new font metrics, once for
+        // all bars sharing the provider
+        if !self.primary {
+            return;
+        }
+        self.reload_theme();
+    }
+
+    fn reload_theme(&self) {
+        if let Err(e) = theme::load(&self.css_provider, &self.theme_config, self.is_light()) {
             eprintln!("{}", e);
-            std::process::exit(1);
         }
-    };
+    }
+
+    /// Whether the light palette is in use, from the config or the desktop
+    fn is_light(&self) -> bool {
+        let prefer_light = portal::subscribe().borrow().prefer_light;
+        self.theme_config.color_scheme.is_light(prefer_light)
+    }
+
+    /// Size the bar across its edge, its height or width when vertical
+    fn set_thickness(&self, thickness: i32) {
+        if self.bar_config.position.is_vertical() {
+            self.window.set_width_request(thickness);
+            self.window.set_default_width(thickness);
+        } else {
+            self.window.set_height_request(thickness);
+            self.window.set_default_height(thickness);
+        }
+    }
+
+    fn set_css_class(widget: &impl IsA<gtk::Widget>, class: &str, enabled: bool) {
+        if enabled {
+            widget.add_css_class(class);
+        } else {
+            widget.remove_css_class(class);
+        }
+    }
+}
+
+fn orientation(position: BarPosition) -> gtk::Orientation {
+    if position.is_vertical() {
+        gtk::Orientation::Vertical
+    } else {
+        gtk::Orientation::Horizontal
+    }
+}
+
+/// Push a bar section to one end of the bar, filling it crosswise
+fn align_section(section: &gtk::Box, align: gtk::Align, position: BarPosition) {
+    if position.is_vertical() {
+        section.set_vexpand(true);
+        section.set_valign(align);
+    } else {
+        section.set_hexpand(true);
+        section.set_halign(align);
+    }
+}
+
+/// Stack a widget's boxes vertically and let text labels wrap within the
+/// bar's width, so icons stay as they are and longer text collapses to a
+/// couple of short lines
+fn stack_vertically(widget: &gtk::Widget) {
+    // Popovers keep their own layout
+    if widget.is::<gtk::Popover>() {
+        return;
+    }
+    if let Some(container) = widget.downcast_ref::<gtk::Box>() {
+        container.set_orientation(gtk::Orientation::Vertical);
+    }
+    if let Some(label) = widget.downcast_ref::<gtk::Label>() {
+        label.set_max_width_chars(VERTICAL_LABEL_CHARS);
+        label.set_wrap(true);
+        label.set_wrap_mode(gtk::pango::WrapMode::WordChar);
+        label.set_lines(2);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        label.set_justify(gtk::Justification::Center);
+    }
+
+    let mut child = widget.first_child();
+    while let Some(current) = child {
+        stack_vertically(&current);
+        child = current.next_sibling();
+    }
+}
+
+/// Report the outputs showing a fullscreen window whenever they change
+fn watch_fullscreen(sender: ComponentSender<StatusBar>) {
+    let mut events = compositor::subscribe();
+    thread::spawn(move || {
+        let mut last = None;
+        while let Some(event) = events.blocking_next() {
+            if matches!(
+                event,
+                Event::KeyboardLayoutsChanged(_)
+                    | Event::KeyboardLayoutSwitched(_)
+                    | Event::WorkspaceUrgencyChanged { .. }
+            ) {
+                continue;
+            }
+            let Ok(outputs) = compositor::fullscreen_outputs() else {
+                continue;
+            };
+            if last.as_ref() != Some(&outputs) {
+                last = Some(outputs.clone());
+                sender.input(StatusBarMsg::FullscreenChanged(outputs));
+            }
+        }
+    });
+}
+
+/// Ask once for each credential a widget looked up but found missing, e.g.
+/// the IMAP password on first run
+fn ask_for_missing_secrets() {
+    let mut missing = secrets::subscribe_missing();
+    gtk::glib::spawn_future_local(async move {
+        let mut asked = HashSet::new();
+        while missing.changed().await.is_ok() {
+            let accounts = missing.borrow_and_update().clone();
+            for account in accounts {
+                if asked.insert(account.clone()) {
+                    widgets::settings::ask_secret(&account);
+                }
+            }
+        }
+    });
+}
+
+/// Connected outputs that get a bar, in the display's order, or `None` when
+/// bars aren't configured per output
+fn bar_outputs(config: &Config) -> Option<Vec<gdk::Monitor>> {
+    if config.outputs.is_empty() {
+        return None;
+    }
+    let monitors = gdk::Display::default()?.monitors();
+    let outputs = (0..monitors.n_items())
+        .filter_map(|index| monitors.item(index).and_downcast::<gdk::Monitor>())
+        .filter(|output| output_config(config, output).is_none_or(|output| output.enabled))
+        .collect();
+    Some(outputs)
+}
+
+/// Pause polling while no bar is mapped on a connected output
+///
+/// Outputs that are unplugged or disabled close the bars on them. Outputs
+/// merely blanked by DPMS keep their bars mapped, GTK can't tell them apart.
+fn refresh_outputs_off() {
+    // Unmapped windows are only flagged after the signal handlers ran
+    gtk::glib::idle_add_local_once(|| {
+        let Some(display) = gdk::Display::default() else {
+            return;
+        };
+        let on_screen = relm4::main_application()
+            .windows()
+            .iter()
+            .filter(|window| window.is_mapped())
+            .filter(|window| window.namespace().as_deref() == Some(NAMESPACE))
+            .filter_map(|window| display.monitor_at_surface(&window.surface()?))
+            .any(|output| output.is_valid());
+        power::set_outputs_off(!on_screen);
+    });
+}
+
+fn output_config<'a>(config: &'a Config, output: &gdk::Monitor) -> Option<&'a OutputConfig> {
+    config.outputs.get(output.connector()?.as_str())
+}
 
-    #[cfg(debug_assertions)]
-    std::fs::write("./final.css", &css)?;
+/// Whether the bar on `output` shows the widget `name`
+fn output_shows(config: &Config, output: Option<&gdk::Monitor>, name: &str) -> bool {
+    output
+        .and_then(|output| output_config(config, output))
+        .and_then(|output| output.widgets.as_ref())
+        .is_none_or(|names| names.iter().any(|shown| shown == name))
+}
+
+/// Whether one instance of `name` serves every bar, for the `RUN_ONCE`
+/// widgets and plugins
+fn is_shared(name: &str) -> bool {
+    RUN_ONCE.contains(&name) || !WIDGETS.contains(&name)
+}
+
+/// Whether a bar runs the widget `name`: the ones its output shows, and on
+/// the primary bar the shared ones shown on any of the `others` as well
+fn runs_widget(
+    config: &Config,
+    name: &str,
+    primary: bool,
+    output: Option<&gdk::Monitor>,
+    others: &[gdk::Monitor],
+) -> bool {
+    if !is_shared(name) {
+        return output_shows(config, output, name);
+    }
+    primary
+        && (output_shows(config, output, name)
+            || others
+                .iter()
+                .any(|other| output_shows(config, Some(other), name)))
+}
+
+/// Take a slot out of the bar place holding it, hiding the empty place
+fn take_slot(slot: &gtk::Box) {
+    if let Some(place) = slot.parent().and_downcast::<gtk::Box>() {
+        place.remove(slot);
+        place.set_visible(false);
+    }
+}
+
+/// Move a slot into a bar place, e.g. a shared widget to another bar
+fn move_slot(slot: &gtk::Box, place: &gtk::Box) {
+    if slot.parent().as_ref() != Some(place.upcast_ref()) {
+        take_slot(slot);
+        place.append(slot);
+    }
+    place.set_visible(slot.is_visible());
+}
+
+/// Show or hide a widget, along with its place so the bar's spacing closes
+fn set_slot_visible(slot: &gtk::Box, visible: bool) {
+    slot.set_visible(visible);
+    if let Some(place) = slot.parent() {
+        place.set_visible(visible);
+    }
+}
+
+/// Launch a bar on each of `outputs` besides the primary one
+fn launch_secondary_bars(
+    config: &Config,
+    css_provider: &gtk::CssProvider,
+    outputs: Vec<gdk::Monitor>,
+    primary_sender: &relm4::Sender<StatusBarMsg>,
+) -> Vec<Controller<StatusBar>> {
+    outputs
+        .into_iter()
+        .map(|output| {
+            StatusBar::builder()
+                .launch(StatusBarInit {
+                    config: config.clone(),
+                    css_provider: css_provider.clone(),
+                    output: Some(output),
+                    primary_bar: Some(primary_sender.clone()),
+                })
+                .detach()
+        })
+        .collect()
+}
+
+/// Build the context-menu slot for a launched widget, with the click and
+/// scroll actions configured for it
+fn slot<W>(
+    controller: Option<&Controller<W>>,
+    input_actions: &Rc<RefCell<HashMap<String, WidgetActions>>>,
+    sender: &ComponentSender<StatusBar>,
+) -> Option<(&'static str, gtk::Box)>
+where
+    W: Component + ContextMenu,
+    W::Root: IsA<gtk::Widget>,
+{
+    let root = controller?.widget().clone().upcast::<gtk::Widget>();
+    Some((W::NAME, widget_slot(W::NAME, &root, input_actions, sender)))
+}
+
+/// `slot` for a widget known by name only, like a plugin
+fn widget_slot(
+    name: &'static str,
+    root: &gtk::Widget,
+    input_actions: &Rc<RefCell<HashMap<String, WidgetActions>>>,
+    sender: &ComponentSender<StatusBar>,
+) -> gtk::Box {
+    let menu_sender = sender.clone();
+    let slot = widgets::context_menu::slot(root, move |action| {
+        menu_sender.input(StatusBarMsg::ContextAction(name, action))
+    });
+
+    let input_actions = input_actions.clone();
+    let sender = sender.clone();
+    widgets::context_menu::bind_input(&slot, move |event| {
+        let bound = input_actions
+            .borrow()
+            .get(name)
+            .is_some_and(|actions| event.action(actions).is_some());
+        if bound {
+            sender.input(StatusBarMsg::Input(name, event));
+        }
+        bound
+    });
+    slot
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Subcommands talk to the running bar instead of starting one
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("msg") => {
+            let reply = ipc::send(&args[2..].join(" "))?;
+            println!("{}", reply);
+            return Ok(());
+        }
+        Some("dump-state") => {
+            let reply = ipc::send("dump-state")?;
+            let state: serde_json::Value =
+                serde_json::from_str(&reply).map_err(|_| reply.clone())?;
+            println!("{}", serde_json::to_string_pretty(&state)?);
+            return Ok(());
+        }
+        // Take over from a running bar, e.g. after rebuilding it
+        Some("--replace") if ipc::is_running() => ipc::replace()?,
+        Some("--headless-test") => {
+            headless::enable();
+            if std::env::var_os("GDK_BACKEND").is_none() {
+                // SAFETY: no other threads have been started yet
+                unsafe { std::env::set_var("GDK_BACKEND", "broadway") };
+            }
+        }
+        _ => {}
+    }
+
+    // A headless run sits next to the real bar instead of fighting it
+    if !headless::is_enabled() && ipc::is_running() {
+        eprintln!("statusbar is already running; pass --replace to take over");
+        std::process::exit(1);
+    }
 
-    // Load compiled CSS
+    gtk::init()?;
+
+    let config = Config::load();
+
+    // Compile SCSS to CSS at runtime and load it; the desktop's color scheme
+    // is only known once the portal answers, which reloads it if needed
     let css_provider = gtk::CssProvider::new();
-    css_provider.load_from_data(&css);
+    if let Err(e) = theme::load(
+        &css_provider,
+        &config.theme,
+        config.theme.color_scheme.is_light(false),
+    ) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
+    let display = gtk::gdk::Display::default().expect("Could not connect to display");
     gtk::style_context_add_provider_for_display(
-        &gtk::gdk::Display::default().expect("Could not connect to display"),
+        &display,
         &css_provider,
         gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
     );
 
-    let app = RelmApp::new(APP_ID);
-    app.run::<StatusBar>(());
+    // Shape settings from the config go on top of the theme
+    let bar_provider = gtk::CssProvider::new();
+    bar_provider.load_from_data(&theme::bar_css(&config.bar));
+    gtk::style_context_add_provider_for_display(
+        &display,
+        &bar_provider,
+        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+    );
+
+    let app = if headless::is_enabled() {
+        // Run alongside a real bar instead of handing over to it
+        let application = gtk::Application::builder()
+            .application_id(APP_ID)
+            .flags(gtk::gio::ApplicationFlags::NON_UNIQUE)
+            .build();
+        RelmApp::from_app(application).with_args(Vec::new())
+    } else {
+        RelmApp::new(APP_ID)
+    };
+    app.run::<StatusBar>(StatusBarInit {
+        config,
+        css_provider,
+        output: None,
+        primary_bar: None,
+    });
+
+    // Restore normal colors instead of leaving the filter running
+    night_light::stop();
 
     Ok(())
 }
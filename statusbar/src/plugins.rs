@@ -0,0 +1,51 @@
+// Widgets from other crates, compiled in through cargo features
+//
+// A plugin crate exposes a `statusbar_plugin::Registration`, listed in
+// `registry` behind the feature pulling the crate in:
+//
+//     #[cfg(feature = "uptime")]
+//     statusbar_uptime::registration(),
+use statusbar_plugin::{Instance, Registration};
+
+use crate::config::Config;
+
+/// Every plugin compiled into this build
+fn registry() -> Vec<Registration> {
+    vec![]
+}
+
+/// Names of the plugins compiled into this build
+pub fn names() -> Vec<&'static str> {
+    registry()
+        .iter()
+        .map(|registration| registration.name)
+        .collect()
+}
+
+/// Launch the plugins `runs` picks with their config sections, skipping ones
+/// that fail
+pub fn launch_all(config: &Config, runs: impl Fn(&str) -> bool) -> Vec<(&'static str, Instance)> {
+    registry()
+        .into_iter()
+        .filter(|registration| runs(registration.name))
+        .filter_map(|registration| {
+            let section = config.plugins.get(registration.name).cloned();
+            match registration.launch(section) {
+                Ok(instance) => Some((registration.name, instance)),
+                Err(e) => {
+                    eprintln!("Failed to launch {}: {}", registration.name, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Launch the plugin called `name` again, e.g. after its section changed
+pub fn launch(name: &str, config: &Config) -> Result<Instance, String> {
+    let registration = registry()
+        .into_iter()
+        .find(|registration| registration.name == name)
+        .ok_or_else(|| format!("Unknown plugin '{}'", name))?;
+    registration.launch(config.plugins.get(name).cloned())
+}
@@ -0,0 +1,338 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use tokio::sync::watch;
+
+/// Volume at 100%, as defined by PulseAudio
+const VOLUME_NORM: u64 = 65536;
+
+/// Volume of one channel, e.g. `front-left`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChannelVolume {
+    pub value: u32,
+}
+
+/// Average volume over all channels in percent
+fn volume_percent(volume: &HashMap<String, ChannelVolume>) -> u32 {
+    if volume.is_empty() {
+        return 0;
+    }
+    let total: u64 = volume.values().map(|channel| channel.value as u64).sum();
+    let average = total / volume.len() as u64;
+    ((average * 100 + VOLUME_NORM / 2) / VOLUME_NORM) as u32
+}
+
+/// An output device
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Sink {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub mute: bool,
+    #[serde(default)]
+    pub volume: HashMap<String, ChannelVolume>,
+}
+
+impl Sink {
+    pub fn volume_percent(&self) -> u32 {
+        volume_percent(&self.volume)
+    }
+}
+
+/// A playback stream of an application
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SinkInput {
+    pub index: u32,
+    /// Index of the sink the stream plays to
+    pub sink: u32,
+    #[serde(default)]
+    pub mute: bool,
+    #[serde(default)]
+    pub volume: HashMap<String, ChannelVolume>,
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+impl SinkInput {
+    pub fn volume_percent(&self) -> u32 {
+        volume_percent(&self.volume)
+    }
+
+    pub fn app_name(&self) -> &str {
+        self.app_names().next().unwrap_or("Unknown app")
+    }
+
+    /// Icon name or binary to look the app icon up by
+    pub fn icon_name(&self) -> Option<&str> {
+        ["application.icon_name", "application.process.binary"]
+            .into_iter()
+            .find_map(|key| self.properties.get(key).map(String::as_str))
+    }
+
+    /// What the stream plays, e.g. a tab or track title
+    pub fn media_name(&self) -> Option<&str> {
+        self.properties.get("media.name").map(String::as_str)
+    }
+
+    pub fn process_id(&self) -> Option<u32> {
+        self.properties.get("application.process.id")?.parse().ok()
+    }
+
+    /// Names the application reports for itself, e.g. `Firefox` and `firefox`
+    pub fn app_names(&self) -> impl Iterator<Item = &str> {
+        ["application.name", "application.process.binary"]
+            .into_iter()
+            .filter_map(|key| self.properties.get(key).map(String::as_str))
+    }
+}
+
+/// An input device, or the monitor of an output device
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Source {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub mute: bool,
+}
+
+impl Source {
+    /// Monitors capture what a sink plays rather than a microphone
+    pub fn is_monitor(&self) -> bool {
+        self.name.ends_with(".monitor")
+    }
+}
+
+/// A recording stream of an application
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SourceOutput {
+    pub index: u32,
+    /// Index of the source the stream records from
+    pub source: u32,
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+impl SourceOutput {
+    pub fn app_name(&self) -> Option<&str> {
+        ["application.name", "application.process.binary"]
+            .into_iter()
+            .find_map(|key| self.properties.get(key).map(String::as_str))
+    }
+}
+
+/// Sinks, sources and streams of the PulseAudio (or PipeWire) server
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioState {
+    pub sinks: Vec<Sink>,
+    pub sink_inputs: Vec<SinkInput>,
+    pub sources: Vec<Source>,
+    pub source_outputs: Vec<SourceOutput>,
+    /// Name of the sink used when apps don't pick one
+    pub default_sink: Option<String>,
+    /// Name of the source used when apps don't pick one
+    pub default_source: Option<String>,
+}
+
+impl AudioState {
+    pub fn sink(&self, index: u32) -> Option<&Sink> {
+        self.sinks.iter().find(|sink| sink.index == index)
+    }
+
+    pub fn default_sink(&self) -> Option<&Sink> {
+        let name = self.default_sink.as_deref()?;
+        self.sinks.iter().find(|sink| sink.name == name)
+    }
+
+    pub fn default_source(&self) -> Option<&Source> {
+        let name = self.default_source.as_deref()?;
+        self.sources.iter().find(|source| source.name == name)
+    }
+
+    /// Apps recording from a microphone, without those capturing a monitor
+    pub fn recording_apps(&self) -> Vec<&str> {
+        let mut apps: Vec<&str> = self
+            .source_outputs
+            .iter()
+            .filter(|output| {
+                self.sources
+                    .iter()
+                    .any(|source| source.index == output.source && !source.is_monitor())
+            })
+            .map(|output| output.app_name().unwrap_or("Unknown app"))
+            .collect();
+        apps.sort_unstable();
+        apps.dedup();
+        apps
+    }
+
+    /// Sink an application plays to, matched by process id, then by name
+    ///
+    /// Browsers play from helper processes, so the name is needed as well.
+    pub fn sink_for_app(&self, pid: Option<u32>, names: &[&str]) -> Option<&Sink> {
+        let by_pid = pid.and_then(|pid| {
+            self.sink_inputs
+                .iter()
+                .find(|input| input.process_id() == Some(pid))
+        });
+        let input = by_pid.or_else(|| {
+            self.sink_inputs.iter().find(|input| {
+                input.app_names().any(|app| {
+                    let app = app.to_lowercase();
+                    names.iter().any(|name| name.to_lowercase().contains(&app))
+                })
+            })
+        })?;
+        self.sink(input.sink)
+    }
+}
+
+static AUDIO_STATE: OnceLock<watch::Receiver<AudioState>> = OnceLock::new();
+
+/// Subscribe to sink and stream changes, starting the watcher on first use
+pub fn subscribe() -> watch::Receiver<AudioState> {
+    AUDIO_STATE
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(AudioState::default());
+            thread::spawn(move || {
+                if let Err(e) = watch_audio(tx) {
+                    eprintln!("Audio service error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+fn watch_audio(tx: watch::Sender<AudioState>) -> Result<(), String> {
+    publish(&tx, query()?);
+
+    let mut child = Command::new("pactl")
+        .arg("subscribe")
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run pactl: {}", e))?;
+    let stdout = child.stdout.take().ok_or("pactl has no stdout")?;
+
+    // Lines look like "Event 'change' on sink-input #42"
+    for line in BufReader::new(stdout).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        // Default device changes are reported as server changes
+        if [" on sink", " on source", " on server"]
+            .iter()
+            .any(|kind| line.contains(kind))
+        {
+            publish(&tx, query()?);
+        }
+    }
+
+    let _ = child.wait();
+    Err("pactl subscribe exited".to_string())
+}
+
+fn publish(tx: &watch::Sender<AudioState>, state: AudioState) {
+    tx.send_if_modified(|current| {
+        if *current == state {
+            return false;
+        }
+        *current = state;
+        true
+    });
+}
+
+fn query() -> Result<AudioState, String> {
+    Ok(AudioState {
+        sinks: pactl_list("sinks")?,
+        sink_inputs: pactl_list("sink-inputs")?,
+        sources: pactl_list("sources")?,
+        source_outputs: pactl_list("source-outputs")?,
+        default_sink: default_device("sink"),
+        default_source: default_device("source"),
+    })
+}
+
+/// Name of the default sink or source
+fn default_device(kind: &str) -> Option<String> {
+    pactl(&[&format!("get-default-{}", kind)])
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// A sink or playback stream whose volume can be changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stream {
+    Sink(u32),
+    SinkInput(u32),
+}
+
+impl Stream {
+    /// pactl object name and index, e.g. `sink-input` and `42`
+    fn target(self) -> (&'static str, String) {
+        match self {
+            Stream::Sink(index) => ("sink", index.to_string()),
+            Stream::SinkInput(index) => ("sink-input", index.to_string()),
+        }
+    }
+}
+
+/// Set the volume of all channels of a stream
+pub fn set_volume(stream: Stream, percent: u32) -> Result<(), String> {
+    let (kind, index) = stream.target();
+    pactl(&[
+        &format!("set-{}-volume", kind),
+        &index,
+        &format!("{}%", percent),
+    ])?;
+    Ok(())
+}
+
+pub fn set_mute(stream: Stream, mute: bool) -> Result<(), String> {
+    let (kind, index) = stream.target();
+    pactl(&[
+        &format!("set-{}-mute", kind),
+        &index,
+        if mute { "1" } else { "0" },
+    ])?;
+    Ok(())
+}
+
+/// Mute or unmute the default source, e.g. the microphone
+pub fn set_default_source_mute(mute: bool) -> Result<(), String> {
+    pactl(&[
+        "set-source-mute",
+        "@DEFAULT_SOURCE@",
+        if mute { "1" } else { "0" },
+    ])?;
+    Ok(())
+}
+
+/// Run pactl and return its output
+fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run pactl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "pactl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn pactl_list<T: for<'de> Deserialize<'de>>(kind: &str) -> Result<Vec<T>, String> {
+    let output = Command::new("pactl")
+        .args(["--format=json", "list", kind])
+        .output()
+        .map_err(|e| format!("Failed to run pactl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pactl list {} failed", kind));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())
+}
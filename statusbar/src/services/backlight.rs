@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::watch;
+use zbus::proxy;
+
+use super::bus;
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// sysfs has no change notifications for brightness, so it is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// logind session proxy; SetBrightness lets the session owner write sysfs
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
+
+/// A display backlight, e.g. `intel_backlight`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backlight {
+    pub name: String,
+    pub brightness: u32,
+    pub max_brightness: u32,
+}
+
+impl Backlight {
+    pub fn percent(&self) -> u32 {
+        if self.max_brightness == 0 {
+            return 0;
+        }
+        let max = self.max_brightness as u64;
+        ((self.brightness as u64 * 100 + max / 2) / max) as u32
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        let read = |file: &str| -> Option<u32> {
+            fs::read_to_string(path.join(file))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+        };
+        Some(Self {
+            name: path.file_name()?.to_string_lossy().into_owned(),
+            // actual_brightness is what the hardware reports back
+            brightness: read("actual_brightness").or_else(|| read("brightness"))?,
+            max_brightness: read("max_brightness")?,
+        })
+    }
+}
+
+static BACKLIGHTS: OnceLock<watch::Sender<Vec<Backlight>>> = OnceLock::new();
+
+fn backlights() -> &'static watch::Sender<Vec<Backlight>> {
+    BACKLIGHTS.get_or_init(|| {
+        let (tx, _) = watch::channel(read_backlights());
+        let poll_tx = tx.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                publish(&poll_tx, read_backlights());
+            }
+        });
+        tx
+    })
+}
+
+/// Subscribe to the backlights, sorted by name, starting the poller on first use
+pub fn subscribe() -> watch::Receiver<Vec<Backlight>> {
+    backlights().subscribe()
+}
+
+fn publish(tx: &watch::Sender<Vec<Backlight>>, state: Vec<Backlight>) {
+    tx.send_if_modified(|current| {
+        if *current == state {
+            return false;
+        }
+        *current = state;
+        true
+    });
+}
+
+fn read_backlights() -> Vec<Backlight> {
+    let Ok(entries) = fs::read_dir(BACKLIGHT_DIR) else {
+        return Vec::new();
+    };
+    let mut backlights: Vec<Backlight> = entries
+        .flatten()
+        .filter_map(|entry| Backlight::read(&entry.path()))
+        .collect();
+    backlights.sort_by(|a, b| a.name.cmp(&b.name));
+    backlights
+}
+
+/// Set a backlight to a percentage of its maximum
+///
+/// Writes sysfs directly when permitted (e.g. through a udev rule) and
+/// goes through logind otherwise.
+pub async fn set_brightness(name: &str, percent: u32) -> Result<(), String> {
+    let Some(backlight) = backlights()
+        .borrow()
+        .iter()
+        .find(|backlight| backlight.name == name)
+        .cloned()
+    else {
+        return Err(format!("No backlight named {}", name));
+    };
+
+    // Never turn the panel fully off; it looks like the screen died
+    let max = backlight.max_brightness as u64;
+    let value = ((max * percent.min(100) as u64 + 50) / 100).max(1) as u32;
+
+    let path: PathBuf = [BACKLIGHT_DIR, name, "brightness"].iter().collect();
+    if fs::write(&path, value.to_string()).is_err() {
+        set_brightness_logind(name, value)
+            .await
+            .map_err(|e| format!("Failed to set brightness of {}: {}", name, e))?;
+    }
+
+    publish(backlights(), read_backlights());
+    Ok(())
+}
+
+async fn set_brightness_logind(name: &str, value: u32) -> zbus::Result<()> {
+    let connection = bus::system().await?;
+    let session = SessionProxy::new(&connection).await?;
+    session.set_brightness("backlight", name, value).await
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use serde_json::Value;
+use zbus::{connection, fdo, interface};
+
+use super::bus;
+use super::ipc::{self, IpcCommand, IpcResult, Responder};
+
+/// Not the app id itself: GApplication already owns that name on its own
+/// connection, which doesn't serve this interface
+const SERVICE_NAME: &str = "com.github.iceice666.statusbar.Control";
+const SERVICE_PATH: &str = "/com/github/iceice666/statusbar";
+
+type CommandHandler = Arc<dyn Fn(IpcCommand, Responder) + Send + Sync>;
+
+/// `com.github.iceice666.statusbar.Control`, the control socket's commands
+/// over D-Bus
+struct BarService {
+    on_command: CommandHandler,
+}
+
+impl BarService {
+    /// Run a command on the GTK thread without blocking the bus
+    async fn run(&self, command: IpcCommand) -> fdo::Result<Value> {
+        let on_command = self.on_command.clone();
+        let result: IpcResult =
+            tokio::task::spawn_blocking(move || ipc::dispatch(&*on_command, command))
+                .await
+                .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        result.map_err(fdo::Error::Failed)
+    }
+
+    async fn widget_state(&self, widget: &str) -> fdo::Result<Value> {
+        self.run(IpcCommand::GetState {
+            widget: Some(widget.to_string()),
+        })
+        .await
+    }
+}
+
+#[interface(name = "com.github.iceice666.statusbar.Control")]
+impl BarService {
+    async fn show(&self) -> fdo::Result<()> {
+        self.run(IpcCommand::SetVisible(true)).await?;
+        Ok(())
+    }
+
+    async fn hide(&self) -> fdo::Result<()> {
+        self.run(IpcCommand::SetVisible(false)).await?;
+        Ok(())
+    }
+
+    async fn toggle_visibility(&self) -> fdo::Result<()> {
+        self.run(IpcCommand::ToggleVisibility).await?;
+        Ok(())
+    }
+
+    /// JSON snapshot of a widget, or of all of them for an empty name
+    async fn get_state(&self, widget: &str) -> fdo::Result<String> {
+        let widget = Some(widget.to_string()).filter(|widget| !widget.is_empty());
+        Ok(self.run(IpcCommand::GetState { widget }).await?.to_string())
+    }
+
+    /// Network the Wi-Fi is connected to, empty when disconnected
+    async fn ssid(&self) -> fdo::Result<String> {
+        let state = self.widget_state("wifi").await?;
+        Ok(state["ssid"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Battery charge in percent, or -1 without a battery
+    async fn battery_percentage(&self) -> fdo::Result<f64> {
+        let state = self.widget_state("battery").await?;
+        if state["present"] != true {
+            return Ok(-1.0);
+        }
+        Ok(state["charge_level"].as_f64().unwrap_or(-1.0))
+    }
+
+    /// Title and artist of the current track and whether it is playing
+    async fn now_playing(&self) -> fdo::Result<(String, String, bool)> {
+        let state = self.widget_state("media_player").await?;
+        Ok((
+            state["title"].as_str().unwrap_or_default().to_string(),
+            state["artist"].as_str().unwrap_or_default().to_string(),
+            state["is_playing"] == true,
+        ))
+    }
+}
+
+/// Own `com.github.iceice666.statusbar.Control` on the session bus
+pub fn serve(on_command: impl Fn(IpcCommand, Responder) + Send + Sync + 'static) {
+    let on_command: CommandHandler = Arc::new(on_command);
+    relm4::spawn(async move {
+        if let Err(e) = register(on_command).await {
+            eprintln!("Failed to register {}: {}", SERVICE_NAME, e);
+        }
+    });
+}
+
+async fn register(on_command: CommandHandler) -> zbus::Result<()> {
+    let connection = connection::Builder::session()?
+        .serve_at(SERVICE_PATH, BarService { on_command })?
+        .build()
+        .await?;
+    bus::own_name(&connection, SERVICE_NAME).await?;
+
+    // Keep the connection, and with it the exported object, alive
+    std::future::pending::<()>().await;
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+use zbus::fdo::{ManagedObjects, ObjectManagerProxy};
+use zbus::message::Type as MessageType;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, MatchRule, MessageStream, interface, proxy};
+
+use super::bus;
+
+const BLUEZ: &str = "org.bluez";
+
+/// Where the pairing agent is exported on the bar's connection
+const AGENT_PATH: &str = "/com/github/iceice666/statusbar/agent";
+
+#[proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter {
+    fn start_discovery(&self) -> zbus::Result<()>;
+
+    fn stop_discovery(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn powered(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn set_powered(&self, powered: bool) -> zbus::Result<()>;
+}
+
+#[proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+trait Device {
+    fn connect(&self) -> zbus::Result<()>;
+
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    fn pair(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn trusted(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn set_trusted(&self, trusted: bool) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.bluez.AgentManager1",
+    default_service = "org.bluez",
+    default_path = "/org/bluez"
+)]
+trait AgentManager {
+    fn register_agent(&self, agent: &ObjectPath<'_>, capability: &str) -> zbus::Result<()>;
+
+    fn unregister_agent(&self, agent: &ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+/// Pairing agent without input or output, so only "just works" pairing
+///
+/// Devices that need a PIN or passkey are rejected by BlueZ instead of
+/// prompting somewhere the user can't see.
+struct Agent;
+
+#[interface(name = "org.bluez.Agent1")]
+impl Agent {
+    fn release(&self) {}
+
+    fn request_authorization(&self, _device: OwnedObjectPath) {}
+
+    fn authorize_service(&self, _device: OwnedObjectPath, _uuid: String) {}
+
+    fn cancel(&self) {}
+}
+
+/// The Bluetooth controller
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adapter {
+    pub path: String,
+    pub name: String,
+    pub powered: bool,
+    pub discovering: bool,
+}
+
+/// A device known to the adapter, paired or found by a scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    pub path: String,
+    pub name: String,
+    pub address: String,
+    /// Freedesktop icon name, e.g. `audio-headset`
+    pub icon: Option<String>,
+    pub paired: bool,
+    pub connected: bool,
+    /// Charge in percent, for devices reporting it
+    pub battery: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BluetoothState {
+    pub adapter: Option<Adapter>,
+    /// Paired devices first, then by name
+    pub devices: Vec<Device>,
+}
+
+impl BluetoothState {
+    pub fn connected(&self) -> impl Iterator<Item = &Device> {
+        self.devices.iter().filter(|device| device.connected)
+    }
+
+    fn from_objects(objects: &ManagedObjects) -> Self {
+        let interface = |path: &OwnedObjectPath, name: &str| {
+            objects
+                .get(path)?
+                .iter()
+                .find(|(interface, _)| interface.as_str() == name)
+                .map(|(_, properties)| properties)
+        };
+
+        // The first adapter is the one used, like bluetoothctl does
+        let mut adapter_paths: Vec<&OwnedObjectPath> = objects
+            .keys()
+            .filter(|path| interface(path, "org.bluez.Adapter1").is_some())
+            .collect();
+        adapter_paths.sort_by_key(|path| path.as_str());
+        let adapter = adapter_paths.first().and_then(|path| {
+            let properties = interface(path, "org.bluez.Adapter1")?;
+            Some(Adapter {
+                path: path.to_string(),
+                name: string(properties, "Alias").unwrap_or_default(),
+                powered: flag(properties, "Powered"),
+                discovering: flag(properties, "Discovering"),
+            })
+        });
+
+        let mut devices: Vec<Device> = objects
+            .keys()
+            .filter_map(|path| {
+                let properties = interface(path, "org.bluez.Device1")?;
+                let address = string(properties, "Address").unwrap_or_default();
+                Some(Device {
+                    path: path.to_string(),
+                    name: string(properties, "Alias")
+                        .or_else(|| string(properties, "Name"))
+                        .unwrap_or_else(|| address.clone()),
+                    address,
+                    icon: string(properties, "Icon"),
+                    paired: flag(properties, "Paired"),
+                    connected: flag(properties, "Connected"),
+                    battery: interface(path, "org.bluez.Battery1")
+                        .and_then(|battery| battery.get("Percentage"))
+                        .and_then(|value| u8::try_from(value).ok()),
+                })
+            })
+            .collect();
+        devices.sort_by(|a, b| b.paired.cmp(&a.paired).then_with(|| a.name.cmp(&b.name)));
+
+        Self { adapter, devices }
+    }
+}
+
+fn string(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    let value: &Value = properties.get(key)?;
+    <&str>::try_from(value).ok().map(str::to_string)
+}
+
+fn flag(properties: &HashMap<String, OwnedValue>, key: &str) -> bool {
+    properties
+        .get(key)
+        .and_then(|value| bool::try_from(value).ok())
+        .unwrap_or(false)
+}
+
+static BLUETOOTH_STATE: OnceLock<watch::Receiver<BluetoothState>> = OnceLock::new();
+
+/// Subscribe to the adapter and devices, starting the watcher on first use
+pub fn subscribe() -> watch::Receiver<BluetoothState> {
+    BLUETOOTH_STATE
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(BluetoothState::default());
+            relm4::spawn(async move {
+                if let Err(e) = watch_bluez(tx).await {
+                    eprintln!("Bluetooth service error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+async fn watch_bluez(tx: watch::Sender<BluetoothState>) -> zbus::Result<()> {
+    let connection = bus::system().await?;
+    let objects = ObjectManagerProxy::builder(&connection)
+        .destination(BLUEZ)?
+        .path("/")?
+        .build()
+        .await?;
+
+    // Any signal from BlueZ (property or object changes) triggers a re-read
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .sender(BLUEZ)?
+        .build();
+    let mut signals = MessageStream::for_match_rule(rule, &connection, None).await?;
+
+    publish(&tx, &objects).await;
+    while signals.next().await.is_some() {
+        publish(&tx, &objects).await;
+    }
+
+    Ok(())
+}
+
+async fn publish(tx: &watch::Sender<BluetoothState>, objects: &ObjectManagerProxy<'_>) {
+    // BlueZ not running looks the same as having no adapter
+    let state = match objects.get_managed_objects().await {
+        Ok(objects) => BluetoothState::from_objects(&objects),
+        Err(_) => BluetoothState::default(),
+    };
+    tx.send_if_modified(|current| {
+        if *current == state {
+            return false;
+        }
+        *current = state;
+        true
+    });
+}
+
+async fn adapter(connection: &Connection, path: &str) -> zbus::Result<AdapterProxy<'static>> {
+    AdapterProxy::builder(connection)
+        .path(path.to_string())?
+        .build()
+        .await
+}
+
+async fn device(connection: &Connection, path: &str) -> zbus::Result<DeviceProxy<'static>> {
+    DeviceProxy::builder(connection)
+        .path(path.to_string())?
+        .build()
+        .await
+}
+
+pub async fn set_powered(adapter_path: &str, powered: bool) -> Result<(), String> {
+    let connection = bus::system().await.map_err(|e| e.to_string())?;
+    adapter(&connection, adapter_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .set_powered(powered)
+        .await
+        .map_err(|e| format!("Failed to power the adapter: {}", e))
+}
+
+/// Start or stop scanning for new devices
+pub async fn set_discovering(adapter_path: &str, discovering: bool) -> Result<(), String> {
+    let connection = bus::system().await.map_err(|e| e.to_string())?;
+    let adapter = adapter(&connection, adapter_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let result = if discovering {
+        adapter.start_discovery().await
+    } else {
+        adapter.stop_discovery().await
+    };
+    result.map_err(|e| format!("Failed to change discovery: {}", e))
+}
+
+pub async fn connect(device_path: &str) -> Result<(), String> {
+    let connection = bus::system().await.map_err(|e| e.to_string())?;
+    device(&connection, device_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to connect: {}", e))
+}
+
+pub async fn disconnect(device_path: &str) -> Result<(), String> {
+    let connection = bus::system().await.map_err(|e| e.to_string())?;
+    device(&connection, device_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .disconnect()
+        .await
+        .map_err(|e| format!("Failed to disconnect: {}", e))
+}
+
+/// Pair with a device, trust it so it can reconnect, then connect
+pub async fn pair(device_path: &str) -> Result<(), String> {
+    pair_with_agent(device_path)
+        .await
+        .map_err(|e| format!("Failed to pair: {}", e))
+}
+
+async fn pair_with_agent(device_path: &str) -> zbus::Result<()> {
+    // BlueZ asks the agent registered by the connection calling Pair
+    let connection = bus::system().await?;
+    connection.object_server().at(AGENT_PATH, Agent).await?;
+    let agents = AgentManagerProxy::new(&connection).await?;
+    let agent_path = ObjectPath::try_from(AGENT_PATH)?;
+    agents
+        .register_agent(&agent_path, "NoInputNoOutput")
+        .await?;
+
+    let device = device(&connection, device_path).await?;
+    let result = async {
+        device.pair().await?;
+        device.set_trusted(true).await?;
+        device.connect().await
+    }
+    .await;
+
+    let _ = agents.unregister_agent(&agent_path).await;
+    let _ = connection
+        .object_server()
+        .remove::<Agent, _>(AGENT_PATH)
+        .await;
+    result
+}
@@ -0,0 +1,34 @@
+// Bus connections shared by every service
+//
+// zbus connections are cheap to clone and route each signal to all proxies
+// subscribed to it, so one connection per bus serves the whole bar. Services
+// that own a well-known name or become a monitor keep a connection of their
+// own.
+use tokio::sync::OnceCell;
+use zbus::Connection;
+use zbus::fdo::{RequestNameFlags, RequestNameReply};
+
+static SYSTEM: OnceCell<Connection> = OnceCell::const_new();
+static SESSION: OnceCell<Connection> = OnceCell::const_new();
+
+/// The shared system bus connection, opened on first use
+pub async fn system() -> zbus::Result<Connection> {
+    SYSTEM.get_or_try_init(Connection::system).await.cloned()
+}
+
+/// The shared session bus connection, opened on first use
+pub async fn session() -> zbus::Result<Connection> {
+    SESSION.get_or_try_init(Connection::session).await.cloned()
+}
+
+/// Own `name` on `connection`, failing rather than waiting in the queue
+/// when another client has it
+pub async fn own_name(connection: &Connection, name: &str) -> zbus::Result<()> {
+    let reply = connection
+        .request_name_with_flags(name, RequestNameFlags::DoNotQueue.into())
+        .await?;
+    match reply {
+        RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner => Ok(()),
+        RequestNameReply::InQueue | RequestNameReply::Exists => Err(zbus::Error::NameTaken),
+    }
+}
@@ -0,0 +1,237 @@
+use serde_json::Value;
+use std::collections::HashSet;
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::{CompositorBackend, Event, Window, Workspace, emit_state};
+
+/// Hyprland, over the sockets of `HYPRLAND_INSTANCE_SIGNATURE`
+#[derive(Default)]
+pub struct Hyprland {
+    /// Windows that requested attention and haven't been focused since
+    ///
+    /// Hyprland only announces urgency as an event, so it's tracked here.
+    urgent: Mutex<HashSet<u64>>,
+}
+
+/// Directory holding the instance's sockets
+fn socket_dir() -> Result<PathBuf, String> {
+    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .map_err(|_| "HYPRLAND_INSTANCE_SIGNATURE is not set".to_string())?;
+
+    // Older versions keep their sockets in /tmp
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join("hypr").join(&signature))
+        .filter(|dir| dir.exists());
+    Ok(runtime_dir.unwrap_or_else(|| PathBuf::from("/tmp/hypr").join(signature)))
+}
+
+/// Send a command on its own connection and read the whole reply
+fn command(command: &str) -> Result<String, String> {
+    let path = socket_dir()?.join(".socket.sock");
+    let mut socket = UnixStream::connect(&path)
+        .map_err(|e| format!("Failed to connect to {}: {}", path.display(), e))?;
+    socket
+        .write_all(command.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    socket
+        .read_to_string(&mut reply)
+        .map_err(|e| e.to_string())?;
+    Ok(reply)
+}
+
+/// Run a query with JSON output, e.g. `clients`
+fn query(name: &str) -> Result<Value, String> {
+    serde_json::from_str(&command(&format!("j/{}", name))?).map_err(|e| e.to_string())
+}
+
+fn dispatch(args: &str) -> Result<(), String> {
+    let reply = command(&format!("dispatch {}", args))?;
+    if reply.trim() == "ok" {
+        Ok(())
+    } else {
+        Err(reply.trim().to_string())
+    }
+}
+
+/// Windows are identified by their address, e.g. `0x55d5c7e2a0b0`
+fn parse_address(address: &str) -> Option<u64> {
+    u64::from_str_radix(address.trim().trim_start_matches("0x"), 16)
+        .ok()
+        .filter(|&address| address != 0)
+}
+
+/// Regular workspaces have positive ids, special ones negative
+fn workspace_id(value: &Value) -> Option<u64> {
+    value.as_i64().filter(|&id| id > 0).map(|id| id as u64)
+}
+
+impl Hyprland {
+    fn urgent(&self) -> std::sync::MutexGuard<'_, HashSet<u64>> {
+        self.urgent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CompositorBackend for Hyprland {
+    fn name(&self) -> &'static str {
+        "Hyprland"
+    }
+
+    fn workspaces(&self) -> Result<Vec<Workspace>, String> {
+        let monitors = query("monitors")?;
+        let monitors = monitors.as_array().map(Vec::as_slice).unwrap_or_default();
+        let active: Vec<u64> = monitors
+            .iter()
+            .filter_map(|monitor| workspace_id(&monitor["activeWorkspace"]["id"]))
+            .collect();
+        let focused = monitors
+            .iter()
+            .find(|monitor| monitor["focused"] == true)
+            .and_then(|monitor| workspace_id(&monitor["activeWorkspace"]["id"]));
+
+        let windows = self.windows()?;
+        let urgent = self.urgent();
+        let mut workspaces: Vec<Workspace> = query("workspaces")?
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|workspace| {
+                let id = workspace_id(&workspace["id"])?;
+                Some(Workspace {
+                    id,
+                    idx: u32::try_from(id).unwrap_or(u32::MAX),
+                    name: workspace["name"].as_str().map(str::to_string),
+                    output: workspace["monitor"].as_str().map(str::to_string),
+                    is_active: active.contains(&id),
+                    is_focused: focused == Some(id),
+                    is_urgent: windows.iter().any(|window| {
+                        window.workspace_id == Some(id) && urgent.contains(&window.id)
+                    }),
+                    active_window_id: workspace["lastwindow"].as_str().and_then(parse_address),
+                })
+            })
+            .collect();
+        workspaces.sort_by_key(|workspace| workspace.idx);
+        Ok(workspaces)
+    }
+
+    fn windows(&self) -> Result<Vec<Window>, String> {
+        Ok(query("clients")?
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|client| client["mapped"] != false)
+            .filter_map(|client| {
+                Some(Window {
+                    id: parse_address(client["address"].as_str()?)?,
+                    title: client["title"].as_str().map(str::to_string),
+                    app_id: client["class"].as_str().map(str::to_string),
+                    workspace_id: workspace_id(&client["workspace"]["id"]),
+                    // The focused window is first in the focus history
+                    is_focused: client["focusHistoryID"] == 0,
+                    is_floating: client["floating"] == true,
+                    // A mode number in recent versions, a bool in older ones
+                    is_fullscreen: client["fullscreen"] == true
+                        || client["fullscreen"].as_u64().is_some_and(|mode| mode != 0),
+                })
+            })
+            .collect())
+    }
+
+    fn focus_workspace(&self, id: u64) -> Result<(), String> {
+        dispatch(&format!("workspace {}", id))
+    }
+
+    fn rename_workspace(&self, id: u64, name: Option<&str>) -> Result<(), String> {
+        // Unnamed workspaces are called by their id
+        let name = name.map_or_else(|| id.to_string(), str::to_string);
+        dispatch(&format!("renameworkspace {} {}", id, name))
+    }
+
+    fn focus_window(&self, id: u64) -> Result<(), String> {
+        dispatch(&format!("focuswindow address:0x{:x}", id))
+    }
+
+    fn close_window(&self, id: u64) -> Result<(), String> {
+        dispatch(&format!("closewindow address:0x{:x}", id))
+    }
+
+    fn toggle_maximized(&self, id: u64) -> Result<(), String> {
+        // `fullscreen 1` maximizes, but only acts on the focused window
+        self.focus_window(id)?;
+        dispatch("fullscreen 1")
+    }
+
+    fn toggle_floating(&self, id: u64) -> Result<(), String> {
+        dispatch(&format!("togglefloating address:0x{:x}", id))
+    }
+
+    fn move_window_to_workspace(&self, window_id: u64, workspace: &str) -> Result<(), String> {
+        dispatch(&format!(
+            "movetoworkspacesilent name:{},address:0x{:x}",
+            workspace, window_id
+        ))
+    }
+
+    fn minimize_window(&self, id: u64) -> Result<(), String> {
+        // Hyprland has no minimized state, a special workspace hides the
+        // window until it's toggled
+        dispatch(&format!(
+            "movetoworkspacesilent special:minimized,address:0x{:x}",
+            id
+        ))
+    }
+
+    fn read_events(&self, connected: &dyn Fn(), emit: &mut dyn FnMut(Event)) -> Result<(), String> {
+        let path = socket_dir()?.join(".socket2.sock");
+        let socket = UnixStream::connect(&path)
+            .map_err(|e| format!("Failed to connect to {}: {}", path.display(), e))?;
+        connected();
+        emit_state(self, emit)?;
+
+        // Lines look like "activewindowv2>>55d5c7e2a0b0"
+        for line in BufReader::new(socket).lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            let Some((name, data)) = line.split_once(">>") else {
+                continue;
+            };
+
+            match name {
+                "activewindowv2" => {
+                    let id = parse_address(data);
+                    if let Some(id) = id {
+                        self.urgent().remove(&id);
+                    }
+                    emit_state(self, emit)?;
+                    emit(Event::WindowFocusChanged(id));
+                }
+                "closewindow" => {
+                    if let Some(id) = parse_address(data) {
+                        self.urgent().remove(&id);
+                        emit(Event::WindowClosed(id));
+                    }
+                    emit_state(self, emit)?;
+                }
+                "urgent" => {
+                    if let Some(id) = parse_address(data) {
+                        self.urgent().insert(id);
+                    }
+                    emit_state(self, emit)?;
+                }
+                "workspace" | "focusedmon" | "createworkspace" | "destroyworkspace"
+                | "renameworkspace" | "moveworkspace" | "openwindow" | "movewindow"
+                | "windowtitle" | "fullscreen" | "changefloatingmode" => {
+                    emit_state(self, emit)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
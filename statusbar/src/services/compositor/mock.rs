@@ -0,0 +1,101 @@
+use std::thread;
+
+use super::{CompositorBackend, Event, KeyboardLayouts, Window, Workspace, emit_state};
+
+/// Fixed workspaces and windows for `--headless-test`
+///
+/// Actions are accepted and ignored, so the state never changes.
+pub struct Mock;
+
+impl CompositorBackend for Mock {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn workspaces(&self) -> Result<Vec<Workspace>, String> {
+        let workspace = |id: u64, is_focused: bool, active_window_id: Option<u64>| Workspace {
+            id,
+            idx: id as u32,
+            name: None,
+            output: Some("HEADLESS-1".to_string()),
+            is_active: is_focused,
+            is_focused,
+            is_urgent: false,
+            active_window_id,
+        };
+        Ok(vec![
+            workspace(1, true, Some(1)),
+            workspace(2, false, Some(3)),
+            workspace(3, false, None),
+        ])
+    }
+
+    fn windows(&self) -> Result<Vec<Window>, String> {
+        let window = |id: u64, title: &str, app_id: &str, workspace_id: u64| Window {
+            id,
+            title: Some(title.to_string()),
+            app_id: Some(app_id.to_string()),
+            workspace_id: Some(workspace_id),
+            is_focused: id == 1,
+            is_floating: false,
+            is_fullscreen: false,
+        };
+        Ok(vec![
+            window(1, "~/statusbar", "Alacritty", 1),
+            window(2, "Mozilla Firefox", "firefox", 1),
+            window(3, "Files", "org.gnome.Nautilus", 2),
+        ])
+    }
+
+    fn focus_workspace(&self, _id: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn rename_workspace(&self, _id: u64, _name: Option<&str>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn focus_window(&self, _id: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn close_window(&self, _id: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn toggle_maximized(&self, _id: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn toggle_floating(&self, _id: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn move_window_to_workspace(&self, _window_id: u64, _workspace: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn minimize_window(&self, _id: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn keyboard_layouts(&self) -> Result<KeyboardLayouts, String> {
+        Ok(KeyboardLayouts {
+            names: vec!["English (US)".to_string(), "German".to_string()],
+            current: 0,
+        })
+    }
+
+    fn switch_keyboard_layout(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn read_events(&self, connected: &dyn Fn(), emit: &mut dyn FnMut(Event)) -> Result<(), String> {
+        connected();
+        emit_state(self, emit)?;
+        // Nothing ever changes
+        loop {
+            thread::park();
+        }
+    }
+}
@@ -0,0 +1,350 @@
+// Workspace and window data, independent of the running compositor
+mod hyprland;
+mod mock;
+mod niri;
+mod sway;
+
+use serde::Serialize;
+use std::env;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::watch;
+
+use super::health;
+use crate::headless;
+
+/// Events queued per subscriber before it starts missing some
+const EVENT_BUFFER: usize = 256;
+
+/// Wait before the first reconnection attempt, doubled on each failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Workspace {
+    pub id: u64,
+    /// Position shown on the workspace button
+    pub idx: u32,
+    pub name: Option<String>,
+    pub output: Option<String>,
+    /// Shown on its output
+    pub is_active: bool,
+    /// Shown on the focused output
+    pub is_focused: bool,
+    /// A window on the workspace requests attention
+    pub is_urgent: bool,
+    pub active_window_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Window {
+    pub id: u64,
+    pub title: Option<String>,
+    pub app_id: Option<String>,
+    pub workspace_id: Option<u64>,
+    pub is_focused: bool,
+    pub is_floating: bool,
+    pub is_fullscreen: bool,
+}
+
+/// Keyboard layouts configured in the compositor
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyboardLayouts {
+    /// Full layout names, e.g. `English (US)`
+    pub names: Vec<String>,
+    /// Index of the active layout in `names`
+    pub current: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The full list of workspaces
+    WorkspacesChanged(Vec<Workspace>),
+    WorkspaceActivated {
+        id: u64,
+        focused: bool,
+    },
+    WorkspaceUrgencyChanged {
+        id: u64,
+        urgent: bool,
+    },
+    /// The full list of windows
+    WindowsChanged(Vec<Window>),
+    WindowOpenedOrChanged(Window),
+    WindowClosed(u64),
+    WindowFocusChanged(Option<u64>),
+    KeyboardLayoutsChanged(KeyboardLayouts),
+    /// Index of the layout switched to
+    KeyboardLayoutSwitched(usize),
+}
+
+/// Access to a compositor's workspaces and windows
+///
+/// Every call but [`CompositorBackend::read_events`] uses a connection of
+/// its own, so requests can be made from any thread.
+pub trait CompositorBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Workspaces on all outputs
+    fn workspaces(&self) -> Result<Vec<Workspace>, String>;
+
+    fn windows(&self) -> Result<Vec<Window>, String>;
+
+    fn focused_window(&self) -> Result<Option<Window>, String> {
+        Ok(self.windows()?.into_iter().find(|window| window.is_focused))
+    }
+
+    fn focus_workspace(&self, id: u64) -> Result<(), String>;
+
+    /// Name a workspace, or drop its name with `None`
+    fn rename_workspace(&self, id: u64, name: Option<&str>) -> Result<(), String>;
+
+    fn focus_window(&self, id: u64) -> Result<(), String>;
+
+    /// Ask a window to close, as if its close button was clicked
+    fn close_window(&self, id: u64) -> Result<(), String>;
+
+    /// Maximize the window, or restore it if it is maximized
+    fn toggle_maximized(&self, id: u64) -> Result<(), String>;
+
+    /// Move the window between the tiling and the floating layer
+    fn toggle_floating(&self, id: u64) -> Result<(), String>;
+
+    /// Move a window to the workspace called `workspace` without following it
+    fn move_window_to_workspace(&self, window_id: u64, workspace: &str) -> Result<(), String>;
+
+    /// Hide a window away until the user brings it back
+    fn minimize_window(&self, _id: u64) -> Result<(), String> {
+        Err(format!("{} can't minimize windows", self.name()))
+    }
+
+    /// Whether the compositor has an overview of all workspaces
+    fn has_overview(&self) -> bool {
+        false
+    }
+
+    fn toggle_overview(&self) -> Result<(), String> {
+        Err(format!("{} has no overview", self.name()))
+    }
+
+    fn keyboard_layouts(&self) -> Result<KeyboardLayouts, String> {
+        Err(format!("{} doesn't report keyboard layouts", self.name()))
+    }
+
+    /// Switch to the next keyboard layout
+    fn switch_keyboard_layout(&self) -> Result<(), String> {
+        Err(format!("{} doesn't report keyboard layouts", self.name()))
+    }
+
+    /// Open the event stream and forward events until it closes
+    ///
+    /// `connected` is called once the stream is open. The current state is
+    /// sent first, so subscribers see it again after a reconnect.
+    fn read_events(&self, connected: &dyn Fn(), emit: &mut dyn FnMut(Event)) -> Result<(), String>;
+}
+
+/// Send the full state, for backends without fine-grained events
+fn emit_state(backend: &dyn CompositorBackend, emit: &mut dyn FnMut(Event)) -> Result<(), String> {
+    emit(Event::WorkspacesChanged(backend.workspaces()?));
+    emit(Event::WindowsChanged(backend.windows()?));
+    Ok(())
+}
+
+static BACKEND: OnceLock<Option<Box<dyn CompositorBackend>>> = OnceLock::new();
+
+/// Pick the backend from the sockets the compositor advertises
+fn detect() -> Option<Box<dyn CompositorBackend>> {
+    if headless::mock_compositor() {
+        Some(Box::new(mock::Mock))
+    } else if env::var_os("NIRI_SOCKET").is_some() {
+        Some(Box::new(niri::Niri))
+    } else if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        Some(Box::new(hyprland::Hyprland::default()))
+    } else if env::var_os("SWAYSOCK").is_some() || env::var_os("I3SOCK").is_some() {
+        Some(Box::new(sway::Sway))
+    } else {
+        eprintln!("No supported compositor found (niri, Sway, i3 or Hyprland)");
+        None
+    }
+}
+
+fn backend() -> Result<&'static dyn CompositorBackend, String> {
+    BACKEND
+        .get_or_init(detect)
+        .as_deref()
+        .ok_or_else(|| "No supported compositor".to_string())
+}
+
+/// The one event stream, shared by all subscribers
+struct Stream {
+    events: broadcast::Sender<Event>,
+    connected: watch::Receiver<bool>,
+}
+
+static STREAM: OnceLock<Stream> = OnceLock::new();
+
+/// A widget's view of the shared compositor event stream
+pub struct Events(broadcast::Receiver<Event>);
+
+impl Events {
+    /// Block until the next event, or `None` once the stream is gone
+    pub fn blocking_next(&mut self) -> Option<Event> {
+        loop {
+            match self.0.blocking_recv() {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(missed)) => {
+                    eprintln!("Compositor subscriber missed {} events", missed);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+fn stream() -> &'static Stream {
+    STREAM.get_or_init(|| {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let events_tx = events.clone();
+        let connected = match backend() {
+            Ok(backend) => {
+                // Assume the compositor is up until the first attempt says otherwise
+                let (connected_tx, connected) = watch::channel(true);
+                let reader =
+                    thread::spawn(move || keep_reading(backend, &events_tx, &connected_tx));
+                // Cut the backoff short
+                let reader = reader.thread().clone();
+                health::on_retry(health::COMPOSITOR, move || {
+                    reader.unpark();
+                    true
+                });
+                connected
+            }
+            Err(_) => watch::channel(false).1,
+        };
+        Stream { events, connected }
+    })
+}
+
+/// Subscribe to compositor events, opening the one event stream on first use
+///
+/// Events from before the call are not replayed, so subscribers query the
+/// initial state with [`workspaces`], [`windows`] or [`focused_window`].
+pub fn subscribe() -> Events {
+    Events(stream().events.subscribe())
+}
+
+/// Whether the event stream is currently connected
+pub fn connection() -> watch::Receiver<bool> {
+    stream().connected.clone()
+}
+
+pub fn workspaces() -> Result<Vec<Workspace>, String> {
+    backend()?.workspaces()
+}
+
+pub fn windows() -> Result<Vec<Window>, String> {
+    backend()?.windows()
+}
+
+pub fn focused_window() -> Result<Option<Window>, String> {
+    backend()?.focused_window()
+}
+
+/// Outputs whose shown workspace has a fullscreen window in front
+pub fn fullscreen_outputs() -> Result<Vec<String>, String> {
+    let windows = windows()?;
+    Ok(workspaces()?
+        .into_iter()
+        .filter(|workspace| workspace.is_active)
+        .filter(|workspace| {
+            windows
+                .iter()
+                .any(|window| Some(window.id) == workspace.active_window_id && window.is_fullscreen)
+        })
+        .filter_map(|workspace| workspace.output)
+        .collect())
+}
+
+pub fn focus_workspace(id: u64) -> Result<(), String> {
+    backend()?.focus_workspace(id)
+}
+
+pub fn rename_workspace(id: u64, name: Option<&str>) -> Result<(), String> {
+    backend()?.rename_workspace(id, name)
+}
+
+pub fn focus_window(id: u64) -> Result<(), String> {
+    backend()?.focus_window(id)
+}
+
+pub fn close_window(id: u64) -> Result<(), String> {
+    backend()?.close_window(id)
+}
+
+pub fn toggle_maximized(id: u64) -> Result<(), String> {
+    backend()?.toggle_maximized(id)
+}
+
+pub fn toggle_floating(id: u64) -> Result<(), String> {
+    backend()?.toggle_floating(id)
+}
+
+pub fn move_window_to_workspace(window_id: u64, workspace: &str) -> Result<(), String> {
+    backend()?.move_window_to_workspace(window_id, workspace)
+}
+
+pub fn minimize_window(id: u64) -> Result<(), String> {
+    backend()?.minimize_window(id)
+}
+
+pub fn has_overview() -> bool {
+    backend().is_ok_and(|backend| backend.has_overview())
+}
+
+pub fn toggle_overview() -> Result<(), String> {
+    backend()?.toggle_overview()
+}
+
+pub fn keyboard_layouts() -> Result<KeyboardLayouts, String> {
+    backend()?.keyboard_layouts()
+}
+
+pub fn switch_keyboard_layout() -> Result<(), String> {
+    backend()?.switch_keyboard_layout()
+}
+
+/// Read events forever, reconnecting with backoff when the compositor goes away
+fn keep_reading(
+    backend: &dyn CompositorBackend,
+    events: &broadcast::Sender<Event>,
+    connected: &watch::Sender<bool>,
+) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let result = backend.read_events(
+            &|| {
+                connected.send_replace(true);
+                health::clear(health::COMPOSITOR);
+            },
+            // Only fails while nobody is subscribed
+            &mut |event| {
+                let _ = events.send(event);
+            },
+        );
+
+        let error = match result {
+            Ok(()) => format!("{} event stream closed", backend.name()),
+            Err(e) => format!("{} IPC error: {}", backend.name(), e),
+        };
+        health::report(health::COMPOSITOR, error);
+        if connected.send_replace(false) {
+            backoff = INITIAL_BACKOFF;
+        }
+
+        // Woken early when a retry is asked for
+        thread::park_timeout(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
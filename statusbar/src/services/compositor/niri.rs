@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use niri_ipc::{
+    Action, LayoutSwitchTarget, Request, Response, WorkspaceReferenceArg, socket::Socket,
+};
+
+use super::{CompositorBackend, Event, KeyboardLayouts, Window, Workspace};
+
+/// niri, over the socket in `NIRI_SOCKET`
+pub struct Niri;
+
+impl Niri {
+    /// Send a single request on its own connection
+    fn request(request: Request) -> Result<Response, String> {
+        let mut socket = Socket::connect().map_err(|e| e.to_string())?;
+        socket.send(request).map_err(|e| e.to_string())?
+    }
+
+    fn action(action: Action) -> Result<(), String> {
+        Self::request(Request::Action(action))?;
+        Ok(())
+    }
+
+    /// Logical size of the output each workspace is on
+    ///
+    /// niri doesn't report fullscreen windows over IPC, so a window is taken
+    /// as fullscreen when its tile covers the whole output.
+    fn workspace_sizes() -> Result<HashMap<u64, (f64, f64)>, String> {
+        let Response::Outputs(outputs) = Self::request(Request::Outputs)? else {
+            return Err("Unexpected response".to_string());
+        };
+        let Response::Workspaces(workspaces) = Self::request(Request::Workspaces)? else {
+            return Err("Unexpected response".to_string());
+        };
+        Ok(workspaces
+            .into_iter()
+            .filter_map(|workspace| {
+                let logical = outputs
+                    .get(workspace.output.as_deref()?)?
+                    .logical
+                    .as_ref()?;
+                Some((
+                    workspace.id,
+                    (f64::from(logical.width), f64::from(logical.height)),
+                ))
+            })
+            .collect())
+    }
+}
+
+impl CompositorBackend for Niri {
+    fn name(&self) -> &'static str {
+        "niri"
+    }
+
+    fn workspaces(&self) -> Result<Vec<Workspace>, String> {
+        match Self::request(Request::Workspaces)? {
+            Response::Workspaces(workspaces) => {
+                Ok(workspaces.into_iter().map(Into::into).collect())
+            }
+            _ => Err("Unexpected response".to_string()),
+        }
+    }
+
+    fn windows(&self) -> Result<Vec<Window>, String> {
+        match Self::request(Request::Windows)? {
+            Response::Windows(windows) => {
+                let sizes = Self::workspace_sizes()?;
+                Ok(windows
+                    .into_iter()
+                    .map(|window| convert_window(window, &sizes))
+                    .collect())
+            }
+            _ => Err("Unexpected response".to_string()),
+        }
+    }
+
+    fn focused_window(&self) -> Result<Option<Window>, String> {
+        match Self::request(Request::FocusedWindow)? {
+            Response::FocusedWindow(window) => {
+                let sizes = Self::workspace_sizes()?;
+                Ok(window.map(|window| convert_window(window, &sizes)))
+            }
+            _ => Err("Unexpected response".to_string()),
+        }
+    }
+
+    fn focus_workspace(&self, id: u64) -> Result<(), String> {
+        Self::action(Action::FocusWorkspace {
+            reference: WorkspaceReferenceArg::Id(id),
+        })
+    }
+
+    fn rename_workspace(&self, id: u64, name: Option<&str>) -> Result<(), String> {
+        let reference = Some(WorkspaceReferenceArg::Id(id));
+        match name {
+            Some(name) => Self::action(Action::SetWorkspaceName {
+                name: name.to_string(),
+                workspace: reference,
+            }),
+            None => Self::action(Action::UnsetWorkspaceName { reference }),
+        }
+    }
+
+    fn focus_window(&self, id: u64) -> Result<(), String> {
+        Self::action(Action::FocusWindow { id })
+    }
+
+    fn close_window(&self, id: u64) -> Result<(), String> {
+        Self::action(Action::CloseWindow { id: Some(id) })
+    }
+
+    fn toggle_maximized(&self, id: u64) -> Result<(), String> {
+        // Columns are maximized rather than windows, so focus its column first
+        self.focus_window(id)?;
+        Self::action(Action::MaximizeColumn {})
+    }
+
+    fn toggle_floating(&self, id: u64) -> Result<(), String> {
+        Self::action(Action::ToggleWindowFloating { id: Some(id) })
+    }
+
+    fn move_window_to_workspace(&self, window_id: u64, workspace: &str) -> Result<(), String> {
+        Self::action(Action::MoveWindowToWorkspace {
+            window_id: Some(window_id),
+            reference: WorkspaceReferenceArg::Name(workspace.to_string()),
+            focus: false,
+        })
+    }
+
+    fn has_overview(&self) -> bool {
+        true
+    }
+
+    fn toggle_overview(&self) -> Result<(), String> {
+        Self::action(Action::ToggleOverview {})
+    }
+
+    fn keyboard_layouts(&self) -> Result<KeyboardLayouts, String> {
+        match Self::request(Request::KeyboardLayouts)? {
+            Response::KeyboardLayouts(layouts) => Ok(layouts.into()),
+            _ => Err("Unexpected response".to_string()),
+        }
+    }
+
+    fn switch_keyboard_layout(&self) -> Result<(), String> {
+        Self::action(Action::SwitchLayout {
+            layout: LayoutSwitchTarget::Next,
+        })
+    }
+
+    fn read_events(&self, connected: &dyn Fn(), emit: &mut dyn FnMut(Event)) -> Result<(), String> {
+        let mut socket = Socket::connect().map_err(|e| e.to_string())?;
+
+        let reply = socket
+            .send(Request::EventStream)
+            .map_err(|e| e.to_string())?;
+        if !matches!(reply, Ok(Response::Handled)) {
+            return Err("niri refused the event stream".to_string());
+        }
+        connected();
+
+        // niri starts the stream with its full state
+        let mut read_event = socket.read_events();
+        while let Ok(event) = read_event() {
+            match event {
+                // Window events carry no fullscreen state, so look it up
+                niri_ipc::Event::WindowsChanged { .. }
+                | niri_ipc::Event::WindowLayoutsChanged { .. } => {
+                    emit(Event::WindowsChanged(self.windows()?));
+                }
+                niri_ipc::Event::WindowOpenedOrChanged { window } => {
+                    let sizes = Self::workspace_sizes()?;
+                    emit(Event::WindowOpenedOrChanged(convert_window(window, &sizes)));
+                }
+                event => {
+                    if let Some(event) = convert_event(event) {
+                        emit(event);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn convert_event(event: niri_ipc::Event) -> Option<Event> {
+    use niri_ipc::Event as NiriEvent;
+
+    Some(match event {
+        NiriEvent::WorkspacesChanged { workspaces } => {
+            Event::WorkspacesChanged(workspaces.into_iter().map(Into::into).collect())
+        }
+        NiriEvent::WorkspaceActivated { id, focused } => Event::WorkspaceActivated { id, focused },
+        NiriEvent::WorkspaceUrgencyChanged { id, urgent } => {
+            Event::WorkspaceUrgencyChanged { id, urgent }
+        }
+        NiriEvent::WindowClosed { id } => Event::WindowClosed(id),
+        NiriEvent::WindowFocusChanged { id } => Event::WindowFocusChanged(id),
+        NiriEvent::KeyboardLayoutsChanged { keyboard_layouts } => {
+            Event::KeyboardLayoutsChanged(keyboard_layouts.into())
+        }
+        NiriEvent::KeyboardLayoutSwitched { idx } => Event::KeyboardLayoutSwitched(idx.into()),
+        _ => return None,
+    })
+}
+
+impl From<niri_ipc::Workspace> for Workspace {
+    fn from(workspace: niri_ipc::Workspace) -> Self {
+        Self {
+            id: workspace.id,
+            idx: workspace.idx.into(),
+            name: workspace.name,
+            output: workspace.output,
+            is_active: workspace.is_active,
+            is_focused: workspace.is_focused,
+            is_urgent: workspace.is_urgent,
+            active_window_id: workspace.active_window_id,
+        }
+    }
+}
+
+impl From<niri_ipc::KeyboardLayouts> for KeyboardLayouts {
+    fn from(layouts: niri_ipc::KeyboardLayouts) -> Self {
+        Self {
+            names: layouts.names,
+            current: layouts.current_idx.into(),
+        }
+    }
+}
+
+/// Convert a window, given the output sizes from [`Niri::workspace_sizes`]
+fn convert_window(window: niri_ipc::Window, sizes: &HashMap<u64, (f64, f64)>) -> Window {
+    let (width, height) = window.layout.tile_size;
+    let is_fullscreen = !window.is_floating
+        && window
+            .workspace_id
+            .and_then(|id| sizes.get(&id))
+            .is_some_and(|&(output_width, output_height)| {
+                width.round() >= output_width && height.round() >= output_height
+            });
+    Window {
+        id: window.id,
+        title: window.title,
+        app_id: window.app_id,
+        workspace_id: window.workspace_id,
+        is_focused: window.is_focused,
+        is_floating: window.is_floating,
+        is_fullscreen,
+    }
+}
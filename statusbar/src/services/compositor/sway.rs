@@ -0,0 +1,307 @@
+use serde_json::{Value, json};
+use std::env;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+
+use super::{CompositorBackend, Event, KeyboardLayouts, Window, Workspace, emit_state};
+
+const MAGIC: &[u8; 6] = b"i3-ipc";
+
+// Message types of the i3 IPC protocol, which Sway implements too
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_TREE: u32 = 4;
+const GET_INPUTS: u32 = 100;
+
+/// Event types have the high bit set
+const EVENT_WORKSPACE: u32 = 0x8000_0000;
+const EVENT_WINDOW: u32 = 0x8000_0003;
+const EVENT_INPUT: u32 = 0x8000_0015;
+
+/// Sway or i3, over the socket in `SWAYSOCK` or `I3SOCK`
+pub struct Sway;
+
+/// A connection to the IPC socket
+struct Connection(UnixStream);
+
+impl Connection {
+    fn open() -> Result<Self, String> {
+        let path = env::var("SWAYSOCK")
+            .or_else(|_| env::var("I3SOCK"))
+            .map_err(|_| "Neither SWAYSOCK nor I3SOCK is set".to_string())?;
+        UnixStream::connect(&path)
+            .map(Self)
+            .map_err(|e| format!("Failed to connect to {}: {}", path, e))
+    }
+
+    /// Messages are the magic string, payload length and type, then the payload
+    fn send(&mut self, kind: u32, payload: &str) -> Result<(), String> {
+        let mut message = Vec::with_capacity(MAGIC.len() + 8 + payload.len());
+        message.extend_from_slice(MAGIC);
+        message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        message.extend_from_slice(&kind.to_ne_bytes());
+        message.extend_from_slice(payload.as_bytes());
+        self.0.write_all(&message).map_err(|e| e.to_string())
+    }
+
+    fn receive(&mut self) -> Result<(u32, Value), String> {
+        let mut header = [0u8; 14];
+        self.0.read_exact(&mut header).map_err(|e| e.to_string())?;
+        if &header[..6] != MAGIC {
+            return Err("Invalid IPC header".to_string());
+        }
+        let length = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]);
+        let kind = u32::from_ne_bytes([header[10], header[11], header[12], header[13]]);
+
+        let mut payload = vec![0u8; length as usize];
+        self.0.read_exact(&mut payload).map_err(|e| e.to_string())?;
+        let payload = serde_json::from_slice(&payload).map_err(|e| e.to_string())?;
+        Ok((kind, payload))
+    }
+
+    fn request(&mut self, kind: u32, payload: &str) -> Result<Value, String> {
+        self.send(kind, payload)?;
+        Ok(self.receive()?.1)
+    }
+}
+
+impl Sway {
+    fn request(kind: u32, payload: &str) -> Result<Value, String> {
+        Connection::open()?.request(kind, payload)
+    }
+
+    /// The workspace as reported by `GET_WORKSPACES`
+    fn workspace(id: u64) -> Result<Value, String> {
+        Self::request(GET_WORKSPACES, "")?
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|workspace| workspace["id"].as_u64() == Some(id))
+            .cloned()
+            .ok_or_else(|| format!("No workspace with id {}", id))
+    }
+
+    fn run_command(command: &str) -> Result<(), String> {
+        let reply = Self::request(RUN_COMMAND, command)?;
+        // One result per command in the payload
+        let failed = reply
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|result| result["success"] != true);
+        match failed {
+            Some(result) => Err(result["error"]
+                .as_str()
+                .unwrap_or("Command failed")
+                .to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl CompositorBackend for Sway {
+    fn name(&self) -> &'static str {
+        "Sway"
+    }
+
+    fn workspaces(&self) -> Result<Vec<Workspace>, String> {
+        let windows = self.windows()?;
+        let reply = Self::request(GET_WORKSPACES, "")?;
+
+        let mut workspaces: Vec<Workspace> = reply
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(position, workspace)| {
+                let id = workspace["id"].as_u64()?;
+                // Named workspaces without a number have a num of -1
+                let idx = workspace["num"]
+                    .as_u64()
+                    .and_then(|num| u32::try_from(num).ok())
+                    .unwrap_or(position as u32 + 1);
+                Some(Workspace {
+                    id,
+                    idx,
+                    name: workspace["name"].as_str().map(str::to_string),
+                    output: workspace["output"].as_str().map(str::to_string),
+                    is_active: workspace["visible"] == true,
+                    is_focused: workspace["focused"] == true,
+                    is_urgent: workspace["urgent"] == true,
+                    active_window_id: windows
+                        .iter()
+                        .find(|window| window.workspace_id == Some(id))
+                        .map(|window| window.id),
+                })
+            })
+            .collect();
+        workspaces.sort_by_key(|workspace| workspace.idx);
+        Ok(workspaces)
+    }
+
+    fn windows(&self) -> Result<Vec<Window>, String> {
+        let tree = Self::request(GET_TREE, "")?;
+        let mut windows = Vec::new();
+        collect_windows(&tree, None, &mut windows);
+        Ok(windows)
+    }
+
+    fn focus_workspace(&self, id: u64) -> Result<(), String> {
+        let workspace = Self::workspace(id)?;
+        Self::run_command(&format!("workspace {}", workspace["name"]))
+    }
+
+    fn rename_workspace(&self, id: u64, name: Option<&str>) -> Result<(), String> {
+        let workspace = Self::workspace(id)?;
+        // Without a name a workspace goes back to being called by its number
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => workspace["num"]
+                .as_u64()
+                .map(|num| num.to_string())
+                .ok_or_else(|| "Workspace has no number to fall back to".to_string())?,
+        };
+        Self::run_command(&format!(
+            "rename workspace {} to {}",
+            workspace["name"],
+            json!(name)
+        ))
+    }
+
+    fn focus_window(&self, id: u64) -> Result<(), String> {
+        Self::run_command(&format!("[con_id={}] focus", id))
+    }
+
+    fn close_window(&self, id: u64) -> Result<(), String> {
+        Self::run_command(&format!("[con_id={}] kill", id))
+    }
+
+    fn toggle_maximized(&self, id: u64) -> Result<(), String> {
+        // Sway has no maximized state; fullscreen is the closest
+        Self::run_command(&format!("[con_id={}] fullscreen toggle", id))
+    }
+
+    fn toggle_floating(&self, id: u64) -> Result<(), String> {
+        Self::run_command(&format!("[con_id={}] floating toggle", id))
+    }
+
+    fn move_window_to_workspace(&self, window_id: u64, workspace: &str) -> Result<(), String> {
+        Self::run_command(&format!(
+            "[con_id={}] move container to workspace {}",
+            window_id,
+            json!(workspace)
+        ))
+    }
+
+    fn minimize_window(&self, id: u64) -> Result<(), String> {
+        // The scratchpad is what sway has for minimizing
+        Self::run_command(&format!("[con_id={}] move scratchpad", id))
+    }
+
+    fn keyboard_layouts(&self) -> Result<KeyboardLayouts, String> {
+        // Every keyboard has its own layouts; the first with any stands for all
+        let inputs = Self::request(GET_INPUTS, "")?;
+        let keyboard = inputs
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|input| {
+                input["type"] == "keyboard"
+                    && input["xkb_layout_names"]
+                        .as_array()
+                        .is_some_and(|names| !names.is_empty())
+            })
+            .ok_or_else(|| "No keyboard with layouts".to_string())?;
+        Ok(KeyboardLayouts {
+            names: keyboard["xkb_layout_names"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|name| name.as_str().map(str::to_string))
+                .collect(),
+            current: keyboard["xkb_active_layout_index"].as_u64().unwrap_or(0) as usize,
+        })
+    }
+
+    fn switch_keyboard_layout(&self) -> Result<(), String> {
+        Self::run_command("input type:keyboard xkb_switch_layout next")
+    }
+
+    fn read_events(&self, connected: &dyn Fn(), emit: &mut dyn FnMut(Event)) -> Result<(), String> {
+        let mut connection = Connection::open()?;
+        let reply = connection.request(SUBSCRIBE, r#"["workspace", "window", "input"]"#)?;
+        if reply["success"] != true {
+            return Err("Sway refused the event subscription".to_string());
+        }
+        connected();
+        emit_state(self, emit)?;
+
+        // Events carry only the changed container, so the state is re-read
+        loop {
+            let (kind, payload) = connection.receive()?;
+            let change = payload["change"].as_str().unwrap_or_default();
+            match kind {
+                EVENT_WORKSPACE => {
+                    emit(Event::WorkspacesChanged(self.workspaces()?));
+                    if change == "focus" {
+                        let focused = self.focused_window()?.map(|window| window.id);
+                        emit(Event::WindowFocusChanged(focused));
+                    }
+                }
+                EVENT_WINDOW => {
+                    let id = payload["container"]["id"].as_u64();
+                    if let ("close", Some(id)) = (change, id) {
+                        emit(Event::WindowClosed(id));
+                    }
+                    emit_state(self, emit)?;
+                    if change == "focus" {
+                        emit(Event::WindowFocusChanged(id));
+                    }
+                }
+                EVENT_INPUT if matches!(change, "xkb_layout" | "xkb_keymap") => {
+                    emit(Event::KeyboardLayoutsChanged(self.keyboard_layouts()?));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Collect the leaves of the layout tree, which are the windows
+fn collect_windows(node: &Value, workspace_id: Option<u64>, windows: &mut Vec<Window>) {
+    let workspace_id = if node["type"] == "workspace" {
+        node["id"].as_u64()
+    } else {
+        workspace_id
+    };
+
+    let children: Vec<&Value> = [&node["nodes"], &node["floating_nodes"]]
+        .into_iter()
+        .filter_map(Value::as_array)
+        .flatten()
+        .collect();
+
+    let is_window =
+        children.is_empty() && matches!(node["type"].as_str(), Some("con" | "floating_con"));
+    if let Some(id) = node["id"].as_u64().filter(|_| is_window) {
+        // Wayland windows have an app id, X11 ones a window class
+        let app_id = node["app_id"]
+            .as_str()
+            .or_else(|| node["window_properties"]["class"].as_str());
+        windows.push(Window {
+            id,
+            title: node["name"].as_str().map(str::to_string),
+            app_id: app_id.map(str::to_string),
+            workspace_id,
+            is_focused: node["focused"] == true,
+            is_floating: node["type"] == "floating_con",
+            // 1 for fullscreen on its output, 2 across all outputs
+            is_fullscreen: node["fullscreen_mode"].as_u64().unwrap_or(0) != 0,
+        });
+    }
+
+    for child in children {
+        collect_windows(child, workspace_id, windows);
+    }
+}
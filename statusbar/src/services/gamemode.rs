@@ -0,0 +1,130 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::fs;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::watch;
+use zbus::proxy;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::bus;
+
+/// Governors change without notice, so they are re-read on this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[proxy(
+    interface = "com.feralinteractive.GameMode",
+    default_service = "com.feralinteractive.GameMode",
+    default_path = "/com/feralinteractive/GameMode"
+)]
+trait GameMode {
+    /// Process ids of the registered games with their objects
+    fn list_games(&self) -> zbus::Result<Vec<(i32, OwnedObjectPath)>>;
+
+    #[zbus(signal)]
+    fn game_registered(&self, pid: i32, game: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn game_unregistered(&self, pid: i32, game: OwnedObjectPath) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Game {
+    pub pid: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Performance {
+    /// Games GameMode is active for
+    pub games: Vec<Game>,
+    /// Distinct governors across all CPUs
+    pub governors: Vec<String>,
+}
+
+impl Performance {
+    pub fn performance_governor(&self) -> bool {
+        self.governors
+            .iter()
+            .any(|governor| governor == "performance")
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.games.is_empty() || self.performance_governor()
+    }
+}
+
+static PERFORMANCE: OnceLock<watch::Receiver<Performance>> = OnceLock::new();
+
+/// Subscribe to GameMode and CPU governor changes, starting the watcher on
+/// first use
+pub fn subscribe() -> watch::Receiver<Performance> {
+    PERFORMANCE
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(Performance::default());
+            relm4::spawn(async move {
+                if let Err(e) = watch_performance(tx).await {
+                    eprintln!("GameMode service error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+async fn watch_performance(tx: watch::Sender<Performance>) -> zbus::Result<()> {
+    let gamemode = GameModeProxy::new(&bus::session().await?).await?;
+    let mut registered = gamemode.receive_game_registered().await?;
+    let mut unregistered = gamemode.receive_game_unregistered().await?;
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {}
+            Some(_) = registered.next() => {}
+            Some(_) = unregistered.next() => {}
+        }
+
+        let performance = Performance {
+            games: games(&gamemode).await,
+            governors: governors(),
+        };
+        tx.send_if_modified(|current| {
+            if *current == performance {
+                return false;
+            }
+            *current = performance;
+            true
+        });
+    }
+}
+
+/// Registered games, none if gamemoded isn't running
+async fn games(gamemode: &GameModeProxy<'_>) -> Vec<Game> {
+    let Ok(games) = gamemode.list_games().await else {
+        return Vec::new();
+    };
+    games
+        .into_iter()
+        .map(|(pid, _)| Game {
+            pid,
+            name: fs::read_to_string(format!("/proc/{}/comm", pid))
+                .map(|comm| comm.trim().to_string())
+                .unwrap_or_else(|_| pid.to_string()),
+        })
+        .collect()
+}
+
+fn governors() -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/sys/devices/system/cpu") else {
+        return Vec::new();
+    };
+    let mut governors: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path().join("cpufreq/scaling_governor")).ok())
+        .map(|governor| governor.trim().to_string())
+        .collect();
+    governors.sort();
+    governors.dedup();
+    governors
+}
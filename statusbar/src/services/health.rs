@@ -0,0 +1,109 @@
+// Failures of the backends widgets depend on
+//
+// Services report errors here rather than only printing them, so the
+// diagnostics widget can list the ones that keep happening. Retrying is up to
+// whoever owns the backend, see `on_retry`.
+use chrono::{DateTime, Local};
+use std::sync::OnceLock;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::watch;
+
+/// Backend names, as shown in the diagnostics popover
+pub const COMPOSITOR: &str = "Compositor";
+pub const NETWORK: &str = "NetworkManager";
+pub const MPRIS: &str = "Media players";
+
+/// A backend failing since `since`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub backend: &'static str,
+    /// The latest error
+    pub message: String,
+    pub since: DateTime<Local>,
+    pub last: DateTime<Local>,
+    /// Failures in a row
+    pub count: u32,
+}
+
+static FAILURES: OnceLock<watch::Sender<Vec<Failure>>> = OnceLock::new();
+static RETRIES: OnceLock<broadcast::Sender<&'static str>> = OnceLock::new();
+
+fn failures() -> &'static watch::Sender<Vec<Failure>> {
+    FAILURES.get_or_init(|| watch::channel(Vec::new()).0)
+}
+
+fn retries() -> &'static broadcast::Sender<&'static str> {
+    RETRIES.get_or_init(|| broadcast::channel(8).0)
+}
+
+/// Subscribe to the backends currently failing
+pub fn subscribe() -> watch::Receiver<Vec<Failure>> {
+    failures().subscribe()
+}
+
+/// Record that `backend` failed
+///
+/// Only the first failure in a row, or one with a different error, is
+/// printed, so a backend retrying in a loop doesn't flood the log.
+pub fn report(backend: &'static str, error: impl ToString) {
+    let message = error.to_string();
+    let now = Local::now();
+    failures().send_modify(|failures| {
+        let failing = failures
+            .iter_mut()
+            .find(|failure| failure.backend == backend);
+        match failing {
+            Some(failure) => {
+                if failure.message != message {
+                    eprintln!("{}: {}", backend, message);
+                }
+                failure.message = message;
+                failure.last = now;
+                failure.count += 1;
+            }
+            None => {
+                eprintln!("{}: {}", backend, message);
+                failures.push(Failure {
+                    backend,
+                    message,
+                    since: now,
+                    last: now,
+                    count: 1,
+                });
+            }
+        }
+    });
+}
+
+/// Record that `backend` works again
+pub fn clear(backend: &'static str) {
+    failures().send_if_modified(|failures| {
+        let before = failures.len();
+        failures.retain(|failure| failure.backend != backend);
+        failures.len() != before
+    });
+}
+
+/// Ask the owner of `backend` to try again right away
+pub fn retry(backend: &'static str) {
+    // Fails only when nobody can retry it
+    let _ = retries().send(backend);
+}
+
+/// Call `f` whenever a retry of `backend` is asked for, until it returns false
+pub fn on_retry(backend: &'static str, f: impl Fn() -> bool + Send + 'static) {
+    let mut retries_rx = retries().subscribe();
+    relm4::spawn(async move {
+        loop {
+            match retries_rx.recv().await {
+                Ok(requested) if requested == backend => {
+                    if !f() {
+                        break;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
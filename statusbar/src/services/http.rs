@@ -0,0 +1,189 @@
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_NONE_MATCH, RETRY_AFTER};
+use reqwest::{Client, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// Give up on a single attempt after this long
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts per request, including the first
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry, doubled on each failure
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Longest `Retry-After` we are willing to honor
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Minimum gap between any two requests the bar sends
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Body of a response together with its validator
+struct CachedResponse {
+    etag: String,
+    body: String,
+}
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+static CACHE: OnceLock<Mutex<HashMap<String, CachedResponse>>> = OnceLock::new();
+static NEXT_SLOT: OnceLock<tokio::sync::Mutex<Instant>> = OnceLock::new();
+
+fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .timeout(TIMEOUT)
+            .user_agent(concat!("statusbar/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to configure the HTTP client: {}", e);
+                Client::new()
+            })
+    })
+}
+
+fn cache() -> std::sync::MutexGuard<'static, HashMap<String, CachedResponse>> {
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Wait for our turn so requests are spaced by at least [`MIN_INTERVAL`]
+async fn wait_for_slot() {
+    let next_slot = NEXT_SLOT.get_or_init(|| tokio::sync::Mutex::new(Instant::now()));
+    let mut next = next_slot.lock().await;
+    time::sleep_until(*next).await;
+    *next = Instant::now() + MIN_INTERVAL;
+}
+
+/// Fetch `url` as text
+///
+/// Requests are rate limited across the whole bar and retried with backoff
+/// on network errors, rate limiting and server errors. Responses carrying an
+/// `ETag` are remembered, so an unchanged resource is revalidated instead of
+/// downloaded again.
+pub async fn get(url: &str) -> Result<String, String> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match fetch(url).await {
+            Ok(body) => return Ok(body),
+            Err(Failure::Fatal(e)) => return Err(e),
+            Err(Failure::Retry(e, retry_after)) if attempt < MAX_ATTEMPTS => {
+                let delay = retry_after.unwrap_or(backoff).min(MAX_RETRY_AFTER);
+                eprintln!("Request to {} failed: {}, retrying in {:?}", url, e, delay);
+                time::sleep(delay).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(Failure::Retry(e, _)) => return Err(e),
+        }
+    }
+}
+
+/// Fetch `url` and parse it as JSON
+pub async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, String> {
+    let body = get(url).await?;
+    serde_json::from_str(&body).map_err(|e| format!("Invalid JSON from {}: {}", url, e))
+}
+
+/// Send a request to an API acting on the user's account, with basic auth
+/// and an optional JSON body, returning the JSON reply or null if empty
+///
+/// Unlike [`get`] nothing is cached or retried, as repeating a change isn't
+/// always safe.
+pub async fn send_json(
+    method: Method,
+    url: &str,
+    auth: (&str, &str),
+    body: Option<&Value>,
+) -> Result<Value, String> {
+    wait_for_slot().await;
+
+    let mut request = client()
+        .request(method, url)
+        .basic_auth(auth.0, Some(auth.1));
+    if let Some(body) = body {
+        request = request
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, text.trim()));
+    }
+    if text.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Invalid JSON from {}: {}", url, e))
+}
+
+enum Failure {
+    /// Worth trying again, optionally after a delay the server asked for
+    Retry(String, Option<Duration>),
+    Fatal(String),
+}
+
+async fn fetch(url: &str) -> Result<String, Failure> {
+    wait_for_slot().await;
+
+    let mut request = client().get(url);
+    if let Some(cached) = cache().get(url) {
+        request = request.header(IF_NONE_MATCH, cached.etag.as_str());
+    }
+
+    let response = request.send().await.map_err(|e| {
+        if e.is_timeout() || e.is_connect() || e.is_request() {
+            Failure::Retry(e.to_string(), None)
+        } else {
+            Failure::Fatal(e.to_string())
+        }
+    })?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED {
+        return cache()
+            .get(url)
+            .map(|cached| cached.body.clone())
+            .ok_or_else(|| Failure::Fatal("Not modified, but nothing cached".to_string()));
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .map(Duration::from_secs);
+        return Err(Failure::Retry(status.to_string(), retry_after));
+    }
+    if !status.is_success() {
+        return Err(Failure::Fatal(status.to_string()));
+    }
+
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .text()
+        .await
+        .map_err(|e| Failure::Retry(e.to_string(), None))?;
+
+    if let Some(etag) = etag {
+        cache().insert(
+            url.to_string(),
+            CachedResponse {
+                etag,
+                body: body.clone(),
+            },
+        );
+    }
+    Ok(body)
+}
@@ -0,0 +1,51 @@
+use futures_util::StreamExt;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+use zbus::proxy;
+
+use super::bus;
+
+// logind session proxy (the "auto" path resolves to the caller's session)
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    /// Set by the idle daemon once the session has been idle long enough
+    #[zbus(property)]
+    fn idle_hint(&self) -> zbus::Result<bool>;
+}
+
+static IDLE_STATE: OnceLock<watch::Receiver<bool>> = OnceLock::new();
+
+/// Subscribe to session idle state, starting the watcher on first use
+pub fn subscribe() -> watch::Receiver<bool> {
+    IDLE_STATE
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(false);
+            relm4::spawn(async move {
+                if let Err(e) = watch_idle_hint(tx).await {
+                    eprintln!("Idle service error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+async fn watch_idle_hint(tx: watch::Sender<bool>) -> zbus::Result<()> {
+    let connection = bus::system().await?;
+    let session = SessionProxy::new(&connection).await?;
+
+    tx.send_replace(session.idle_hint().await?);
+
+    let mut changes = session.receive_idle_hint_changed().await;
+    while let Some(change) = changes.next().await {
+        if let Ok(idle) = change.get().await {
+            tx.send_replace(idle);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::watch;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{Connection, connection, proxy};
+
+use super::bus;
+use crate::config::InputMethodBackend;
+
+/// fcitx5 has no signal for switching input methods, so it is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wait before looking for a daemon that isn't running
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One entry of `AvailableInputMethods`: unique name, name, native name,
+/// icon, label, language code and whether it's configurable
+type Fcitx5InputMethod = (String, String, String, String, String, String, bool);
+
+#[proxy(
+    interface = "org.fcitx.Fcitx.Controller1",
+    default_service = "org.fcitx.Fcitx5",
+    default_path = "/controller"
+)]
+trait Fcitx5Controller {
+    fn current_input_method(&self) -> zbus::Result<String>;
+
+    fn current_input_method_group(&self) -> zbus::Result<String>;
+
+    /// Default layout and the `(input method, layout)` pairs of a group
+    fn input_method_group_info(&self, name: &str) -> zbus::Result<(String, Vec<(String, String)>)>;
+
+    fn available_input_methods(&self) -> zbus::Result<Vec<Fcitx5InputMethod>>;
+
+    #[zbus(name = "SetCurrentIM")]
+    fn set_current_im(&self, name: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn input_method_groups_changed(&self) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.IBus",
+    default_service = "org.freedesktop.IBus",
+    default_path = "/org/freedesktop/IBus"
+)]
+trait IBus {
+    fn get_global_engine(&self) -> zbus::Result<OwnedValue>;
+
+    fn set_global_engine(&self, name: &str) -> zbus::Result<()>;
+
+    fn get_engines_by_names(&self, names: &[&str]) -> zbus::Result<Vec<OwnedValue>>;
+
+    #[zbus(signal)]
+    fn global_engine_changed(&self, name: String) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InputMethod {
+    /// Name the daemon knows it by, e.g. `keyboard-us` or `libpinyin`
+    pub id: String,
+    /// Human readable name
+    pub name: String,
+    /// Short label the daemon suggests, may be empty
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct InputMethods {
+    /// Configured input methods, in switching order
+    pub methods: Vec<InputMethod>,
+    /// id of the active one
+    pub current: String,
+}
+
+static FCITX5: OnceLock<watch::Receiver<Option<InputMethods>>> = OnceLock::new();
+static IBUS: OnceLock<watch::Receiver<Option<InputMethods>>> = OnceLock::new();
+
+/// Subscribe to the input methods of a daemon, starting the watcher on first
+/// use; None while the daemon isn't running
+pub fn subscribe(backend: InputMethodBackend) -> watch::Receiver<Option<InputMethods>> {
+    let cell = match backend {
+        InputMethodBackend::Fcitx5 => &FCITX5,
+        InputMethodBackend::Ibus => &IBUS,
+    };
+    cell.get_or_init(|| {
+        let (tx, rx) = watch::channel(None);
+        relm4::spawn(async move {
+            let mut reported = false;
+            loop {
+                let result = match backend {
+                    InputMethodBackend::Fcitx5 => {
+                        watch_fcitx5(&tx).await.map_err(|e| e.to_string())
+                    }
+                    InputMethodBackend::Ibus => watch_ibus(&tx).await,
+                };
+                // Log when the daemon goes away, not on every retry
+                if let Err(e) = result {
+                    if !reported || tx.borrow().is_some() {
+                        eprintln!("Input method service error: {}", e);
+                    }
+                    reported = true;
+                }
+                tx.send_replace(None);
+                tokio::time::sleep(RETRY_INTERVAL).await;
+            }
+        });
+        rx
+    })
+    .clone()
+}
+
+/// Activate an input method by id
+pub async fn switch(backend: InputMethodBackend, id: &str) -> Result<(), String> {
+    let result = match backend {
+        InputMethodBackend::Fcitx5 => {
+            let connection = bus::session().await.map_err(|e| e.to_string())?;
+            let controller = Fcitx5ControllerProxy::new(&connection)
+                .await
+                .map_err(|e| e.to_string())?;
+            controller.set_current_im(id).await
+        }
+        InputMethodBackend::Ibus => {
+            let ibus = IBusProxy::new(&ibus_connection().await?)
+                .await
+                .map_err(|e| e.to_string())?;
+            ibus.set_global_engine(id).await
+        }
+    };
+    result.map_err(|e| format!("Failed to switch to {}: {}", id, e))
+}
+
+fn publish(tx: &watch::Sender<Option<InputMethods>>, methods: InputMethods) {
+    tx.send_if_modified(|current| {
+        if current.as_ref() == Some(&methods) {
+            return false;
+        }
+        *current = Some(methods);
+        true
+    });
+}
+
+async fn watch_fcitx5(tx: &watch::Sender<Option<InputMethods>>) -> zbus::Result<()> {
+    let controller = Fcitx5ControllerProxy::new(&bus::session().await?).await?;
+    let mut groups_changed = controller.receive_input_method_groups_changed().await?;
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+    // The group's input methods only change with the group or its settings
+    let mut group = None;
+    let mut methods = Vec::new();
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {}
+            Some(_) = groups_changed.next() => group = None,
+        }
+
+        let current_group = controller.current_input_method_group().await?;
+        if group.as_ref() != Some(&current_group) {
+            methods = fcitx5_methods(&controller, &current_group).await?;
+            group = Some(current_group);
+        }
+        let current = controller.current_input_method().await?;
+        publish(
+            tx,
+            InputMethods {
+                methods: methods.clone(),
+                current,
+            },
+        );
+    }
+}
+
+async fn fcitx5_methods(
+    controller: &Fcitx5ControllerProxy<'_>,
+    group: &str,
+) -> zbus::Result<Vec<InputMethod>> {
+    let (_, items) = controller.input_method_group_info(group).await?;
+    let available = controller.available_input_methods().await?;
+    Ok(items
+        .into_iter()
+        .map(|(id, _)| {
+            let info = available.iter().find(|info| info.0 == id);
+            InputMethod {
+                name: info.map_or_else(|| id.clone(), |info| info.1.clone()),
+                label: info.map(|info| info.4.clone()).unwrap_or_default(),
+                id,
+            }
+        })
+        .collect())
+}
+
+/// IBus runs its own bus rather than using the session one
+async fn ibus_connection() -> Result<Connection, String> {
+    let address = tokio::task::spawn_blocking(ibus_address)
+        .await
+        .map_err(|e| e.to_string())??;
+    connection::Builder::address(address.as_str())
+        .map_err(|e| e.to_string())?
+        .build()
+        .await
+        .map_err(|e| format!("Failed to connect to ibus-daemon: {}", e))
+}
+
+fn ibus_address() -> Result<String, String> {
+    if let Ok(address) = std::env::var("IBUS_ADDRESS") {
+        return Ok(address);
+    }
+    let output = Command::new("ibus")
+        .arg("address")
+        .output()
+        .map_err(|e| format!("Failed to run ibus: {}", e))?;
+    let address = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || address.is_empty() || address == "(null)" {
+        return Err("ibus-daemon is not running".to_string());
+    }
+    Ok(address)
+}
+
+async fn watch_ibus(tx: &watch::Sender<Option<InputMethods>>) -> Result<(), String> {
+    let connection = ibus_connection().await?;
+    let ibus = IBusProxy::new(&connection)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut changes = ibus
+        .receive_global_engine_changed()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        // Re-read the engine list too, it may have been edited since
+        let methods = ibus_methods(&ibus).await.map_err(|e| e.to_string())?;
+        publish(tx, methods);
+        if changes.next().await.is_none() {
+            return Err("ibus-daemon went away".to_string());
+        }
+    }
+}
+
+async fn ibus_methods(ibus: &IBusProxy<'_>) -> zbus::Result<InputMethods> {
+    let current = engine_desc(&ibus.get_global_engine().await?.into());
+
+    let names = tokio::task::spawn_blocking(preload_engines)
+        .await
+        .unwrap_or_default();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut methods: Vec<InputMethod> = ibus
+        .get_engines_by_names(&names)
+        .await?
+        .into_iter()
+        .filter_map(|engine| engine_desc(&engine.into()))
+        .collect();
+    // Without preferences the only known engine is the active one
+    if methods.is_empty() {
+        methods.extend(current.clone());
+    }
+
+    Ok(InputMethods {
+        methods,
+        current: current.map(|engine| engine.id).unwrap_or_default(),
+    })
+}
+
+/// Engines enabled in the IBus preferences, which only live in GSettings
+fn preload_engines() -> Vec<String> {
+    let output = match Command::new("gsettings")
+        .args(["get", "org.freedesktop.ibus.general", "preload-engines"])
+        .output()
+    {
+        Ok(output) if output.status.success() => output.stdout,
+        _ => return Vec::new(),
+    };
+
+    // GVariant text, `['xkb:us::eng', 'libpinyin']` or `@as []`
+    let text = String::from_utf8_lossy(&output);
+    let text = text.trim().trim_start_matches("@as").trim();
+    text.trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|name| name.trim().trim_matches('\'').to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Read a serialized `IBusEngineDesc`, a struct of its type name,
+/// attachments, then name, long name, description, language, license,
+/// author, icon, layout, rank, hotkeys and symbol
+fn engine_desc(value: &Value<'_>) -> Option<InputMethod> {
+    let fields = match value {
+        Value::Value(inner) => return engine_desc(inner),
+        Value::Structure(structure) => structure.fields(),
+        _ => return None,
+    };
+    let string = |index: usize| match fields.get(index) {
+        Some(Value::Str(text)) => text.to_string(),
+        _ => String::new(),
+    };
+
+    let id = string(2);
+    if id.is_empty() {
+        return None;
+    }
+    Some(InputMethod {
+        id,
+        name: string(3),
+        label: string(12),
+    })
+}
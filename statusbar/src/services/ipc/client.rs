@@ -0,0 +1,36 @@
+// Client side of the control socket
+//
+// Only needs std and `dirs`, so `statusbarctl` builds it in as well.
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+/// `$XDG_RUNTIME_DIR/statusbar.sock`
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("statusbar.sock")
+}
+
+/// Send one command to the running bar and return its reply
+pub fn send(command: &str) -> Result<String, String> {
+    let path = socket_path();
+    let mut stream = UnixStream::connect(&path).map_err(|e| {
+        format!(
+            "Is the bar running? Failed to connect to {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    writeln!(stream, "{}", command).map_err(|e| e.to_string())?;
+    stream
+        .shutdown(std::net::Shutdown::Write)
+        .map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| e.to_string())?;
+    Ok(reply.trim_end().to_string())
+}
@@ -0,0 +1,178 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::str::FromStr;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::rpc;
+use super::status_item::QuickAction;
+
+mod client;
+
+pub use client::{send, socket_path};
+
+/// How long a client waits for the bar to answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often `--replace` checks whether the old bar is gone
+const REPLACE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Commands the bar executes for IPC clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcCommand {
+    /// Close the open popover and open the next widget's one
+    CyclePopover,
+    /// Reply with a JSON snapshot of every widget
+    DumpState,
+    /// Snapshot of one widget, or of all of them
+    GetState {
+        widget: Option<String>,
+    },
+    SetWidgetVisible {
+        widget: String,
+        visible: bool,
+    },
+    ReloadWidget {
+        widget: String,
+    },
+    /// Hide the bars, or show them again
+    ToggleVisibility,
+    SetVisible(bool),
+    /// Re-read the config and relaunch every widget
+    ReloadConfig,
+    OpenPopover {
+        widget: String,
+    },
+    SetDoNotDisturb(bool),
+    Trigger(QuickAction),
+}
+
+/// Reply to a command; plain-text clients see strings verbatim
+pub type IpcResult = Result<serde_json::Value, String>;
+
+impl FromStr for IpcCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["cycle-popover"] => Ok(Self::CyclePopover),
+            ["dump-state"] => Ok(Self::DumpState),
+            ["toggle-visibility"] => Ok(Self::ToggleVisibility),
+            ["show"] => Ok(Self::SetVisible(true)),
+            ["hide"] => Ok(Self::SetVisible(false)),
+            ["reload-config"] => Ok(Self::ReloadConfig),
+            ["reload-theme"] => Ok(Self::Trigger(QuickAction::ReloadTheme)),
+            ["open-popover", widget] => Ok(Self::OpenPopover {
+                widget: widget.to_string(),
+            }),
+            ["set", "dnd", "on"] => Ok(Self::SetDoNotDisturb(true)),
+            ["set", "dnd", "off"] => Ok(Self::SetDoNotDisturb(false)),
+            ["set", "dnd", value] => Err(format!("Expected 'on' or 'off', got '{}'", value)),
+            ["trigger", action] => action.parse().map(Self::Trigger),
+            _ => Err(format!("Unknown command '{}'", s.trim())),
+        }
+    }
+}
+
+/// Answers a single command from the GTK thread
+#[derive(Debug)]
+pub struct Responder(mpsc::SyncSender<IpcResult>);
+
+impl Responder {
+    pub fn send(self, reply: IpcResult) {
+        let _ = self.0.send(reply);
+    }
+}
+
+/// Accept commands on the control socket in a background thread
+///
+/// Each client gets its own thread, so one that stays connected doesn't
+/// hold up the others.
+pub fn listen(on_command: impl Fn(IpcCommand, Responder) + Send + Sync + 'static) {
+    let path = socket_path();
+
+    // A previous instance may have left its socket behind
+    let _ = std::fs::remove_file(&path);
+
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to bind {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    let on_command = Arc::new(on_command);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let on_command = on_command.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_client(stream, &*on_command) {
+                    eprintln!("IPC client error: {}", e);
+                }
+            });
+        }
+    });
+}
+
+/// Hand a command to the bar and block until it answers
+pub fn dispatch(on_command: &dyn Fn(IpcCommand, Responder), command: IpcCommand) -> IpcResult {
+    let (tx, rx) = mpsc::sync_channel(1);
+    on_command(command, Responder(tx));
+    rx.recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| Err("The bar did not answer".to_string()))
+}
+
+fn handle_client(
+    stream: UnixStream,
+    on_command: &impl Fn(IpcCommand, Responder),
+) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let run = |command| dispatch(on_command, command);
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // JSON-RPC requests are objects, everything else is a plain command
+        let reply = if line.trim_start().starts_with('{') {
+            rpc::handle(&line, run)
+        } else {
+            Some(match line.parse::<IpcCommand>().and_then(run) {
+                Ok(serde_json::Value::Null) => "ok".to_string(),
+                Ok(serde_json::Value::String(text)) => text,
+                Ok(value) => value.to_string(),
+                Err(e) => format!("error: {}", e),
+            })
+        };
+
+        if let Some(reply) = reply {
+            writeln!(writer, "{}", reply)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether another bar answers on the control socket
+pub fn is_running() -> bool {
+    UnixStream::connect(socket_path()).is_ok()
+}
+
+/// Ask the running bar to quit and wait until it has
+pub fn replace() -> Result<(), String> {
+    send("trigger quit")?;
+
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    while is_running() {
+        if Instant::now() > deadline {
+            return Err("The running bar did not quit".to_string());
+        }
+        thread::sleep(REPLACE_POLL_INTERVAL);
+    }
+    Ok(())
+}
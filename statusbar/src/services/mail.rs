@@ -0,0 +1,160 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::{self, ClientConfig, RootCertStore, pki_types::ServerName};
+
+use super::secrets;
+use crate::config::EmailAccountConfig;
+
+/// Give up on a server that stops answering
+const IMAP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Count unread messages of an account, from its Maildir if it has one and
+/// from its IMAP server otherwise
+pub async fn unread(account: &EmailAccountConfig) -> Result<u32, String> {
+    if !account.maildir.is_empty() {
+        return maildir_unread(Path::new(&account.maildir));
+    }
+
+    // Stored under the account's name
+    let password = secrets::lookup(&account.name).await?;
+    tokio::time::timeout(IMAP_TIMEOUT, imap_unread(account, &password))
+        .await
+        .map_err(|_| format!("{} timed out", account.imap_host))?
+}
+
+/// New messages, plus read-but-unseen ones lacking the `S` flag
+fn maildir_unread(maildir: &Path) -> Result<u32, String> {
+    let read_dir = |name: &str| {
+        fs::read_dir(maildir.join(name))
+            .map_err(|e| format!("Failed to read {}: {}", maildir.join(name).display(), e))
+    };
+
+    let new = read_dir("new")?.flatten().count();
+    let unseen = read_dir("cur")?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // Flags follow `:2,` at the end of the file name
+            name.rsplit_once(":2,")
+                .is_none_or(|(_, flags)| !flags.contains('S'))
+        })
+        .count();
+    Ok((new + unseen) as u32)
+}
+
+async fn imap_unread(account: &EmailAccountConfig, password: &str) -> Result<u32, String> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+    let host = account.imap_host.as_str();
+    let server_name = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+    let tcp = TcpStream::connect((host, account.imap_port))
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", host, e))?;
+    let tls = TlsConnector::from(Arc::new(config))
+        .connect(server_name, tcp)
+        .await
+        .map_err(|e| format!("TLS with {} failed: {}", host, e))?;
+
+    let mut imap = Imap::new(tls);
+    imap.read_greeting().await?;
+    imap.command(&format!(
+        "LOGIN {} {}",
+        quote(&account.username),
+        quote(password)
+    ))
+    .await?;
+    let status = imap
+        .command(&format!("STATUS {} (UNSEEN)", quote(&account.mailbox)))
+        .await?;
+    let _ = imap.command("LOGOUT").await;
+
+    // `* STATUS INBOX (UNSEEN 3)`
+    status
+        .iter()
+        .find_map(|line| {
+            let (_, rest) = line.split_once("UNSEEN ")?;
+            rest.trim_end_matches(')').trim().parse().ok()
+        })
+        .ok_or_else(|| "Unexpected STATUS reply".to_string())
+}
+
+/// IMAP quoted string
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Just enough of an IMAP client to ask for a mailbox's status
+struct Imap<S> {
+    stream: BufReader<S>,
+    next_tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Imap<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            next_tag: 1,
+        }
+    }
+
+    async fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        let read = self
+            .stream
+            .read_line(&mut line)
+            .await
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("Connection closed".to_string());
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    async fn read_greeting(&mut self) -> Result<(), String> {
+        let greeting = self.read_line().await?;
+        if greeting.starts_with("* OK") || greeting.starts_with("* PREAUTH") {
+            Ok(())
+        } else {
+            Err(format!("Unexpected greeting: {}", greeting))
+        }
+    }
+
+    /// Send a command, returning the untagged lines of its reply
+    async fn command(&mut self, command: &str) -> Result<Vec<String>, String> {
+        let tag = format!("a{}", self.next_tag);
+        self.next_tag += 1;
+        self.stream
+            .get_mut()
+            .write_all(format!("{} {}\r\n", tag, command).as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut untagged = Vec::new();
+        loop {
+            let line = self.read_line().await?;
+            let Some(result) = line.strip_prefix(&format!("{} ", tag)) else {
+                untagged.push(line);
+                continue;
+            };
+            return if result.starts_with("OK") {
+                Ok(untagged)
+            } else {
+                // Don't echo the command, it may hold the password
+                Err(format!("Server said: {}", result))
+            };
+        }
+    }
+}
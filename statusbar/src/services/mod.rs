@@ -0,0 +1,35 @@
+// Shared background services used by widgets
+//
+// Async work runs on relm4's runtime, through `relm4::spawn` or component
+// commands, so there is a single tokio runtime for the whole bar.
+pub mod audio;
+pub mod backlight;
+pub mod bar_service;
+pub mod bluetooth;
+pub mod bus;
+pub mod compositor;
+pub mod gamemode;
+pub mod health;
+pub mod http;
+pub mod idle;
+pub mod input_method;
+pub mod ipc;
+pub mod mail;
+pub mod network;
+pub mod night_light;
+pub mod notifications;
+pub mod notify;
+pub mod portal;
+pub mod power;
+pub mod privacy;
+pub mod privacy_indicator;
+pub mod rpc;
+pub mod script;
+pub mod secrets;
+pub mod status_item;
+pub mod systemd;
+pub mod ticker;
+pub mod time_tracking;
+pub mod timedate;
+pub mod updates;
+pub mod vpn;
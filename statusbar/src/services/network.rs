@@ -0,0 +1,78 @@
+use futures_util::StreamExt;
+use nm_dbus::{AccessPoint, ActiveConnection, EapConfig, NetworkManagerClient};
+use std::pin::pin;
+use tokio::sync::OnceCell;
+
+use super::bus;
+
+/// The one NetworkManager client, connected on first use
+static CLIENT: OnceCell<NetworkManagerClient> = OnceCell::const_new();
+
+async fn client() -> Result<&'static NetworkManagerClient, String> {
+    CLIENT
+        .get_or_try_init(|| async {
+            bus::system()
+                .await
+                .map(NetworkManagerClient::with_connection)
+        })
+        .await
+        .map_err(|e| format!("Failed to connect to NetworkManager: {}", e))
+}
+
+/// The connected Wi-Fi network, if any
+pub async fn active_connection() -> Result<Option<ActiveConnection>, String> {
+    client()
+        .await?
+        .get_active_connection()
+        .await
+        .map_err(|e| format!("Failed to get active connection: {}", e))
+}
+
+/// Scan and list the networks in range, strongest first
+pub async fn scan() -> Result<Vec<AccessPoint>, String> {
+    client()
+        .await?
+        .scan_networks()
+        .await
+        .map_err(|e| format!("Failed to scan networks: {}", e))
+}
+
+/// Call `f` with the signal strength of the access point at `path` each time
+/// it changes, until the connection to NetworkManager breaks
+pub async fn watch_strength(path: &str, f: impl Fn(u8)) -> Result<(), String> {
+    let strengths = client()
+        .await?
+        .watch_strength(path)
+        .await
+        .map_err(|e| format!("Failed to watch signal strength: {}", e))?;
+    let mut strengths = pin!(strengths);
+    while let Some(strength) = strengths.next().await {
+        f(strength);
+    }
+    Ok(())
+}
+
+pub async fn connect(ssid: &str) -> Result<(), String> {
+    client()
+        .await?
+        .connect_to_network(ssid)
+        .await
+        .map_err(|e| format!("Failed to connect: {}", e))
+}
+
+/// Connect to the enterprise network `ssid`, saving its 802.1X settings
+pub async fn connect_enterprise(ssid: &str, eap: &EapConfig) -> Result<(), String> {
+    client()
+        .await?
+        .connect_enterprise(ssid, eap)
+        .await
+        .map_err(|e| format!("Failed to connect: {}", e))
+}
+
+pub async fn disconnect(interface: &str) -> Result<(), String> {
+    client()
+        .await?
+        .disconnect(interface)
+        .await
+        .map_err(|e| format!("Failed to disconnect: {}", e))
+}
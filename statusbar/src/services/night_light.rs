@@ -0,0 +1,91 @@
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+
+use crate::config::{NightLightBackend, NightLightConfig};
+
+/// The running filter process; gamma is restored when it exits
+static FILTER: Mutex<Option<Child>> = Mutex::new(None);
+
+impl NightLightBackend {
+    fn command(self, config: &NightLightConfig) -> Command {
+        let day = config.day_temperature;
+        let night = config.night_temperature;
+        let location = config.latitude.zip(config.longitude);
+
+        let mut command = match self {
+            NightLightBackend::Wlsunset => {
+                let mut command = Command::new("wlsunset");
+                match location {
+                    Some((latitude, longitude)) => command
+                        .arg("-l")
+                        .arg(latitude.to_string())
+                        .arg("-L")
+                        .arg(longitude.to_string())
+                        .arg("-t")
+                        .arg(night.to_string())
+                        .arg("-T")
+                        .arg(day.max(night + 1).to_string()),
+                    // wlsunset needs the day warmer than the night, so a
+                    // one-degree "day" keeps the night temperature all day
+                    None => command
+                        .arg("-t")
+                        .arg(night.to_string())
+                        .arg("-T")
+                        .arg((night + 1).to_string()),
+                };
+                command
+            }
+            NightLightBackend::Gammastep => {
+                let mut command = Command::new("gammastep");
+                match location {
+                    Some((latitude, longitude)) => command
+                        .arg("-l")
+                        .arg(format!("{}:{}", latitude, longitude))
+                        .arg("-t")
+                        .arg(format!("{}:{}", day, night)),
+                    None => command.arg("-O").arg(night.to_string()),
+                };
+                command
+            }
+        };
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        command
+    }
+}
+
+/// Start the filter, replacing one already started by the bar
+pub fn start(config: &NightLightConfig) -> Result<(), String> {
+    stop();
+    let child = config
+        .backend
+        .command(config)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", config.backend.name(), e))?;
+    *FILTER.lock().unwrap() = Some(child);
+    Ok(())
+}
+
+/// Stop the filter, restoring normal colors
+pub fn stop() {
+    if let Some(mut child) = FILTER.lock().unwrap().take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Whether the filter started by the bar is still running
+pub fn is_running() -> bool {
+    let mut filter = FILTER.lock().unwrap();
+    match filter.as_mut().map(Child::try_wait) {
+        Some(Ok(None)) => true,
+        Some(_) => {
+            // Exited on its own, e.g. no gamma control on this compositor
+            *filter = None;
+            false
+        }
+        None => false,
+    }
+}
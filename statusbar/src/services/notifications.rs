@@ -0,0 +1,343 @@
+use chrono::{DateTime, Local};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use tokio::sync::watch;
+use zbus::message::Type as MessageType;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, MatchRule, MessageStream, connection, fdo::MonitoringProxy, interface};
+
+use super::bus;
+
+/// Notifications kept in the history
+const HISTORY_LIMIT: usize = 50;
+
+const SERVER_NAME: &str = "org.freedesktop.Notifications";
+const SERVER_PATH: &str = "/org/freedesktop/Notifications";
+
+/// Why a notification was closed, as sent in `NotificationClosed`
+#[derive(Debug, Clone, Copy)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    /// The app called `CloseNotification`
+    Closed = 3,
+}
+
+/// A notification sent by some application
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    /// Icon name or file path, possibly empty
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    /// Action keys and labels; `default` is invoked by clicking the banner
+    pub actions: Vec<(String, String)>,
+    pub critical: bool,
+    /// Milliseconds until the banner expires, 0 for never, -1 for our default
+    pub expire_timeout: i32,
+    pub time: DateTime<Local>,
+}
+
+impl Notification {
+    fn new(id: u32, args: NotifyArgs) -> Self {
+        let (app_name, _, app_icon, summary, body, actions, hints, expire_timeout) = args;
+        Self {
+            id,
+            app_name,
+            app_icon,
+            summary,
+            body,
+            // Actions come as a flat list of key, label pairs
+            actions: actions
+                .chunks_exact(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect(),
+            critical: hints
+                .get("urgency")
+                .and_then(|urgency| u8::try_from(urgency).ok())
+                == Some(2),
+            expire_timeout,
+            time: Local::now(),
+        }
+    }
+}
+
+/// Arguments of `org.freedesktop.Notifications.Notify`
+type NotifyArgs = (
+    String,
+    u32,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, OwnedValue>,
+    i32,
+);
+
+static HISTORY: OnceLock<watch::Sender<Vec<Notification>>> = OnceLock::new();
+
+/// Whether to become the notification daemon instead of watching one
+static SERVE: AtomicBool = AtomicBool::new(false);
+
+/// Connection owning the daemon name, once acquired
+static SERVER: OnceLock<Connection> = OnceLock::new();
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Hold back banners of all but critical notifications
+static DO_NOT_DISTURB: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+fn history() -> &'static watch::Sender<Vec<Notification>> {
+    HISTORY.get_or_init(|| {
+        let (tx, _) = watch::channel(Vec::new());
+        let task_tx = tx.clone();
+        let serve = SERVE.load(Ordering::Relaxed);
+        relm4::spawn(async move {
+            if serve {
+                match run_server(task_tx.clone()).await {
+                    Ok(()) => return,
+                    // Most likely another daemon owns the name
+                    Err(e) => eprintln!("Failed to become the notification daemon: {}", e),
+                }
+            }
+            if let Err(e) = monitor_notifications(task_tx).await {
+                eprintln!("Notification history error: {}", e);
+            }
+        });
+        tx
+    })
+}
+
+/// Act as the desktop's notification daemon
+///
+/// Must run before anything subscribes; the history otherwise comes from
+/// watching the running daemon.
+pub fn serve() {
+    SERVE.store(true, Ordering::Relaxed);
+    history();
+}
+
+/// Whether the bar is the notification daemon, so it should show banners
+pub fn is_serving() -> bool {
+    SERVER.get().is_some()
+}
+
+/// Subscribe to the notification history, newest first
+pub fn subscribe() -> watch::Receiver<Vec<Notification>> {
+    history().subscribe()
+}
+
+fn do_not_disturb() -> &'static watch::Sender<bool> {
+    DO_NOT_DISTURB.get_or_init(|| watch::channel(false).0)
+}
+
+/// Subscribe to do not disturb being switched on or off
+pub fn subscribe_do_not_disturb() -> watch::Receiver<bool> {
+    do_not_disturb().subscribe()
+}
+
+pub fn is_do_not_disturb() -> bool {
+    *do_not_disturb().borrow()
+}
+
+pub fn set_do_not_disturb(enabled: bool) {
+    do_not_disturb().send_if_modified(|current| {
+        let changed = *current != enabled;
+        *current = enabled;
+        changed
+    });
+}
+
+pub fn clear() {
+    let cleared = history().send_replace(Vec::new());
+    for notification in cleared {
+        emit_closed(notification.id, CloseReason::Dismissed);
+    }
+}
+
+/// Remove a notification from the history
+pub fn dismiss(id: u32) {
+    history().send_if_modified(|history| {
+        let before = history.len();
+        history.retain(|notification| notification.id != id);
+        history.len() != before
+    });
+    emit_closed(id, CloseReason::Dismissed);
+}
+
+/// Tell the sender its banner expired; the history keeps the notification
+pub fn expire(id: u32) {
+    emit_closed(id, CloseReason::Expired);
+}
+
+/// Tell the sender an action was picked, then dismiss the notification
+pub fn invoke_action(id: u32, key: &str) {
+    if let Some(connection) = SERVER.get() {
+        let connection = connection.clone();
+        let key = key.to_string();
+        relm4::spawn(async move {
+            let result = async {
+                let emitter = SignalEmitter::new(&connection, SERVER_PATH)?;
+                NotificationServer::action_invoked(&emitter, id, &key).await
+            }
+            .await;
+            if let Err(e) = result {
+                eprintln!("Failed to invoke notification action: {}", e);
+            }
+        });
+    }
+    dismiss(id);
+}
+
+fn emit_closed(id: u32, reason: CloseReason) {
+    let Some(connection) = SERVER.get() else {
+        return;
+    };
+    let connection = connection.clone();
+    relm4::spawn(async move {
+        let result = async {
+            let emitter = SignalEmitter::new(&connection, SERVER_PATH)?;
+            NotificationServer::notification_closed(&emitter, id, reason as u32).await
+        }
+        .await;
+        if let Err(e) = result {
+            eprintln!("Failed to report closed notification: {}", e);
+        }
+    });
+}
+
+fn record(tx: &watch::Sender<Vec<Notification>>, notification: Notification) {
+    tx.send_modify(|history| {
+        history.retain(|existing| existing.id != notification.id);
+        history.insert(0, notification);
+        history.truncate(HISTORY_LIMIT);
+    });
+}
+
+/// `org.freedesktop.Notifications` served by the bar
+struct NotificationServer {
+    tx: watch::Sender<Vec<Notification>>,
+}
+
+#[interface(name = "org.freedesktop.Notifications")]
+impl NotificationServer {
+    fn get_capabilities(&self) -> Vec<&str> {
+        vec!["body", "actions", "persistence"]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        // Replacing keeps the id so the sender can keep updating it
+        let replaces = replaces_id != 0
+            && self
+                .tx
+                .borrow()
+                .iter()
+                .any(|notification| notification.id == replaces_id);
+        let id = if replaces {
+            replaces_id
+        } else {
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        };
+        let args = (
+            app_name,
+            replaces_id,
+            app_icon,
+            summary,
+            body,
+            actions,
+            hints,
+            expire_timeout,
+        );
+        record(&self.tx, Notification::new(id, args));
+        id
+    }
+
+    async fn close_notification(
+        &self,
+        id: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.tx
+            .send_modify(|history| history.retain(|notification| notification.id != id));
+        Self::notification_closed(&emitter, id, CloseReason::Closed as u32).await?;
+        Ok(())
+    }
+
+    fn get_server_information(&self) -> (&str, &str, &str, &str) {
+        ("statusbar", "iceice666", env!("CARGO_PKG_VERSION"), "1.2")
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn action_invoked(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
+}
+
+async fn run_server(tx: watch::Sender<Vec<Notification>>) -> zbus::Result<()> {
+    let connection = connection::Builder::session()?
+        .serve_at(SERVER_PATH, NotificationServer { tx })?
+        .build()
+        .await?;
+    // Fails while mako or dunst runs, so their notifications get watched
+    bus::own_name(&connection, SERVER_NAME).await?;
+
+    // Keep the connection, and with it the daemon name, alive
+    let _ = SERVER.set(connection);
+    Ok(())
+}
+
+/// Record notifications by watching `Notify` calls to whichever daemon runs
+async fn monitor_notifications(tx: watch::Sender<Vec<Notification>>) -> zbus::Result<()> {
+    // A monitor can't send anything, so it gets a connection of its own
+    let connection = zbus::Connection::session().await?;
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::MethodCall)
+        .interface("org.freedesktop.Notifications")?
+        .member("Notify")?
+        .build();
+    MonitoringProxy::new(&connection)
+        .await?
+        .become_monitor(&[rule], 0)
+        .await?;
+
+    let mut messages = MessageStream::from(&connection);
+    while let Some(message) = messages.next().await {
+        let Ok(message) = message else {
+            continue;
+        };
+        let Ok(args) = message.body().deserialize::<NotifyArgs>() else {
+            continue;
+        };
+
+        // The daemon's ids are in its reply, so local ones are used instead
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        record(&tx, Notification::new(id, args));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+// Desktop notifications raised by widgets, through whichever daemon runs
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use zbus::proxy;
+use zbus::zvariant::Value;
+
+use super::bus;
+
+/// Client side of `org.freedesktop.Notifications`
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+/// Send a desktop notification
+pub fn send(summary: &str, body: &str) {
+    send_with_actions(summary, body, &[], |_| {});
+}
+
+/// Send a notification with buttons, given as `(key, label)` pairs
+///
+/// `on_action` gets the key of the button clicked, or `default` when the
+/// notification itself is clicked. It is called at most once.
+pub fn send_with_actions(
+    summary: &str,
+    body: &str,
+    actions: &[(&str, &str)],
+    on_action: impl FnOnce(&str) + Send + 'static,
+) {
+    let summary = summary.to_string();
+    let body = body.to_string();
+    let actions: Vec<String> = actions
+        .iter()
+        .flat_map(|(key, label)| [key.to_string(), label.to_string()])
+        .collect();
+    relm4::spawn(async move {
+        if let Err(e) = notify(&summary, &body, &actions, on_action).await {
+            eprintln!("Failed to send notification: {}", e);
+        }
+    });
+}
+
+async fn notify(
+    summary: &str,
+    body: &str,
+    actions: &[String],
+    on_action: impl FnOnce(&str),
+) -> zbus::Result<()> {
+    let proxy = NotificationsProxy::new(&bus::session().await?).await?;
+
+    // Subscribe first so a quick click isn't missed
+    let mut invoked = proxy.receive_action_invoked().await?;
+    let mut closed = proxy.receive_notification_closed().await?;
+
+    let actions: Vec<&str> = actions.iter().map(String::as_str).collect();
+    let id = proxy
+        .notify(
+            "statusbar",
+            0,
+            "",
+            summary,
+            body,
+            &actions,
+            HashMap::new(),
+            -1,
+        )
+        .await?;
+    if actions.is_empty() {
+        return Ok(());
+    }
+
+    loop {
+        tokio::select! {
+            Some(signal) = invoked.next() => {
+                let args = signal.args()?;
+                if args.id == id {
+                    on_action(&args.action_key);
+                    return Ok(());
+                }
+            }
+            Some(signal) = closed.next() => {
+                if signal.args()?.id == id {
+                    return Ok(());
+                }
+            }
+            else => return Ok(()),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use futures_util::StreamExt;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+use zbus::proxy;
+use zbus::zvariant::Value;
+
+use super::bus;
+
+const INTERFACE_NAMESPACE: &str = "org.gnome.desktop.interface";
+const A11Y_NAMESPACE: &str = "org.gnome.desktop.a11y.interface";
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+
+/// Settings read from the portal on startup
+const WATCHED_KEYS: &[(&str, &str)] = &[
+    (INTERFACE_NAMESPACE, "text-scaling-factor"),
+    (INTERFACE_NAMESPACE, "font-name"),
+    (INTERFACE_NAMESPACE, "enable-animations"),
+    (A11Y_NAMESPACE, "high-contrast"),
+    (APPEARANCE_NAMESPACE, "contrast"),
+    (APPEARANCE_NAMESPACE, "reduced-motion"),
+    (APPEARANCE_NAMESPACE, "color-scheme"),
+];
+
+// XDG desktop portal settings proxy
+#[proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait Settings {
+    /// Read a single setting value
+    fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<zbus::zvariant::OwnedValue>;
+
+    /// Emitted whenever a setting changes
+    #[zbus(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+}
+
+/// Desktop appearance settings relevant to the bar
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortalSettings {
+    /// Text scaling factor (1.0 = unscaled)
+    pub text_scaling_factor: f64,
+    /// Default interface font, e.g. "Cantarell 11"
+    pub font_name: Option<String>,
+    /// The user asked for higher contrast
+    pub high_contrast: bool,
+    /// The user asked for fewer animations
+    pub reduce_motion: bool,
+    /// The desktop is in light mode; no preference counts as dark
+    pub prefer_light: bool,
+    requests: AccessibilityRequests,
+}
+
+/// What GNOME's own keys and the portal's appearance keys each ask for
+///
+/// Either one asking is enough, so one of them saying no doesn't undo the
+/// other saying yes.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct AccessibilityRequests {
+    gnome_high_contrast: bool,
+    gnome_reduce_motion: bool,
+    appearance_high_contrast: bool,
+    appearance_reduce_motion: bool,
+}
+
+impl Default for PortalSettings {
+    fn default() -> Self {
+        Self {
+            text_scaling_factor: 1.0,
+            font_name: None,
+            high_contrast: false,
+            reduce_motion: false,
+            prefer_light: false,
+            requests: AccessibilityRequests::default(),
+        }
+    }
+}
+
+impl PortalSettings {
+    fn apply(&mut self, namespace: &str, key: &str, value: &Value<'_>) {
+        // `Read` wraps values in an extra variant
+        if let Value::Value(inner) = value {
+            return self.apply(namespace, key, inner);
+        }
+
+        match (namespace, key) {
+            (INTERFACE_NAMESPACE, "text-scaling-factor") => {
+                if let Ok(factor) = f64::try_from(value) {
+                    self.text_scaling_factor = factor;
+                }
+            }
+            (INTERFACE_NAMESPACE, "font-name") => {
+                if let Ok(font) = <&str>::try_from(value) {
+                    self.font_name = Some(font.to_string()).filter(|f| !f.is_empty());
+                }
+            }
+            (INTERFACE_NAMESPACE, "enable-animations") => {
+                if let Ok(enabled) = bool::try_from(value) {
+                    self.requests.gnome_reduce_motion = !enabled;
+                }
+            }
+            (A11Y_NAMESPACE, "high-contrast") => {
+                if let Ok(enabled) = bool::try_from(value) {
+                    self.requests.gnome_high_contrast = enabled;
+                }
+            }
+            // 0 = no preference, 1 = more contrast / reduced motion
+            (APPEARANCE_NAMESPACE, "contrast") => {
+                if let Ok(contrast) = u32::try_from(value) {
+                    self.requests.appearance_high_contrast = contrast == 1;
+                }
+            }
+            (APPEARANCE_NAMESPACE, "reduced-motion") => {
+                if let Ok(motion) = u32::try_from(value) {
+                    self.requests.appearance_reduce_motion = motion == 1;
+                }
+            }
+            // 0 = no preference, 1 = dark, 2 = light
+            (APPEARANCE_NAMESPACE, "color-scheme") => {
+                if let Ok(scheme) = u32::try_from(value) {
+                    self.prefer_light = scheme == 2;
+                }
+            }
+            _ => {}
+        }
+
+        self.high_contrast =
+            self.requests.gnome_high_contrast || self.requests.appearance_high_contrast;
+        self.reduce_motion =
+            self.requests.gnome_reduce_motion || self.requests.appearance_reduce_motion;
+    }
+}
+
+static PORTAL_SETTINGS: OnceLock<watch::Receiver<PortalSettings>> = OnceLock::new();
+
+/// Subscribe to portal settings, starting the listener on first use
+pub fn subscribe() -> watch::Receiver<PortalSettings> {
+    PORTAL_SETTINGS
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(PortalSettings::default());
+            relm4::spawn(async move {
+                if let Err(e) = watch_settings(tx).await {
+                    eprintln!("Portal settings error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+async fn watch_settings(tx: watch::Sender<PortalSettings>) -> zbus::Result<()> {
+    let connection = bus::session().await?;
+    let portal = SettingsProxy::new(&connection).await?;
+
+    // Subscribe before reading so no change is missed in between
+    let mut changes = portal.receive_setting_changed().await?;
+
+    let mut settings = PortalSettings::default();
+    for (namespace, key) in WATCHED_KEYS {
+        if let Ok(value) = portal.read_one(namespace, key).await {
+            settings.apply(namespace, key, &value);
+        }
+    }
+    tx.send_replace(settings);
+
+    while let Some(signal) = changes.next().await {
+        if let Ok(args) = signal.args() {
+            tx.send_if_modified(|settings| {
+                let previous = settings.clone();
+                settings.apply(args.namespace, args.key, &args.value);
+                *settings != previous
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn either_source_asking_for_reduced_motion_is_enough() {
+        let mut settings = PortalSettings::default();
+        settings.apply(APPEARANCE_NAMESPACE, "reduced-motion", &Value::from(1u32));
+        settings.apply(INTERFACE_NAMESPACE, "enable-animations", &Value::from(true));
+        assert!(settings.reduce_motion);
+
+        settings.apply(APPEARANCE_NAMESPACE, "reduced-motion", &Value::from(0u32));
+        assert!(!settings.reduce_motion);
+
+        settings.apply(
+            INTERFACE_NAMESPACE,
+            "enable-animations",
+            &Value::from(false),
+        );
+        assert!(settings.reduce_motion);
+    }
+
+    #[test]
+    fn either_source_asking_for_high_contrast_is_enough() {
+        let mut settings = PortalSettings::default();
+        settings.apply(A11Y_NAMESPACE, "high-contrast", &Value::from(true));
+        settings.apply(APPEARANCE_NAMESPACE, "contrast", &Value::from(0u32));
+        assert!(settings.high_contrast);
+
+        settings.apply(A11Y_NAMESPACE, "high-contrast", &Value::from(false));
+        assert!(!settings.high_contrast);
+
+        settings.apply(APPEARANCE_NAMESPACE, "contrast", &Value::from(1u32));
+        assert!(settings.high_contrast);
+    }
+}
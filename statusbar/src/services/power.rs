@@ -0,0 +1,131 @@
+// Whether polling is worth it right now
+//
+// Combines logind's idle hint, its suspend notice and whether the bars are
+// shown at all, by the user or on an output that's still there. Timers slow down while the session is idle and stop while it
+// sleeps or nothing is on screen; see `widgets::poll`.
+use futures_util::StreamExt;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+use zbus::proxy;
+
+use super::{bus, idle};
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    /// Sent with `true` right before suspending and `false` after resuming
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+/// How eagerly widgets should poll, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Activity {
+    /// Suspending or hidden, don't poll at all
+    Paused,
+    /// Idle but possibly still looked at, poll now and then
+    Idle,
+    Active,
+}
+
+/// What the session and the bars are up to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerState {
+    pub idle: bool,
+    pub asleep: bool,
+    /// Every bar is hidden
+    pub hidden: bool,
+    /// No bar is mapped on a connected output, e.g. the outputs were
+    /// unplugged or turned off
+    pub outputs_off: bool,
+}
+
+impl PowerState {
+    pub fn activity(self) -> Activity {
+        if self.asleep || self.hidden || self.outputs_off {
+            Activity::Paused
+        } else if self.idle {
+            Activity::Idle
+        } else {
+            Activity::Active
+        }
+    }
+}
+
+static POWER_STATE: OnceLock<watch::Sender<PowerState>> = OnceLock::new();
+
+fn state() -> &'static watch::Sender<PowerState> {
+    POWER_STATE.get_or_init(|| {
+        let (tx, _) = watch::channel(PowerState::default());
+        relm4::spawn(async {
+            if let Err(e) = watch_session().await {
+                eprintln!("Power service error: {}", e);
+            }
+        });
+        tx
+    })
+}
+
+/// Subscribe to the power state, starting the watcher on first use
+pub fn subscribe() -> watch::Receiver<PowerState> {
+    state().subscribe()
+}
+
+/// How eagerly widgets should poll at the moment
+pub fn activity() -> Activity {
+    state().borrow().activity()
+}
+
+/// Wait for the machine to wake up from suspend
+///
+/// For services polling on tokio timers, which don't count the time asleep.
+pub async fn wake_up(
+    power_rx: &mut watch::Receiver<PowerState>,
+) -> Result<(), watch::error::RecvError> {
+    loop {
+        let was_asleep = power_rx.borrow_and_update().asleep;
+        power_rx.changed().await?;
+        if was_asleep && !power_rx.borrow().asleep {
+            return Ok(());
+        }
+    }
+}
+
+/// Record whether the bars are hidden, e.g. through `statusbarctl hide`
+pub fn set_hidden(hidden: bool) {
+    state().send_if_modified(|state| std::mem::replace(&mut state.hidden, hidden) != hidden);
+}
+
+/// Record whether any bar is still on an output that's on
+pub fn set_outputs_off(off: bool) {
+    state().send_if_modified(|state| std::mem::replace(&mut state.outputs_off, off) != off);
+}
+
+async fn watch_session() -> zbus::Result<()> {
+    let connection = bus::system().await?;
+    let manager = ManagerProxy::new(&connection).await?;
+    let mut sleep = manager.receive_prepare_for_sleep().await?;
+    let mut idle_rx = idle::subscribe();
+
+    loop {
+        tokio::select! {
+            Some(signal) = sleep.next() => {
+                let asleep = signal.args()?.start;
+                state().send_modify(|state| state.asleep = asleep);
+            }
+            changed = idle_rx.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let idle = *idle_rx.borrow_and_update();
+                state().send_if_modified(|state| {
+                    std::mem::replace(&mut state.idle, idle) != idle
+                });
+            }
+            else => return Ok(()),
+        }
+    }
+}
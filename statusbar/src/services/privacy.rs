@@ -0,0 +1,69 @@
+use std::sync::OnceLock;
+use tokio::sync::watch;
+
+use super::privacy_indicator;
+
+/// Shown in place of redacted text
+const PLACEHOLDER: &str = "••••••";
+
+static PRIVACY_SCREEN: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+fn flag() -> &'static watch::Sender<bool> {
+    PRIVACY_SCREEN.get_or_init(|| watch::channel(false).0)
+}
+
+/// Subscribe to the privacy screen being switched on or off
+pub fn subscribe() -> watch::Receiver<bool> {
+    flag().subscribe()
+}
+
+pub fn is_enabled() -> bool {
+    *flag().borrow()
+}
+
+pub fn set_enabled(enabled: bool) {
+    flag().send_if_modified(|current| {
+        let changed = *current != enabled;
+        *current = enabled;
+        changed
+    });
+}
+
+pub fn toggle() {
+    set_enabled(!is_enabled());
+}
+
+/// Turn the privacy screen on while the screen is shared
+///
+/// Whatever was set before sharing started is restored when it ends.
+pub fn redact_while_sharing() {
+    let mut capture_rx = privacy_indicator::subscribe();
+    relm4::spawn(async move {
+        let mut before_sharing = None;
+        while capture_rx.changed().await.is_ok() {
+            let sharing = capture_rx.borrow().is_sharing_screen();
+            match (sharing, before_sharing) {
+                (true, None) => {
+                    before_sharing = Some(is_enabled());
+                    set_enabled(true);
+                }
+                (false, Some(previous)) => {
+                    before_sharing = None;
+                    set_enabled(previous);
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// `text`, or a placeholder while the privacy screen is on
+///
+/// Empty text stays empty so widgets that hide themselves still do.
+pub fn redact(text: &str) -> &str {
+    if is_enabled() && !text.is_empty() {
+        PLACEHOLDER
+    } else {
+        text
+    }
+}
@@ -0,0 +1,206 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// How often the PipeWire graph is inspected
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What is currently being captured, and by which apps
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptureState {
+    /// Apps receiving a screencast
+    pub screen: Vec<String>,
+    /// Apps reading from a camera
+    pub camera: Vec<String>,
+    /// Apps recording from a microphone
+    pub microphone: Vec<String>,
+}
+
+impl CaptureState {
+    pub fn is_sharing_screen(&self) -> bool {
+        !self.screen.is_empty()
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        !self.screen.is_empty() || !self.camera.is_empty() || !self.microphone.is_empty()
+    }
+}
+
+static CAPTURE_STATE: OnceLock<watch::Receiver<CaptureState>> = OnceLock::new();
+
+/// Subscribe to capture changes, starting the watcher on first use
+pub fn subscribe() -> watch::Receiver<CaptureState> {
+    CAPTURE_STATE
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(CaptureState::default());
+            thread::spawn(move || {
+                if let Err(e) = watch_capture(tx) {
+                    eprintln!("Privacy indicator error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+fn watch_capture(tx: watch::Sender<CaptureState>) -> Result<(), String> {
+    loop {
+        let state = query()?;
+        tx.send_if_modified(|current| {
+            if *current == state {
+                return false;
+            }
+            *current = state;
+            true
+        });
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn query() -> Result<CaptureState, String> {
+    let output = Command::new("pw-dump")
+        .output()
+        .map_err(|e| format!("Failed to run pw-dump: {}", e))?;
+    if !output.status.success() {
+        return Err("pw-dump failed".to_string());
+    }
+
+    let objects: Vec<Value> = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    let graph = PipeWireGraph::new(&objects);
+
+    // Apps opening the camera directly never show up in PipeWire
+    let mut camera = graph.camera_consumers();
+    camera.extend(video_device_users());
+    camera.sort();
+    camera.dedup();
+
+    Ok(CaptureState {
+        screen: graph.screen_consumers(),
+        camera,
+        microphone: graph.microphone_consumers(),
+    })
+}
+
+/// Processes holding a `/dev/video*` device open, like `fuser` finds them
+fn video_device_users() -> Vec<String> {
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    processes
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .filter(|entry| {
+            // Other users' fds aren't readable, which is fine: not our camera
+            fs::read_dir(entry.path().join("fd")).is_ok_and(|fds| {
+                fds.flatten().any(|fd| {
+                    fs::read_link(fd.path())
+                        .is_ok_and(|target| target.to_string_lossy().starts_with("/dev/video"))
+                })
+            })
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path().join("comm")).ok())
+        .map(|comm| comm.trim().to_string())
+        // PipeWire holds the device for its clients, which are found above
+        .filter(|comm| !matches!(comm.as_str(), "pipewire" | "wireplumber"))
+        .collect()
+}
+
+/// Nodes and links of a `pw-dump`
+struct PipeWireGraph<'a> {
+    nodes: HashMap<u64, &'a Value>,
+    /// `(output node, input node)` of every active link
+    links: Vec<(u64, u64)>,
+}
+
+impl<'a> PipeWireGraph<'a> {
+    fn new(objects: &'a [Value]) -> Self {
+        let mut nodes = HashMap::new();
+        let mut links = Vec::new();
+
+        for object in objects {
+            let (Some(id), Some(kind)) = (object["id"].as_u64(), object["type"].as_str()) else {
+                continue;
+            };
+            let info = &object["info"];
+            match kind {
+                "PipeWire:Interface:Node" => {
+                    nodes.insert(id, info);
+                }
+                "PipeWire:Interface:Link" if info["state"] == "active" => {
+                    if let (Some(output), Some(input)) = (
+                        info["output-node-id"].as_u64(),
+                        info["input-node-id"].as_u64(),
+                    ) {
+                        links.push((output, input));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { nodes, links }
+    }
+
+    /// Apps linked to a video source that isn't a camera
+    ///
+    /// Compositors and portals publish screencasts as device-less
+    /// `Video/Source` nodes, while cameras carry a `device.api`.
+    fn screen_consumers(&self) -> Vec<String> {
+        self.consumers(|props| {
+            props["media.class"] == "Video/Source" && props.get("device.api").is_none()
+        })
+    }
+
+    fn camera_consumers(&self) -> Vec<String> {
+        self.consumers(|props| {
+            props["media.class"] == "Video/Source" && props.get("device.api").is_some()
+        })
+    }
+
+    /// Apps linked to an audio source; sink monitors are a different class
+    fn microphone_consumers(&self) -> Vec<String> {
+        self.consumers(|props| props["media.class"] == "Audio/Source")
+    }
+
+    /// Apps linked to a node matching `is_source`, sorted and deduplicated
+    fn consumers(&self, is_source: impl Fn(&Value) -> bool) -> Vec<String> {
+        let mut apps: Vec<String> = self
+            .links
+            .iter()
+            .filter(|(output, _)| {
+                self.nodes
+                    .get(output)
+                    .is_some_and(|node| is_source(&node["props"]))
+            })
+            // Level meters like pavucontrol's peak detection aren't recording
+            .filter(|(_, input)| {
+                self.nodes
+                    .get(input)
+                    .is_none_or(|node| node["props"]["stream.monitor"] != true)
+            })
+            .filter_map(|(_, input)| self.app_name(*input))
+            .collect();
+        apps.sort();
+        apps.dedup();
+        apps
+    }
+
+    fn app_name(&self, node: u64) -> Option<String> {
+        let props = &self.nodes.get(&node)?["props"];
+        ["application.name", "node.description", "node.name"]
+            .into_iter()
+            .find_map(|key| props[key].as_str())
+            .map(str::to_string)
+    }
+}
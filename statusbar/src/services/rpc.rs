@@ -0,0 +1,194 @@
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::ipc::{IpcCommand, IpcResult};
+
+/// Bumped whenever a method is removed or changes shape
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// Standard JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The bar rejected or failed to run the command
+const COMMAND_FAILED: i64 = -32000;
+
+/// Methods of protocol version 1: `(method, params, result)`
+///
+/// Widgets are named like their config sections (`wifi`, `clock`, ...) and
+/// actions use the kebab-case names accepted by `action.trigger`.
+const METHODS: &[(&str, &str, &str)] = &[
+    ("bar.version", "{}", "{ protocol: u32, version: string }"),
+    ("bar.methods", "{}", "[{ method, params, result }]"),
+    ("state.get", "{ widget?: string }", "object"),
+    ("widget.show", "{ widget: string }", "null"),
+    ("widget.hide", "{ widget: string }", "null"),
+    ("widget.reload", "{ widget: string }", "null"),
+    ("popover.cycle", "{}", "null"),
+    ("popover.open", "{ widget: string }", "null"),
+    ("bar.show", "{}", "null"),
+    ("bar.hide", "{}", "null"),
+    ("bar.toggle", "{}", "null"),
+    ("config.reload", "{}", "null"),
+    ("notifications.set-dnd", "{ enabled: bool }", "null"),
+    (
+        "action.trigger",
+        "{ action: toggle-pomodoro | start-focus | stop-focus | start-break | \
+         toggle-privacy | open-settings | reload-theme | quit }",
+        "null",
+    ),
+];
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Absent for notifications, which get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct StateParams {
+    widget: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WidgetParams {
+    widget: String,
+}
+
+#[derive(Deserialize)]
+struct DoNotDisturbParams {
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct ActionParams {
+    action: String,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Answer one JSON-RPC request line, or `None` for notifications
+pub fn handle(line: &str, dispatch: impl Fn(IpcCommand) -> IpcResult) -> Option<String> {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(response(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, e.to_string())),
+            ));
+        }
+    };
+
+    let result = if request.jsonrpc != "2.0" {
+        Err(RpcError::new(
+            INVALID_REQUEST,
+            "Only JSON-RPC 2.0 is supported",
+        ))
+    } else {
+        call(&request.method, request.params, dispatch)
+    };
+
+    request.id.map(|id| response(id, result))
+}
+
+fn call(
+    method: &str,
+    params: Value,
+    dispatch: impl Fn(IpcCommand) -> IpcResult,
+) -> Result<Value, RpcError> {
+    let command = match method {
+        "bar.version" => {
+            return Ok(json!({
+                "protocol": PROTOCOL_VERSION,
+                "version": env!("CARGO_PKG_VERSION"),
+            }));
+        }
+        "bar.methods" => {
+            let methods: Vec<Value> = METHODS
+                .iter()
+                .map(|(method, params, result)| {
+                    json!({ "method": method, "params": params, "result": result })
+                })
+                .collect();
+            return Ok(Value::Array(methods));
+        }
+        "state.get" => {
+            let StateParams { widget } = parse_params(params)?;
+            IpcCommand::GetState { widget }
+        }
+        "widget.show" | "widget.hide" => {
+            let WidgetParams { widget } = parse_params(params)?;
+            IpcCommand::SetWidgetVisible {
+                widget,
+                visible: method == "widget.show",
+            }
+        }
+        "widget.reload" => {
+            let WidgetParams { widget } = parse_params(params)?;
+            IpcCommand::ReloadWidget { widget }
+        }
+        "popover.cycle" => IpcCommand::CyclePopover,
+        "popover.open" => {
+            let WidgetParams { widget } = parse_params(params)?;
+            IpcCommand::OpenPopover { widget }
+        }
+        "bar.show" => IpcCommand::SetVisible(true),
+        "bar.hide" => IpcCommand::SetVisible(false),
+        "bar.toggle" => IpcCommand::ToggleVisibility,
+        "config.reload" => IpcCommand::ReloadConfig,
+        "notifications.set-dnd" => {
+            let DoNotDisturbParams { enabled } = parse_params(params)?;
+            IpcCommand::SetDoNotDisturb(enabled)
+        }
+        "action.trigger" => {
+            let ActionParams { action } = parse_params(params)?;
+            let action = action
+                .parse()
+                .map_err(|e: String| RpcError::new(INVALID_PARAMS, e))?;
+            IpcCommand::Trigger(action)
+        }
+        other => {
+            return Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", other),
+            ));
+        }
+    };
+
+    dispatch(command).map_err(|e| RpcError::new(COMMAND_FAILED, e))
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    // Omitted params are treated as an empty object
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> String {
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    };
+    response.to_string()
+}
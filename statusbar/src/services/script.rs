@@ -0,0 +1,349 @@
+use std::env;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::ScriptSandboxConfig;
+
+/// How often a running script is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Why a user script produced no usable output
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    Spawn(String),
+    /// Killed after running longer than the configured timeout
+    Timeout(Duration),
+    /// Exited unsuccessfully, with the first line it wrote to stderr
+    Failed {
+        status: Option<i32>,
+        stderr: String,
+    },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(e) => write!(f, "Failed to start: {}", e),
+            Self::Timeout(timeout) => write!(f, "Timed out after {}s", timeout.as_secs()),
+            Self::Failed { status, stderr } => {
+                match status {
+                    Some(code) => write!(f, "Exited with status {}", code)?,
+                    None => write!(f, "Killed by a signal")?,
+                }
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// What a script wrote to stdout
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptOutput {
+    pub stdout: String,
+    /// Output beyond `max_output_bytes` was dropped
+    pub truncated: bool,
+}
+
+/// Run `command` with `sh -c` inside the configured sandbox, blocking until
+/// it exits or times out
+pub fn run(command: &str, sandbox: &ScriptSandboxConfig) -> Result<ScriptOutput, ScriptError> {
+    let mut child = sandboxed_command(command, sandbox)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Own process group, so a timeout kills everything the script started
+        .process_group(0)
+        .spawn()
+        .map_err(|e| ScriptError::Spawn(e.to_string()))?;
+
+    let stdout = read_limited(child.stdout.take(), sandbox.max_output_bytes);
+    let stderr = read_limited(child.stderr.take(), sandbox.max_output_bytes);
+
+    let timeout = Duration::from_secs(sandbox.timeout_seconds.max(1));
+    let deadline = Instant::now() + timeout;
+    let status = wait_until(&mut child, deadline, timeout)?;
+    // Anything the script left running in the background holds the pipes
+    // open, so it goes with the script
+    signal_group(child.id());
+
+    let (stdout, truncated) = stdout
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .map_err(|_| ScriptError::Timeout(timeout))?;
+    if !status.success() {
+        let (stderr, _) = stderr
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or_default();
+        return Err(ScriptError::Failed {
+            status: status.code(),
+            stderr: stderr.lines().next().unwrap_or_default().trim().to_string(),
+        });
+    }
+
+    Ok(ScriptOutput { stdout, truncated })
+}
+
+/// Run `command` inside the sandbox without a timeout, passing each line it
+/// writes to `on_line` until it exits
+///
+/// Lines longer than `max_output_bytes` are cut short. The script is killed
+/// once `on_line` returns false.
+pub fn watch(
+    command: &str,
+    sandbox: &ScriptSandboxConfig,
+    mut on_line: impl FnMut(&str) -> bool,
+) -> Result<(), ScriptError> {
+    let mut child = sandboxed_command(command, sandbox)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| ScriptError::Spawn(e.to_string()))?;
+
+    let stderr = read_limited(child.stderr.take(), sandbox.max_output_bytes);
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(mut line) = line else {
+                break;
+            };
+            if line.len() > sandbox.max_output_bytes {
+                let mut end = sandbox.max_output_bytes;
+                while !line.is_char_boundary(end) {
+                    end -= 1;
+                }
+                line.truncate(end);
+            }
+            if !on_line(&line) {
+                kill_group(&mut child);
+                return Ok(());
+            }
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| ScriptError::Spawn(e.to_string()))?;
+    if !status.success() {
+        let (stderr, _) = stderr.recv().unwrap_or_default();
+        return Err(ScriptError::Failed {
+            status: status.code(),
+            stderr: stderr.lines().next().unwrap_or_default().trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn sandboxed_command(command: &str, sandbox: &ScriptSandboxConfig) -> Command {
+    let mut cmd = if sandbox.bwrap {
+        // Read-only view of the system with private /tmp and namespaces
+        let mut bwrap = Command::new("bwrap");
+        bwrap.args([
+            "--ro-bind",
+            "/",
+            "/",
+            "--dev",
+            "/dev",
+            "--proc",
+            "/proc",
+            "--tmpfs",
+            "/tmp",
+            "--unshare-all",
+            "--die-with-parent",
+            "--new-session",
+        ]);
+        if sandbox.allow_network {
+            bwrap.arg("--share-net");
+        }
+        bwrap.args(["--", "sh", "-c", command]);
+        bwrap
+    } else {
+        let mut sh = Command::new("sh");
+        sh.args(["-c", command]);
+        sh
+    };
+
+    if sandbox.clean_env {
+        cmd.env_clear();
+        for name in &sandbox.keep_env {
+            if let Some(value) = env::var_os(name) {
+                cmd.env(name, value);
+            }
+        }
+    }
+
+    cmd
+}
+
+/// Read a pipe on its own thread, keeping at most `limit` bytes
+///
+/// The rest is drained so a chatty script doesn't block on a full pipe.
+fn read_limited(
+    pipe: Option<impl Read + Send + 'static>,
+    limit: usize,
+) -> mpsc::Receiver<(String, bool)> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let Some(mut pipe) = pipe else {
+            let _ = tx.send((String::new(), false));
+            return;
+        };
+
+        let mut kept = Vec::new();
+        let mut total = 0;
+        let mut buffer = [0u8; 8192];
+        while let Ok(read) = pipe.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let room = limit.saturating_sub(kept.len());
+            kept.extend_from_slice(&buffer[..read.min(room)]);
+            total += read;
+        }
+
+        let text = String::from_utf8_lossy(&kept).into_owned();
+        let _ = tx.send((text, total > limit));
+    });
+    rx
+}
+
+/// Wait for the script to exit, killing it at `deadline`
+fn wait_until(
+    child: &mut Child,
+    deadline: Instant,
+    timeout: Duration,
+) -> Result<std::process::ExitStatus, ScriptError> {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                kill_group(child);
+                return Err(ScriptError::Timeout(timeout));
+            }
+            Err(e) => {
+                kill_group(child);
+                return Err(ScriptError::Spawn(e.to_string()));
+            }
+        }
+    }
+}
+
+/// Kill the script with everything it spawned, then reap it
+fn kill_group(child: &mut Child) {
+    if !signal_group(child.id()) {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
+/// Send SIGKILL to the process group led by the script, false when none of
+/// it is left
+fn signal_group(leader: u32) -> bool {
+    let group = format!("-{}", leader);
+    Command::new("kill")
+        .args(["-KILL", "--", &group])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox() -> ScriptSandboxConfig {
+        ScriptSandboxConfig {
+            timeout_seconds: 1,
+            ..ScriptSandboxConfig::default()
+        }
+    }
+
+    #[test]
+    fn output_is_returned() {
+        let output = run("echo hello", &sandbox()).unwrap();
+        assert_eq!(output.stdout, "hello\n");
+        assert!(!output.truncated);
+    }
+
+    #[test]
+    fn failures_carry_the_status_and_stderr() {
+        let error = run("echo oops >&2; exit 3", &sandbox()).unwrap_err();
+        assert_eq!(
+            error,
+            ScriptError::Failed {
+                status: Some(3),
+                stderr: "oops".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn a_slow_script_times_out() {
+        let started = Instant::now();
+        let error = run("sleep 5", &sandbox()).unwrap_err();
+        assert_eq!(error, ScriptError::Timeout(Duration::from_secs(1)));
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn background_children_do_not_hold_the_run_open() {
+        let started = Instant::now();
+        let output = run("sleep 5 & echo done", &sandbox()).unwrap();
+        assert_eq!(output.stdout, "done\n");
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn watching_stops_when_asked() {
+        let mut lines = Vec::new();
+        let started = Instant::now();
+        watch(
+            "while true; do echo tick; sleep 0.1; done",
+            &sandbox(),
+            |line| {
+                lines.push(line.to_string());
+                lines.len() < 3
+            },
+        )
+        .unwrap();
+        assert_eq!(lines, ["tick", "tick", "tick"]);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn output_past_the_limit_is_dropped() {
+        let sandbox = ScriptSandboxConfig {
+            max_output_bytes: 10,
+            ..sandbox()
+        };
+        let output = run("printf '%0100d' 0", &sandbox).unwrap();
+        assert_eq!(output.stdout, "0000000000");
+        assert!(output.truncated);
+    }
+
+    #[test]
+    fn clean_env_keeps_only_the_listed_variables() {
+        let clean = ScriptSandboxConfig {
+            keep_env: vec!["PATH".to_string()],
+            ..sandbox()
+        };
+        let output = run("printf '%s' \"${HOME-unset}\"", &clean).unwrap();
+        assert_eq!(output.stdout, "unset");
+
+        let inherited = ScriptSandboxConfig {
+            clean_env: false,
+            ..sandbox()
+        };
+        let output = run("printf '%s' \"${HOME-unset}\"", &inherited).unwrap();
+        let home = env::var("HOME").unwrap_or_else(|_| "unset".to_string());
+        assert_eq!(output.stdout, home);
+    }
+}
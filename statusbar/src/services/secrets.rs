@@ -0,0 +1,230 @@
+// Credentials kept in the desktop keyring through the Secret Service API
+//
+// Items carry the attributes `service=statusbar` and `account=<name>`, the
+// same ones `secret-tool store --label=... service statusbar account <name>`
+// writes, so secrets stored by hand keep working.
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+use tokio::sync::watch;
+use zbus::proxy;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Type, Value};
+
+use super::bus;
+
+const SERVICE: &str = "statusbar";
+
+/// Accounts looked up but not found, waiting for the user to enter them
+static MISSING: OnceLock<watch::Sender<BTreeSet<String>>> = OnceLock::new();
+
+/// A `(oayays)` secret, in plain text over the session bus
+#[derive(Debug, Serialize, Deserialize, Type)]
+struct Secret {
+    session: OwnedObjectPath,
+    parameters: Vec<u8>,
+    value: Vec<u8>,
+    content_type: String,
+}
+
+#[proxy(
+    interface = "org.freedesktop.Secret.Service",
+    default_service = "org.freedesktop.secrets",
+    default_path = "/org/freedesktop/secrets"
+)]
+trait Service {
+    fn open_session(
+        &self,
+        algorithm: &str,
+        input: &Value<'_>,
+    ) -> zbus::Result<(OwnedValue, OwnedObjectPath)>;
+
+    fn search_items(
+        &self,
+        attributes: HashMap<&str, &str>,
+    ) -> zbus::Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>)>;
+
+    fn unlock(
+        &self,
+        objects: &[ObjectPath<'_>],
+    ) -> zbus::Result<(Vec<OwnedObjectPath>, OwnedObjectPath)>;
+
+    fn get_secrets(
+        &self,
+        items: &[ObjectPath<'_>],
+        session: &ObjectPath<'_>,
+    ) -> zbus::Result<HashMap<OwnedObjectPath, Secret>>;
+}
+
+/// The default collection, usually the login keyring
+#[proxy(
+    interface = "org.freedesktop.Secret.Collection",
+    default_service = "org.freedesktop.secrets",
+    default_path = "/org/freedesktop/secrets/aliases/default"
+)]
+trait Collection {
+    fn create_item(
+        &self,
+        properties: HashMap<&str, Value<'_>>,
+        secret: &Secret,
+        replace: bool,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.Secret.Session",
+    default_service = "org.freedesktop.secrets"
+)]
+trait Session {
+    fn close(&self) -> zbus::Result<()>;
+}
+
+/// A keyring dialog, e.g. asking for the password to unlock it
+#[proxy(
+    interface = "org.freedesktop.Secret.Prompt",
+    default_service = "org.freedesktop.secrets"
+)]
+trait Prompt {
+    fn prompt(&self, window_id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn completed(&self, dismissed: bool, result: OwnedValue) -> zbus::Result<()>;
+}
+
+fn missing() -> &'static watch::Sender<BTreeSet<String>> {
+    MISSING.get_or_init(|| watch::channel(BTreeSet::new()).0)
+}
+
+/// Subscribe to accounts whose secret was looked up but isn't stored
+pub fn subscribe_missing() -> watch::Receiver<BTreeSet<String>> {
+    missing().subscribe()
+}
+
+/// Look up the secret stored for `account`, unlocking the keyring if needed
+pub async fn lookup(account: &str) -> Result<String, String> {
+    let secret = read(account)
+        .await
+        .map_err(|e| format!("Failed to read the keyring: {}", e))?;
+    let Some(secret) = secret else {
+        missing().send_if_modified(|missing| missing.insert(account.to_string()));
+        return Err(format!("No secret stored for {}", account));
+    };
+    String::from_utf8(secret).map_err(|_| format!("The secret for {} isn't text", account))
+}
+
+/// Store `secret` for `account`, replacing what was stored before
+pub async fn store(account: &str, secret: &str) -> Result<(), String> {
+    write(account, secret)
+        .await
+        .map_err(|e| format!("Failed to store the secret for {}: {}", account, e))?;
+    missing().send_if_modified(|missing| missing.remove(account));
+    Ok(())
+}
+
+async fn read(account: &str) -> zbus::Result<Option<Vec<u8>>> {
+    let connection = bus::session().await?;
+    let service = ServiceProxy::new(&connection).await?;
+
+    let attributes = HashMap::from([("service", SERVICE), ("account", account)]);
+    let (unlocked, locked) = service.search_items(attributes).await?;
+    let item = match (unlocked.first(), locked.first()) {
+        (Some(item), _) => item.clone(),
+        (None, Some(item)) => {
+            let (_, prompt) = service.unlock(&[item.as_ref()]).await?;
+            if !run_prompt(&connection, prompt).await? {
+                return Err(zbus::Error::Failure("the keyring stayed locked".into()));
+            }
+            item.clone()
+        }
+        (None, None) => return Ok(None),
+    };
+
+    let session = open_session(&service).await?;
+    let secrets = service
+        .get_secrets(&[item.as_ref()], &session.as_ref())
+        .await;
+    close_session(&connection, session).await;
+    Ok(secrets?.remove(&item).map(|secret| secret.value))
+}
+
+async fn write(account: &str, secret: &str) -> zbus::Result<()> {
+    let connection = bus::session().await?;
+    let service = ServiceProxy::new(&connection).await?;
+    let collection = CollectionProxy::new(&connection).await?;
+
+    let attributes = HashMap::from([("service", SERVICE), ("account", account)]);
+    let properties = HashMap::from([
+        (
+            "org.freedesktop.Secret.Item.Label",
+            Value::from(format!("statusbar: {}", account)),
+        ),
+        (
+            "org.freedesktop.Secret.Item.Attributes",
+            Value::from(attributes),
+        ),
+    ]);
+
+    let session = open_session(&service).await?;
+    let secret = Secret {
+        session: session.clone(),
+        parameters: Vec::new(),
+        value: secret.as_bytes().to_vec(),
+        content_type: "text/plain".to_string(),
+    };
+    // The item is only created once the prompt is answered, using the
+    // secret encoded for this session, so it stays open until then
+    let created = async {
+        let (_, prompt) = collection.create_item(properties, &secret, true).await?;
+        run_prompt(&connection, prompt).await
+    }
+    .await;
+    close_session(&connection, session).await;
+
+    if !created? {
+        return Err(zbus::Error::Failure("the keyring stayed locked".into()));
+    }
+    Ok(())
+}
+
+/// Open a session passing secrets unencrypted, which is fine on the user's
+/// own session bus
+async fn open_session(service: &ServiceProxy<'_>) -> zbus::Result<OwnedObjectPath> {
+    let (_, session) = service.open_session("plain", &Value::from("")).await?;
+    Ok(session)
+}
+
+async fn close_session(connection: &zbus::Connection, session: OwnedObjectPath) {
+    let closed = async {
+        SessionProxy::builder(connection)
+            .path(session)?
+            .build()
+            .await?
+            .close()
+            .await
+    };
+    if let Err(e) = closed.await {
+        eprintln!("Failed to close the keyring session: {}", e);
+    }
+}
+
+/// Show the keyring's prompt, if it needs one, and wait for the user
+///
+/// Returns false when the prompt was dismissed.
+async fn run_prompt(connection: &zbus::Connection, prompt: OwnedObjectPath) -> zbus::Result<bool> {
+    // "/" means the keyring didn't need to ask
+    if prompt.as_str() == "/" {
+        return Ok(true);
+    }
+    let prompt = PromptProxy::builder(connection)
+        .path(prompt)?
+        .build()
+        .await?;
+
+    // Subscribe first so a quick answer isn't missed
+    let mut completed = prompt.receive_completed().await?;
+    prompt.prompt("").await?;
+    match completed.next().await {
+        Some(signal) => Ok(!signal.args()?.dismissed),
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,276 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Str, Type, Value};
+use zbus::{connection, interface, proxy};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+
+/// Quick settings offered in the item's menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickAction {
+    TogglePomodoro,
+    StartFocus,
+    StopFocus,
+    StartBreak,
+    TogglePrivacy,
+    OpenSettings,
+    ReloadTheme,
+    Quit,
+}
+
+impl FromStr for QuickAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "toggle-pomodoro" => Ok(Self::TogglePomodoro),
+            "start-focus" => Ok(Self::StartFocus),
+            "stop-focus" => Ok(Self::StopFocus),
+            "start-break" => Ok(Self::StartBreak),
+            "toggle-privacy" => Ok(Self::TogglePrivacy),
+            "open-settings" => Ok(Self::OpenSettings),
+            "reload-theme" => Ok(Self::ReloadTheme),
+            "quit" => Ok(Self::Quit),
+            other => Err(format!("Unknown action '{}'", other)),
+        }
+    }
+}
+
+/// Menu entries in display order; `None` is a separator
+const MENU: &[Option<(QuickAction, &str)>] = &[
+    Some((QuickAction::TogglePomodoro, "Start/stop pomodoro")),
+    Some((QuickAction::StartFocus, "Start focus session")),
+    Some((QuickAction::StopFocus, "Stop focus session")),
+    Some((QuickAction::StartBreak, "Take a break now")),
+    None,
+    Some((QuickAction::TogglePrivacy, "Toggle privacy screen")),
+    None,
+    Some((QuickAction::OpenSettings, "Settings…")),
+    Some((QuickAction::ReloadTheme, "Reload theme")),
+    None,
+    Some((QuickAction::Quit, "Quit")),
+];
+
+type ActionHandler = Box<dyn Fn(QuickAction) + Send + Sync>;
+
+// Registry other bars use to discover tray items
+#[proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher"
+)]
+trait StatusNotifierWatcher {
+    fn register_status_notifier_item(&self, service: &str) -> zbus::Result<()>;
+}
+
+/// `org.kde.StatusNotifierItem` describing the bar itself
+struct StatusNotifierItem;
+
+#[interface(name = "org.kde.StatusNotifierItem")]
+impl StatusNotifierItem {
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "ApplicationStatus"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        "statusbar"
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        "Statusbar"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "Active"
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> &str {
+        "preferences-system"
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(MENU_PATH).expect("valid object path")
+    }
+
+    // Everything is in the menu, so clicks have nothing else to do
+    fn activate(&self, _x: i32, _y: i32) {}
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {}
+
+    fn scroll(&self, _delta: i32, _orientation: &str) {}
+}
+
+/// A `(ia{sv}av)` node of the dbusmenu layout
+#[derive(Debug, Serialize, Type, Value, OwnedValue)]
+struct Layout {
+    id: i32,
+    properties: HashMap<String, OwnedValue>,
+    children: Vec<OwnedValue>,
+}
+
+/// `com.canonical.dbusmenu` exposing [`MENU`]
+struct Menu {
+    on_action: ActionHandler,
+}
+
+impl Menu {
+    /// Menu ids start at 1; 0 is the root
+    fn entry(id: i32) -> Option<Option<(QuickAction, &'static str)>> {
+        let index = usize::try_from(id).ok()?.checked_sub(1)?;
+        MENU.get(index).copied()
+    }
+
+    fn properties(id: i32) -> HashMap<String, OwnedValue> {
+        let mut properties = HashMap::new();
+        match Self::entry(id) {
+            Some(Some((_, label))) => {
+                properties.insert("label".to_string(), OwnedValue::from(Str::from(label)));
+            }
+            Some(None) => {
+                properties.insert("type".to_string(), OwnedValue::from(Str::from("separator")));
+            }
+            None => {
+                properties.insert(
+                    "children-display".to_string(),
+                    OwnedValue::from(Str::from("submenu")),
+                );
+            }
+        }
+        properties
+    }
+
+    fn layout(id: i32) -> Layout {
+        let children = if id == 0 {
+            (1..=MENU.len() as i32)
+                .filter_map(|child| OwnedValue::try_from(Self::layout(child)).ok())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Layout {
+            id,
+            properties: Self::properties(id),
+            children,
+        }
+    }
+}
+
+#[interface(name = "com.canonical.dbusmenu")]
+impl Menu {
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        _recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> (u32, Layout) {
+        (1, Self::layout(parent_id))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+        ids.into_iter()
+            .map(|id| (id, Self::properties(id)))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: &str) -> OwnedValue {
+        Self::properties(id)
+            .remove(name)
+            .unwrap_or_else(|| OwnedValue::from(false))
+    }
+
+    fn event(&self, id: i32, event_id: &str, _data: Value<'_>, _timestamp: u32) {
+        if event_id != "clicked" {
+            return;
+        }
+        if let Some(Some((action, _))) = Self::entry(id) {
+            (self.on_action)(action);
+        }
+    }
+
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        for (id, event_id, _, _) in events {
+            if event_id != "clicked" {
+                continue;
+            }
+            if let Some(Some((action, _))) = Self::entry(id) {
+                (self.on_action)(action);
+            }
+        }
+        Vec::new()
+    }
+
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        (Vec::new(), Vec::new())
+    }
+
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Publish the bar's quick settings as a tray item for other bars to host
+pub fn serve(on_action: impl Fn(QuickAction) + Send + Sync + 'static) {
+    let on_action: ActionHandler = Box::new(on_action);
+    relm4::spawn(async move {
+        if let Err(e) = register(on_action).await {
+            eprintln!("Status notifier item error: {}", e);
+        }
+    });
+}
+
+async fn register(on_action: ActionHandler) -> zbus::Result<()> {
+    let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    let connection = connection::Builder::session()?
+        .name(name.as_str())?
+        .serve_at(ITEM_PATH, StatusNotifierItem)?
+        .serve_at(MENU_PATH, Menu { on_action })?
+        .build()
+        .await?;
+
+    StatusNotifierWatcherProxy::new(&connection)
+        .await?
+        .register_status_notifier_item(&name)
+        .await?;
+
+    // Keep the connection, and with it the exported objects, alive
+    std::future::pending::<()>().await;
+    Ok(())
+}
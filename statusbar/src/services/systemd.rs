@@ -0,0 +1,150 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::watch;
+use zbus::proxy;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::{bus, power};
+
+/// Units can fail without a job finishing, e.g. a crashing service, so the
+/// lists are also re-read on this interval
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// One entry of `ListUnits`
+type UnitInfo = (
+    String,
+    String,
+    String,
+    String,
+    String,
+    String,
+    OwnedObjectPath,
+    u32,
+    String,
+    OwnedObjectPath,
+);
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    fn list_units_filtered(&self, states: &[&str]) -> zbus::Result<Vec<UnitInfo>>;
+
+    #[zbus(allow_interactive_auth)]
+    fn restart_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(allow_interactive_auth)]
+    fn reset_failed_unit(&self, name: &str) -> zbus::Result<()>;
+
+    /// Emitted when a job finishes, which is when units usually fail
+    #[zbus(signal)]
+    fn job_removed(&self, id: u32, job: OwnedObjectPath, unit: String, result: String);
+}
+
+/// Which service manager a unit belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    System,
+    User,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailedUnit {
+    pub name: String,
+    pub description: String,
+    pub scope: Scope,
+}
+
+static FAILED_UNITS: OnceLock<watch::Receiver<Vec<FailedUnit>>> = OnceLock::new();
+
+/// Subscribe to the failed units of both managers, starting the watcher on
+/// first use
+pub fn subscribe() -> watch::Receiver<Vec<FailedUnit>> {
+    FAILED_UNITS
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(Vec::new());
+            relm4::spawn(async move {
+                if let Err(e) = watch_units(tx).await {
+                    eprintln!("systemd service error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+async fn watch_units(tx: watch::Sender<Vec<FailedUnit>>) -> zbus::Result<()> {
+    let system = ManagerProxy::new(&bus::system().await?).await?;
+    let user = ManagerProxy::new(&bus::session().await?).await?;
+
+    let mut system_jobs = system.receive_job_removed().await?;
+    let mut user_jobs = user.receive_job_removed().await?;
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    let mut power_rx = power::subscribe();
+
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {}
+            Ok(()) = power::wake_up(&mut power_rx) => {}
+            Some(_) = system_jobs.next() => {}
+            Some(_) = user_jobs.next() => {}
+            else => break,
+        }
+
+        let mut units = failed_units(&system, Scope::System).await;
+        units.extend(failed_units(&user, Scope::User).await);
+        tx.send_if_modified(|current| {
+            if *current == units {
+                return false;
+            }
+            *current = units;
+            true
+        });
+    }
+
+    Ok(())
+}
+
+async fn failed_units(manager: &ManagerProxy<'_>, scope: Scope) -> Vec<FailedUnit> {
+    match manager.list_units_filtered(&["failed"]).await {
+        Ok(units) => units
+            .into_iter()
+            .map(|(name, description, ..)| FailedUnit {
+                name,
+                description,
+                scope,
+            })
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to list failed {:?} units: {}", scope, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Clear the failed state and restart a unit
+///
+/// System units need polkit to allow it, which may prompt for a password.
+pub async fn restart(scope: Scope, name: &str) -> Result<(), String> {
+    let connection = match scope {
+        Scope::System => bus::system().await,
+        Scope::User => bus::session().await,
+    }
+    .map_err(|e| e.to_string())?;
+    let manager = ManagerProxy::new(&connection)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // A unit over its start limit refuses to restart until reset
+    let _ = manager.reset_failed_unit(name).await;
+    manager
+        .restart_unit(name, "replace")
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to restart {}: {}", name, e))
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::http;
+use crate::config::TickerProvider;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Quote {
+    pub symbol: String,
+    pub price: f64,
+    /// Change since the previous close, or over the last 24 hours for coins
+    pub change_percent: f64,
+    pub currency: String,
+    /// Prices over the day, oldest first
+    pub history: Vec<f64>,
+}
+
+impl TickerProvider {
+    pub async fn quote(self, symbol: &str, currency: &str) -> Result<Quote, String> {
+        match self {
+            TickerProvider::Yahoo => yahoo_quote(symbol).await,
+            TickerProvider::CoinGecko => coingecko_quote(symbol, currency).await,
+        }
+    }
+}
+
+async fn yahoo_quote(symbol: &str) -> Result<Quote, String> {
+    let url = format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}?range=1d&interval=5m",
+        symbol
+    );
+    let reply: Value = http::get_json(&url).await?;
+    let result = &reply["chart"]["result"][0];
+    let meta = &result["meta"];
+
+    let unexpected = || format!("Unexpected reply from Yahoo for {}", symbol);
+    let price = meta["regularMarketPrice"].as_f64().ok_or_else(unexpected)?;
+    let previous_close = meta["chartPreviousClose"].as_f64().ok_or_else(unexpected)?;
+
+    Ok(Quote {
+        symbol: symbol.to_string(),
+        price,
+        change_percent: (price / previous_close - 1.0) * 100.0,
+        currency: meta["currency"].as_str().unwrap_or_default().to_string(),
+        // Intervals without trades are null
+        history: result["indicators"]["quote"][0]["close"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_f64)
+            .collect(),
+    })
+}
+
+async fn coingecko_quote(id: &str, currency: &str) -> Result<Quote, String> {
+    let url = format!(
+        "https://api.coingecko.com/api/v3/coins/{}/market_chart?vs_currency={}&days=1",
+        id, currency
+    );
+    let reply: Value = http::get_json(&url).await?;
+
+    // `[timestamp, price]` pairs covering the last 24 hours
+    let history: Vec<f64> = reply["prices"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|point| point[1].as_f64())
+        .collect();
+    let (Some(first), Some(price)) = (history.first(), history.last()) else {
+        return Err(format!("Unexpected reply from CoinGecko for {}", id));
+    };
+
+    Ok(Quote {
+        symbol: id.to_string(),
+        price: *price,
+        change_percent: (price / first - 1.0) * 100.0,
+        currency: currency.to_uppercase(),
+        history,
+    })
+}
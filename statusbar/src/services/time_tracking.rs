@@ -0,0 +1,263 @@
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::process::Command;
+
+use super::{http, secrets};
+use crate::config::TimeTrackingBackend;
+
+const TOGGL_API: &str = "https://api.track.toggl.com/api/v9";
+
+/// Something time can be tracked against
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Task {
+    pub description: String,
+    pub tags: Vec<String>,
+    /// Where Toggl files the task, kept so restarting it lands there again
+    #[serde(skip)]
+    project_id: Option<u64>,
+    #[serde(skip)]
+    workspace_id: Option<u64>,
+}
+
+impl Task {
+    /// A task typed by the user
+    pub fn new(description: String) -> Self {
+        Self {
+            description,
+            tags: Vec::new(),
+            project_id: None,
+            workspace_id: None,
+        }
+    }
+}
+
+/// The task being tracked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Running {
+    pub task: Task,
+    pub start: DateTime<Utc>,
+    /// Toggl's time entry
+    id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tracking {
+    pub running: Option<Running>,
+    /// Most recent first, without duplicates or the running task
+    pub recent: Vec<Task>,
+}
+
+impl TimeTrackingBackend {
+    pub async fn status(self, max_recent: usize) -> Result<Tracking, String> {
+        match self {
+            TimeTrackingBackend::Timewarrior => {
+                tokio::task::spawn_blocking(move || timewarrior_status(max_recent))
+                    .await
+                    .map_err(|e| e.to_string())?
+            }
+            TimeTrackingBackend::Toggl => toggl_status(max_recent).await,
+        }
+    }
+
+    /// Start tracking a task, stopping the running one first
+    pub async fn start(self, task: Task, running: Option<Running>) -> Result<(), String> {
+        match self {
+            // Starting an interval closes the open one
+            TimeTrackingBackend::Timewarrior => {
+                let tags = if task.tags.is_empty() {
+                    vec![task.description]
+                } else {
+                    task.tags
+                };
+                tokio::task::spawn_blocking(move || {
+                    let mut args = vec!["start".to_string()];
+                    args.extend(tags);
+                    timew(&args).map(|_| ())
+                })
+                .await
+                .map_err(|e| e.to_string())?
+            }
+            TimeTrackingBackend::Toggl => {
+                if let Some(running) = running {
+                    toggl_stop(&running).await?;
+                }
+                toggl_start(task).await
+            }
+        }
+    }
+
+    pub async fn stop(self, running: Running) -> Result<(), String> {
+        match self {
+            TimeTrackingBackend::Timewarrior => {
+                tokio::task::spawn_blocking(|| timew(&["stop".to_string()]).map(|_| ()))
+                    .await
+                    .map_err(|e| e.to_string())?
+            }
+            TimeTrackingBackend::Toggl => toggl_stop(&running).await,
+        }
+    }
+}
+
+/// Keep the newest occurrence of each task, skipping the running one
+fn recent_tasks(
+    tasks: impl Iterator<Item = Task>,
+    running: Option<&Running>,
+    max_recent: usize,
+) -> Vec<Task> {
+    let mut recent: Vec<Task> = Vec::new();
+    for task in tasks {
+        if recent.len() == max_recent {
+            break;
+        }
+        let seen = recent
+            .iter()
+            .any(|other| other.description == task.description);
+        let is_running =
+            running.is_some_and(|running| running.task.description == task.description);
+        if !task.description.is_empty() && !seen && !is_running {
+            recent.push(task);
+        }
+    }
+    recent
+}
+
+fn timew(args: &[String]) -> Result<String, String> {
+    let output = Command::new("timew")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run timew: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "timew failed: {}",
+            stderr.lines().next().unwrap_or_default().trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Read this week's intervals, where the open one has no `end`
+fn timewarrior_status(max_recent: usize) -> Result<Tracking, String> {
+    let output = timew(&["export".to_string(), ":week".to_string()])?;
+    let intervals: Vec<Value> =
+        serde_json::from_str(&output).map_err(|e| format!("Invalid timew export: {}", e))?;
+
+    // Timewarrior has no descriptions, a task is its set of tags
+    let task = |interval: &Value| {
+        let tags: Vec<String> = interval["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str().map(str::to_string))
+            .collect();
+        Task {
+            description: tags.join(", "),
+            tags,
+            project_id: None,
+            workspace_id: None,
+        }
+    };
+
+    let running = intervals
+        .iter()
+        .find(|interval| interval.get("end").is_none())
+        .and_then(|interval| {
+            // `20240131T093000Z`
+            let start = interval["start"].as_str()?;
+            let start = NaiveDateTime::parse_from_str(start, "%Y%m%dT%H%M%SZ").ok()?;
+            Some(Running {
+                task: task(interval),
+                start: start.and_utc(),
+                id: None,
+            })
+        });
+    let recent = recent_tasks(
+        intervals.iter().rev().map(task),
+        running.as_ref(),
+        max_recent,
+    );
+
+    Ok(Tracking { running, recent })
+}
+
+async fn toggl(method: Method, path: &str, body: Option<&Value>) -> Result<Value, String> {
+    let token = secrets::lookup("toggl").await?;
+    let url = format!("{}{}", TOGGL_API, path);
+    http::send_json(method, &url, (&token, "api_token"), body)
+        .await
+        .map_err(|e| format!("Toggl: {}", e))
+}
+
+fn toggl_task(entry: &Value) -> Task {
+    Task {
+        description: entry["description"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        tags: entry["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str().map(str::to_string))
+            .collect(),
+        project_id: entry["project_id"].as_u64(),
+        workspace_id: entry["workspace_id"].as_u64(),
+    }
+}
+
+/// Recent entries, newest first, include the running one with a negative
+/// duration, so one request covers both
+async fn toggl_status(max_recent: usize) -> Result<Tracking, String> {
+    let entries = toggl(Method::GET, "/me/time_entries", None).await?;
+    let entries = entries.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    let running = entries
+        .iter()
+        .find(|entry| {
+            entry["duration"]
+                .as_i64()
+                .is_some_and(|duration| duration < 0)
+        })
+        .and_then(|entry| {
+            let start = DateTime::parse_from_rfc3339(entry["start"].as_str()?).ok()?;
+            Some(Running {
+                task: toggl_task(entry),
+                start: start.with_timezone(&Utc),
+                id: entry["id"].as_u64(),
+            })
+        });
+    let recent = recent_tasks(entries.iter().map(toggl_task), running.as_ref(), max_recent);
+
+    Ok(Tracking { running, recent })
+}
+
+async fn toggl_start(task: Task) -> Result<(), String> {
+    // Typed tasks go to the default workspace
+    let workspace_id = match task.workspace_id {
+        Some(id) => id,
+        None => toggl(Method::GET, "/me", None).await?["default_workspace_id"]
+            .as_u64()
+            .ok_or_else(|| "Toggl: no default workspace".to_string())?,
+    };
+    let entry = json!({
+        "created_with": "statusbar",
+        "description": task.description,
+        "tags": task.tags,
+        "project_id": task.project_id,
+        "workspace_id": workspace_id,
+        "start": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        "duration": -1,
+    });
+    let path = format!("/workspaces/{}/time_entries", workspace_id);
+    toggl(Method::POST, &path, Some(&entry)).await.map(|_| ())
+}
+
+async fn toggl_stop(running: &Running) -> Result<(), String> {
+    let (Some(workspace_id), Some(id)) = (running.task.workspace_id, running.id) else {
+        return Err("Toggl: the running entry is incomplete".to_string());
+    };
+    let path = format!("/workspaces/{}/time_entries/{}/stop", workspace_id, id);
+    toggl(Method::PATCH, &path, None).await.map(|_| ())
+}
@@ -0,0 +1,88 @@
+use futures_util::StreamExt;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+use zbus::proxy;
+
+use super::bus;
+
+// systemd-timedated, activated on demand
+#[proxy(
+    interface = "org.freedesktop.timedate1",
+    default_service = "org.freedesktop.timedate1",
+    default_path = "/org/freedesktop/timedate1"
+)]
+trait TimeDate {
+    /// IANA name of the system timezone, e.g. `Europe/Berlin`
+    #[zbus(property)]
+    fn timezone(&self) -> zbus::Result<String>;
+}
+
+// systemd-localed, activated on demand
+#[proxy(
+    interface = "org.freedesktop.locale1",
+    default_service = "org.freedesktop.locale1",
+    default_path = "/org/freedesktop/locale1"
+)]
+trait Locale {
+    /// Assignments like `LANG=en_US.UTF-8`
+    #[zbus(property)]
+    fn locale(&self) -> zbus::Result<Vec<String>>;
+}
+
+/// System timezone and locale, as configured with timedatectl and localectl
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeSettings {
+    pub timezone: String,
+    pub locale: Vec<String>,
+}
+
+static SETTINGS: OnceLock<watch::Receiver<TimeSettings>> = OnceLock::new();
+
+/// Subscribe to timezone and locale changes, starting the watcher on first use
+///
+/// `chrono::Local` picks up a new `/etc/localtime` by itself, so widgets only
+/// need to format their dates again when this changes.
+pub fn subscribe() -> watch::Receiver<TimeSettings> {
+    SETTINGS
+        .get_or_init(|| {
+            let (tx, rx) = watch::channel(TimeSettings::default());
+            relm4::spawn(async move {
+                if let Err(e) = watch_settings(tx).await {
+                    eprintln!("Time settings service error: {}", e);
+                }
+            });
+            rx
+        })
+        .clone()
+}
+
+async fn watch_settings(tx: watch::Sender<TimeSettings>) -> zbus::Result<()> {
+    let connection = bus::system().await?;
+    let timedate = TimeDateProxy::new(&connection).await?;
+    let locale = LocaleProxy::new(&connection).await?;
+
+    tx.send_replace(TimeSettings {
+        timezone: timedate.timezone().await.unwrap_or_default(),
+        locale: locale.locale().await.unwrap_or_default(),
+    });
+
+    let mut timezone_changes = timedate.receive_timezone_changed().await;
+    let mut locale_changes = locale.receive_locale_changed().await;
+    loop {
+        tokio::select! {
+            Some(change) = timezone_changes.next() => {
+                if let Ok(timezone) = change.get().await {
+                    tx.send_modify(|settings| settings.timezone = timezone);
+                }
+            }
+            Some(change) = locale_changes.next() => {
+                if let Ok(locale) = change.get().await {
+                    tx.send_modify(|settings| settings.locale = locale);
+                }
+            }
+            else => break,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use serde::Serialize;
+use std::process::Command;
+
+pub use crate::config::UpdateBackend;
+
+/// A package with a newer version available
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Update {
+    pub name: String,
+    /// Installed version, when the backend reports it
+    pub current: Option<String>,
+    pub new: String,
+    pub backend: UpdateBackend,
+}
+
+impl UpdateBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            UpdateBackend::Pacman => "pacman",
+            UpdateBackend::Apt => "apt",
+            UpdateBackend::Dnf => "dnf",
+            UpdateBackend::Flatpak => "flatpak",
+        }
+    }
+
+    /// List pending updates, blocking until the backend answers
+    pub fn check(self) -> Result<Vec<Update>, String> {
+        let (program, args): (&str, &[&str]) = match self {
+            UpdateBackend::Pacman => ("checkupdates", &[]),
+            UpdateBackend::Apt => ("apt", &["list", "--upgradable"]),
+            UpdateBackend::Dnf => ("dnf", &["check-update", "--quiet"]),
+            UpdateBackend::Flatpak => (
+                "flatpak",
+                &["remote-ls", "--updates", "--columns=application,version"],
+            ),
+        };
+
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+        // checkupdates exits with 2 when there is nothing to update, and
+        // dnf with 100 when there is
+        let ok = match self {
+            UpdateBackend::Pacman => matches!(output.status.code(), Some(0 | 2)),
+            UpdateBackend::Dnf => matches!(output.status.code(), Some(0 | 100)),
+            _ => output.status.success(),
+        };
+        if !ok {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "{} failed: {}",
+                program,
+                stderr.lines().next().unwrap_or_default().trim()
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(match self {
+            UpdateBackend::Pacman => parse_pacman(&stdout),
+            UpdateBackend::Apt => parse_apt(&stdout),
+            UpdateBackend::Dnf => parse_dnf(&stdout),
+            UpdateBackend::Flatpak => parse_flatpak(&stdout),
+        })
+    }
+}
+
+/// Check every backend, keeping the updates of those that answered
+///
+/// Updates are sorted by name; errors are returned alongside them.
+pub fn check_all(backends: &[UpdateBackend]) -> (Vec<Update>, Vec<String>) {
+    let mut updates = Vec::new();
+    let mut errors = Vec::new();
+    for backend in backends {
+        match backend.check() {
+            Ok(found) => updates.extend(found),
+            Err(e) => errors.push(e),
+        }
+    }
+    updates.sort_by(|a, b| a.name.cmp(&b.name));
+    (updates, errors)
+}
+
+/// `linux 6.9.1.arch1-1 -> 6.9.2.arch1-1`
+fn parse_pacman(stdout: &str) -> Vec<Update> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let current = fields.next()?;
+            let new = fields.nth(1)?;
+            Some(Update {
+                name: name.to_string(),
+                current: Some(current.to_string()),
+                new: new.to_string(),
+                backend: UpdateBackend::Pacman,
+            })
+        })
+        .collect()
+}
+
+/// `curl/stable 7.88.1-10+deb12u6 amd64 [upgradable from: 7.88.1-10+deb12u5]`
+fn parse_apt(stdout: &str) -> Vec<Update> {
+    stdout
+        .lines()
+        .filter(|line| line.contains("[upgradable from:"))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.split('/').next()?;
+            let new = fields.next()?;
+            let current = line
+                .rsplit_once("upgradable from: ")
+                .map(|(_, rest)| rest.trim_end_matches(']').to_string());
+            Some(Update {
+                name: name.to_string(),
+                current,
+                new: new.to_string(),
+                backend: UpdateBackend::Apt,
+            })
+        })
+        .collect()
+}
+
+/// `kernel.x86_64  6.9.2-200.fc40  updates`
+fn parse_dnf(stdout: &str) -> Vec<Update> {
+    stdout
+        .lines()
+        // Packages replaced by others are listed again in a trailing section
+        .take_while(|line| !line.starts_with("Obsoleting"))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [package, new, _repository] = fields[..] else {
+                return None;
+            };
+            let name = package.rsplit_once('.').map_or(package, |(name, _)| name);
+            Some(Update {
+                name: name.to_string(),
+                current: None,
+                new: new.to_string(),
+                backend: UpdateBackend::Dnf,
+            })
+        })
+        .collect()
+}
+
+/// `org.mozilla.firefox\t127.0`, with the version possibly empty
+fn parse_flatpak(stdout: &str) -> Vec<Update> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let name = fields.next()?.trim();
+            if name.is_empty() {
+                return None;
+            }
+            Some(Update {
+                name: name.to_string(),
+                current: None,
+                new: fields.next().unwrap_or_default().trim().to_string(),
+                backend: UpdateBackend::Flatpak,
+            })
+        })
+        .collect()
+}
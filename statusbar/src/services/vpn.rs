@@ -0,0 +1,214 @@
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::config::VpnBackend;
+
+const TAILSCALE_SOCKET: &str = "/var/run/tailscale/tailscaled.sock";
+
+/// Don't let a wedged daemon block the polling thread forever
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A Tailscale node that can route all traffic
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExitNode {
+    /// Stable node ID, as the prefs expect it
+    pub id: String,
+    pub name: String,
+    pub online: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Peer {
+    pub name: String,
+    pub ip: String,
+    pub online: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VpnStatus {
+    pub connected: bool,
+    /// Daemon state as reported, e.g. `NeedsLogin` or `Connecting`
+    pub state: String,
+    /// Exit node or Mullvad relay traffic leaves through
+    pub exit_node: Option<String>,
+    pub exit_nodes: Vec<ExitNode>,
+    /// Online peers first, then by name
+    pub peers: Vec<Peer>,
+}
+
+impl VpnBackend {
+    /// Ask the daemon for its status, blocking until it answers
+    pub fn status(self) -> Result<VpnStatus, String> {
+        match self {
+            VpnBackend::Tailscale => tailscale_status(),
+            VpnBackend::Mullvad => mullvad_status(),
+        }
+    }
+
+    pub fn set_connected(self, connected: bool) -> Result<(), String> {
+        match self {
+            VpnBackend::Tailscale => tailscale_set_prefs(json!({
+                "WantRunning": connected,
+                "WantRunningSet": true,
+            })),
+            VpnBackend::Mullvad => {
+                mullvad(&[if connected { "connect" } else { "disconnect" }]).map(|_| ())
+            }
+        }
+    }
+
+    /// Route traffic through a Tailscale exit node, or directly for `None`
+    pub fn set_exit_node(self, id: Option<&str>) -> Result<(), String> {
+        match self {
+            VpnBackend::Tailscale => tailscale_set_prefs(json!({
+                "ExitNodeID": id.unwrap_or_default(),
+                "ExitNodeIDSet": true,
+            })),
+            VpnBackend::Mullvad => Err("Mullvad picks relays by location".to_string()),
+        }
+    }
+}
+
+/// One request to tailscaled's LocalAPI, returning the JSON body
+fn tailscale_request(method: &str, path: &str, body: Option<&Value>) -> Result<Value, String> {
+    let mut stream = UnixStream::connect(TAILSCALE_SOCKET)
+        .map_err(|e| format!("Failed to reach tailscaled: {}", e))?;
+    stream
+        .set_read_timeout(Some(SOCKET_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    // HTTP/1.0 so the reply is never chunked and ends with the connection
+    let body = body.map(Value::to_string).unwrap_or_default();
+    let request = format!(
+        "{} {} HTTP/1.0\r\nHost: local-tailscaled.sock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .map_err(|e| e.to_string())?;
+    let (head, body) = reply
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "Malformed reply from tailscaled".to_string())?;
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        // Changing prefs needs `tailscale set --operator=$USER` once
+        return Err(format!("tailscaled: {}", body.trim()));
+    }
+    serde_json::from_str(body).map_err(|e| e.to_string())
+}
+
+fn tailscale_set_prefs(prefs: Value) -> Result<(), String> {
+    tailscale_request("PATCH", "/localapi/v0/prefs", Some(&prefs)).map(|_| ())
+}
+
+fn tailscale_status() -> Result<VpnStatus, String> {
+    let status = tailscale_request("GET", "/localapi/v0/status", None)?;
+    let state = status["BackendState"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+    // MagicDNS names end with the tailnet's domain, host names may be local
+    let name = |peer: &Value| {
+        peer["DNSName"]
+            .as_str()
+            .and_then(|dns| dns.split('.').next())
+            .filter(|name| !name.is_empty())
+            .or_else(|| peer["HostName"].as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let peers_json: Vec<&Value> = status["Peer"]
+        .as_object()
+        .map(|peers| peers.values().collect())
+        .unwrap_or_default();
+
+    let mut exit_nodes: Vec<ExitNode> = peers_json
+        .iter()
+        .filter(|peer| peer["ExitNodeOption"] == true)
+        .map(|peer| ExitNode {
+            id: peer["ID"].as_str().unwrap_or_default().to_string(),
+            name: name(peer),
+            online: peer["Online"] == true,
+        })
+        .collect();
+    exit_nodes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut peers: Vec<Peer> = peers_json
+        .iter()
+        .map(|peer| Peer {
+            name: name(peer),
+            ip: peer["TailscaleIPs"][0]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            online: peer["Online"] == true,
+        })
+        .collect();
+    peers.sort_by(|a, b| b.online.cmp(&a.online).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(VpnStatus {
+        connected: state == "Running",
+        exit_node: peers_json
+            .iter()
+            .find(|peer| peer["ExitNode"] == true)
+            .map(|peer| name(peer)),
+        state,
+        exit_nodes,
+        peers,
+    })
+}
+
+fn mullvad(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("mullvad")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run mullvad: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "mullvad failed: {}",
+            stderr.lines().next().unwrap_or_default().trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Read `mullvad status`, which starts with the state and lists the relay
+/// on a `Relay:` line once connected
+fn mullvad_status() -> Result<VpnStatus, String> {
+    let output = mullvad(&["status"])?;
+    let mut lines = output.lines().map(str::trim);
+    let first = lines.next().unwrap_or_default();
+    // Older versions print `Connected to <relay> in <location>`
+    let (state, inline_relay) = match first.split_once(" to ") {
+        Some((state, rest)) => (state, rest.split(" in ").next()),
+        None => (first, None),
+    };
+    let relay = inline_relay.map(str::to_string).or_else(|| {
+        lines
+            .find_map(|line| line.strip_prefix("Relay:"))
+            .map(|relay| relay.trim().to_string())
+    });
+
+    Ok(VpnStatus {
+        connected: state == "Connected",
+        state: state.to_string(),
+        exit_node: relay,
+        exit_nodes: Vec::new(),
+        peers: Vec::new(),
+    })
+}
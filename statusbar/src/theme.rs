@@ -0,0 +1,198 @@
+use relm4::gtk;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{BarConfig, ThemeConfig};
+
+/// Where the theme's files appear to grass; imports below it are served by
+/// [`ThemeFs`]
+const THEME_ROOT: &str = "/statusbar-theme";
+
+/// Embed the default theme, so installed binaries don't need the source tree
+macro_rules! embed_theme {
+    ($($file:literal),* $(,)?) => {
+        &[$(($file, include_str!(concat!("../theme/", $file)))),*]
+    };
+}
+
+const EMBEDDED_THEME: &[(&str, &str)] = embed_theme![
+    "style.scss",
+    "_variables.scss",
+    "_mixins.scss",
+    "base.scss",
+    "accessibility.scss",
+    "animations.scss",
+    "widgets/battery.scss",
+    "widgets/bluetooth.scss",
+    "widgets/break_reminder.scss",
+    "widgets/brightness.scss",
+    "widgets/clock.scss",
+    "widgets/countdown.scss",
+    "widgets/custom.scss",
+    "widgets/diagnostics.scss",
+    "widgets/disk_alert.scss",
+    "widgets/email.scss",
+    "widgets/failed_units.scss",
+    "widgets/focus_timer.scss",
+    "widgets/gamemode.scss",
+    "widgets/input_method.scss",
+    "widgets/keyboard_layout.scss",
+    "widgets/lua.scss",
+    "widgets/media_player.scss",
+    "widgets/microphone.scss",
+    "widgets/night_light.scss",
+    "widgets/notification_center.scss",
+    "widgets/popover.scss",
+    "widgets/privacy_indicator.scss",
+    "widgets/resources.scss",
+    "widgets/screen_time.scss",
+    "widgets/settings.scss",
+    "widgets/taskbar.scss",
+    "widgets/ticker.scss",
+    "widgets/time_tracking.scss",
+    "widgets/tray.scss",
+    "widgets/updates.scss",
+    "widgets/volume.scss",
+    "widgets/vpn.scss",
+    "widgets/weather.scss",
+    "widgets/wifi.scss",
+    "widgets/window_title.scss",
+    "widgets/workspace.scss",
+];
+
+/// The embedded theme, with files of the same name in the override
+/// directories taking precedence one by one
+#[derive(Debug)]
+struct ThemeFs {
+    overrides: Vec<PathBuf>,
+}
+
+impl ThemeFs {
+    /// `$XDG_CONFIG_HOME/statusbar/`, then `/usr/share/statusbar/`
+    fn new() -> Self {
+        let overrides = dirs::config_dir()
+            .map(|dir| dir.join("statusbar"))
+            .into_iter()
+            .chain([PathBuf::from("/usr/share/statusbar")])
+            .collect();
+        Self { overrides }
+    }
+
+    /// `path` relative to the theme's root, with `.` and `..` resolved
+    fn relative(path: &Path) -> Option<PathBuf> {
+        let mut relative = PathBuf::new();
+        for component in path.strip_prefix(THEME_ROOT).ok()?.components() {
+            match component {
+                Component::Normal(name) => relative.push(name),
+                Component::ParentDir => {
+                    relative.pop();
+                }
+                _ => {}
+            }
+        }
+        Some(relative)
+    }
+
+    fn override_path(&self, relative: &Path) -> Option<PathBuf> {
+        self.overrides
+            .iter()
+            .map(|dir| dir.join(relative))
+            .find(|path| path.is_file())
+    }
+
+    fn embedded(relative: &Path) -> Option<&'static str> {
+        EMBEDDED_THEME
+            .iter()
+            .find(|(name, _)| Path::new(name) == relative)
+            .map(|(_, contents)| *contents)
+    }
+}
+
+impl grass::Fs for ThemeFs {
+    fn is_dir(&self, path: &Path) -> bool {
+        Self::relative(path).is_some_and(|relative| {
+            relative.as_os_str().is_empty()
+                || relative == Path::new("widgets")
+                || self
+                    .overrides
+                    .iter()
+                    .any(|dir| dir.join(&relative).is_dir())
+        })
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        Self::relative(path).is_some_and(|relative| {
+            self.override_path(&relative).is_some() || Self::embedded(&relative).is_some()
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let relative = Self::relative(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "outside the theme"))?;
+        if let Some(path) = self.override_path(&relative) {
+            return std::fs::read(path);
+        }
+        Self::embedded(&relative)
+            .map(|contents| contents.as_bytes().to_vec())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not part of the theme"))
+    }
+}
+
+/// Compile SCSS to CSS at runtime, with `$color-scheme` set to `light` or
+/// `dark` for the palette to pick its colors by and `variables` set ahead of
+/// the theme's defaults
+pub fn compile_scss(light: bool, variables: &HashMap<String, String>) -> Result<String, String> {
+    let color_scheme = if light { "light" } else { "dark" };
+
+    let mut scss = format!("$color-scheme: {};\n", color_scheme);
+    for (name, value) in variables {
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            eprintln!("Ignoring theme variable '{}': not a SCSS name", name);
+            continue;
+        }
+        scss.push_str(&format!("${}: {};\n", name, value));
+    }
+    scss.push_str("@import 'style';\n");
+
+    let fs = ThemeFs::new();
+    let options = grass::Options::default().fs(&fs).load_path(THEME_ROOT);
+    grass::from_string(scss, &options).map_err(|e| format!("Failed to compile SCSS:\n{}", e))
+}
+
+/// Compile the theme in the light or dark palette and (re)load it into
+/// `provider`
+///
+/// A mistake in the configured variables falls back to the plain theme, so
+/// it can't leave the bar unstyled.
+pub fn load(provider: &gtk::CssProvider, config: &ThemeConfig, light: bool) -> Result<(), String> {
+    let mut variables = config.variables.clone();
+    variables
+        .entry("animation-duration".to_string())
+        .or_insert_with(|| format!("{}ms", config.animations.duration_ms));
+
+    let css = compile_scss(light, &variables).or_else(|e| {
+        if config.variables.is_empty() {
+            return Err(e);
+        }
+        eprintln!("{}\nIgnoring [theme.variables]", e);
+        compile_scss(light, &HashMap::new())
+    })?;
+
+    #[cfg(debug_assertions)]
+    std::fs::write("./final.css", &css).map_err(|e| e.to_string())?;
+
+    provider.load_from_data(&css);
+    Ok(())
+}
+
+/// CSS for the bar shape set in the config, which the SCSS can't know about
+pub fn bar_css(config: &BarConfig) -> String {
+    format!(
+        ".statusbar-window.floating .statusbar-container {{ border-radius: {}px; }}",
+        config.corner_radius.max(0)
+    )
+}
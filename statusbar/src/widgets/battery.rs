@@ -1,16 +1,210 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
+use serde_json::json;
+use std::collections::VecDeque;
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 
+use super::poll::{self, Poll};
 use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use super::tooltip;
+use crate::config::BatteryConfig;
+
+/// Rates are averaged over this long, so the estimate doesn't jump with load
+const RATE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// The shown estimate only moves once it's off by more than this fraction
+const ESTIMATE_TOLERANCE: f32 = 0.05;
+
+/// The kernel's `status` of the battery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Charging,
+    Discharging,
+    /// Plugged in and topped up
+    Full,
+    /// Plugged in but held at a charge limit below full
+    NotCharging,
+    Unknown,
+}
+
+impl Status {
+    fn parse(status: &str) -> Self {
+        match status {
+            "Charging" => Status::Charging,
+            "Discharging" => Status::Discharging,
+            "Full" => Status::Full,
+            "Not charging" => Status::NotCharging,
+            _ => Status::Unknown,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Status::Charging => "Charging",
+            Status::Discharging => "Discharging",
+            Status::Full => "Full",
+            Status::NotCharging => "Not charging",
+            Status::Unknown => "Unknown",
+        }
+    }
+
+    /// On AC power, whether charging or not
+    fn is_plugged_in(self) -> bool {
+        matches!(self, Status::Charging | Status::Full | Status::NotCharging)
+    }
+}
+
+/// What's in the battery and how fast that changes, from `energy_now` and
+/// `power_now` (µWh, µW) or, on batteries reporting charge instead,
+/// `charge_now` and `current_now` (µAh, µA)
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    now: f32,
+    full: Option<f32>,
+    rate: f32,
+    /// `voltage_now` in µV, to turn a current into watts
+    voltage: Option<f32>,
+    /// Whether the figures are charge rather than energy
+    charge: bool,
+}
+
+impl Reading {
+    fn read(battery_path: &str) -> Option<Self> {
+        Self::from_values(|name| {
+            fs::read_to_string(format!("{}/{}", battery_path, name))
+                .ok()
+                .and_then(|value| value.trim().parse::<f32>().ok())
+        })
+    }
+
+    /// Build a reading from the attribute files `value` returns
+    fn from_values(value: impl Fn(&str) -> Option<f32>) -> Option<Self> {
+        let (now, full, rate, charge) = match value("energy_now") {
+            Some(energy) => (energy, value("energy_full"), value("power_now"), false),
+            None => (
+                value("charge_now")?,
+                value("charge_full"),
+                value("current_now"),
+                true,
+            ),
+        };
+        Some(Self {
+            now,
+            full,
+            // Some drivers report the rate negative while discharging
+            rate: rate.unwrap_or(0.0).abs(),
+            voltage: value("voltage_now"),
+            charge,
+        })
+    }
+
+    /// Draw from or into the battery
+    fn watts(&self) -> Option<f32> {
+        if self.rate <= 0.0 {
+            return None;
+        }
+        match self.charge {
+            false => Some(self.rate / 1_000_000.0),
+            true => self
+                .voltage
+                .map(|voltage| self.rate * voltage / 1_000_000_000_000.0),
+        }
+    }
+
+    /// Hours until empty, or full while charging, at `rate`
+    fn hours_left(&self, status: Status, rate: f32) -> Option<f32> {
+        if rate <= 0.0 {
+            return None;
+        }
+        match status {
+            Status::Charging => Some((self.full? - self.now).max(0.0) / rate),
+            _ => Some(self.now / rate),
+        }
+    }
+}
+
+/// Recent rates, reset when the battery starts or stops charging
+#[derive(Debug)]
+struct RateAverage {
+    rates: VecDeque<(SystemTime, f32)>,
+    /// Status the rates were sampled in
+    status: Status,
+    /// When that status was first seen
+    since: SystemTime,
+}
+
+impl RateAverage {
+    fn new() -> Self {
+        Self {
+            rates: VecDeque::new(),
+            status: Status::Unknown,
+            since: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    /// Add `rate`, sampled at `now` in `status`, and average the recent ones
+    fn add(&mut self, status: Status, rate: f32, now: SystemTime) -> f32 {
+        if status != self.status {
+            self.rates.clear();
+            self.status = status;
+            self.since = now;
+        }
+
+        self.rates.retain(|(sampled, _)| {
+            now.duration_since(*sampled)
+                .is_ok_and(|age| age <= RATE_WINDOW)
+        });
+        if rate > 0.0 {
+            self.rates.push_back((now, rate));
+        }
+
+        if self.rates.is_empty() {
+            return 0.0;
+        }
+        self.rates.iter().map(|(_, rate)| rate).sum::<f32>() / self.rates.len() as f32
+    }
+
+    /// Still within the first window of the status, when a missing rate may
+    /// just not have been sampled yet
+    fn warming_up(&self, now: SystemTime) -> bool {
+        now.duration_since(self.since)
+            .is_ok_and(|age| age < RATE_WINDOW)
+    }
+
+    fn clear(&mut self) {
+        self.rates.clear();
+        self.status = Status::Unknown;
+    }
+}
+
+/// Keep `shown` unless `hours` is off from it by more than
+/// `ESTIMATE_TOLERANCE`, so the estimate doesn't flicker between polls
+fn debounce(shown: Option<f32>, hours: f32) -> f32 {
+    match shown {
+        Some(shown) if (hours - shown).abs() <= shown * ESTIMATE_TOLERANCE => shown,
+        _ => hours,
+    }
+}
 
 pub struct Battery {
+    config: BatteryConfig,
     charge_level: f32,
-    is_charging: bool,
+    status: Status,
+    /// Draw from or into the battery, if the kernel reports it
+    power_watts: Option<f32>,
+    /// Estimate from the rate averaged over `RATE_WINDOW`
     time_remaining: String,
+    /// Hours behind `time_remaining`, when there is an estimate
+    estimate: Option<f32>,
+    /// Estimate from the rate at the last reading
+    time_remaining_now: String,
+    rates: RateAverage,
     battery_path: Option<String>,
     popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
 }
 
 #[derive(Debug, Clone)]
@@ -21,7 +215,7 @@ pub enum BatteryMsg {
 
 #[relm4::component(pub)]
 impl SimpleComponent for Battery {
-    type Init = ();
+    type Init = BatteryConfig;
     type Input = BatteryMsg;
     type Output = ();
 
@@ -36,6 +230,10 @@ impl SimpleComponent for Battery {
             gtk::Button {
                 set_css_classes: &["battery-button"],
                 connect_clicked => BatteryMsg::TogglePopover,
+                #[watch]
+                set_tooltip_markup: model.tooltip().as_deref(),
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&model.accessible_label())],
 
                 gtk::Box {
                     set_orientation: gtk::Orientation::Horizontal,
@@ -43,9 +241,9 @@ impl SimpleComponent for Battery {
 
                     gtk::Label {
                         #[watch]
-                        set_label: &Self::battery_icon(model.charge_level, model.is_charging),
+                        set_label: &Self::battery_icon(model.charge_level, model.status),
                         #[watch]
-                        set_css_classes: &["battery-icon", &Self::battery_status_class(model.charge_level, model.is_charging)],
+                        set_css_classes: &["battery-icon", &Self::battery_status_class(model.charge_level, model.status)],
                     },
                 }
             }
@@ -53,7 +251,7 @@ impl SimpleComponent for Battery {
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -68,15 +266,22 @@ impl SimpleComponent for Battery {
                 margin: 12,
                 spacing: 8,
                 width: None,
+                ..Default::default()
             })
             .detach();
 
-        let model = Battery {
+        let mut model = Battery {
+            config,
             charge_level: 0.0,
-            is_charging: false,
+            status: Status::Unknown,
+            power_watts: None,
             time_remaining: String::new(),
+            estimate: None,
+            time_remaining_now: String::new(),
+            rates: RateAverage::new(),
             battery_path,
             popover,
+            timers: Vec::new(),
         };
 
         let widgets = view_output!();
@@ -89,10 +294,9 @@ impl SimpleComponent for Battery {
 
         // Update every 30 seconds
         let sender_clone = sender.clone();
-        gtk::glib::timeout_add_seconds_local(30, move || {
-            sender_clone.input(BatteryMsg::Update);
-            gtk::glib::ControlFlow::Continue
-        });
+        model.timers.push(poll::every(30, move || {
+            sender_clone.input(BatteryMsg::Update)
+        }));
 
         // Initial update
         sender.input(BatteryMsg::Update);
@@ -140,49 +344,74 @@ impl Battery {
 
             // Read status
             if let Ok(status) = fs::read_to_string(format!("{}/status", battery_path)) {
-                self.is_charging = status.trim() == "Charging";
+                self.status = Status::parse(status.trim());
             }
 
-            // Calculate time remaining (simplified)
-            if let Ok(energy_now) = fs::read_to_string(format!("{}/energy_now", battery_path)) {
-                if let Ok(power_now) = fs::read_to_string(format!("{}/power_now", battery_path)) {
-                    if let (Ok(energy), Ok(power)) = (
-                        energy_now.trim().parse::<f32>(),
-                        power_now.trim().parse::<f32>(),
-                    ) {
-                        if power > 0.0 {
-                            let hours = energy / power;
-                            let h = hours as i32;
-                            let m = ((hours - h as f32) * 60.0) as i32;
-
-                            if self.is_charging {
-                                self.time_remaining = format!("{}h {}m until full", h, m);
-                            } else {
-                                self.time_remaining = format!("{}h {}m remaining", h, m);
-                            }
-                        } else {
-                            self.time_remaining = "Calculating...".to_string();
-                        }
-                    }
-                }
+            let reading = Reading::read(battery_path);
+            self.power_watts = reading.as_ref().and_then(Reading::watts);
+
+            // There's no time to estimate while the battery is held where it is
+            if !matches!(self.status, Status::Charging | Status::Discharging) {
+                self.clear_estimate();
+                self.rates.clear();
+                return;
             }
+            // Nor without a reading, or knowing where charging ends
+            let reading = reading
+                .filter(|reading| self.status == Status::Discharging || reading.full.is_some());
+            let Some(reading) = reading else {
+                self.clear_estimate();
+                return;
+            };
+
+            // Going by the wall clock so time asleep counts too
+            let now = SystemTime::now();
+            if self.status != self.rates.status {
+                self.estimate = None;
+            }
+            let rate = self.rates.add(self.status, reading.rate, now);
+            self.estimate = reading
+                .hours_left(self.status, rate)
+                .map(|hours| debounce(self.estimate, hours));
+            self.time_remaining = match self.estimate {
+                Some(hours) => self.format_hours(hours),
+                None if self.rates.warming_up(now) => "Calculating...".to_string(),
+                None => String::new(),
+            };
+            self.time_remaining_now = reading
+                .hours_left(self.status, reading.rate)
+                .map(|hours| self.format_hours(hours))
+                .unwrap_or_default();
+        }
+    }
+
+    fn clear_estimate(&mut self) {
+        self.time_remaining.clear();
+        self.estimate = None;
+        self.time_remaining_now.clear();
+    }
+
+    fn format_hours(&self, hours: f32) -> String {
+        let h = hours as i32;
+        let m = ((hours - h as f32) * 60.0) as i32;
+
+        if self.status == Status::Charging {
+            format!("{}h {}m until full", h, m)
+        } else {
+            format!("{}h {}m remaining", h, m)
         }
     }
 
     fn update_popover_content(&self) {
         let mut items = vec![
-            PopoverItem::DetailRow {
+            PopoverItem::Progress {
                 label: "Battery Level".to_string(),
-                value: format!("{}%", self.charge_level as i32),
-                value_css: "battery-detail".to_string(),
+                fraction: f64::from(self.charge_level) / 100.0,
+                css: Self::battery_status_class(self.charge_level, self.status),
             },
             PopoverItem::DetailRow {
                 label: "Status".to_string(),
-                value: if self.is_charging {
-                    "Charging".to_string()
-                } else {
-                    "Discharging".to_string()
-                },
+                value: self.status.label().to_string(),
                 value_css: "battery-detail".to_string(),
             },
         ];
@@ -196,12 +425,55 @@ impl Battery {
             });
         }
 
+        // The unsmoothed figure, for seeing what a load does right away
+        if !self.time_remaining_now.is_empty() {
+            items.push(PopoverItem::DetailRow {
+                label: "At current rate".to_string(),
+                value: self.time_remaining_now.clone(),
+                value_css: "battery-detail".to_string(),
+            });
+        }
+
         self.popover.emit(PopoverMsg::UpdateItems(items));
     }
 
-    fn battery_icon(level: f32, charging: bool) -> &'static str {
-        if charging {
-            return "󰂄"; // Charging icon
+    fn tooltip(&self) -> Option<String> {
+        let status = self.status.label();
+        let time = match self.time_remaining.as_str() {
+            "Calculating..." => "",
+            time => time,
+        };
+        let power = self
+            .power_watts
+            .map(|watts| format!("{:.1} W", watts))
+            .unwrap_or_default();
+        tooltip::render(
+            &self.config.tooltip,
+            &[
+                ("percentage", &format!("{:.0}", self.charge_level)),
+                ("status", status),
+                ("power", &power),
+                ("time", time),
+            ],
+        )
+    }
+
+    /// What screen readers announce for the glyph-only button
+    fn accessible_label(&self) -> String {
+        let state = match self.status {
+            Status::Discharging => String::new(),
+            status => format!(", {}", status.label().to_lowercase()),
+        };
+        format!("Battery {:.0}%{}", self.charge_level, state)
+    }
+
+    fn battery_icon(level: f32, status: Status) -> &'static str {
+        match status {
+            Status::Charging => return "󰂄",    // Charging icon
+            Status::Full => return "󰂅",        // Full and plugged in
+            Status::NotCharging => return "󰚥", // Plugged in, held at a limit
+            Status::Unknown => return "󰂑",     // Status unknown
+            Status::Discharging => {}
         }
 
         match level as i32 {
@@ -218,9 +490,13 @@ impl Battery {
         }
     }
 
-    fn battery_status_class(level: f32, charging: bool) -> String {
-        if charging {
+    fn battery_status_class(level: f32, status: Status) -> String {
+        if status == Status::Charging {
             "battery-charging".to_string()
+        } else if status.is_plugged_in() {
+            "battery-plugged".to_string()
+        } else if status == Status::Unknown {
+            "battery-unknown".to_string()
         } else if level <= 20.0 {
             "battery-low".to_string()
         } else if level <= 50.0 {
@@ -230,3 +506,128 @@ impl Battery {
         }
     }
 }
+
+impl Snapshot for Battery {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "present": self.battery_path.is_some(),
+            "charge_level": self.charge_level,
+            "status": self.status.label(),
+            "is_charging": self.status == Status::Charging,
+            "power_watts": self.power_watts,
+            "time_remaining": self.time_remaining,
+            "time_remaining_now": self.time_remaining_now,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + seconds)
+    }
+
+    #[test]
+    fn statuses_are_told_apart() {
+        assert_eq!(Status::parse("Charging"), Status::Charging);
+        assert_eq!(Status::parse("Discharging"), Status::Discharging);
+        assert_eq!(Status::parse("Full"), Status::Full);
+        assert_eq!(Status::parse("Not charging"), Status::NotCharging);
+        assert_eq!(Status::parse("Unknown"), Status::Unknown);
+        assert_eq!(Status::parse("Bogus"), Status::Unknown);
+
+        assert!(Status::NotCharging.is_plugged_in());
+        assert!(Status::Full.is_plugged_in());
+        assert!(!Status::Discharging.is_plugged_in());
+        assert!(!Status::Unknown.is_plugged_in());
+    }
+
+    #[test]
+    fn charge_readings_use_the_voltage_for_watts() {
+        let reading = Reading::from_values(|name| match name {
+            "charge_now" => Some(2_000_000.0),
+            "charge_full" => Some(4_000_000.0),
+            "current_now" => Some(-1_000_000.0),
+            "voltage_now" => Some(12_000_000.0),
+            _ => None,
+        })
+        .unwrap();
+        assert!(reading.charge);
+        assert_eq!(reading.watts(), Some(12.0));
+        assert_eq!(
+            reading.hours_left(Status::Discharging, reading.rate),
+            Some(2.0)
+        );
+        assert_eq!(
+            reading.hours_left(Status::Charging, reading.rate),
+            Some(2.0)
+        );
+    }
+
+    #[test]
+    fn energy_readings_are_preferred() {
+        let reading = Reading::from_values(|name| match name {
+            "energy_now" => Some(30_000_000.0),
+            "power_now" => Some(15_000_000.0),
+            "charge_now" => Some(1.0),
+            _ => None,
+        })
+        .unwrap();
+        assert!(!reading.charge);
+        assert_eq!(reading.watts(), Some(15.0));
+        assert_eq!(
+            reading.hours_left(Status::Discharging, reading.rate),
+            Some(2.0)
+        );
+        // Charging has no end without `energy_full`
+        assert_eq!(reading.hours_left(Status::Charging, reading.rate), None);
+    }
+
+    #[test]
+    fn charge_readings_without_a_voltage_have_no_watts() {
+        let reading = Reading::from_values(|name| match name {
+            "charge_now" => Some(2_000_000.0),
+            "current_now" => Some(1_000_000.0),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(reading.watts(), None);
+        assert!(Reading::from_values(|_| None).is_none());
+    }
+
+    #[test]
+    fn rates_are_averaged_over_the_window() {
+        let mut rates = RateAverage::new();
+        assert_eq!(rates.add(Status::Discharging, 10.0, at(0)), 10.0);
+        assert_eq!(rates.add(Status::Discharging, 20.0, at(60)), 15.0);
+        // Missing rates don't drag the average down
+        assert_eq!(rates.add(Status::Discharging, 0.0, at(90)), 15.0);
+
+        let later = RATE_WINDOW.as_secs() + 30;
+        assert_eq!(rates.add(Status::Discharging, 30.0, at(later)), 25.0);
+    }
+
+    #[test]
+    fn rates_start_over_when_charging_starts() {
+        let mut rates = RateAverage::new();
+        rates.add(Status::Discharging, 10.0, at(0));
+        assert_eq!(rates.add(Status::Charging, 40.0, at(30)), 40.0);
+        assert!(rates.warming_up(at(30)));
+        assert!(!rates.warming_up(at(30 + RATE_WINDOW.as_secs())));
+
+        rates.clear();
+        assert_eq!(rates.add(Status::Charging, 0.0, at(600)), 0.0);
+        assert!(rates.warming_up(at(600)));
+    }
+
+    #[test]
+    fn small_changes_keep_the_shown_estimate() {
+        assert_eq!(debounce(None, 2.0), 2.0);
+        assert_eq!(debounce(Some(2.0), 2.05), 2.0);
+        assert_eq!(debounce(Some(2.0), 1.95), 2.0);
+        assert_eq!(debounce(Some(2.0), 2.5), 2.5);
+        assert_eq!(debounce(Some(2.0), 1.5), 1.5);
+    }
+}
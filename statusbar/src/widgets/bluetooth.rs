@@ -0,0 +1,425 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::BluetoothConfig;
+use crate::services::bluetooth::{self, BluetoothState, Device};
+use crate::services::privacy;
+
+/// Discovery drains the battery, so a scan stops on its own
+const SCAN_SECONDS: u32 = 30;
+
+pub struct Bluetooth {
+    config: BluetoothConfig,
+    state: BluetoothState,
+    /// Device with a connect, disconnect or pair call in flight
+    busy: Option<String>,
+    /// Last failed action, shown until the next one
+    error: Option<String>,
+    popover: Controller<PopoverComponent>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BluetoothMsg {
+    Update(BluetoothState),
+    TogglePopover,
+    PrivacyChanged,
+    SetPowered(bool),
+    SetScanning(bool),
+    Connect(String),
+    Disconnect(String),
+    Pair(String),
+    /// A device action finished, with the error if it failed
+    ActionFinished(Result<(), String>),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Bluetooth {
+    type Init = BluetoothConfig;
+    type Input = BluetoothMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["bluetooth-widget", "widget"],
+            #[watch]
+            set_visible: model.config.enabled && model.state.adapter.is_some(),
+            #[watch]
+            set_class_active: ("bluetooth-off", !model.powered()),
+            #[watch]
+            set_class_active: ("bluetooth-connected", model.state.connected().next().is_some()),
+
+            #[name = "bluetooth_button"]
+            gtk::Button {
+                set_css_classes: &["bluetooth-button"],
+                connect_clicked => BluetoothMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Bluetooth")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Label {
+                    set_css_classes: &["bluetooth-icon"],
+                    #[watch]
+                    set_label: model.icon(),
+                }
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Bluetooth".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(320),
+                scrollable: true,
+                max_height: Some(480),
+                ..Default::default()
+            })
+            .detach();
+
+        let model = Bluetooth {
+            config,
+            state: BluetoothState::default(),
+            busy: None,
+            error: None,
+            popover,
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.bluetooth_button.clone().upcast::<gtk::Widget>());
+
+        if !model.config.enabled {
+            return ComponentParts { model, widgets };
+        }
+
+        // Re-render device names when the privacy screen is toggled
+        let mut privacy_rx = privacy::subscribe();
+        let privacy_sender = sender.clone();
+        relm4::spawn(async move {
+            while privacy_rx.changed().await.is_ok() {
+                if !poll::deliver(&privacy_sender, BluetoothMsg::PrivacyChanged) {
+                    break;
+                }
+            }
+        });
+
+        let mut bluetooth_rx = bluetooth::subscribe();
+        relm4::spawn(async move {
+            loop {
+                let state = bluetooth_rx.borrow_and_update().clone();
+                if !poll::deliver(&sender, BluetoothMsg::Update(state)) {
+                    break;
+                }
+                if bluetooth_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            BluetoothMsg::Update(state) => {
+                self.state = state;
+                self.update_popover_content(&sender);
+            }
+            BluetoothMsg::TogglePopover => {
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+            BluetoothMsg::PrivacyChanged => self.update_popover_content(&sender),
+            BluetoothMsg::SetPowered(powered) => {
+                let Some(adapter) = self.adapter_path() else {
+                    return;
+                };
+                Self::run(&sender, async move {
+                    bluetooth::set_powered(&adapter, powered).await
+                });
+            }
+            BluetoothMsg::SetScanning(scanning) => {
+                let Some(adapter) = self.adapter_path() else {
+                    return;
+                };
+                if scanning {
+                    let stop_sender = sender.clone();
+                    gtk::glib::timeout_add_seconds_local_once(SCAN_SECONDS, move || {
+                        stop_sender.input(BluetoothMsg::SetScanning(false));
+                    });
+                } else if !self
+                    .state
+                    .adapter
+                    .as_ref()
+                    .is_some_and(|adapter| adapter.discovering)
+                {
+                    return;
+                }
+                Self::run(&sender, async move {
+                    bluetooth::set_discovering(&adapter, scanning).await
+                });
+            }
+            BluetoothMsg::Connect(path) => {
+                self.start(&path, &sender);
+                Self::run(&sender, async move { bluetooth::connect(&path).await });
+            }
+            BluetoothMsg::Disconnect(path) => {
+                self.start(&path, &sender);
+                Self::run(&sender, async move { bluetooth::disconnect(&path).await });
+            }
+            BluetoothMsg::Pair(path) => {
+                self.start(&path, &sender);
+                Self::run(&sender, async move { bluetooth::pair(&path).await });
+            }
+            BluetoothMsg::ActionFinished(result) => {
+                self.busy = None;
+                if let Err(e) = result {
+                    eprintln!("Bluetooth: {}", e);
+                    self.error = Some(e);
+                }
+                self.update_popover_content(&sender);
+            }
+        }
+    }
+}
+
+impl Bluetooth {
+    fn powered(&self) -> bool {
+        self.state
+            .adapter
+            .as_ref()
+            .is_some_and(|adapter| adapter.powered)
+    }
+
+    fn adapter_path(&self) -> Option<String> {
+        self.state
+            .adapter
+            .as_ref()
+            .map(|adapter| adapter.path.clone())
+    }
+
+    fn icon(&self) -> &'static str {
+        if !self.powered() {
+            "󰂲"
+        } else if self.state.connected().next().is_some() {
+            "󰂱"
+        } else {
+            "󰂯"
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        if !self.powered() {
+            return "Bluetooth off".to_string();
+        }
+        let connected: Vec<String> = self.state.connected().map(device_label).collect();
+        if connected.is_empty() {
+            "No devices connected".to_string()
+        } else {
+            connected.join("\n")
+        }
+    }
+
+    /// Mark a device busy until its action finishes
+    fn start(&mut self, path: &str, sender: &ComponentSender<Self>) {
+        self.busy = Some(path.to_string());
+        self.error = None;
+        self.update_popover_content(sender);
+    }
+
+    /// Run a BlueZ call and report back; state changes arrive as signals
+    fn run(
+        sender: &ComponentSender<Self>,
+        action: impl Future<Output = Result<(), String>> + Send + 'static,
+    ) {
+        let sender = sender.clone();
+        relm4::spawn(async move {
+            sender.input(BluetoothMsg::ActionFinished(action.await));
+        });
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let mut items = vec![PopoverItem::Custom(self.create_header(sender))];
+
+        if let Some(error) = &self.error {
+            let label = gtk::Label::new(Some(error));
+            label.set_css_classes(&["bluetooth-error"]);
+            label.set_wrap(true);
+            label.set_xalign(0.0);
+            items.push(PopoverItem::Custom(label.upcast()));
+        }
+
+        if self.powered() {
+            items.push(PopoverItem::Separator);
+
+            let (paired, found): (Vec<&Device>, Vec<&Device>) =
+                self.state.devices.iter().partition(|device| device.paired);
+            let list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+            list.set_css_classes(&["bluetooth-list"]);
+            if paired.is_empty() {
+                list.append(&Self::section_label("No paired devices"));
+            }
+            for device in paired {
+                list.append(&self.create_device_item(device, sender));
+            }
+
+            let discovering = self
+                .state
+                .adapter
+                .as_ref()
+                .is_some_and(|adapter| adapter.discovering);
+            if discovering || !found.is_empty() {
+                list.append(&Self::section_label(if discovering {
+                    "Nearby devices (scanning…)"
+                } else {
+                    "Nearby devices"
+                }));
+            }
+            for device in found {
+                list.append(&self.create_device_item(device, sender));
+            }
+
+            items.push(PopoverItem::Custom(list.upcast()));
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+
+    /// Adapter name with the power switch and scan button
+    fn create_header(&self, sender: &ComponentSender<Self>) -> gtk::Widget {
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        header.set_css_classes(&["bluetooth-header"]);
+
+        let name = self
+            .state
+            .adapter
+            .as_ref()
+            .map_or("No adapter", |adapter| adapter.name.as_str());
+        let label = gtk::Label::new(Some(name));
+        label.set_halign(gtk::Align::Start);
+        label.set_hexpand(true);
+        label.set_css_classes(&["bluetooth-adapter"]);
+        header.append(&label);
+
+        if self.powered() {
+            let scan = gtk::Button::from_icon_name("view-refresh-symbolic");
+            scan.set_css_classes(&["bluetooth-scan-button", "flat"]);
+            scan.set_tooltip_text(Some("Scan for devices"));
+            let scan_sender = sender.clone();
+            scan.connect_clicked(move |_| {
+                scan_sender.input(BluetoothMsg::SetScanning(true));
+            });
+            header.append(&scan);
+        }
+
+        let power = gtk::Switch::new();
+        power.set_active(self.powered());
+        power.set_valign(gtk::Align::Center);
+        let power_sender = sender.clone();
+        power.connect_state_set(move |_, powered| {
+            power_sender.input(BluetoothMsg::SetPowered(powered));
+            gtk::glib::Propagation::Proceed
+        });
+        header.append(&power);
+
+        header.upcast()
+    }
+
+    fn section_label(text: &str) -> gtk::Label {
+        let label = gtk::Label::new(Some(text));
+        label.set_css_classes(&["bluetooth-section"]);
+        label.set_halign(gtk::Align::Start);
+        label
+    }
+
+    fn create_device_item(&self, device: &Device, sender: &ComponentSender<Self>) -> gtk::Widget {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        row.set_css_classes(&["bluetooth-device"]);
+        if device.connected {
+            row.add_css_class("bluetooth-device-connected");
+        }
+
+        let icon = gtk::Image::from_icon_name(device.icon.as_deref().unwrap_or("bluetooth"));
+        row.append(&icon);
+
+        let name = gtk::Label::new(Some(privacy::redact(&device.name)));
+        name.set_halign(gtk::Align::Start);
+        name.set_hexpand(true);
+        name.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        name.set_css_classes(&["bluetooth-device-name"]);
+        row.append(&name);
+
+        if let Some(battery) = device.battery {
+            let label = gtk::Label::new(Some(&format!("{}%", battery)));
+            label.set_css_classes(&["bluetooth-battery"]);
+            row.append(&label);
+        }
+
+        let (label, msg) = if !device.paired {
+            ("Pair", BluetoothMsg::Pair(device.path.clone()))
+        } else if device.connected {
+            ("Disconnect", BluetoothMsg::Disconnect(device.path.clone()))
+        } else {
+            ("Connect", BluetoothMsg::Connect(device.path.clone()))
+        };
+        let busy = self.busy.as_deref() == Some(device.path.as_str());
+        let button = gtk::Button::with_label(if busy { "…" } else { label });
+        button.set_css_classes(&["bluetooth-action-button"]);
+        button.set_sensitive(self.busy.is_none());
+        let button_sender = sender.clone();
+        button.connect_clicked(move |_| {
+            button_sender.input(msg.clone());
+        });
+        row.append(&button);
+
+        row.upcast()
+    }
+}
+
+/// Device name with its battery level, if known
+fn device_label(device: &Device) -> String {
+    let name = privacy::redact(&device.name);
+    match device.battery {
+        Some(battery) => format!("{} ({}%)", name, battery),
+        None => name.to_string(),
+    }
+}
+
+impl Snapshot for Bluetooth {
+    fn snapshot(&self) -> serde_json::Value {
+        let devices: Vec<serde_json::Value> = self
+            .state
+            .devices
+            .iter()
+            .map(|device| {
+                json!({
+                    "name": device.name,
+                    "address": device.address,
+                    "paired": device.paired,
+                    "connected": device.connected,
+                    "battery": device.battery,
+                })
+            })
+            .collect();
+        json!({
+            "enabled": self.config.enabled,
+            "adapter": self.state.adapter.as_ref().map(|adapter| &adapter.name),
+            "powered": self.powered(),
+            "devices": devices,
+        })
+    }
+}
@@ -0,0 +1,437 @@
+use gtk::glib;
+use gtk::prelude::*;
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+use relm4::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::BreakReminderConfig;
+use crate::services::{idle, notify};
+
+/// Escalation stages of the break reminder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BreakStage {
+    /// Within the work interval
+    Working,
+    /// Interval elapsed: bar turns colored and a notification is sent
+    Due,
+    /// Reminder ignored for too long: overlay is shown if enabled
+    Overdue,
+    /// Break in progress
+    OnBreak,
+}
+
+impl BreakStage {
+    fn css_class(self) -> &'static str {
+        match self {
+            BreakStage::Working => "break-working",
+            BreakStage::Due => "break-due",
+            BreakStage::Overdue => "break-overdue",
+            BreakStage::OnBreak => "break-active",
+        }
+    }
+}
+
+pub struct BreakReminder {
+    config: BreakReminderConfig,
+    active_secs: u64,
+    idle_secs: u64,
+    break_remaining: u64,
+    breaks_taken: u32,
+    is_idle: bool,
+    stage: BreakStage,
+    popover: Controller<PopoverComponent>,
+    overlay: Option<BreakOverlay>,
+    /// Counts active time every second, removed on shutdown
+    tick_timer: Option<glib::SourceId>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BreakReminderMsg {
+    Tick,
+    IdleChanged(bool),
+    StartBreak,
+    SkipBreak,
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for BreakReminder {
+    type Init = BreakReminderConfig;
+    type Input = BreakReminderMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["break-reminder-widget", "widget"],
+            set_visible: model.config.enabled,
+
+            #[name = "break_button"]
+            gtk::Button {
+                set_css_classes: &["break-reminder-button"],
+                connect_clicked => BreakReminderMsg::TogglePopover,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&format!("Break reminder, {}", model.status_text()))],
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰈈",
+                        #[watch]
+                        set_css_classes: &["break-reminder-icon", model.stage.css_class()],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.status_text(),
+                        set_css_classes: &["break-reminder-label"],
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Create popover without parent (will be set after widgets are created)
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Break Reminder".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(260),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut model = BreakReminder {
+            config,
+            active_secs: 0,
+            idle_secs: 0,
+            break_remaining: 0,
+            breaks_taken: 0,
+            is_idle: false,
+            stage: BreakStage::Working,
+            popover,
+            overlay: None,
+            tick_timer: None,
+        };
+
+        let widgets = view_output!();
+
+        // Set parent widget after widgets are created
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.break_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            // Count active time every second
+            let sender_clone = sender.clone();
+            model.tick_timer = Some(glib::timeout_add_seconds_local(1, move || {
+                sender_clone.input(BreakReminderMsg::Tick);
+                glib::ControlFlow::Continue
+            }));
+
+            // Pause counting while the session is idle
+            let mut idle_rx = idle::subscribe();
+            let sender_clone = sender.clone();
+            relm4::spawn(async move {
+                while idle_rx.changed().await.is_ok() {
+                    let is_idle = *idle_rx.borrow();
+                    if !poll::deliver(&sender_clone, BreakReminderMsg::IdleChanged(is_idle)) {
+                        break;
+                    }
+                }
+            });
+
+            model.update_popover_content(&sender);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            BreakReminderMsg::Tick => {
+                self.tick(&sender);
+            }
+            BreakReminderMsg::IdleChanged(is_idle) => {
+                self.is_idle = is_idle;
+                self.idle_secs = 0;
+            }
+            BreakReminderMsg::StartBreak => {
+                self.start_break(&sender);
+            }
+            BreakReminderMsg::SkipBreak => {
+                self.finish_break(false, &sender);
+            }
+            BreakReminderMsg::TogglePopover => {
+                self.update_popover_content(&sender);
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if let Some(timer) = self.tick_timer.take() {
+            timer.remove();
+        }
+    }
+}
+
+impl BreakReminder {
+    fn tick(&mut self, sender: &ComponentSender<Self>) {
+        if self.stage == BreakStage::OnBreak {
+            self.break_remaining = self.break_remaining.saturating_sub(1);
+            if let Some(overlay) = &self.overlay {
+                overlay.set_remaining(self.break_remaining);
+            }
+            if self.break_remaining == 0 {
+                self.finish_break(true, sender);
+            }
+            return;
+        }
+
+        if self.is_idle {
+            // Being away long enough counts as a break
+            self.idle_secs += 1;
+            if self.idle_secs >= self.config.break_seconds && self.active_secs > 0 {
+                self.active_secs = 0;
+                self.set_stage(BreakStage::Working, sender);
+            }
+            return;
+        }
+
+        self.active_secs += 1;
+
+        let interval = self.config.interval_minutes * 60;
+        let overdue_at = interval + self.config.overlay_delay_minutes * 60;
+        let stage = if self.active_secs >= overdue_at {
+            BreakStage::Overdue
+        } else if self.active_secs >= interval {
+            BreakStage::Due
+        } else {
+            BreakStage::Working
+        };
+
+        if stage != self.stage {
+            self.set_stage(stage, sender);
+        } else if self.active_secs.is_multiple_of(60) {
+            self.update_popover_content(sender);
+        }
+    }
+
+    fn set_stage(&mut self, stage: BreakStage, sender: &ComponentSender<Self>) {
+        self.stage = stage;
+
+        match stage {
+            BreakStage::Due => {
+                if self.config.notify {
+                    let action_sender = sender.clone();
+                    notify::send_with_actions(
+                        "Time for a break",
+                        &format!(
+                            "Look at something 20 feet away for {} seconds.",
+                            self.config.break_seconds
+                        ),
+                        &[("start", "Take a break"), ("skip", "Skip")],
+                        move |action| match action {
+                            "start" | "default" => {
+                                action_sender.input(BreakReminderMsg::StartBreak)
+                            }
+                            "skip" => action_sender.input(BreakReminderMsg::SkipBreak),
+                            _ => {}
+                        },
+                    );
+                }
+            }
+            BreakStage::Overdue => {
+                if self.config.overlay {
+                    sender.input(BreakReminderMsg::StartBreak);
+                } else if self.config.notify {
+                    notify::send(
+                        "Break overdue",
+                        &format!(
+                            "You have been active for {} minutes.",
+                            self.active_secs / 60
+                        ),
+                    );
+                }
+            }
+            BreakStage::Working | BreakStage::OnBreak => {}
+        }
+
+        self.update_popover_content(sender);
+    }
+
+    fn start_break(&mut self, sender: &ComponentSender<Self>) {
+        if self.stage == BreakStage::OnBreak {
+            return;
+        }
+
+        self.break_remaining = self.config.break_seconds;
+        self.stage = BreakStage::OnBreak;
+
+        if self.config.overlay {
+            let overlay = BreakOverlay::new(sender);
+            overlay.set_remaining(self.break_remaining);
+            overlay.window.present();
+            self.overlay = Some(overlay);
+        }
+
+        self.update_popover_content(sender);
+    }
+
+    fn finish_break(&mut self, completed: bool, sender: &ComponentSender<Self>) {
+        if let Some(overlay) = self.overlay.take() {
+            overlay.window.close();
+        }
+
+        if completed {
+            self.breaks_taken += 1;
+        }
+
+        self.active_secs = 0;
+        self.break_remaining = 0;
+        self.set_stage(BreakStage::Working, sender);
+    }
+
+    fn status_text(&self) -> String {
+        match self.stage {
+            BreakStage::Working => {
+                let interval = self.config.interval_minutes * 60;
+                let remaining = interval.saturating_sub(self.active_secs);
+                format!("{}m", remaining.div_ceil(60))
+            }
+            BreakStage::Due | BreakStage::Overdue => "Break".to_string(),
+            BreakStage::OnBreak => format!("{}s", self.break_remaining),
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let mut items = vec![
+            PopoverItem::DetailRow {
+                label: "Active".to_string(),
+                value: format!("{} min", self.active_secs / 60),
+                value_css: "break-detail".to_string(),
+            },
+            PopoverItem::DetailRow {
+                label: "Interval".to_string(),
+                value: format!(
+                    "{} min / {} s",
+                    self.config.interval_minutes, self.config.break_seconds
+                ),
+                value_css: "break-detail".to_string(),
+            },
+            PopoverItem::DetailRow {
+                label: "Breaks Taken".to_string(),
+                value: self.breaks_taken.to_string(),
+                value_css: "break-detail".to_string(),
+            },
+            PopoverItem::Separator,
+        ];
+
+        let (label, msg) = if self.stage == BreakStage::OnBreak {
+            ("Skip Break", BreakReminderMsg::SkipBreak)
+        } else {
+            ("Take a Break Now", BreakReminderMsg::StartBreak)
+        };
+
+        let button = gtk::Button::with_label(label);
+        button.set_css_classes(&["break-action-button"]);
+        let sender_clone = sender.clone();
+        button.connect_clicked(move |_| {
+            sender_clone.input(msg.clone());
+        });
+        items.push(PopoverItem::Custom(button.upcast::<gtk::Widget>()));
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+/// Fullscreen layer-shell window shown during an enforced break
+struct BreakOverlay {
+    window: gtk::Window,
+    countdown: gtk::Label,
+}
+
+impl BreakOverlay {
+    fn new(sender: &ComponentSender<BreakReminder>) -> Self {
+        let window = gtk::Window::new();
+        window.init_layer_shell();
+        window.set_layer(Layer::Overlay);
+        window.set_namespace(Some("statusbar-break"));
+        window.set_keyboard_mode(KeyboardMode::OnDemand);
+        window.set_exclusive_zone(-1);
+        for edge in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+            window.set_anchor(edge, true);
+        }
+        window.set_css_classes(&["break-overlay"]);
+
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(16)
+            .halign(gtk::Align::Center)
+            .valign(gtk::Align::Center)
+            .build();
+
+        let title = gtk::Label::builder()
+            .label("Take a break")
+            .css_classes(vec!["break-overlay-title"])
+            .build();
+
+        let hint = gtk::Label::builder()
+            .label("Look at something 20 feet away and let your eyes rest.")
+            .css_classes(vec!["break-overlay-hint"])
+            .build();
+
+        let countdown = gtk::Label::builder()
+            .css_classes(vec!["break-overlay-countdown"])
+            .build();
+
+        let skip_button = gtk::Button::with_label("Skip");
+        skip_button.set_css_classes(&["break-overlay-skip"]);
+        skip_button.set_halign(gtk::Align::Center);
+        let sender_clone = sender.clone();
+        skip_button.connect_clicked(move |_| {
+            sender_clone.input(BreakReminderMsg::SkipBreak);
+        });
+
+        content.append(&title);
+        content.append(&hint);
+        content.append(&countdown);
+        content.append(&skip_button);
+        window.set_child(Some(&content));
+
+        Self { window, countdown }
+    }
+
+    fn set_remaining(&self, seconds: u64) {
+        self.countdown.set_label(&format!("{}s", seconds));
+    }
+}
+
+impl Snapshot for BreakReminder {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "stage": self.stage,
+            "active_secs": self.active_secs,
+            "break_remaining": self.break_remaining,
+            "breaks_taken": self.breaks_taken,
+            "is_idle": self.is_idle,
+        })
+    }
+}
@@ -0,0 +1,284 @@
+use gtk::prelude::*;
+use gtk::{gdk, glib};
+use relm4::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::BrightnessConfig;
+use crate::services::backlight::{self, Backlight};
+
+/// Slider of one display in the popover
+///
+/// Kept across updates so a slider being dragged isn't replaced.
+struct DisplayRow {
+    row: gtk::Box,
+    scale: gtk::Scale,
+    handler: glib::SignalHandlerId,
+}
+
+impl DisplayRow {
+    fn new(name: &str, sender: &ComponentSender<Brightness>) -> Self {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(8)
+            .css_classes(vec!["brightness-row"])
+            .build();
+
+        let label = gtk::Label::builder()
+            .label(name)
+            .css_classes(vec!["brightness-display-name"])
+            .halign(gtk::Align::Start)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .width_chars(14)
+            .max_width_chars(14)
+            .xalign(0.0)
+            .build();
+
+        let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 1.0, 100.0, 1.0);
+        scale.set_hexpand(true);
+        scale.set_width_request(160);
+        scale.add_css_class("brightness-scale");
+        let name = name.to_string();
+        let sender = sender.clone();
+        let handler = scale.connect_value_changed(move |scale| {
+            let percent = scale.value().round() as u32;
+            sender.input(BrightnessMsg::Set(name.clone(), percent));
+        });
+
+        row.append(&label);
+        row.append(&scale);
+
+        Self {
+            row,
+            scale,
+            handler,
+        }
+    }
+
+    fn update(&self, percent: u32) {
+        self.scale.block_signal(&self.handler);
+        self.scale.set_value(percent as f64);
+        self.scale.unblock_signal(&self.handler);
+    }
+}
+
+fn brightness_icon(percent: u32) -> &'static str {
+    match percent {
+        0..=33 => "󰃞",
+        34..=66 => "󰃟",
+        _ => "󰃠",
+    }
+}
+
+pub struct Brightness {
+    config: BrightnessConfig,
+    /// Backlights sorted by name; the first one is shown on the bar
+    displays: Vec<Backlight>,
+    popover: Controller<PopoverComponent>,
+    /// Box holding one row per display
+    rows_box: gtk::Box,
+    rows: HashMap<String, DisplayRow>,
+}
+
+#[derive(Debug)]
+pub enum BrightnessMsg {
+    Update(Vec<Backlight>),
+    TogglePopover,
+    /// Scroll on the bar, `true` for brighter
+    Scroll(bool),
+    Set(String, u32),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Brightness {
+    type Init = BrightnessConfig;
+    type Input = BrightnessMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["brightness-widget", "widget"],
+            #[watch]
+            set_visible: model.config.enabled && !model.displays.is_empty(),
+
+            #[name = "brightness_button"]
+            gtk::Button {
+                set_css_classes: &["brightness-button"],
+                connect_clicked => BrightnessMsg::TogglePopover,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&format!("Brightness {}%", model.percent()))],
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_css_classes: &["brightness-icon"],
+                        #[watch]
+                        set_label: brightness_icon(model.percent()),
+                    },
+
+                    gtk::Label {
+                        set_css_classes: &["brightness-label"],
+                        #[watch]
+                        set_label: &format!("{}%", model.percent()),
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Brightness".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(280),
+                ..Default::default()
+            })
+            .detach();
+
+        let rows_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(4)
+            .build();
+        popover.emit(PopoverMsg::UpdateItems(vec![PopoverItem::Custom(
+            rows_box.clone().upcast(),
+        )]));
+
+        let model = Brightness {
+            config,
+            displays: Vec::new(),
+            popover,
+            rows_box,
+            rows: HashMap::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.brightness_button.clone().upcast::<gtk::Widget>());
+
+        if !model.config.enabled {
+            return ComponentParts { model, widgets };
+        }
+
+        // Plain scrolling changes the brightness of the first display
+        let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        let scroll_sender = sender.clone();
+        scroll.connect_scroll(move |controller, _, dy| {
+            if controller
+                .current_event_state()
+                .contains(gdk::ModifierType::SHIFT_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+            if dy != 0.0 {
+                scroll_sender.input(BrightnessMsg::Scroll(dy < 0.0));
+            }
+            glib::Propagation::Stop
+        });
+        root.add_controller(scroll);
+
+        let mut backlight_rx = backlight::subscribe();
+        relm4::spawn(async move {
+            loop {
+                let displays = backlight_rx.borrow_and_update().clone();
+                if !poll::deliver(&sender, BrightnessMsg::Update(displays)) {
+                    break;
+                }
+                if backlight_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            BrightnessMsg::Update(displays) => {
+                self.displays = displays;
+                self.sync_rows(&sender);
+            }
+            BrightnessMsg::TogglePopover => {
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+            BrightnessMsg::Scroll(up) => {
+                let Some(display) = self.displays.first() else {
+                    return;
+                };
+                let percent = if up {
+                    (display.percent() + self.config.step).min(100)
+                } else {
+                    display.percent().saturating_sub(self.config.step)
+                };
+                Self::set(display.name.clone(), percent);
+            }
+            BrightnessMsg::Set(name, percent) => Self::set(name, percent),
+        }
+    }
+}
+
+impl Brightness {
+    fn percent(&self) -> u32 {
+        self.displays.first().map_or(0, Backlight::percent)
+    }
+
+    fn set(name: String, percent: u32) {
+        relm4::spawn(async move {
+            if let Err(e) = backlight::set_brightness(&name, percent).await {
+                eprintln!("{}", e);
+            }
+        });
+    }
+
+    /// Add, remove and update the per-display sliders
+    fn sync_rows(&mut self, sender: &ComponentSender<Self>) {
+        let displays = &self.displays;
+        let rows_box = &self.rows_box;
+        self.rows.retain(|name, row| {
+            let present = displays.iter().any(|display| &display.name == name);
+            if !present {
+                rows_box.remove(&row.row);
+            }
+            present
+        });
+
+        for display in displays {
+            let row = self.rows.entry(display.name.clone()).or_insert_with(|| {
+                let row = DisplayRow::new(&display.name, sender);
+                rows_box.append(&row.row);
+                row
+            });
+            row.update(display.percent());
+        }
+    }
+}
+
+impl Snapshot for Brightness {
+    fn snapshot(&self) -> serde_json::Value {
+        let displays: Vec<serde_json::Value> = self
+            .displays
+            .iter()
+            .map(|display| json!({ "name": display.name, "percent": display.percent() }))
+            .collect();
+        json!({
+            "enabled": self.config.enabled,
+            "displays": displays,
+        })
+    }
+}
@@ -1,40 +1,153 @@
-use chrono::Local;
+use chrono::format::{Fixed, Item, Numeric, StrftimeItems};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
 use gtk::glib;
 use gtk::prelude::*;
 use relm4::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+
+use super::poll::{self, Poll};
+use super::snapshot::Snapshot;
+use super::tooltip;
+use crate::config::{ClockConfig, PomodoroConfig};
+use crate::services::notifications::{self, Notification};
+use crate::services::notify;
+use crate::services::timedate;
+
+/// Phases of the pomodoro cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PomodoroPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl PomodoroPhase {
+    fn label(self) -> &'static str {
+        match self {
+            PomodoroPhase::Work => "Work",
+            PomodoroPhase::ShortBreak => "Short break",
+            PomodoroPhase::LongBreak => "Long break",
+        }
+    }
+
+    fn css_class(self) -> &'static str {
+        match self {
+            PomodoroPhase::Work => "pomodoro-work",
+            PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak => "pomodoro-break",
+        }
+    }
+}
+
+/// State of a running pomodoro
+struct Pomodoro {
+    phase: PomodoroPhase,
+    remaining_secs: u64,
+    /// Work phases finished since the timer was started
+    completed_work: u32,
+}
+
+/// Pomodoro section of the calendar popover
+struct PomodoroControls {
+    status_label: gtk::Label,
+    toggle_button: gtk::Button,
+    skip_button: gtk::Button,
+}
+
+/// Today's sunrise/sunset rows for a fixed location
+struct SunTimes {
+    latitude: f64,
+    longitude: f64,
+    date: Option<NaiveDate>,
+    sunrise_label: gtk::Label,
+    sunset_label: gtk::Label,
+    day_length_label: gtk::Label,
+}
 
 pub struct Clock {
+    format: String,
     current_time: String,
+    tooltip_template: String,
+    tooltip: Option<String>,
+    world_clocks: Vec<(Tz, gtk::Label)>,
     popover: gtk::Popover,
+    calendar: gtk::Calendar,
+    sun_times: Option<SunTimes>,
+    pomodoro_config: PomodoroConfig,
+    pomodoro: Option<Pomodoro>,
+    pomodoro_timer: Option<glib::SourceId>,
+    /// Pending wake-up at the next minute, unless the label shows seconds
+    minute_timer: Option<glib::SourceId>,
+    pomodoro_controls: Option<PomodoroControls>,
+    /// Notification column beside the calendar, if enabled
+    notification_list: Option<gtk::Box>,
+    timers: Vec<Poll>,
 }
 
 #[derive(Debug)]
 pub enum ClockMsg {
     UpdateTime,
+    MinuteTick,
+    /// The session woke up from suspend or came back from idle
+    Resumed,
     ToggleCalendar,
+    TogglePomodoro,
+    SkipPomodoroPhase,
+    PomodoroTick,
+    NotificationsChanged(Vec<Notification>),
+    ClearNotifications,
+    /// The system timezone or locale changed
+    TimeSettingsChanged,
+}
+
+#[derive(Debug)]
+pub enum ClockOutput {
+    /// A pomodoro work phase started and should be guarded as focus time
+    FocusStarted { minutes: u64 },
+    /// The work phase ended or the pomodoro was stopped
+    FocusEnded,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for Clock {
-    type Init = ();
+    type Init = ClockConfig;
     type Input = ClockMsg;
-    type Output = ();
+    type Output = ClockOutput;
 
     view! {
         #[name = "clock_box"]
         gtk::Box {
             set_orientation: gtk::Orientation::Horizontal,
             set_spacing: 0,
-            set_css_classes: &["clock-widget", "widget"],
+            #[watch]
+            set_css_classes: &model.css_classes(),
 
             #[name = "time_button"]
             gtk::Button {
                 set_css_classes: &["clock-time-button"],
+                #[watch]
+                set_tooltip_markup: model.tooltip.as_deref(),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
 
-                gtk::Label {
-                    #[watch]
-                    set_label: &model.current_time,
-                    set_css_classes: &["clock-label"],
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.current_time,
+                        set_css_classes: &["clock-label"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_visible: model.pomodoro.is_some(),
+                        #[watch]
+                        set_label: &model.pomodoro_remaining(),
+                        set_css_classes: &["pomodoro-label"],
+                    },
                 },
 
                 connect_clicked => ClockMsg::ToggleCalendar,
@@ -43,37 +156,180 @@ impl SimpleComponent for Clock {
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        let popover_box = gtk::Box::new(gtk::Orientation::Vertical, 8);
+
+        // World clocks above the calendar
+        let mut world_clocks = Vec::new();
+        if !config.world_clocks.is_empty() {
+            let world_clock_box = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            world_clock_box.set_css_classes(&["world-clocks"]);
+
+            for world_clock in &config.world_clocks {
+                let tz: Tz = match world_clock.timezone.parse() {
+                    Ok(tz) => tz,
+                    Err(e) => {
+                        eprintln!("Invalid timezone '{}': {}", world_clock.timezone, e);
+                        continue;
+                    }
+                };
+
+                let (row, time_label) = Self::create_world_clock_row(&world_clock.name);
+                world_clock_box.append(&row);
+                world_clocks.push((tz, time_label));
+            }
+
+            popover_box.append(&world_clock_box);
+            popover_box.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+        }
+
         // Create calendar popover
         let calendar = gtk::Calendar::new();
-        let popover = gtk::Popover::builder().child(&calendar).build();
+        calendar.set_show_week_numbers(config.show_week_numbers);
+        popover_box.append(&calendar);
+
+        // Sunrise/sunset for the configured location
+        let sun_times = match (config.latitude, config.longitude) {
+            (Some(latitude), Some(longitude)) => {
+                let sun_box = gtk::Box::new(gtk::Orientation::Vertical, 2);
+                sun_box.set_css_classes(&["sun-times"]);
+
+                let (sunrise_row, sunrise_label) = Self::create_world_clock_row("Sunrise");
+                let (sunset_row, sunset_label) = Self::create_world_clock_row("Sunset");
+                let (day_length_row, day_length_label) = Self::create_world_clock_row("Daylight");
+                sun_box.append(&sunrise_row);
+                sun_box.append(&sunset_row);
+                sun_box.append(&day_length_row);
+
+                popover_box.append(&sun_box);
+                Some(SunTimes {
+                    latitude,
+                    longitude,
+                    date: None,
+                    sunrise_label,
+                    sunset_label,
+                    day_length_label,
+                })
+            }
+            _ => None,
+        };
 
-        let model = Clock {
-            current_time: Self::format_time(),
+        // Pomodoro controls below the calendar
+        let pomodoro_controls = if config.pomodoro.enabled {
+            let (pomodoro_box, controls) = Self::create_pomodoro_controls(&sender);
+            popover_box.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+            popover_box.append(&pomodoro_box);
+            Some(controls)
+        } else {
+            None
+        };
+
+        // Notification history to the right of the calendar
+        let popover_child = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        popover_child.append(&popover_box);
+        let notification_list = if config.show_notifications {
+            let (notification_box, list) = Self::create_notification_column(&sender);
+            popover_child.append(&gtk::Separator::new(gtk::Orientation::Vertical));
+            popover_child.append(&notification_box);
+            Some(list)
+        } else {
+            None
+        };
+
+        let popover = gtk::Popover::builder().child(&popover_child).build();
+
+        let format = Self::validate_format(config.format);
+        let mut model = Clock {
+            current_time: Self::format_time(&format),
+            format,
+            tooltip_template: config.tooltip,
+            tooltip: None,
+            world_clocks,
             popover: popover.clone(),
+            calendar,
+            sun_times,
+            pomodoro_config: config.pomodoro,
+            pomodoro: None,
+            pomodoro_timer: None,
+            minute_timer: None,
+            pomodoro_controls,
+            notification_list,
+            timers: Vec::new(),
         };
 
+        model.update_tooltip();
+        model.update_world_clocks();
+        model.update_sun_times();
+        model.update_pomodoro_controls();
+
         let widgets = view_output!();
 
         // Set popover parent to the time button
         popover.set_parent(&widgets.time_button);
 
-        // Set up periodic updates (every second)
-        glib::timeout_add_seconds_local(1, move || {
-            sender.input(ClockMsg::UpdateTime);
-            glib::ControlFlow::Continue
+        if model.notification_list.is_some() {
+            let mut notifications_rx = notifications::subscribe();
+            let sender_clone = sender.clone();
+            relm4::spawn(async move {
+                loop {
+                    let history = notifications_rx.borrow_and_update().clone();
+                    if !poll::deliver(&sender_clone, ClockMsg::NotificationsChanged(history)) {
+                        break;
+                    }
+                    if notifications_rx.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        // Reformat right away after `timedatectl set-timezone`
+        let mut time_settings_rx = timedate::subscribe();
+        let sender_clone = sender.clone();
+        relm4::spawn(async move {
+            while time_settings_rx.changed().await.is_ok() {
+                if !poll::deliver(&sender_clone, ClockMsg::TimeSettingsChanged) {
+                    break;
+                }
+            }
         });
 
+        // Tick every second only when the label actually shows seconds
+        if Self::shows_seconds(&model.format) {
+            model
+                .timers
+                .push(poll::every(1, move || sender.input(ClockMsg::UpdateTime)));
+        } else {
+            // The minute timer runs on monotonic time, which stops in suspend,
+            // so it is realigned on wake-up
+            let resume_sender = sender.clone();
+            model.timers.push(poll::on_resume(move || {
+                resume_sender.input(ClockMsg::Resumed)
+            }));
+            model.schedule_minute_tick(&sender);
+        }
+
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            ClockMsg::UpdateTime => {
-                self.current_time = Self::format_time();
+            ClockMsg::UpdateTime => self.update_time(),
+            ClockMsg::MinuteTick => {
+                // A fired once-timer is gone already
+                self.minute_timer = None;
+                self.update_time();
+                self.schedule_minute_tick(&sender);
+            }
+            ClockMsg::Resumed => {
+                self.update_time();
+                if let Some(timer) = self.minute_timer.take() {
+                    timer.remove();
+                    self.schedule_minute_tick(&sender);
+                }
             }
             ClockMsg::ToggleCalendar => {
                 if self.popover.is_visible() {
@@ -82,12 +338,550 @@ impl SimpleComponent for Clock {
                     self.popover.popup();
                 }
             }
+            ClockMsg::TogglePomodoro => {
+                if !self.pomodoro_config.enabled {
+                    return;
+                }
+                if self.pomodoro.is_some() {
+                    self.stop_pomodoro(&sender);
+                } else {
+                    self.start_pomodoro(&sender);
+                }
+                self.update_pomodoro_controls();
+            }
+            ClockMsg::SkipPomodoroPhase => {
+                self.advance_pomodoro(&sender);
+                self.update_pomodoro_controls();
+            }
+            ClockMsg::PomodoroTick => {
+                let finished = match &mut self.pomodoro {
+                    Some(pomodoro) => {
+                        pomodoro.remaining_secs = pomodoro.remaining_secs.saturating_sub(1);
+                        pomodoro.remaining_secs == 0
+                    }
+                    None => false,
+                };
+
+                if finished {
+                    self.advance_pomodoro(&sender);
+                    if let Some(pomodoro) = &self.pomodoro {
+                        let body = match pomodoro.phase {
+                            PomodoroPhase::Work => "Back to work",
+                            PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak => {
+                                "Time for a break"
+                            }
+                        };
+                        notify::send("Pomodoro", body);
+                    }
+                }
+                self.update_pomodoro_controls();
+            }
+            ClockMsg::NotificationsChanged(history) => {
+                self.update_notification_list(&history);
+            }
+            ClockMsg::ClearNotifications => {
+                notifications::clear();
+            }
+            ClockMsg::TimeSettingsChanged => {
+                self.current_time = Self::format_time(&self.format);
+                self.update_tooltip();
+                self.update_world_clocks();
+                if let Some(sun) = &mut self.sun_times {
+                    sun.date = None;
+                }
+                self.update_sun_times();
+                self.select_today();
+                let history = notifications::subscribe().borrow().clone();
+                self.update_notification_list(&history);
+            }
+        }
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if let Some(timer) = self.pomodoro_timer.take() {
+            timer.remove();
+        }
+        if let Some(timer) = self.minute_timer.take() {
+            timer.remove();
         }
     }
 }
 
 impl Clock {
-    fn format_time() -> String {
-        Local::now().format("%H:%M:%S").to_string()
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["clock-widget", "widget"];
+        if let Some(pomodoro) = &self.pomodoro {
+            classes.push(pomodoro.phase.css_class());
+        }
+        classes
     }
+
+    fn pomodoro_remaining(&self) -> String {
+        self.pomodoro
+            .as_ref()
+            .map(|pomodoro| {
+                format!(
+                    "{:02}:{:02}",
+                    pomodoro.remaining_secs / 60,
+                    pomodoro.remaining_secs % 60
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    fn phase_minutes(&self, phase: PomodoroPhase) -> u64 {
+        match phase {
+            PomodoroPhase::Work => self.pomodoro_config.work_minutes,
+            PomodoroPhase::ShortBreak => self.pomodoro_config.short_break_minutes,
+            PomodoroPhase::LongBreak => self.pomodoro_config.long_break_minutes,
+        }
+    }
+
+    fn start_pomodoro(&mut self, sender: &ComponentSender<Self>) {
+        self.pomodoro = Some(Pomodoro {
+            phase: PomodoroPhase::Work,
+            remaining_secs: self.phase_minutes(PomodoroPhase::Work) * 60,
+            completed_work: 0,
+        });
+        self.notify_phase_started(sender, PomodoroPhase::Work);
+
+        let sender_clone = sender.clone();
+        self.pomodoro_timer = Some(glib::timeout_add_seconds_local(1, move || {
+            sender_clone.input(ClockMsg::PomodoroTick);
+            glib::ControlFlow::Continue
+        }));
+    }
+
+    fn stop_pomodoro(&mut self, sender: &ComponentSender<Self>) {
+        if let Some(timer) = self.pomodoro_timer.take() {
+            timer.remove();
+        }
+        let working = self
+            .pomodoro
+            .take()
+            .is_some_and(|pomodoro| pomodoro.phase == PomodoroPhase::Work);
+        if working {
+            self.notify_phase_ended(sender);
+        }
+    }
+
+    /// Move on to the next phase: work -> short/long break -> work
+    fn advance_pomodoro(&mut self, sender: &ComponentSender<Self>) {
+        let Some(pomodoro) = &self.pomodoro else {
+            return;
+        };
+
+        let was_work = pomodoro.phase == PomodoroPhase::Work;
+        let completed_work = pomodoro.completed_work + u32::from(was_work);
+        let next = if !was_work {
+            PomodoroPhase::Work
+        } else if completed_work % self.pomodoro_config.sessions_before_long_break.max(1) == 0 {
+            PomodoroPhase::LongBreak
+        } else {
+            PomodoroPhase::ShortBreak
+        };
+
+        self.pomodoro = Some(Pomodoro {
+            phase: next,
+            remaining_secs: self.phase_minutes(next) * 60,
+            completed_work,
+        });
+
+        if was_work {
+            self.notify_phase_ended(sender);
+        } else {
+            self.notify_phase_started(sender, next);
+        }
+    }
+
+    fn notify_phase_started(&self, sender: &ComponentSender<Self>, phase: PomodoroPhase) {
+        if phase == PomodoroPhase::Work && self.pomodoro_config.focus_during_work {
+            let _ = sender.output(ClockOutput::FocusStarted {
+                minutes: self.pomodoro_config.work_minutes,
+            });
+        }
+    }
+
+    fn notify_phase_ended(&self, sender: &ComponentSender<Self>) {
+        if self.pomodoro_config.focus_during_work {
+            let _ = sender.output(ClockOutput::FocusEnded);
+        }
+    }
+
+    fn update_pomodoro_controls(&self) {
+        let Some(controls) = &self.pomodoro_controls else {
+            return;
+        };
+
+        match &self.pomodoro {
+            Some(pomodoro) => {
+                controls.status_label.set_label(&format!(
+                    "{} · {} left",
+                    pomodoro.phase.label(),
+                    self.pomodoro_remaining()
+                ));
+                controls.toggle_button.set_label("Stop");
+                controls.skip_button.set_sensitive(true);
+            }
+            None => {
+                controls.status_label.set_label("Pomodoro");
+                controls.toggle_button.set_label("Start");
+                controls.skip_button.set_sensitive(false);
+            }
+        }
+    }
+
+    fn create_pomodoro_controls(sender: &ComponentSender<Self>) -> (gtk::Box, PomodoroControls) {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(8)
+            .css_classes(vec!["pomodoro-controls"])
+            .build();
+
+        let status_label = gtk::Label::builder()
+            .css_classes(vec!["detail-label"])
+            .halign(gtk::Align::Start)
+            .hexpand(true)
+            .build();
+
+        let toggle_button = gtk::Button::builder()
+            .css_classes(vec!["pomodoro-button"])
+            .build();
+        let sender_clone = sender.clone();
+        toggle_button.connect_clicked(move |_| sender_clone.input(ClockMsg::TogglePomodoro));
+
+        let skip_button = gtk::Button::builder()
+            .label("Skip")
+            .css_classes(vec!["pomodoro-button"])
+            .build();
+        let sender_clone = sender.clone();
+        skip_button.connect_clicked(move |_| sender_clone.input(ClockMsg::SkipPomodoroPhase));
+
+        row.append(&status_label);
+        row.append(&toggle_button);
+        row.append(&skip_button);
+
+        (
+            row,
+            PomodoroControls {
+                status_label,
+                toggle_button,
+                skip_button,
+            },
+        )
+    }
+
+    /// Create the notification column, returning it and its list box
+    fn create_notification_column(sender: &ComponentSender<Self>) -> (gtk::Box, gtk::Box) {
+        let column = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(4)
+            .css_classes(vec!["clock-notifications"])
+            .build();
+
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        let title = gtk::Label::builder()
+            .label("Notifications")
+            .css_classes(vec!["detail-label", "clock-notifications-title"])
+            .halign(gtk::Align::Start)
+            .hexpand(true)
+            .build();
+        let clear_button = gtk::Button::builder()
+            .label("Clear")
+            .css_classes(vec!["clock-notifications-clear"])
+            .build();
+        let sender_clone = sender.clone();
+        clear_button.connect_clicked(move |_| sender_clone.input(ClockMsg::ClearNotifications));
+        header.append(&title);
+        header.append(&clear_button);
+
+        let list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        let scrolled = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(360)
+            .vexpand(true)
+            .child(&list)
+            .build();
+
+        column.append(&header);
+        column.append(&scrolled);
+        (column, list)
+    }
+
+    fn update_notification_list(&self, history: &[Notification]) {
+        let Some(list) = &self.notification_list else {
+            return;
+        };
+
+        while let Some(child) = list.first_child() {
+            list.remove(&child);
+        }
+
+        if history.is_empty() {
+            let empty = gtk::Label::builder()
+                .label("No notifications")
+                .css_classes(vec!["clock-notifications-empty"])
+                .vexpand(true)
+                .build();
+            list.append(&empty);
+            return;
+        }
+
+        for notification in history {
+            list.append(&Self::create_notification_row(notification));
+        }
+    }
+
+    fn create_notification_row(notification: &Notification) -> gtk::Box {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(2)
+            .css_classes(vec!["clock-notification"])
+            .build();
+
+        let source = gtk::Label::builder()
+            .label(format!(
+                "{} · {}",
+                notification.app_name,
+                notification.time.with_timezone(&Local).format("%H:%M")
+            ))
+            .css_classes(vec!["clock-notification-source"])
+            .halign(gtk::Align::Start)
+            .build();
+        let summary = gtk::Label::builder()
+            .label(&notification.summary)
+            .css_classes(vec!["clock-notification-summary"])
+            .halign(gtk::Align::Start)
+            .xalign(0.0)
+            .wrap(true)
+            .build();
+
+        row.append(&source);
+        row.append(&summary);
+        if !notification.body.is_empty() {
+            let body = gtk::Label::builder()
+                .label(&notification.body)
+                .css_classes(vec!["clock-notification-body"])
+                .halign(gtk::Align::Start)
+                .xalign(0.0)
+                .wrap(true)
+                .build();
+            row.append(&body);
+        }
+        row
+    }
+
+    fn format_time(format: &str) -> String {
+        Local::now().format(format).to_string()
+    }
+
+    /// Fill the tooltip template in with the current date
+    fn update_tooltip(&mut self) {
+        let now = Local::now();
+        let mut timezone = timedate::subscribe().borrow().timezone.clone();
+        if timezone.is_empty() {
+            timezone = now.format("UTC%:z").to_string();
+        }
+        self.tooltip = tooltip::render(
+            &self.tooltip_template,
+            &[
+                ("date", &now.format("%A, %-d %B %Y").to_string()),
+                ("time", &now.format("%X").to_string()),
+                ("week", &now.format("%V").to_string()),
+                ("timezone", &timezone),
+            ],
+        );
+    }
+
+    /// Fall back to the default format if `format` has invalid specifiers
+    fn validate_format(format: String) -> String {
+        if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+            eprintln!("Invalid clock format '{}', using the default", format);
+            return ClockConfig::default().format;
+        }
+        format
+    }
+
+    /// Whether the format contains a component that changes every second
+    fn shows_seconds(format: &str) -> bool {
+        StrftimeItems::new(format).any(|item| {
+            matches!(
+                item,
+                Item::Numeric(
+                    Numeric::Second | Numeric::Nanosecond | Numeric::Timestamp,
+                    _
+                ) | Item::Fixed(
+                    Fixed::Nanosecond
+                        | Fixed::Nanosecond3
+                        | Fixed::Nanosecond6
+                        | Fixed::Nanosecond9
+                        | Fixed::RFC2822
+                        | Fixed::RFC3339
+                )
+            )
+        })
+    }
+
+    fn update_time(&mut self) {
+        self.current_time = Self::format_time(&self.format);
+        self.update_tooltip();
+        self.update_world_clocks();
+        self.update_sun_times();
+    }
+
+    /// Wake up at the next minute boundary, which reschedules in turn
+    fn schedule_minute_tick(&mut self, sender: &ComponentSender<Self>) {
+        let now = Local::now();
+        let elapsed_ms = now.second() as u64 * 1000 + (now.nanosecond() / 1_000_000) as u64;
+        // `nanosecond` exceeds 1s during a leap second
+        let delay_ms = 60_000u64.saturating_sub(elapsed_ms).max(1);
+
+        let sender = sender.clone();
+        self.minute_timer = Some(glib::timeout_add_local_once(
+            Duration::from_millis(delay_ms),
+            move || sender.input(ClockMsg::MinuteTick),
+        ));
+    }
+
+    /// Move the calendar to today's date in the current timezone
+    fn select_today(&self) {
+        let today = Local::now().date_naive();
+        match glib::DateTime::from_utc(
+            today.year(),
+            today.month() as i32,
+            today.day() as i32,
+            0,
+            0,
+            0.0,
+        ) {
+            Ok(date) => self.calendar.select_day(&date),
+            Err(e) => eprintln!("Failed to select today in the calendar: {}", e),
+        }
+    }
+
+    fn update_world_clocks(&self) {
+        let now = Utc::now();
+        for (tz, label) in &self.world_clocks {
+            label.set_label(&now.with_timezone(tz).format("%H:%M %a").to_string());
+        }
+    }
+
+    /// Recompute sunrise/sunset once per day
+    fn update_sun_times(&mut self) {
+        let Some(sun) = &mut self.sun_times else {
+            return;
+        };
+
+        let today = Local::now().date_naive();
+        if sun.date == Some(today) {
+            return;
+        }
+        sun.date = Some(today);
+
+        match sunrise_sunset(today, sun.latitude, sun.longitude) {
+            Some((sunrise, sunset)) => {
+                let day_length = sunset - sunrise;
+                sun.sunrise_label
+                    .set_label(&sunrise.with_timezone(&Local).format("%H:%M").to_string());
+                sun.sunset_label
+                    .set_label(&sunset.with_timezone(&Local).format("%H:%M").to_string());
+                sun.day_length_label.set_label(&format!(
+                    "{}h {:02}m",
+                    day_length.num_hours(),
+                    day_length.num_minutes() % 60
+                ));
+            }
+            // The sun doesn't cross the horizon today
+            None => {
+                sun.sunrise_label.set_label("—");
+                sun.sunset_label.set_label("—");
+                sun.day_length_label.set_label("Polar day/night");
+            }
+        }
+    }
+
+    /// Create a name/time row, returning the row and its time label
+    fn create_world_clock_row(name: &str) -> (gtk::Box, gtk::Label) {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(8)
+            .css_classes(vec!["detail-row"])
+            .build();
+
+        let name_label = gtk::Label::builder()
+            .label(name)
+            .css_classes(vec!["detail-label"])
+            .halign(gtk::Align::Start)
+            .hexpand(true)
+            .build();
+
+        let time_label = gtk::Label::builder()
+            .css_classes(vec!["detail-value", "world-clock-time"])
+            .halign(gtk::Align::End)
+            .build();
+
+        row.append(&name_label);
+        row.append(&time_label);
+        (row, time_label)
+    }
+}
+
+impl Snapshot for Clock {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "time": self.current_time,
+            "pomodoro": self.pomodoro.as_ref().map(|pomodoro| json!({
+                "phase": pomodoro.phase,
+                "remaining_secs": pomodoro.remaining_secs,
+                "completed_work": pomodoro.completed_work,
+            })),
+        })
+    }
+}
+
+/// Sunrise and sunset on `date` using the sunrise equation
+///
+/// Returns `None` during polar day or night.
+fn sunrise_sunset(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    const J2000: f64 = 2_451_545.0;
+    const UNIX_EPOCH_JULIAN: f64 = 2_440_587.5;
+
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let day = (date - j2000).num_days() as f64;
+
+    // Mean solar noon, then the sun's position at that time
+    let mean_noon = day - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_noon)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic_longitude = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit =
+        J2000 + mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    let to_utc = |julian: f64| {
+        let seconds = ((julian - UNIX_EPOCH_JULIAN) * 86_400.0).round() as i64;
+        Utc.timestamp_opt(seconds, 0).single()
+    };
+    Some((
+        to_utc(transit - hour_angle / 360.0)?,
+        to_utc(transit + hour_angle / 360.0)?,
+    ))
 }
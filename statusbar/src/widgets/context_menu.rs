@@ -0,0 +1,292 @@
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+use relm4::gtk;
+
+use crate::config::WidgetActions;
+
+use super::{
+    Battery, Bluetooth, BreakReminder, Brightness, Clock, Countdown, Custom, Diagnostics,
+    DiskAlert, Email, FailedUnits, FocusTimer, GameMode, InputMethod, KeyboardLayout, LuaWidgets,
+    MediaPlayer, Microphone, NightLight, NotificationCenter, PrivacyIndicator, Resources,
+    ScreenTime, SystemTray, Taskbar, Ticker, TimeTracking, Updates, Volume, Vpn, Weather, WiFi,
+    WindowTitle, WorkspaceWidget,
+};
+
+/// Entries of the standard right-click menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextAction {
+    Hide,
+    Settings,
+    Reload,
+}
+
+impl ContextAction {
+    const ALL: [ContextAction; 3] = [Self::Hide, Self::Settings, Self::Reload];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Hide => "Hide widget",
+            Self::Settings => "Widget settings…",
+            Self::Reload => "Reload",
+        }
+    }
+
+    fn action_name(self) -> &'static str {
+        match self {
+            Self::Hide => "hide",
+            Self::Settings => "settings",
+            Self::Reload => "reload",
+        }
+    }
+}
+
+/// Clicks and scrolls a slot can bind to a configured action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    LeftClick,
+    MiddleClick,
+    RightClick,
+    ScrollUp,
+    ScrollDown,
+}
+
+impl InputEvent {
+    /// The action `actions` binds to this input, if any
+    pub fn action(self, actions: &WidgetActions) -> Option<&str> {
+        match self {
+            Self::LeftClick => actions.left_click.as_deref(),
+            Self::MiddleClick => actions.middle_click.as_deref(),
+            Self::RightClick => actions.right_click.as_deref(),
+            Self::ScrollUp => actions.scroll_up.as_deref(),
+            Self::ScrollDown => actions.scroll_down.as_deref(),
+        }
+    }
+}
+
+/// Route clicks and scrolls on `slot` to `on_input` before the widget sees
+/// them
+///
+/// `on_input` returns whether the input is bound; bound input is taken from
+/// the widget, anything else reaches it as usual.
+pub fn bind_input(slot: &gtk::Box, on_input: impl Fn(InputEvent) -> bool + Clone + 'static) {
+    for (button, event) in [
+        (gdk::BUTTON_PRIMARY, InputEvent::LeftClick),
+        (gdk::BUTTON_MIDDLE, InputEvent::MiddleClick),
+        (gdk::BUTTON_SECONDARY, InputEvent::RightClick),
+    ] {
+        let click = gtk::GestureClick::new();
+        click.set_button(button);
+        click.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let on_input = on_input.clone();
+        click.connect_pressed(move |gesture, _, _, _| {
+            if on_input(event) {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+            }
+        });
+        slot.add_controller(click);
+    }
+
+    let scroll = gtk::EventControllerScroll::new(
+        gtk::EventControllerScrollFlags::VERTICAL | gtk::EventControllerScrollFlags::DISCRETE,
+    );
+    scroll.set_propagation_phase(gtk::PropagationPhase::Capture);
+    scroll.connect_scroll(move |_, _, dy| {
+        let event = if dy < 0.0 {
+            InputEvent::ScrollUp
+        } else {
+            InputEvent::ScrollDown
+        };
+        if dy != 0.0 && on_input(event) {
+            glib::Propagation::Stop
+        } else {
+            glib::Propagation::Proceed
+        }
+    });
+    slot.add_controller(scroll);
+}
+
+/// Standard right-click menu shared by every bar widget
+pub trait ContextMenu {
+    /// Identifier of the widget, matching its config section
+    const NAME: &'static str;
+}
+
+/// Wrap `root` in a slot that opens the context menu on secondary click
+///
+/// The bar hides and reloads the slot rather than the widget root, so
+/// widgets remain free to toggle their own visibility.
+pub fn slot(
+    root: &impl IsA<gtk::Widget>,
+    on_action: impl Fn(ContextAction) + Clone + 'static,
+) -> gtk::Box {
+    let slot = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    slot.set_css_classes(&["widget-slot"]);
+    slot.append(root);
+
+    let menu = gio::Menu::new();
+    let actions = gio::SimpleActionGroup::new();
+    for action in ContextAction::ALL {
+        menu.append(
+            Some(action.label()),
+            Some(&format!("widget.{}", action.action_name())),
+        );
+
+        let simple_action = gio::SimpleAction::new(action.action_name(), None);
+        let on_action = on_action.clone();
+        simple_action.connect_activate(move |_, _| on_action(action));
+        actions.add_action(&simple_action);
+    }
+    slot.insert_action_group("widget", Some(&actions));
+
+    let popover = gtk::PopoverMenu::from_model(Some(&menu));
+    popover.set_css_classes(&["context-menu"]);
+    popover.set_has_arrow(false);
+    popover.set_parent(&slot);
+
+    let click = gtk::GestureClick::new();
+    click.set_button(gtk::gdk::BUTTON_SECONDARY);
+    click.connect_pressed(move |gesture, _, x, y| {
+        gesture.set_state(gtk::EventSequenceState::Claimed);
+        popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    });
+    slot.add_controller(click);
+
+    slot
+}
+
+impl ContextMenu for WorkspaceWidget {
+    const NAME: &'static str = "workspace";
+}
+
+impl ContextMenu for WindowTitle {
+    const NAME: &'static str = "window_title";
+}
+
+impl ContextMenu for Taskbar {
+    const NAME: &'static str = "taskbar";
+}
+
+impl ContextMenu for MediaPlayer {
+    const NAME: &'static str = "media_player";
+}
+
+impl ContextMenu for Resources {
+    const NAME: &'static str = "resources";
+}
+
+impl ContextMenu for DiskAlert {
+    const NAME: &'static str = "disk_alert";
+}
+
+impl ContextMenu for WiFi {
+    const NAME: &'static str = "wifi";
+}
+
+impl ContextMenu for Bluetooth {
+    const NAME: &'static str = "bluetooth";
+}
+
+impl ContextMenu for Volume {
+    const NAME: &'static str = "volume";
+}
+
+impl ContextMenu for Brightness {
+    const NAME: &'static str = "brightness";
+}
+
+impl ContextMenu for NightLight {
+    const NAME: &'static str = "night_light";
+}
+
+impl ContextMenu for Microphone {
+    const NAME: &'static str = "microphone";
+}
+
+impl ContextMenu for KeyboardLayout {
+    const NAME: &'static str = "keyboard_layout";
+}
+
+impl ContextMenu for InputMethod {
+    const NAME: &'static str = "input_method";
+}
+
+impl ContextMenu for Battery {
+    const NAME: &'static str = "battery";
+}
+
+impl ContextMenu for BreakReminder {
+    const NAME: &'static str = "break_reminder";
+}
+
+impl ContextMenu for ScreenTime {
+    const NAME: &'static str = "screen_time";
+}
+
+impl ContextMenu for FocusTimer {
+    const NAME: &'static str = "focus";
+}
+
+impl ContextMenu for TimeTracking {
+    const NAME: &'static str = "time_tracking";
+}
+
+impl ContextMenu for Countdown {
+    const NAME: &'static str = "countdown";
+}
+
+impl ContextMenu for Weather {
+    const NAME: &'static str = "weather";
+}
+
+impl ContextMenu for Ticker {
+    const NAME: &'static str = "ticker";
+}
+
+impl ContextMenu for Email {
+    const NAME: &'static str = "email";
+}
+
+impl ContextMenu for Vpn {
+    const NAME: &'static str = "vpn";
+}
+
+impl ContextMenu for Updates {
+    const NAME: &'static str = "updates";
+}
+
+impl ContextMenu for FailedUnits {
+    const NAME: &'static str = "failed_units";
+}
+
+impl ContextMenu for Diagnostics {
+    const NAME: &'static str = "diagnostics";
+}
+
+impl ContextMenu for NotificationCenter {
+    const NAME: &'static str = "notifications";
+}
+
+impl ContextMenu for PrivacyIndicator {
+    const NAME: &'static str = "privacy";
+}
+
+impl ContextMenu for GameMode {
+    const NAME: &'static str = "gamemode";
+}
+
+impl ContextMenu for Custom {
+    const NAME: &'static str = "custom";
+}
+
+impl ContextMenu for LuaWidgets {
+    const NAME: &'static str = "lua";
+}
+
+impl ContextMenu for SystemTray {
+    const NAME: &'static str = "tray";
+}
+
+impl ContextMenu for Clock {
+    const NAME: &'static str = "clock";
+}
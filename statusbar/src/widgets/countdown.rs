@@ -0,0 +1,269 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeDelta};
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::CountdownConfig;
+use crate::services::notify;
+
+struct Deadline {
+    name: String,
+    at: DateTime<Local>,
+}
+
+pub struct Countdown {
+    config: CountdownConfig,
+    /// Valid targets, soonest first
+    deadlines: Vec<Deadline>,
+    /// Time of the last tick, deadlines between it and now just passed
+    now: DateTime<Local>,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum CountdownMsg {
+    Tick,
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Countdown {
+    type Init = CountdownConfig;
+    type Input = CountdownMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            #[watch]
+            set_visible: model.config.enabled && model.shown().is_some(),
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            #[name = "countdown_button"]
+            gtk::Button {
+                set_css_classes: &["countdown-button"],
+                connect_clicked => CountdownMsg::TogglePopover,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&format!("Countdown {}", model.label()))],
+                #[watch]
+                set_tooltip_text: model.tooltip().as_deref(),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰔟",
+                        set_css_classes: &["countdown-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.label(),
+                        set_css_classes: &["countdown-label"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Countdowns".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(240),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut deadlines: Vec<Deadline> = config
+            .targets
+            .iter()
+            .filter_map(|target| match parse_at(&target.at) {
+                Some(at) => Some(Deadline {
+                    name: target.name.clone(),
+                    at,
+                }),
+                None => {
+                    eprintln!("Invalid countdown time for {}: {}", target.name, target.at);
+                    None
+                }
+            })
+            .collect();
+        deadlines.sort_by_key(|deadline| deadline.at);
+
+        let mut model = Countdown {
+            config,
+            deadlines,
+            now: Local::now(),
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.countdown_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled && !model.deadlines.is_empty() {
+            model
+                .timers
+                .push(poll::every(1, move || sender.input(CountdownMsg::Tick)));
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            CountdownMsg::Tick => {
+                let now = Local::now();
+                // Only deadlines passing while the bar runs, not at startup
+                for deadline in &self.deadlines {
+                    if self.now < deadline.at && deadline.at <= now {
+                        let summary = match deadline.name.as_str() {
+                            "" => "Countdown",
+                            name => name,
+                        };
+                        notify::send(summary, "The countdown has ended");
+                    }
+                }
+                self.now = now;
+
+                if self.popover.widget().is_visible() {
+                    self.update_popover_content();
+                }
+            }
+            CountdownMsg::TogglePopover => {
+                self.update_popover_content();
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl Countdown {
+    fn expired(&self, deadline: &Deadline) -> bool {
+        let hours = i64::try_from(self.config.expired_hours).unwrap_or(i64::MAX);
+        self.now - deadline.at > TimeDelta::try_hours(hours).unwrap_or(TimeDelta::MAX)
+    }
+
+    /// The deadline in the bar: one that just passed, or the next one
+    fn shown(&self) -> Option<&Deadline> {
+        self.deadlines
+            .iter()
+            .find(|deadline| !self.expired(deadline))
+    }
+
+    fn left(&self, deadline: &Deadline) -> String {
+        if deadline.at <= self.now {
+            return "passed".to_string();
+        }
+        format_left(deadline.at - self.now)
+    }
+
+    fn label(&self) -> String {
+        let Some(deadline) = self.shown() else {
+            return String::new();
+        };
+        let left = self.left(deadline);
+        match (deadline.name.is_empty(), deadline.at <= self.now) {
+            (true, _) => left,
+            (false, true) => format!("{} passed", deadline.name),
+            (false, false) => format!("{} in {}", deadline.name, left),
+        }
+    }
+
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["countdown-widget", "widget"];
+        if self.shown().is_some_and(|deadline| deadline.at <= self.now) {
+            classes.push("countdown-passed");
+        }
+        classes
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let deadline = self.shown()?;
+        Some(deadline.at.format("%a %-d %b %Y, %H:%M").to_string())
+    }
+
+    fn update_popover_content(&self) {
+        let items = self
+            .deadlines
+            .iter()
+            .filter(|deadline| !self.expired(deadline))
+            .map(|deadline| PopoverItem::DetailRow {
+                label: deadline.name.clone(),
+                value: self.left(deadline),
+                value_css: if deadline.at <= self.now {
+                    "countdown-detail-passed"
+                } else {
+                    "countdown-detail"
+                }
+                .to_string(),
+            })
+            .collect();
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+/// Two most significant units, e.g. `3d 4h` or `12m 30s`
+fn format_left(left: TimeDelta) -> String {
+    let seconds = left.num_seconds();
+    let (days, hours) = (seconds / 86400, seconds / 3600 % 24);
+    let (minutes, seconds) = (seconds / 60 % 60, seconds % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m {}s", minutes, seconds)
+    }
+}
+
+fn parse_at(at: &str) -> Option<DateTime<Local>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(at) {
+        return Some(at.with_timezone(&Local));
+    }
+    let naive = NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(at, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })?;
+    naive.and_local_timezone(Local).earliest()
+}
+
+impl Snapshot for Countdown {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "label": self.label(),
+            "deadlines": self
+                .deadlines
+                .iter()
+                .map(|deadline| json!({
+                    "name": deadline.name,
+                    "at": deadline.at.to_rfc3339(),
+                    "left": self.left(deadline),
+                    "expired": self.expired(deadline),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
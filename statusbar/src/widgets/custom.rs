@@ -0,0 +1,362 @@
+use gtk::prelude::*;
+use gtk::{gdk, glib};
+use relm4::prelude::*;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::process::Command;
+use std::thread;
+
+use super::poll::{self, Poll};
+use super::snapshot::Snapshot;
+use crate::config::{CustomConfig, CustomFormat, CustomScriptConfig, ScriptSandboxConfig};
+use crate::services::script;
+
+/// What a script asked to be shown
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CustomOutput {
+    pub text: String,
+    /// Picks the icon from `icons`
+    pub alt: String,
+    pub tooltip: String,
+    pub classes: Vec<String>,
+}
+
+impl CustomOutput {
+    /// Read the output of one run
+    fn parse(format: CustomFormat, stdout: &str) -> Result<Self, String> {
+        match format {
+            CustomFormat::Plain => {
+                let mut lines = stdout.lines().map(str::trim);
+                Ok(Self {
+                    text: lines.next().unwrap_or_default().to_string(),
+                    tooltip: lines.next().unwrap_or_default().to_string(),
+                    classes: lines
+                        .next()
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(str::to_string)
+                        .collect(),
+                    alt: String::new(),
+                })
+            }
+            CustomFormat::Json => {
+                let value: Value = serde_json::from_str(stdout.trim())
+                    .map_err(|e| format!("Invalid JSON output: {}", e))?;
+                let field = |key: &str| value[key].as_str().unwrap_or_default().to_string();
+                // waybar accepts a single class or a list of them
+                let classes = match &value["class"] {
+                    Value::String(class) => vec![class.clone()],
+                    Value::Array(classes) => classes
+                        .iter()
+                        .filter_map(|class| class.as_str().map(str::to_string))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                Ok(Self {
+                    text: field("text"),
+                    alt: field("alt"),
+                    tooltip: field("tooltip"),
+                    classes,
+                })
+            }
+        }
+    }
+
+    /// Read one line of a continuously running script, which is all of it
+    /// in plain format
+    fn parse_line(format: CustomFormat, line: &str) -> Result<Self, String> {
+        match format {
+            CustomFormat::Plain => Ok(Self {
+                text: line.trim().to_string(),
+                ..Self::default()
+            }),
+            CustomFormat::Json => Self::parse(format, line),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CustomAction {
+    Click,
+    MiddleClick,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// One script's button and its last output
+struct Block {
+    config: CustomScriptConfig,
+    output: CustomOutput,
+    error: Option<String>,
+    running: bool,
+    button: gtk::Button,
+    label: gtk::Label,
+}
+
+impl Block {
+    fn new(config: CustomScriptConfig) -> Self {
+        let label = gtk::Label::new(None);
+        let button = gtk::Button::builder().child(&label).build();
+        let block = Self {
+            config,
+            output: CustomOutput::default(),
+            error: None,
+            running: false,
+            button,
+            label,
+        };
+        block.render();
+        block
+    }
+
+    fn render(&self) {
+        let icon = self
+            .config
+            .icons
+            .get(&self.output.alt)
+            .unwrap_or(&self.config.icon);
+        let text = match (icon.is_empty(), self.output.text.is_empty()) {
+            (_, true) => icon.clone(),
+            (true, false) => self.output.text.clone(),
+            (false, false) => format!("{} {}", icon, self.output.text),
+        };
+        self.label.set_label(&text);
+
+        let tooltip = match &self.error {
+            Some(error) => Some(error.as_str()),
+            None if self.output.tooltip.is_empty() => None,
+            None => Some(self.output.tooltip.as_str()),
+        };
+        self.button.set_tooltip_text(tooltip);
+
+        let name_class = format!("custom-{}", self.config.name);
+        let mut classes = vec!["custom-block", name_class.as_str()];
+        classes.extend(self.output.classes.iter().map(String::as_str));
+        if self.error.is_some() {
+            classes.push("custom-error");
+        }
+        self.button.set_css_classes(&classes);
+
+        // Scripts hide their block by printing nothing, like in waybar
+        self.button
+            .set_visible(!self.output.text.is_empty() || self.error.is_some());
+    }
+
+    fn command(&self, action: CustomAction) -> &str {
+        match action {
+            CustomAction::Click => &self.config.on_click,
+            CustomAction::MiddleClick => &self.config.on_middle_click,
+            CustomAction::ScrollUp => &self.config.on_scroll_up,
+            CustomAction::ScrollDown => &self.config.on_scroll_down,
+        }
+    }
+}
+
+pub struct Custom {
+    config: CustomConfig,
+    sandbox: ScriptSandboxConfig,
+    blocks: Vec<Block>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum CustomMsg {
+    Refresh(usize),
+    Output(usize, Result<CustomOutput, String>),
+    Action(usize, CustomAction),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Custom {
+    type Init = (CustomConfig, ScriptSandboxConfig);
+    type Input = CustomMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 4,
+            set_css_classes: &["custom-widget", "widget"],
+            set_visible: model.config.enabled && !model.config.scripts.is_empty(),
+        }
+    }
+
+    fn init(
+        (config, sandbox): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = Custom {
+            config,
+            sandbox,
+            blocks: Vec::new(),
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        if !model.config.enabled {
+            return ComponentParts { model, widgets };
+        }
+
+        for (index, config) in model.config.scripts.iter().enumerate() {
+            let block = Block::new(config.clone());
+            Self::connect_actions(&block.button, index, &sender);
+            root.append(&block.button);
+
+            if config.interval_seconds == 0 {
+                Self::watch(index, config, &model.sandbox, &sender);
+            } else {
+                let timer_sender = sender.clone();
+                let interval = config.interval_seconds.min(u32::MAX as u64) as u32;
+                model.timers.push(poll::every(interval, move || {
+                    timer_sender.input(CustomMsg::Refresh(index))
+                }));
+                sender.input(CustomMsg::Refresh(index));
+            }
+            model.blocks.push(block);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            CustomMsg::Refresh(index) => {
+                let Some(block) = self.blocks.get_mut(index) else {
+                    return;
+                };
+                if block.running || block.config.interval_seconds == 0 {
+                    return;
+                }
+                block.running = true;
+
+                let exec = block.config.exec.clone();
+                let format = block.config.format;
+                let sandbox = self.sandbox.clone();
+                thread::spawn(move || {
+                    let result = script::run(&exec, &sandbox)
+                        .map_err(|e| e.to_string())
+                        .and_then(|output| CustomOutput::parse(format, &output.stdout));
+                    sender.input(CustomMsg::Output(index, result));
+                });
+            }
+            CustomMsg::Output(index, result) => {
+                let Some(block) = self.blocks.get_mut(index) else {
+                    return;
+                };
+                block.running = false;
+                match result {
+                    Ok(output) => {
+                        block.output = output;
+                        block.error = None;
+                    }
+                    Err(e) => {
+                        eprintln!("Custom script {:?}: {}", block.config.name, e);
+                        block.error = Some(e);
+                    }
+                }
+                block.render();
+            }
+            CustomMsg::Action(index, action) => {
+                let Some(block) = self.blocks.get(index) else {
+                    return;
+                };
+                let command = block.command(action).to_string();
+                if command.is_empty() {
+                    return;
+                }
+                // Actions launch things, so they run outside the script sandbox
+                thread::spawn(move || {
+                    match Command::new("sh").arg("-c").arg(&command).status() {
+                        Ok(status) if !status.success() => {
+                            eprintln!("{:?} exited with {}", command, status);
+                        }
+                        Ok(_) => {}
+                        Err(e) => eprintln!("Failed to run {:?}: {}", command, e),
+                    }
+                    sender.input(CustomMsg::Refresh(index));
+                });
+            }
+        }
+    }
+}
+
+impl Custom {
+    fn connect_actions(button: &gtk::Button, index: usize, sender: &ComponentSender<Self>) {
+        let click_sender = sender.clone();
+        button.connect_clicked(move |_| {
+            click_sender.input(CustomMsg::Action(index, CustomAction::Click));
+        });
+
+        let middle_click = gtk::GestureClick::new();
+        middle_click.set_button(gdk::BUTTON_MIDDLE);
+        let middle_sender = sender.clone();
+        middle_click.connect_released(move |_, _, _, _| {
+            middle_sender.input(CustomMsg::Action(index, CustomAction::MiddleClick));
+        });
+        button.add_controller(middle_click);
+
+        let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        let scroll_sender = sender.clone();
+        scroll.connect_scroll(move |controller, _, dy| {
+            if controller
+                .current_event_state()
+                .contains(gdk::ModifierType::SHIFT_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+            let action = if dy < 0.0 {
+                CustomAction::ScrollUp
+            } else {
+                CustomAction::ScrollDown
+            };
+            if dy != 0.0 {
+                scroll_sender.input(CustomMsg::Action(index, action));
+            }
+            glib::Propagation::Stop
+        });
+        button.add_controller(scroll);
+    }
+
+    /// Keep a script running, showing each line it prints
+    fn watch(
+        index: usize,
+        config: &CustomScriptConfig,
+        sandbox: &ScriptSandboxConfig,
+        sender: &ComponentSender<Self>,
+    ) {
+        let exec = config.exec.clone();
+        let format = config.format;
+        let sandbox = sandbox.clone();
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let result = script::watch(&exec, &sandbox, |line| {
+                let output = CustomOutput::parse_line(format, line);
+                poll::deliver(&sender, CustomMsg::Output(index, output))
+            });
+            let error = match result {
+                Ok(()) => "Exited".to_string(),
+                Err(e) => e.to_string(),
+            };
+            sender.input(CustomMsg::Output(index, Err(error)));
+        });
+    }
+}
+
+impl Snapshot for Custom {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "blocks": self
+                .blocks
+                .iter()
+                .map(|block| json!({
+                    "name": block.config.name,
+                    "output": block.output,
+                    "error": block.error,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
@@ -0,0 +1,212 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::DiagnosticsConfig;
+use crate::services::health::{self, Failure};
+
+pub struct Diagnostics {
+    config: DiagnosticsConfig,
+    /// Backends that failed at least `min_failures` times in a row
+    failures: Vec<Failure>,
+    popover: Controller<PopoverComponent>,
+}
+
+#[derive(Debug)]
+pub enum DiagnosticsMsg {
+    FailuresChanged(Vec<Failure>),
+    Retry(&'static str),
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Diagnostics {
+    type Init = DiagnosticsConfig;
+    type Input = DiagnosticsMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["diagnostics-widget", "widget"],
+            // Nothing to see while every backend works
+            #[watch]
+            set_visible: model.config.enabled && !model.failures.is_empty(),
+
+            #[name = "diagnostics_button"]
+            gtk::Button {
+                set_css_classes: &["diagnostics-button"],
+                connect_clicked => DiagnosticsMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Backend errors")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰀪",
+                        set_css_classes: &["diagnostics-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.failures.len().to_string(),
+                        set_css_classes: &["diagnostics-count"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Backend errors".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(320),
+                ..Default::default()
+            })
+            .detach();
+
+        let model = Diagnostics {
+            config,
+            failures: Vec::new(),
+            popover,
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.diagnostics_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let mut failures_rx = health::subscribe();
+            relm4::spawn(async move {
+                loop {
+                    let failures = failures_rx.borrow_and_update().clone();
+                    if !poll::deliver(&sender, DiagnosticsMsg::FailuresChanged(failures)) {
+                        break;
+                    }
+                    if failures_rx.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            DiagnosticsMsg::FailuresChanged(failures) => {
+                self.failures = failures
+                    .into_iter()
+                    .filter(|failure| failure.count >= self.config.min_failures)
+                    .collect();
+                self.update_popover_content(&sender);
+            }
+            DiagnosticsMsg::Retry(backend) => health::retry(backend),
+            DiagnosticsMsg::TogglePopover => {
+                self.update_popover_content(&sender);
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl Diagnostics {
+    fn tooltip(&self) -> String {
+        match self.failures.as_slice() {
+            [failure] => format!("{} keeps failing", failure.backend),
+            failures => format!("{} backends keep failing", failures.len()),
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let mut items = Vec::new();
+        for failure in &self.failures {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+
+            let text = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            text.set_hexpand(true);
+            text.append(
+                &gtk::Label::builder()
+                    .label(failure.backend)
+                    .css_classes(vec!["diagnostics-backend"])
+                    .halign(gtk::Align::Start)
+                    .build(),
+            );
+            text.append(
+                &gtk::Label::builder()
+                    .label(&failure.message)
+                    .css_classes(vec!["diagnostics-message"])
+                    .halign(gtk::Align::Start)
+                    .wrap(true)
+                    .xalign(0.0)
+                    .build(),
+            );
+            text.append(
+                &gtk::Label::builder()
+                    .label(format!(
+                        "Failing since {}, {} times, last at {}",
+                        failure.since.format("%H:%M:%S"),
+                        failure.count,
+                        failure.last.format("%H:%M:%S")
+                    ))
+                    .css_classes(vec!["diagnostics-time"])
+                    .halign(gtk::Align::Start)
+                    .build(),
+            );
+            row.append(&text);
+
+            let retry = gtk::Button::builder()
+                .label("Retry")
+                .css_classes(vec!["diagnostics-retry"])
+                .valign(gtk::Align::Center)
+                .build();
+            let retry_sender = sender.clone();
+            let backend = failure.backend;
+            retry.connect_clicked(move |_| {
+                retry_sender.input(DiagnosticsMsg::Retry(backend));
+            });
+            row.append(&retry);
+
+            items.push(PopoverItem::Custom(row.upcast()));
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for Diagnostics {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "failures": self
+                .failures
+                .iter()
+                .map(|failure| json!({
+                    "backend": failure.backend,
+                    "message": failure.message,
+                    "since": failure.since.to_rfc3339(),
+                    "last": failure.last.to_rfc3339(),
+                    "count": failure.count,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
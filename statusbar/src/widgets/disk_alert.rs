@@ -0,0 +1,208 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use std::path::Path;
+use sysinfo::Disks;
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::DiskAlertConfig;
+
+/// A monitored filesystem over the threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FullDisk {
+    pub mount_point: String,
+    pub used_percent: f64,
+    pub available: u64,
+}
+
+pub struct DiskAlert {
+    config: DiskAlertConfig,
+    full: Vec<FullDisk>,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum DiskAlertMsg {
+    Check,
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for DiskAlert {
+    type Init = DiskAlertConfig;
+    type Input = DiskAlertMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["disk-alert-widget", "widget"],
+            // Silent until a filesystem fills up
+            #[watch]
+            set_visible: model.config.enabled && !model.full.is_empty(),
+
+            #[name = "disk_alert_button"]
+            gtk::Button {
+                set_css_classes: &["disk-alert-button"],
+                connect_clicked => DiskAlertMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Disk space")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰋊",
+                        set_css_classes: &["disk-alert-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.label(),
+                        set_css_classes: &["disk-alert-label"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Disk space".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(260),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut model = DiskAlert {
+            config,
+            full: Vec::new(),
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.disk_alert_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let sender_clone = sender.clone();
+            let interval = model.config.interval_seconds.max(1) as u32;
+            model.timers.push(poll::every(interval, move || {
+                sender_clone.input(DiskAlertMsg::Check)
+            }));
+            poll::after_first_frame(move || sender.input(DiskAlertMsg::Check));
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            DiskAlertMsg::Check => {
+                // A fresh list, so filesystems mounted since are seen
+                self.full = self.full_disks(&Disks::new_with_refreshed_list());
+                self.update_popover_content();
+            }
+            DiskAlertMsg::TogglePopover => {
+                self.update_popover_content();
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl DiskAlert {
+    fn full_disks(&self, disks: &Disks) -> Vec<FullDisk> {
+        let threshold = self.config.threshold_percent as f64;
+        let mut full: Vec<FullDisk> = disks
+            .iter()
+            .filter(|disk| disk.total_space() > 0)
+            .filter(|disk| self.is_monitored(disk.mount_point()))
+            .map(|disk| {
+                let used = disk.total_space() - disk.available_space();
+                FullDisk {
+                    mount_point: disk.mount_point().to_string_lossy().into_owned(),
+                    used_percent: used as f64 / disk.total_space() as f64 * 100.0,
+                    available: disk.available_space(),
+                }
+            })
+            .filter(|disk| disk.used_percent >= threshold)
+            .collect();
+        // Bind mounts of one filesystem show up more than once
+        full.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+        full.dedup_by(|a, b| a.mount_point == b.mount_point);
+        full.sort_by(|a, b| b.used_percent.total_cmp(&a.used_percent));
+        full
+    }
+
+    /// Every disk when no mount points are configured
+    fn is_monitored(&self, mount_point: &Path) -> bool {
+        self.config.mount_points.is_empty()
+            || self
+                .config
+                .mount_points
+                .iter()
+                .any(|monitored| Path::new(monitored) == mount_point)
+    }
+
+    /// Fullest filesystem first
+    fn label(&self) -> String {
+        self.full
+            .first()
+            .map(|disk| format!("{:.0}%", disk.used_percent))
+            .unwrap_or_default()
+    }
+
+    fn tooltip(&self) -> String {
+        self.full
+            .iter()
+            .map(|disk| format!("{} is {:.0}% full", disk.mount_point, disk.used_percent))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn update_popover_content(&self) {
+        let items = self
+            .full
+            .iter()
+            .map(|disk| PopoverItem::DetailRow {
+                label: disk.mount_point.clone(),
+                value: format!(
+                    "{:.0}% ({:.1}G free)",
+                    disk.used_percent,
+                    disk.available as f64 / 1_073_741_824.0
+                ),
+                value_css: "disk-alert-detail".to_string(),
+            })
+            .collect();
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for DiskAlert {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "threshold_percent": self.config.threshold_percent,
+            "full": self.full,
+        })
+    }
+}
@@ -0,0 +1,259 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+use std::process::Command;
+use std::thread;
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::EmailConfig;
+use crate::services::mail;
+
+pub struct Email {
+    config: EmailConfig,
+    /// Last result per account, in config order
+    unread: Vec<Option<Result<u32, String>>>,
+    checking: bool,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum EmailMsg {
+    Check,
+    Checked(Vec<Result<u32, String>>),
+    OpenClient,
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Email {
+    type Init = EmailConfig;
+    type Input = EmailMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_visible: model.config.enabled && !model.config.accounts.is_empty(),
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            #[name = "email_button"]
+            gtk::Button {
+                set_css_classes: &["email-button"],
+                connect_clicked => EmailMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Email")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: if model.total() > 0 { "󰇮" } else { "󰇯" },
+                        set_css_classes: &["email-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_visible: model.total() > 0,
+                        #[watch]
+                        set_label: &model.total().to_string(),
+                        set_css_classes: &["email-count"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Mail".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(260),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut model = Email {
+            unread: vec![None; config.accounts.len()],
+            config,
+            checking: false,
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.email_button.clone().upcast::<gtk::Widget>());
+
+        // Middle click opens the mail client right away
+        let click = gtk::GestureClick::new();
+        click.set_button(gtk::gdk::BUTTON_MIDDLE);
+        let click_sender = sender.clone();
+        click.connect_released(move |_, _, _, _| click_sender.input(EmailMsg::OpenClient));
+        widgets.email_button.add_controller(click);
+
+        if model.config.enabled && !model.config.accounts.is_empty() {
+            let sender_clone = sender.clone();
+            let interval = (model.config.interval_minutes.max(1) * 60) as u32;
+            model.timers.push(poll::every(interval, move || {
+                sender_clone.input(EmailMsg::Check)
+            }));
+            sender.input(EmailMsg::Check);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            EmailMsg::Check => {
+                if self.checking {
+                    return;
+                }
+                self.checking = true;
+
+                let accounts = self.config.accounts.clone();
+                relm4::spawn(async move {
+                    let mut results = Vec::new();
+                    for account in &accounts {
+                        results.push(mail::unread(account).await);
+                    }
+                    sender.input(EmailMsg::Checked(results));
+                });
+            }
+            EmailMsg::Checked(results) => {
+                self.checking = false;
+                for (account, result) in self.config.accounts.iter().zip(&results) {
+                    if let Err(e) = result {
+                        eprintln!("Failed to check mail for {}: {}", account.name, e);
+                    }
+                }
+                self.unread = results.into_iter().map(Some).collect();
+                self.update_popover_content(&sender);
+            }
+            EmailMsg::OpenClient => {
+                if self.config.mail_client.is_empty() {
+                    return;
+                }
+                self.popover.widget().popdown();
+                let command = self.config.mail_client.clone();
+                // Counts change once mail is read, so check again afterwards
+                thread::spawn(move || {
+                    if let Err(e) = Command::new("sh").arg("-c").arg(&command).status() {
+                        eprintln!("Failed to open the mail client: {}", e);
+                    }
+                    sender.input(EmailMsg::Check);
+                });
+            }
+            EmailMsg::TogglePopover => {
+                self.update_popover_content(&sender);
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl Email {
+    fn total(&self) -> u32 {
+        self.unread
+            .iter()
+            .flatten()
+            .filter_map(|result| result.as_ref().ok())
+            .sum()
+    }
+
+    fn has_errors(&self) -> bool {
+        self.unread
+            .iter()
+            .any(|result| matches!(result, Some(Err(_))))
+    }
+
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["email-widget", "widget"];
+        if self.total() > 0 {
+            classes.push("email-unread");
+        }
+        if self.has_errors() {
+            classes.push("email-error");
+        }
+        classes
+    }
+
+    fn tooltip(&self) -> String {
+        match self.total() {
+            0 => "No unread mail".to_string(),
+            1 => "1 unread message".to_string(),
+            total => format!("{} unread messages", total),
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let mut items: Vec<PopoverItem> = self
+            .config
+            .accounts
+            .iter()
+            .zip(&self.unread)
+            .map(|(account, unread)| {
+                let (value, value_css) = match unread {
+                    None => ("…".to_string(), "email-detail"),
+                    Some(Ok(count)) => (count.to_string(), "email-detail"),
+                    Some(Err(e)) => (e.clone(), "email-detail-error"),
+                };
+                PopoverItem::DetailRow {
+                    label: account.name.clone(),
+                    value,
+                    value_css: value_css.to_string(),
+                }
+            })
+            .collect();
+
+        if !self.config.mail_client.is_empty() {
+            let open = gtk::Button::builder()
+                .label("Open mail")
+                .css_classes(vec!["email-open"])
+                .build();
+            let open_sender = sender.clone();
+            open.connect_clicked(move |_| open_sender.input(EmailMsg::OpenClient));
+            items.push(PopoverItem::Separator);
+            items.push(PopoverItem::Custom(open.upcast()));
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for Email {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "total": self.total(),
+            "accounts": self
+                .config
+                .accounts
+                .iter()
+                .zip(&self.unread)
+                .map(|(account, unread)| json!({
+                    "name": account.name,
+                    "unread": unread.as_ref().and_then(|result| result.as_ref().ok()),
+                    "error": unread.as_ref().and_then(|result| result.as_ref().err()),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
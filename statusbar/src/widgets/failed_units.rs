@@ -0,0 +1,236 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+use std::collections::HashSet;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::FailedUnitsConfig;
+use crate::services::systemd::{self, FailedUnit, Scope};
+
+pub struct FailedUnits {
+    config: FailedUnitsConfig,
+    units: Vec<FailedUnit>,
+    /// Units with a restart in flight
+    restarting: HashSet<String>,
+    error: Option<String>,
+    popover: Controller<PopoverComponent>,
+}
+
+#[derive(Debug)]
+pub enum FailedUnitsMsg {
+    UnitsChanged(Vec<FailedUnit>),
+    Restart(Scope, String),
+    Restarted(String, Result<(), String>),
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for FailedUnits {
+    type Init = FailedUnitsConfig;
+    type Input = FailedUnitsMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["failed-units-widget", "widget"],
+            // Nothing to see while everything is healthy
+            #[watch]
+            set_visible: model.config.enabled && !model.units.is_empty(),
+
+            #[name = "failed_units_button"]
+            gtk::Button {
+                set_css_classes: &["failed-units-button"],
+                connect_clicked => FailedUnitsMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Failed units")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰀦",
+                        set_css_classes: &["failed-units-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.units.len().to_string(),
+                        set_css_classes: &["failed-units-count"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Failed units".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(320),
+                ..Default::default()
+            })
+            .detach();
+
+        let model = FailedUnits {
+            config,
+            units: Vec::new(),
+            restarting: HashSet::new(),
+            error: None,
+            popover,
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.failed_units_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let mut units_rx = systemd::subscribe();
+            relm4::spawn(async move {
+                loop {
+                    let units = units_rx.borrow_and_update().clone();
+                    if !poll::deliver(&sender, FailedUnitsMsg::UnitsChanged(units)) {
+                        break;
+                    }
+                    if units_rx.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            FailedUnitsMsg::UnitsChanged(units) => {
+                self.units = units
+                    .into_iter()
+                    .filter(|unit| !self.config.ignore.contains(&unit.name))
+                    .collect();
+                self.update_popover_content(&sender);
+            }
+            FailedUnitsMsg::Restart(scope, name) => {
+                if !self.restarting.insert(name.clone()) {
+                    return;
+                }
+                self.error = None;
+                self.update_popover_content(&sender);
+                relm4::spawn(async move {
+                    let result = systemd::restart(scope, &name).await;
+                    sender.input(FailedUnitsMsg::Restarted(name, result));
+                });
+            }
+            FailedUnitsMsg::Restarted(name, result) => {
+                self.restarting.remove(&name);
+                if let Err(e) = result {
+                    eprintln!("{}", e);
+                    self.error = Some(e);
+                }
+                // The list itself updates once systemd finishes the job
+                self.update_popover_content(&sender);
+            }
+            FailedUnitsMsg::TogglePopover => {
+                self.update_popover_content(&sender);
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl FailedUnits {
+    fn tooltip(&self) -> String {
+        match self.units.len() {
+            1 => format!("{} failed", self.units[0].name),
+            count => format!("{} units failed", count),
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let mut items = Vec::new();
+        for unit in &self.units {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+
+            let text = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            text.set_hexpand(true);
+            let name = match unit.scope {
+                Scope::System => unit.name.clone(),
+                Scope::User => format!("{} (user)", unit.name),
+            };
+            text.append(
+                &gtk::Label::builder()
+                    .label(name)
+                    .css_classes(vec!["failed-units-name"])
+                    .halign(gtk::Align::Start)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .build(),
+            );
+            text.append(
+                &gtk::Label::builder()
+                    .label(&unit.description)
+                    .css_classes(vec!["failed-units-description"])
+                    .halign(gtk::Align::Start)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .build(),
+            );
+            row.append(&text);
+
+            let restarting = self.restarting.contains(&unit.name);
+            let restart = gtk::Button::builder()
+                .label(if restarting {
+                    "Restarting…"
+                } else {
+                    "Restart"
+                })
+                .css_classes(vec!["failed-units-restart"])
+                .valign(gtk::Align::Center)
+                .sensitive(!restarting)
+                .build();
+            let restart_sender = sender.clone();
+            let scope = unit.scope;
+            let unit_name = unit.name.clone();
+            restart.connect_clicked(move |_| {
+                restart_sender.input(FailedUnitsMsg::Restart(scope, unit_name.clone()));
+            });
+            row.append(&restart);
+
+            items.push(PopoverItem::Custom(row.upcast()));
+        }
+
+        if let Some(error) = &self.error {
+            items.push(PopoverItem::Separator);
+            items.push(PopoverItem::DetailRow {
+                label: "Error".to_string(),
+                value: error.clone(),
+                value_css: "failed-units-error".to_string(),
+            });
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for FailedUnits {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "units": self.units,
+            "error": self.error,
+        })
+    }
+}
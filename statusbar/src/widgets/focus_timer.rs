@@ -0,0 +1,383 @@
+use chrono::{DateTime, Local};
+use gtk::glib;
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::thread;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::{DistractionAction, FocusConfig};
+use crate::services::compositor::{self, Event};
+use crate::services::notify;
+
+/// A distracting app gaining focus during a session
+#[derive(Debug, Clone)]
+struct Interruption {
+    time: DateTime<Local>,
+    app_id: String,
+}
+
+pub struct FocusTimer {
+    config: FocusConfig,
+    remaining_secs: u64,
+    is_running: bool,
+    interruptions: Vec<Interruption>,
+    popover: Controller<PopoverComponent>,
+    /// Starts or stops a session, kept across popover updates so the ticks
+    /// don't replace it under the pointer
+    session_button: gtk::Button,
+    /// Counts down every second, removed on shutdown
+    tick_timer: Option<glib::SourceId>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FocusTimerMsg {
+    Tick,
+    StartSession,
+    /// Start a session of a given length, e.g. a pomodoro work phase
+    StartSessionFor {
+        minutes: u64,
+    },
+    StopSession,
+    ToggleSession,
+    WindowFocused {
+        id: u64,
+        app_id: Option<String>,
+    },
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for FocusTimer {
+    type Init = FocusConfig;
+    type Input = FocusTimerMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["focus-timer-widget", "widget"],
+            set_visible: model.config.enabled,
+
+            #[name = "focus_button"]
+            gtk::Button {
+                set_css_classes: &["focus-timer-button"],
+                connect_clicked => FocusTimerMsg::TogglePopover,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&if model.is_running {
+                    format!("Focus timer, {} left", Self::format_remaining(model.remaining_secs))
+                } else {
+                    "Focus timer".to_string()
+                })],
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰀘",
+                        #[watch]
+                        set_css_classes: &[
+                            "focus-timer-icon",
+                            if model.is_running { "focus-running" } else { "focus-stopped" },
+                        ],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_visible: model.is_running,
+                        #[watch]
+                        set_label: &Self::format_remaining(model.remaining_secs),
+                        set_css_classes: &["focus-timer-label"],
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Create popover without parent (will be set after widgets are created)
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Focus".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(280),
+                ..Default::default()
+            })
+            .detach();
+
+        let session_button = gtk::Button::new();
+        session_button.set_css_classes(&["focus-action-button"]);
+        let button_sender = sender.clone();
+        session_button.connect_clicked(move |_| {
+            button_sender.input(FocusTimerMsg::ToggleSession);
+        });
+
+        let mut model = FocusTimer {
+            config,
+            remaining_secs: 0,
+            is_running: false,
+            interruptions: Vec::new(),
+            popover,
+            session_button,
+            tick_timer: None,
+        };
+
+        let widgets = view_output!();
+
+        // Set parent widget after widgets are created
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.focus_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let sender_clone = sender.clone();
+            model.tick_timer = Some(glib::timeout_add_seconds_local(1, move || {
+                sender_clone.input(FocusTimerMsg::Tick);
+                glib::ControlFlow::Continue
+            }));
+
+            Self::listen_compositor_events(sender.clone());
+
+            model.update_popover_content();
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            FocusTimerMsg::Tick => {
+                if self.is_running {
+                    self.remaining_secs = self.remaining_secs.saturating_sub(1);
+                    if self.remaining_secs == 0 {
+                        self.is_running = false;
+                        notify::send(
+                            "Focus session complete",
+                            &format!("{} interruptions", self.interruptions.len()),
+                        );
+                    }
+                    // Keep the time left in the popover counting down
+                    self.update_popover_content();
+                }
+            }
+            FocusTimerMsg::StartSession => {
+                self.start_session(self.config.duration_minutes);
+            }
+            FocusTimerMsg::StartSessionFor { minutes } => {
+                if self.config.enabled {
+                    self.start_session(minutes);
+                }
+            }
+            FocusTimerMsg::StopSession => {
+                self.stop_session();
+            }
+            FocusTimerMsg::ToggleSession => {
+                if self.is_running {
+                    self.stop_session();
+                } else {
+                    self.start_session(self.config.duration_minutes);
+                }
+            }
+            FocusTimerMsg::WindowFocused { id, app_id } => {
+                let Some(app_id) = app_id else {
+                    return;
+                };
+                if self.is_running && self.is_distracting(&app_id) {
+                    self.handle_distraction(id, app_id);
+                    self.update_popover_content();
+                }
+            }
+            FocusTimerMsg::TogglePopover => {
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if let Some(timer) = self.tick_timer.take() {
+            timer.remove();
+        }
+    }
+}
+
+impl FocusTimer {
+    fn start_session(&mut self, minutes: u64) {
+        self.is_running = true;
+        self.remaining_secs = minutes * 60;
+        self.interruptions.clear();
+        self.update_popover_content();
+    }
+
+    fn stop_session(&mut self) {
+        self.is_running = false;
+        self.remaining_secs = 0;
+        self.update_popover_content();
+    }
+
+    fn is_distracting(&self, app_id: &str) -> bool {
+        self.config
+            .distracting_apps
+            .iter()
+            .any(|app| app.eq_ignore_ascii_case(app_id))
+    }
+
+    fn handle_distraction(&mut self, window_id: u64, app_id: String) {
+        let interruption = Interruption {
+            time: Local::now(),
+            app_id,
+        };
+        Self::log_interruption(&interruption);
+
+        let put_away = match (self.config.action, &self.config.move_to_workspace) {
+            (DistractionAction::Move, Some(workspace)) => {
+                Some(compositor::move_window_to_workspace(window_id, workspace))
+            }
+            (DistractionAction::Minimize, _) => Some(compositor::minimize_window(window_id)),
+            _ => None,
+        };
+        if let Some(Err(e)) = &put_away {
+            eprintln!("Failed to put away distracting window: {}", e);
+        }
+        // Warn instead when the window wasn't put away
+        if !matches!(put_away, Some(Ok(()))) {
+            notify::send(
+                "Stay focused",
+                &format!(
+                    "{} is on your distraction list. {} left in this session.",
+                    interruption.app_id,
+                    Self::format_remaining(self.remaining_secs)
+                ),
+            );
+        }
+
+        self.interruptions.push(interruption);
+    }
+
+    fn update_popover_content(&self) {
+        let mut items = vec![
+            PopoverItem::DetailRow {
+                label: "Status".to_string(),
+                value: if self.is_running {
+                    format!("{} left", Self::format_remaining(self.remaining_secs))
+                } else {
+                    "Idle".to_string()
+                },
+                value_css: "focus-detail".to_string(),
+            },
+            PopoverItem::DetailRow {
+                label: "Interruptions".to_string(),
+                value: self.interruptions.len().to_string(),
+                value_css: "focus-detail".to_string(),
+            },
+        ];
+
+        for interruption in self.interruptions.iter().rev().take(5) {
+            items.push(PopoverItem::DetailRow {
+                label: interruption.app_id.clone(),
+                value: interruption
+                    .time
+                    .with_timezone(&Local)
+                    .format("%H:%M")
+                    .to_string(),
+                value_css: "focus-detail".to_string(),
+            });
+        }
+
+        items.push(PopoverItem::Separator);
+
+        self.session_button.set_label(if self.is_running {
+            "Stop Session"
+        } else {
+            "Start Session"
+        });
+        items.push(PopoverItem::Custom(
+            self.session_button.clone().upcast::<gtk::Widget>(),
+        ));
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+
+    fn format_remaining(secs: u64) -> String {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+
+    fn log_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("statusbar").join("focus-interruptions.log"))
+    }
+
+    fn log_interruption(interruption: &Interruption) {
+        let Some(path) = Self::log_path() else {
+            return;
+        };
+
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| {
+                writeln!(
+                    file,
+                    "{} {}",
+                    interruption.time.format("%Y-%m-%d %H:%M:%S"),
+                    interruption.app_id
+                )
+            });
+
+        if let Err(e) = result {
+            eprintln!("Failed to log interruption to {}: {}", path.display(), e);
+        }
+    }
+
+    fn listen_compositor_events(sender: ComponentSender<Self>) {
+        let mut events = compositor::subscribe();
+        thread::spawn(move || {
+            while let Some(event) = events.blocking_next() {
+                let Event::WindowFocusChanged(Some(_)) = event else {
+                    continue;
+                };
+                let Some((id, app_id)) = Self::get_focused_window() else {
+                    continue;
+                };
+                if !poll::deliver(&sender, FocusTimerMsg::WindowFocused { id, app_id }) {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn get_focused_window() -> Option<(u64, Option<String>)> {
+        let window = compositor::focused_window().ok()??;
+        Some((window.id, window.app_id))
+    }
+}
+
+impl Snapshot for FocusTimer {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "is_running": self.is_running,
+            "remaining_secs": self.remaining_secs,
+            "interruptions": self.interruptions.iter().map(|interruption| json!({
+                "time": interruption.time.to_rfc3339(),
+                "app_id": interruption.app_id,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
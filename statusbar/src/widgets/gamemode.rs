@@ -0,0 +1,113 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll;
+use super::snapshot::Snapshot;
+use crate::config::GameModeConfig;
+use crate::services::gamemode::{self, Performance};
+
+pub struct GameMode {
+    config: GameModeConfig,
+    performance: Performance,
+}
+
+#[derive(Debug)]
+pub enum GameModeMsg {
+    PerformanceChanged(Performance),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for GameMode {
+    type Init = GameModeConfig;
+    type Input = GameModeMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["gamemode-widget", "widget"],
+            // Only worth the space while something is running hot
+            #[watch]
+            set_visible: model.config.enabled && model.performance.is_active(),
+            #[watch]
+            set_tooltip_text: Some(&model.tooltip()),
+
+            gtk::Label {
+                #[watch]
+                set_label: if model.performance.games.is_empty() { "󰓅" } else { "󰊗" },
+                set_css_classes: &["gamemode-icon"],
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = GameMode {
+            config,
+            performance: Performance::default(),
+        };
+
+        let widgets = view_output!();
+
+        if model.config.enabled {
+            let mut performance_rx = gamemode::subscribe();
+            relm4::spawn(async move {
+                loop {
+                    let performance = performance_rx.borrow_and_update().clone();
+                    if !poll::deliver(&sender, GameModeMsg::PerformanceChanged(performance)) {
+                        break;
+                    }
+                    if performance_rx.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            GameModeMsg::PerformanceChanged(performance) => {
+                self.performance = performance;
+            }
+        }
+    }
+}
+
+impl GameMode {
+    fn tooltip(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.performance.games.is_empty() {
+            let names: Vec<&str> = self
+                .performance
+                .games
+                .iter()
+                .map(|game| game.name.as_str())
+                .collect();
+            lines.push(format!("GameMode active for {}", names.join(", ")));
+        }
+        if self.performance.performance_governor() {
+            lines.push(format!(
+                "CPU governor: {}",
+                self.performance.governors.join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Snapshot for GameMode {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "active": self.performance.is_active(),
+            "performance": self.performance,
+        })
+    }
+}
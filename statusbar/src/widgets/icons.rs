@@ -0,0 +1,64 @@
+use gtk::gio;
+use gtk::prelude::*;
+use relm4::gtk;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Shown for apps without a desktop entry or a matching themed icon
+const FALLBACK_ICON: &str = "application-x-executable";
+
+thread_local! {
+    /// Resolved icons by app_id; GTK objects stay on the main thread
+    static CACHE: RefCell<Option<HashMap<String, gio::Icon>>> = const { RefCell::new(None) };
+}
+
+/// Icon for an app, e.g. from a window's app_id
+///
+/// Looking up desktop entries scans the application directories, so results
+/// are cached until the icon theme changes.
+pub fn app_icon(app_id: &str) -> gio::Icon {
+    CACHE.with_borrow_mut(|cache| {
+        let cache = cache.get_or_insert_with(|| {
+            watch_icon_theme();
+            HashMap::new()
+        });
+        cache
+            .entry(app_id.to_string())
+            .or_insert_with(|| resolve(app_id))
+            .clone()
+    })
+}
+
+/// Drop cached icons when the user switches icon themes
+fn watch_icon_theme() {
+    let Some(settings) = gtk::Settings::default() else {
+        return;
+    };
+    settings.connect_gtk_icon_theme_name_notify(|_| {
+        CACHE.with_borrow_mut(|cache| {
+            if let Some(cache) = cache {
+                cache.clear();
+            }
+        });
+    });
+}
+
+/// Icon from the app's desktop entry, falling back to an icon named after it
+fn resolve(app_id: &str) -> gio::Icon {
+    // Some apps report a capitalized app_id for a lowercase desktop file
+    let desktop_icon = [app_id.to_string(), app_id.to_lowercase()]
+        .iter()
+        .find_map(|name| gio::DesktopAppInfo::new(&format!("{}.desktop", name))?.icon());
+    if let Some(icon) = desktop_icon {
+        return icon;
+    }
+
+    let has_themed_icon = gtk::gdk::Display::default()
+        .is_some_and(|display| gtk::IconTheme::for_display(&display).has_icon(app_id));
+    let name = if has_themed_icon {
+        app_id
+    } else {
+        FALLBACK_ICON
+    };
+    gio::ThemedIcon::new(name).upcast()
+}
@@ -0,0 +1,150 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll;
+use super::snapshot::Snapshot;
+use crate::config::InputMethodConfig;
+use crate::services::input_method::{self, InputMethods};
+
+pub struct InputMethod {
+    config: InputMethodConfig,
+    /// None while the daemon isn't running
+    methods: Option<InputMethods>,
+}
+
+#[derive(Debug)]
+pub enum InputMethodMsg {
+    MethodsChanged(Option<InputMethods>),
+    SwitchNext,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for InputMethod {
+    type Init = InputMethodConfig;
+    type Input = InputMethodMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["input-method-widget", "widget"],
+            // A single input method has nothing to switch to
+            #[watch]
+            set_visible: model.config.enabled
+                && model.methods.as_ref().is_some_and(|methods| methods.methods.len() > 1),
+
+            gtk::Button {
+                set_css_classes: &["input-method-button"],
+                update_property: &[gtk::accessible::Property::Label("Input method")],
+                #[watch]
+                set_label: &model.label(),
+                #[watch]
+                set_tooltip_text: model.current().map(|method| method.name.as_str()),
+                connect_clicked => InputMethodMsg::SwitchNext,
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = InputMethod {
+            config,
+            methods: None,
+        };
+
+        let widgets = view_output!();
+
+        if model.config.enabled {
+            let mut methods_rx = input_method::subscribe(model.config.backend);
+            relm4::spawn(async move {
+                loop {
+                    let methods = methods_rx.borrow_and_update().clone();
+                    if !poll::deliver(&sender, InputMethodMsg::MethodsChanged(methods)) {
+                        break;
+                    }
+                    if methods_rx.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            InputMethodMsg::MethodsChanged(methods) => {
+                self.methods = methods;
+            }
+            InputMethodMsg::SwitchNext => {
+                let Some(methods) = &mut self.methods else {
+                    return;
+                };
+                let index = methods
+                    .methods
+                    .iter()
+                    .position(|method| method.id == methods.current)
+                    .map_or(0, |index| (index + 1) % methods.methods.len());
+                let Some(next) = methods.methods.get(index) else {
+                    return;
+                };
+
+                // fcitx5 is only polled, so show the switch right away
+                methods.current = next.id.clone();
+                let id = next.id.clone();
+                let backend = self.config.backend;
+                relm4::spawn(async move {
+                    if let Err(e) = input_method::switch(backend, &id).await {
+                        eprintln!("{}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl InputMethod {
+    fn current(&self) -> Option<&input_method::InputMethod> {
+        let methods = self.methods.as_ref()?;
+        methods
+            .methods
+            .iter()
+            .find(|method| method.id == methods.current)
+    }
+
+    /// Configured label, the daemon's own label, or the first two letters
+    /// of the name
+    fn label(&self) -> String {
+        let Some(method) = self.current() else {
+            return String::new();
+        };
+        if let Some(label) = self.config.labels.get(&method.id) {
+            return label.clone();
+        }
+        if !method.label.is_empty() {
+            return method.label.clone();
+        }
+        method
+            .name
+            .chars()
+            .take(2)
+            .collect::<String>()
+            .to_uppercase()
+    }
+}
+
+impl Snapshot for InputMethod {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "backend": self.config.backend,
+            "methods": self.methods,
+            "label": self.label(),
+        })
+    }
+}
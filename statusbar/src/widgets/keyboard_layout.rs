@@ -0,0 +1,143 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+use std::thread;
+
+use super::poll;
+use super::snapshot::Snapshot;
+use crate::config::KeyboardLayoutConfig;
+use crate::services::compositor::{self, Event, KeyboardLayouts};
+
+pub struct KeyboardLayout {
+    config: KeyboardLayoutConfig,
+    /// None until the compositor reports layouts, or if it never does
+    layouts: Option<KeyboardLayouts>,
+}
+
+#[derive(Debug)]
+pub enum KeyboardLayoutMsg {
+    LayoutsChanged(KeyboardLayouts),
+    LayoutSwitched(usize),
+    SwitchNext,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for KeyboardLayout {
+    type Init = KeyboardLayoutConfig;
+    type Input = KeyboardLayoutMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["keyboard-layout-widget", "widget"],
+            // A single layout has nothing to switch to
+            #[watch]
+            set_visible: model.config.enabled
+                && model.layouts.as_ref().is_some_and(|layouts| layouts.names.len() > 1),
+
+            gtk::Button {
+                set_css_classes: &["keyboard-layout-button"],
+                update_property: &[gtk::accessible::Property::Label("Keyboard layout")],
+                #[watch]
+                set_label: &model.label(),
+                #[watch]
+                set_tooltip_text: model.current_name(),
+                connect_clicked => KeyboardLayoutMsg::SwitchNext,
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = KeyboardLayout {
+            config,
+            layouts: None,
+        };
+
+        let widgets = view_output!();
+
+        if !model.config.enabled {
+            return ComponentParts { model, widgets };
+        }
+
+        let mut events = compositor::subscribe();
+        let event_sender = sender.clone();
+        thread::spawn(move || {
+            while let Some(event) = events.blocking_next() {
+                let msg = match event {
+                    Event::KeyboardLayoutsChanged(layouts) => {
+                        KeyboardLayoutMsg::LayoutsChanged(layouts)
+                    }
+                    Event::KeyboardLayoutSwitched(index) => {
+                        KeyboardLayoutMsg::LayoutSwitched(index)
+                    }
+                    _ => continue,
+                };
+                if !poll::deliver(&event_sender, msg) {
+                    break;
+                }
+            }
+        });
+
+        // Request the initial layouts
+        thread::spawn(move || match compositor::keyboard_layouts() {
+            Ok(layouts) => sender.input(KeyboardLayoutMsg::LayoutsChanged(layouts)),
+            Err(e) => eprintln!("Failed to get keyboard layouts: {}", e),
+        });
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            KeyboardLayoutMsg::LayoutsChanged(layouts) => {
+                self.layouts = Some(layouts);
+            }
+            KeyboardLayoutMsg::LayoutSwitched(index) => {
+                if let Some(layouts) = &mut self.layouts {
+                    layouts.current = index;
+                }
+            }
+            KeyboardLayoutMsg::SwitchNext => {
+                // The compositor reports the new layout back as an event
+                thread::spawn(|| {
+                    if let Err(e) = compositor::switch_keyboard_layout() {
+                        eprintln!("Failed to switch keyboard layout: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl KeyboardLayout {
+    fn current_name(&self) -> Option<&str> {
+        let layouts = self.layouts.as_ref()?;
+        layouts.names.get(layouts.current).map(String::as_str)
+    }
+
+    /// Configured short label, or the first two letters of the name
+    fn label(&self) -> String {
+        let Some(name) = self.current_name() else {
+            return String::new();
+        };
+        match self.config.labels.get(name) {
+            Some(label) => label.clone(),
+            None => name.chars().take(2).collect::<String>().to_uppercase(),
+        }
+    }
+}
+
+impl Snapshot for KeyboardLayout {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "layouts": self.layouts,
+            "label": self.label(),
+        })
+    }
+}
@@ -0,0 +1,581 @@
+// Widgets written in Lua
+//
+// Each `[[lua.widgets]]` script gets its own Lua state and a block in the bar,
+// driven through the global `bar` table:
+//
+//     bar.set_text(text)          bar.set_icon(icon)
+//     bar.set_tooltip(text)       bar.set_css({ "class", ... })
+//     bar.every(seconds, fn)      -- now, then every few seconds
+//     bar.run(command, fn(stdout, error))
+//     bar.fetch(url, fn(body, error))
+//     bar.on_click(fn)            bar.on_scroll(fn("up" | "down"))
+//     -- open a popover listing detail rows, progress bars and buttons
+//     bar.popover({ { label = "CPU", value = "12%" },
+//                   { label = "Memory", progress = 0.4 },
+//                   { separator = true },
+//                   { button = "Restart", on_click = fn } })
+//
+// Scripts run on the GTK thread and only ever record what to show; commands
+// and fetches run in the background and call back into the script once done.
+// They get no `io` or `os` library, so commands go through the sandbox of
+// `bar.run`, and a run that takes longer than `RUN_LIMIT` is stopped.
+use gtk::prelude::*;
+use gtk::{gdk, glib};
+use mlua::{Function, HookTriggers, IntoLuaMulti, Lua, LuaOptions, RegistryKey, StdLib, Table};
+use relm4::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg, PopoverOutput};
+use super::snapshot::Snapshot;
+use crate::config::{Config, LuaConfig, LuaWidgetConfig, ScriptSandboxConfig};
+use crate::services::{http, script};
+
+/// Longest a script or one of its callbacks may hold up the GTK thread
+const RUN_LIMIT: Duration = Duration::from_millis(250);
+
+/// Instructions run between checks against `RUN_LIMIT`
+const INSTRUCTIONS_PER_CHECK: u32 = 10_000;
+
+/// What a script set through `bar`, kept as app data of its Lua state
+#[derive(Default)]
+struct State {
+    text: String,
+    icon: String,
+    tooltip: String,
+    classes: Vec<String>,
+    /// Items the script wants shown, taken by the next render
+    popover: Option<Vec<PopoverItem>>,
+    /// Lua functions waiting to be called back, by id
+    callbacks: HashMap<u64, RegistryKey>,
+    next_id: u64,
+    on_click: Option<u64>,
+    on_scroll: Option<u64>,
+    /// Callbacks of the buttons in the current popover
+    popover_actions: Vec<u64>,
+    /// `bar.every` timers, stopped along with the Lua state
+    timers: Vec<Poll>,
+}
+
+impl State {
+    /// Keep `key` around until it's called back, returning its id
+    fn keep(&mut self, key: RegistryKey) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.callbacks.insert(id, key);
+        id
+    }
+}
+
+fn state(lua: &Lua) -> mlua::Result<mlua::AppDataRefMut<'_, State>> {
+    lua.app_data_mut::<State>()
+        .ok_or_else(|| mlua::Error::runtime("bar state is gone"))
+}
+
+/// One script's state and its block in the bar
+struct Block {
+    config: LuaWidgetConfig,
+    lua: Lua,
+    error: Option<String>,
+    button: gtk::Button,
+    label: gtk::Label,
+    popover: Controller<PopoverComponent>,
+}
+
+impl Block {
+    fn new(index: usize, config: LuaWidgetConfig, sender: &ComponentSender<LuaWidgets>) -> Self {
+        let label = gtk::Label::new(None);
+        let button = gtk::Button::builder().child(&label).build();
+
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                title: config.name.clone(),
+                width: Some(240),
+                ..Default::default()
+            })
+            .forward(sender.input_sender(), move |output| {
+                LuaMsg::Popover(index, output)
+            });
+        popover
+            .model()
+            .set_parent(&button.clone().upcast::<gtk::Widget>());
+
+        // `coroutine` is left out too, as the time limit only covers the
+        // main thread
+        let libraries = StdLib::TABLE | StdLib::STRING | StdLib::UTF8 | StdLib::MATH;
+        let lua = Lua::new_with(libraries, LuaOptions::default())
+            .expect("the safe standard libraries always load");
+        lua.set_app_data(State::default());
+
+        Self {
+            config,
+            lua,
+            error: None,
+            button,
+            label,
+            popover,
+        }
+    }
+
+    /// Set up `bar` and run the script once
+    fn load(&mut self, install: impl FnOnce(&Lua) -> mlua::Result<()>) {
+        let path = script_path(&self.config.path);
+        let result = install(&self.lua).and_then(|()| {
+            let source = fs::read_to_string(&path)
+                .map_err(|e| mlua::Error::runtime(format!("{}: {}", path.display(), e)))?;
+            limit(&self.lua);
+            self.lua
+                .load(&source)
+                .set_name(path.to_string_lossy())
+                .exec()
+        });
+        self.finish(result);
+    }
+
+    /// Call a function the script handed over, forgetting it unless it's
+    /// meant to be called again
+    fn call(&mut self, id: u64, keep: bool, args: impl for<'lua> IntoLuaMulti<'lua>) {
+        let result = {
+            let function = state(&self.lua).and_then(|mut state| {
+                let function = match state.callbacks.get(&id) {
+                    Some(key) => self.lua.registry_value::<Function>(key).map(Some),
+                    None => Ok(None),
+                };
+                if !keep {
+                    state.callbacks.remove(&id);
+                }
+                function
+            });
+            match function {
+                Ok(Some(function)) => {
+                    limit(&self.lua);
+                    function.call::<_, ()>(args)
+                }
+                Ok(None) => return,
+                Err(e) => Err(e),
+            }
+        };
+        self.finish(result);
+    }
+
+    fn finish(&mut self, result: mlua::Result<()>) {
+        match result {
+            Ok(()) => self.error = None,
+            Err(e) => {
+                eprintln!("Lua widget {:?}: {}", self.config.name, e);
+                self.error = Some(e.to_string());
+            }
+        }
+        self.lua.expire_registry_values();
+        self.render();
+    }
+
+    fn render(&self) {
+        let Ok(mut state) = state(&self.lua) else {
+            return;
+        };
+
+        let text = match (state.icon.is_empty(), state.text.is_empty()) {
+            (_, true) => state.icon.clone(),
+            (true, false) => state.text.clone(),
+            (false, false) => format!("{} {}", state.icon, state.text),
+        };
+        self.label.set_label(&text);
+
+        let tooltip = match &self.error {
+            Some(error) => Some(error.as_str()),
+            None if state.tooltip.is_empty() => None,
+            None => Some(state.tooltip.as_str()),
+        };
+        self.button.set_tooltip_text(tooltip);
+
+        let name_class = format!("lua-{}", self.config.name);
+        let mut classes = vec!["lua-block", name_class.as_str()];
+        classes.extend(state.classes.iter().map(String::as_str));
+        if self.error.is_some() {
+            classes.push("lua-error");
+        }
+        self.button.set_css_classes(&classes);
+        self.button
+            .set_visible(!text.is_empty() || self.error.is_some());
+
+        if let Some(items) = state.popover.take() {
+            self.popover.emit(PopoverMsg::UpdateItems(items));
+            if !self.popover.widget().is_visible() {
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+/// Give what the script is about to run `RUN_LIMIT` to finish
+fn limit(lua: &Lua) {
+    stop_at(lua, Instant::now() + RUN_LIMIT, INSTRUCTIONS_PER_CHECK);
+}
+
+/// Stop the running script with an error once `deadline` passes, checking
+/// every so many `instructions`
+///
+/// `pcall` can catch that error, so from then on it's raised on every
+/// instruction until it gets out of the script.
+fn stop_at(lua: &Lua, deadline: Instant, instructions: u32) {
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(instructions),
+        move |lua, _| {
+            if Instant::now() < deadline {
+                return Ok(());
+            }
+            if instructions > 1 {
+                stop_at(lua, deadline, 1);
+            }
+            Err(mlua::Error::runtime(format!(
+                "Stopped after running for {}ms",
+                RUN_LIMIT.as_millis()
+            )))
+        },
+    );
+}
+
+/// Scripts are looked up next to the config file unless given in full
+fn script_path(path: &Path) -> PathBuf {
+    match Config::path().as_deref().and_then(|config| config.parent()) {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+pub struct LuaWidgets {
+    config: LuaConfig,
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug)]
+pub enum LuaMsg {
+    Timer(usize, u64),
+    /// A command or fetch finished, with its output or error
+    Done(usize, u64, Result<String, String>),
+    Click(usize),
+    Scroll(usize, &'static str),
+    Popover(usize, PopoverOutput),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for LuaWidgets {
+    type Init = (LuaConfig, ScriptSandboxConfig);
+    type Input = LuaMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 4,
+            set_css_classes: &["lua-widget", "widget"],
+            set_visible: model.config.enabled && !model.config.widgets.is_empty(),
+        }
+    }
+
+    fn init(
+        (config, sandbox): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = LuaWidgets {
+            config,
+            blocks: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        if !model.config.enabled {
+            return ComponentParts { model, widgets };
+        }
+
+        for (index, config) in model.config.widgets.iter().enumerate() {
+            let mut block = Block::new(index, config.clone(), &sender);
+            Self::connect_actions(&block.button, index, &sender);
+            root.append(&block.button);
+            block.load(|lua| install_api(lua, index, &sandbox, &sender));
+            model.blocks.push(block);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            LuaMsg::Timer(index, id) => {
+                if let Some(block) = self.blocks.get_mut(index) {
+                    block.call(id, true, ());
+                }
+            }
+            LuaMsg::Done(index, id, result) => {
+                let Some(block) = self.blocks.get_mut(index) else {
+                    return;
+                };
+                match result {
+                    Ok(output) => block.call(id, false, (output, None::<String>)),
+                    Err(e) => block.call(id, false, (None::<String>, e)),
+                }
+            }
+            LuaMsg::Click(index) => {
+                let Some(block) = self.blocks.get_mut(index) else {
+                    return;
+                };
+                let on_click = state(&block.lua).ok().and_then(|state| state.on_click);
+                if let Some(id) = on_click {
+                    block.call(id, true, ());
+                }
+            }
+            LuaMsg::Scroll(index, direction) => {
+                let Some(block) = self.blocks.get_mut(index) else {
+                    return;
+                };
+                let on_scroll = state(&block.lua).ok().and_then(|state| state.on_scroll);
+                if let Some(id) = on_scroll {
+                    block.call(id, true, direction);
+                }
+            }
+            LuaMsg::Popover(index, PopoverOutput::Action(action)) => {
+                let Some(block) = self.blocks.get_mut(index) else {
+                    return;
+                };
+                if let Some(id) = action.strip_prefix("lua-").and_then(|id| id.parse().ok()) {
+                    block.call(id, true, ());
+                }
+            }
+            LuaMsg::Popover(..) => {}
+        }
+    }
+}
+
+impl LuaWidgets {
+    fn connect_actions(button: &gtk::Button, index: usize, sender: &ComponentSender<Self>) {
+        let click_sender = sender.clone();
+        button.connect_clicked(move |_| click_sender.input(LuaMsg::Click(index)));
+
+        let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        let scroll_sender = sender.clone();
+        scroll.connect_scroll(move |controller, _, dy| {
+            if controller
+                .current_event_state()
+                .contains(gdk::ModifierType::SHIFT_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+            let direction = if dy < 0.0 { "up" } else { "down" };
+            if dy != 0.0 {
+                scroll_sender.input(LuaMsg::Scroll(index, direction));
+            }
+            glib::Propagation::Stop
+        });
+        button.add_controller(scroll);
+    }
+}
+
+/// Give the script of block `index` its `bar` table
+fn install_api(
+    lua: &Lua,
+    index: usize,
+    sandbox: &ScriptSandboxConfig,
+    sender: &ComponentSender<LuaWidgets>,
+) -> mlua::Result<()> {
+    let bar = lua.create_table()?;
+
+    bar.set(
+        "set_text",
+        lua.create_function(|lua, text: String| {
+            state(lua)?.text = text;
+            Ok(())
+        })?,
+    )?;
+    bar.set(
+        "set_icon",
+        lua.create_function(|lua, icon: String| {
+            state(lua)?.icon = icon;
+            Ok(())
+        })?,
+    )?;
+    bar.set(
+        "set_tooltip",
+        lua.create_function(|lua, tooltip: String| {
+            state(lua)?.tooltip = tooltip;
+            Ok(())
+        })?,
+    )?;
+    bar.set(
+        "set_css",
+        lua.create_function(|lua, classes: Vec<String>| {
+            state(lua)?.classes = classes;
+            Ok(())
+        })?,
+    )?;
+
+    let timer_sender = sender.clone();
+    bar.set(
+        "every",
+        lua.create_function(move |lua, (seconds, callback): (u32, Function)| {
+            let key = lua.create_registry_value(callback)?;
+            let id = state(lua)?.keep(key);
+            let sender = timer_sender.clone();
+            let timer = poll::every(seconds.max(1), move || {
+                sender.input(LuaMsg::Timer(index, id))
+            });
+            state(lua)?.timers.push(timer);
+            timer_sender.input(LuaMsg::Timer(index, id));
+            Ok(())
+        })?,
+    )?;
+
+    let run_sender = sender.clone();
+    let sandbox = sandbox.clone();
+    bar.set(
+        "run",
+        lua.create_function(move |lua, (command, callback): (String, Function)| {
+            let key = lua.create_registry_value(callback)?;
+            let id = state(lua)?.keep(key);
+            let sender = run_sender.clone();
+            let sandbox = sandbox.clone();
+            thread::spawn(move || {
+                let result = script::run(&command, &sandbox)
+                    .map(|output| output.stdout)
+                    .map_err(|e| e.to_string());
+                sender.input(LuaMsg::Done(index, id, result));
+            });
+            Ok(())
+        })?,
+    )?;
+
+    let fetch_sender = sender.clone();
+    bar.set(
+        "fetch",
+        lua.create_function(move |lua, (url, callback): (String, Function)| {
+            let key = lua.create_registry_value(callback)?;
+            let id = state(lua)?.keep(key);
+            let sender = fetch_sender.clone();
+            relm4::spawn(async move {
+                let result = http::get(&url).await;
+                sender.input(LuaMsg::Done(index, id, result));
+            });
+            Ok(())
+        })?,
+    )?;
+
+    bar.set(
+        "on_click",
+        lua.create_function(|lua, callback: Function| {
+            let key = lua.create_registry_value(callback)?;
+            let mut state = state(lua)?;
+            let id = state.keep(key);
+            if let Some(old) = state.on_click.replace(id) {
+                state.callbacks.remove(&old);
+            }
+            Ok(())
+        })?,
+    )?;
+    bar.set(
+        "on_scroll",
+        lua.create_function(|lua, callback: Function| {
+            let key = lua.create_registry_value(callback)?;
+            let mut state = state(lua)?;
+            let id = state.keep(key);
+            if let Some(old) = state.on_scroll.replace(id) {
+                state.callbacks.remove(&old);
+            }
+            Ok(())
+        })?,
+    )?;
+
+    bar.set(
+        "popover",
+        lua.create_function(|lua, items: Vec<Table>| {
+            {
+                let mut state = state(lua)?;
+                for id in std::mem::take(&mut state.popover_actions) {
+                    state.callbacks.remove(&id);
+                }
+            }
+            let mut actions = Vec::new();
+            let items = items
+                .iter()
+                .map(|item| popover_item(lua, item, &mut actions))
+                .collect::<mlua::Result<Vec<_>>>()?;
+
+            let mut state = state(lua)?;
+            state.popover_actions = actions;
+            state.popover = Some(items);
+            Ok(())
+        })?,
+    )?;
+
+    lua.globals().set("bar", bar)
+}
+
+/// Read one entry of `bar.popover`, keeping the callback of a button in
+/// `actions`
+fn popover_item(lua: &Lua, item: &Table, actions: &mut Vec<u64>) -> mlua::Result<PopoverItem> {
+    if item.get::<_, Option<bool>>("separator")?.unwrap_or(false) {
+        return Ok(PopoverItem::Separator);
+    }
+
+    let label = item.get::<_, Option<String>>("label")?.unwrap_or_default();
+    let css = item.get::<_, Option<String>>("css")?.unwrap_or_default();
+
+    if let Some(button) = item.get::<_, Option<String>>("button")? {
+        let action_id = match item.get::<_, Option<Function>>("on_click")? {
+            Some(callback) => {
+                let key = lua.create_registry_value(callback)?;
+                let id = state(lua)?.keep(key);
+                actions.push(id);
+                format!("lua-{}", id)
+            }
+            None => String::new(),
+        };
+        return Ok(PopoverItem::Button {
+            label: button,
+            icon: item.get("icon")?,
+            css,
+            action_id,
+        });
+    }
+
+    if let Some(fraction) = item.get::<_, Option<f64>>("progress")? {
+        return Ok(PopoverItem::Progress {
+            label,
+            fraction: fraction.clamp(0.0, 1.0),
+            css,
+        });
+    }
+
+    Ok(PopoverItem::DetailRow {
+        label,
+        value: item.get::<_, Option<String>>("value")?.unwrap_or_default(),
+        value_css: css,
+    })
+}
+
+impl Snapshot for LuaWidgets {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "blocks": self
+                .blocks
+                .iter()
+                .map(|block| {
+                    let text = state(&block.lua)
+                        .map(|state| state.text.clone())
+                        .unwrap_or_default();
+                    json!({
+                        "name": block.config.name,
+                        "text": text,
+                        "error": block.error,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        })
+    }
+}
@@ -1,18 +1,110 @@
 use gtk::prelude::*;
-use mpris_dbus::{PlaybackStatus, Player, PlayerFinder};
-use relm4::{gtk::Orientation, prelude::*};
+use mpris_dbus::{FindingError, PlaybackStatus, Player, PlayerFinder, TrackID};
+use relm4::{
+    gtk::{Orientation, gdk, glib},
+    prelude::*,
+};
+use serde_json::json;
+use std::time::Duration;
 
+use super::motion::{self, Slide};
+use super::poll::{self, Poll};
 use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::MediaPlayerConfig;
+use crate::services::audio::{self, AudioState};
+use crate::services::privacy;
+use crate::services::{bus, health};
 
 pub struct MediaPlayer {
     track_title: String,
     track_artist: String,
     track_album: String,
+    /// `mpris:artUrl` of the current track
+    art_url: Option<String>,
+    album_art: Option<gdk::Texture>,
     is_playing: bool,
-    player: Option<Player>,
+    /// Queued track after the current one, from the player's TrackList
+    next_track: Option<(TrackID, String)>,
+    track_id: Option<TrackID>,
+    /// Chapters of the current track, sorted by start
+    chapters: Vec<Chapter>,
+    /// Index into `chapters` of the one playing
+    current_chapter: Option<usize>,
+    player: Option<PlayerName>,
+    /// A lookup is running, so timer ticks don't pile up behind a slow bus
+    refreshing: bool,
+    /// Process owning the player's bus name
+    player_pid: Option<u32>,
+    audio: AudioState,
     popover: Controller<PopoverComponent>,
+    seek_step: Duration,
+    /// Position shown briefly after seeking
+    seek_readout: Option<String>,
+    /// Bumped per seek so only the latest readout timer hides the label
+    seek_serial: u64,
+    timers: Vec<Poll>,
 }
 
+/// A chapter marker parsed from the track's metadata
+#[derive(Debug, Clone)]
+struct Chapter {
+    start: Duration,
+    title: String,
+}
+
+/// Names of the active player
+///
+/// mpris players hold a connection that can't leave its thread, so the
+/// widget keeps these and looks the player up again for each control.
+#[derive(Debug, Clone)]
+pub struct PlayerName {
+    unique: String,
+    identity: String,
+    /// Player part of the well-known bus name, e.g. `spotify`
+    short: String,
+}
+
+/// What the active player is up to, read off the main loop
+#[derive(Debug)]
+pub struct PlayerState {
+    player: PlayerName,
+    /// Unless the metadata couldn't be read
+    track: Option<Track>,
+    /// Unless the playback status couldn't be read
+    is_playing: Option<bool>,
+    track_id: Option<TrackID>,
+    /// Only read for tracks with chapters
+    position: Option<Duration>,
+    next_track: Option<(TrackID, String)>,
+}
+
+#[derive(Debug)]
+struct Track {
+    title: String,
+    artist: String,
+    album: String,
+    art_url: Option<String>,
+    chapters: Vec<Chapter>,
+}
+
+/// Results of talking to the player, which blocks on D-Bus
+#[derive(Debug)]
+pub enum MediaPlayerCommand {
+    /// `None` when no player is running, boxed as the state is large
+    Refreshed(Option<Box<PlayerState>>),
+    /// Position readout after a seek, if it went through
+    Seeked(Option<String>),
+    /// A control was sent, or the player was gone
+    Controlled,
+}
+
+/// How long the position readout stays visible after a seek
+const SEEK_READOUT_DURATION: Duration = Duration::from_secs(2);
+
+/// Size of the cover art in the popover
+const ALBUM_ART_SIZE: i32 = 128;
+
 #[derive(Debug, Clone)]
 pub enum MediaPlayerMsg {
     UpdateTrack(String, String),
@@ -22,66 +114,103 @@ pub enum MediaPlayerMsg {
     Previous,
     Refresh,
     TogglePopover,
+    /// Shift+scroll; `true` seeks forwards
+    Seek(bool),
+    HideSeekReadout(u64),
+    /// Jump to the chapter after (`true`) or before the current one
+    SkipChapter(bool),
+    AudioChanged(AudioState),
+    /// Process id resolved for the player with this unique bus name
+    PlayerPid(String, u32),
+    PrivacyChanged,
 }
 
 #[relm4::component(pub)]
-impl SimpleComponent for MediaPlayer {
-    type Init = ();
+impl Component for MediaPlayer {
+    type Init = MediaPlayerConfig;
     type Input = MediaPlayerMsg;
     type Output = ();
+    type CommandOutput = MediaPlayerCommand;
 
     view! {
-        gtk::Box {
-            set_orientation: Orientation::Horizontal,
-            set_spacing: 8,
-            set_css_classes: &["media-player-widget", "widget"],
+        // Slides in when playback starts, with animations on
+        gtk::Revealer {
+            set_transition_type: gtk::RevealerTransitionType::SlideRight,
+            set_transition_duration: motion::duration(),
+            set_visible: false,
             #[watch]
-            set_visible: !model.track_title.is_empty(),
-
-            // Track info button (clickable)
-            #[name = "track_button"]
-            gtk::Button {
-                set_css_classes: &["media-info-button"],
-                connect_clicked => MediaPlayerMsg::TogglePopover,
+            slide: !model.track_title.is_empty(),
 
-                gtk::Label {
-                    #[watch]
-                    set_label: &model.track_title,
-                    set_css_classes: &["media-title"],
-                    set_ellipsize: gtk::pango::EllipsizeMode::End,
-                    set_max_width_chars: 30,
-                }
-            },
-
-            // Controls
             gtk::Box {
                 set_orientation: Orientation::Horizontal,
-                set_css_classes: &["media-controls"],
+                set_spacing: 8,
+                set_css_classes: &["media-player-widget", "widget"],
+                #[watch]
+                set_tooltip_text: model
+                    .next_track
+                    .as_ref()
+                    .map(|(_, title)| format!("Next: {}", privacy::redact(title)))
+                    .as_deref(),
 
+                // Track info button (clickable)
+                #[name = "track_button"]
                 gtk::Button {
-                    set_label: "⏮",
-                    set_css_classes: &["media-button"],
-                    connect_clicked => MediaPlayerMsg::Previous,
+                    set_css_classes: &["media-info-button"],
+                    connect_clicked => MediaPlayerMsg::TogglePopover,
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: privacy::redact(&model.track_title),
+                        set_css_classes: &["media-title"],
+                        set_ellipsize: gtk::pango::EllipsizeMode::End,
+                        set_max_width_chars: 30,
+                    }
                 },
 
-                gtk::Button {
+                gtk::Label {
+                    set_css_classes: &["media-seek-readout"],
                     #[watch]
-                    set_label: if model.is_playing { "⏸" } else { "⏵" },
-                    set_css_classes: &["media-button", "media-play-pause"],
-                    connect_clicked => MediaPlayerMsg::PlayPause,
+                    set_visible: model.seek_readout.is_some(),
+                    #[watch]
+                    set_label: model.seek_readout.as_deref().unwrap_or_default(),
                 },
 
-                gtk::Button {
-                    set_label: "⏭",
-                    set_css_classes: &["media-button"],
-                    connect_clicked => MediaPlayerMsg::Next,
+                // Controls
+                gtk::Box {
+                    set_orientation: Orientation::Horizontal,
+                    set_css_classes: &["media-controls"],
+
+                    gtk::Button {
+                        set_label: "⏮",
+                        update_property: &[gtk::accessible::Property::Label("Previous track")],
+                        set_css_classes: &["media-button"],
+                        connect_clicked => MediaPlayerMsg::Previous,
+                    },
+
+                    gtk::Button {
+                        #[watch]
+                        set_label: if model.is_playing { "⏸" } else { "⏵" },
+                        #[watch]
+                        update_property: &[gtk::accessible::Property::Label(
+                            if model.is_playing { "Pause" } else { "Play" },
+                        )],
+                        set_css_classes: &["media-button", "media-play-pause"],
+                        connect_clicked => MediaPlayerMsg::PlayPause,
+                    },
+
+                    gtk::Button {
+                        set_label: "⏭",
+                        update_property: &[gtk::accessible::Property::Label("Next track")],
+                        set_css_classes: &["media-button"],
+                        connect_clicked => MediaPlayerMsg::Next,
+                    }
                 }
             }
         }
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -93,19 +222,34 @@ impl SimpleComponent for MediaPlayer {
                 margin: 12,
                 spacing: 8,
                 width: Some(300),
+                ..Default::default()
             })
             .detach();
 
-        let model = MediaPlayer {
+        let mut model = MediaPlayer {
             track_title: String::new(),
             track_artist: String::new(),
             track_album: String::new(),
+            art_url: None,
+            album_art: None,
             is_playing: false,
+            next_track: None,
+            track_id: None,
+            chapters: Vec::new(),
+            current_chapter: None,
             player: None,
+            refreshing: false,
+            player_pid: None,
+            audio: AudioState::default(),
             popover,
+            seek_step: Duration::from_secs(config.seek_step_seconds),
+            seek_readout: None,
+            seek_serial: 0,
+            timers: Vec::new(),
         };
 
         let widgets = view_output!();
+        motion::hide_when_slid_out(&root);
 
         // Set parent widget after widgets are created
         model
@@ -113,20 +257,64 @@ impl SimpleComponent for MediaPlayer {
             .model()
             .set_parent(&widgets.track_button.clone().upcast::<gtk::Widget>());
 
+        // Shift+scroll seeks; plain scrolling is left to the bar
+        let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::BOTH_AXES);
+        let scroll_sender = sender.clone();
+        scroll.connect_scroll(move |controller, dx, dy| {
+            if !controller
+                .current_event_state()
+                .contains(gdk::ModifierType::SHIFT_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+            // Some devices turn Shift+wheel into horizontal scrolling
+            let delta = if dy != 0.0 { dy } else { dx };
+            if delta != 0.0 {
+                scroll_sender.input(MediaPlayerMsg::Seek(delta < 0.0));
+            }
+            glib::Propagation::Stop
+        });
+        root.add_controller(scroll);
+
         // Periodic refresh to detect player changes and track updates
         let sender_clone = sender.clone();
-        gtk::glib::timeout_add_seconds_local(2, move || {
-            sender_clone.input(MediaPlayerMsg::Refresh);
-            gtk::glib::ControlFlow::Continue
+        model.timers.push(poll::every(2, move || {
+            sender_clone.input(MediaPlayerMsg::Refresh)
+        }));
+        let retry_sender = sender.clone();
+        health::on_retry(health::MPRIS, move || {
+            poll::deliver(&retry_sender, MediaPlayerMsg::Refresh)
+        });
+
+        // Follow sink changes to show where the player outputs
+        let mut audio_rx = audio::subscribe();
+        let audio_sender = sender.clone();
+        relm4::spawn(async move {
+            while audio_rx.changed().await.is_ok() {
+                let state = audio_rx.borrow().clone();
+                if !poll::deliver(&audio_sender, MediaPlayerMsg::AudioChanged(state)) {
+                    break;
+                }
+            }
+        });
+
+        // Re-render when the privacy screen is toggled
+        let mut privacy_rx = privacy::subscribe();
+        let privacy_sender = sender.clone();
+        relm4::spawn(async move {
+            while privacy_rx.changed().await.is_ok() {
+                if !poll::deliver(&privacy_sender, MediaPlayerMsg::PrivacyChanged) {
+                    break;
+                }
+            }
         });
 
-        // Initial refresh
         sender.input(MediaPlayerMsg::Refresh);
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
         match msg {
             MediaPlayerMsg::UpdateTrack(title, artist) => {
                 self.track_title = title;
@@ -135,98 +323,228 @@ impl SimpleComponent for MediaPlayer {
             MediaPlayerMsg::UpdatePlaybackStatus(is_playing) => {
                 self.is_playing = is_playing;
             }
-            MediaPlayerMsg::PlayPause => {
-                if let Some(ref player) = self.player {
-                    let _ = player.checked_play_pause();
+            MediaPlayerMsg::PlayPause => self.control(&sender, |player| {
+                let _ = player.checked_play_pause();
+                MediaPlayerCommand::Controlled
+            }),
+            MediaPlayerMsg::Next => self.control(&sender, |player| {
+                let _ = player.checked_next();
+                MediaPlayerCommand::Controlled
+            }),
+            MediaPlayerMsg::Previous => self.control(&sender, |player| {
+                let _ = player.checked_previous();
+                MediaPlayerCommand::Controlled
+            }),
+            MediaPlayerMsg::Refresh => {
+                if self.refreshing {
+                    return;
                 }
+                self.refreshing = true;
+                let next_track = self.next_track.clone();
+                sender.spawn_oneshot_command(move || {
+                    MediaPlayerCommand::Refreshed(read_player(next_track).map(Box::new))
+                });
             }
-            MediaPlayerMsg::Next => {
-                if let Some(ref player) = self.player {
-                    let _ = player.checked_next();
+            MediaPlayerMsg::TogglePopover => {
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+            MediaPlayerMsg::Seek(forward) => {
+                let step = self.seek_step;
+                self.control(&sender, move |player| {
+                    MediaPlayerCommand::Seeked(seek(player, step, forward))
+                });
+            }
+            MediaPlayerMsg::HideSeekReadout(serial) => {
+                if serial == self.seek_serial {
+                    self.seek_readout = None;
                 }
             }
-            MediaPlayerMsg::Previous => {
-                if let Some(ref player) = self.player {
-                    let _ = player.checked_previous();
+            MediaPlayerMsg::SkipChapter(forward) => {
+                let (Some(track_id), Some(current)) = (self.track_id.clone(), self.current_chapter)
+                else {
+                    return;
+                };
+                let chapters = self.chapters.clone();
+                self.control(&sender, move |player| {
+                    skip_chapter(player, &track_id, &chapters, current, forward);
+                    MediaPlayerCommand::Controlled
+                });
+            }
+            MediaPlayerMsg::AudioChanged(state) => {
+                self.audio = state;
+                self.update_popover_content(&sender);
+            }
+            MediaPlayerMsg::PrivacyChanged => {
+                self.update_popover_content(&sender);
+            }
+            MediaPlayerMsg::PlayerPid(unique_name, pid) => {
+                let current = self.player.as_ref().map(|player| player.unique.as_str());
+                if current == Some(unique_name.as_str()) {
+                    self.player_pid = Some(pid);
+                    self.update_popover_content(&sender);
                 }
             }
-            MediaPlayerMsg::Refresh => {
-                self.refresh_player_state();
-                self.update_popover_content();
+        }
+    }
+
+    fn update_cmd(
+        &mut self,
+        command: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match command {
+            MediaPlayerCommand::Refreshed(state) => {
+                self.refreshing = false;
+                self.apply_player_state(state.map(|state| *state), &sender);
+                self.update_popover_content(&sender);
             }
-            MediaPlayerMsg::TogglePopover => {
-                self.popover.emit(PopoverMsg::Toggle);
+            MediaPlayerCommand::Seeked(readout) => {
+                let Some(readout) = readout else {
+                    return;
+                };
+                self.seek_readout = Some(readout);
+                self.seek_serial += 1;
+
+                let serial = self.seek_serial;
+                glib::timeout_add_local_once(SEEK_READOUT_DURATION, move || {
+                    sender.input(MediaPlayerMsg::HideSeekReadout(serial));
+                });
             }
+            MediaPlayerCommand::Controlled => sender.input(MediaPlayerMsg::Refresh),
         }
     }
 }
 
 impl MediaPlayer {
-    fn refresh_player_state(&mut self) {
-        // Try to find an active player
-        let player_finder = match PlayerFinder::new() {
-            Ok(finder) => finder,
-            Err(_) => return,
+    /// Show what the active player is up to, or clear the widget when no
+    /// player is running
+    fn apply_player_state(&mut self, state: Option<PlayerState>, sender: &ComponentSender<Self>) {
+        let Some(state) = state else {
+            self.player = None;
+            self.track_title = String::new();
+            self.track_artist = String::new();
+            self.track_album = String::new();
+            self.art_url = None;
+            self.album_art = None;
+            self.is_playing = false;
+            self.next_track = None;
+            self.track_id = None;
+            self.chapters.clear();
+            self.current_chapter = None;
+            return;
         };
 
-        // Get the first active player
-        let player = match player_finder.find_active() {
-            Ok(player) => player,
-            Err(_) => {
-                // No active player, clear state
-                self.player = None;
-                self.track_title = String::new();
-                self.track_artist = String::new();
-                self.track_album = String::new();
-                self.is_playing = false;
-                return;
+        if let Some(track) = state.track {
+            self.track_title = track.title;
+            self.track_artist = track.artist;
+            self.track_album = track.album;
+            self.chapters = track.chapters;
+
+            if track.art_url != self.art_url {
+                self.album_art = track.art_url.as_deref().and_then(load_album_art);
+                self.art_url = track.art_url;
             }
-        };
+        }
+        if let Some(is_playing) = state.is_playing {
+            self.is_playing = is_playing;
+        }
+        self.track_id = state.track_id;
+        self.next_track = state.next_track;
+        self.current_chapter = state.position.and_then(|position| {
+            self.chapters
+                .iter()
+                .rposition(|chapter| chapter.start <= position)
+        });
+
+        let changed = self
+            .player
+            .as_ref()
+            .is_none_or(|old| old.unique != state.player.unique);
+        if changed {
+            self.player_pid = None;
+            let unique_name = state.player.unique.clone();
+            let sender = sender.clone();
+            relm4::spawn(async move {
+                match player_pid(&unique_name).await {
+                    Ok(pid) => sender.input(MediaPlayerMsg::PlayerPid(unique_name, pid)),
+                    Err(e) => eprintln!("Failed to resolve media player process: {}", e),
+                }
+            });
+        }
 
-        // Update metadata
-        if let Ok(metadata) = player.get_metadata() {
-            let title = metadata
-                .title()
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| "Unknown Track".to_string());
+        self.player = Some(state.player);
+    }
 
-            let artist = metadata
-                .artists()
-                .and_then(|artists| artists.first().map(|s| s.to_string()))
-                .unwrap_or_else(|| "Unknown Artist".to_string());
+    /// Run `f` on the active player off the main loop
+    fn control(
+        &self,
+        sender: &ComponentSender<Self>,
+        f: impl FnOnce(&Player) -> MediaPlayerCommand + Send + 'static,
+    ) {
+        let Some(unique_name) = self.player.as_ref().map(|player| player.unique.clone()) else {
+            return;
+        };
+        sender.spawn_oneshot_command(move || {
+            with_player(&unique_name, f).unwrap_or(MediaPlayerCommand::Controlled)
+        });
+    }
 
-            let album = metadata
-                .album_name()
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| "Unknown Album".to_string());
+    /// Description of the sink the player is playing to
+    fn output_target(&self) -> Option<&str> {
+        let player = self.player.as_ref()?;
+        let names = [player.identity.as_str(), player.short.as_str()];
+        self.audio
+            .sink_for_app(self.player_pid, &names)
+            .map(|sink| sink.description.as_str())
+    }
 
-            self.track_title = title;
-            self.track_artist = artist;
-            self.track_album = album;
-        }
+    fn chapter_controls(index: usize, count: usize, sender: &ComponentSender<Self>) -> gtk::Box {
+        let controls = gtk::Box::new(Orientation::Horizontal, 8);
+        controls.set_css_classes(&["media-chapter-controls"]);
+        controls.set_homogeneous(true);
 
-        // Update playback status
-        if let Ok(status) = player.get_playback_status() {
-            self.is_playing = matches!(status, PlaybackStatus::Playing);
+        for (label, forward, sensitive) in [
+            ("⏮ Chapter", false, true),
+            ("Chapter ⏭", true, index + 1 < count),
+        ] {
+            let button = gtk::Button::with_label(label);
+            button.set_css_classes(&["media-chapter-button"]);
+            button.set_sensitive(sensitive);
+            let sender = sender.clone();
+            button.connect_clicked(move |_| {
+                sender.input(MediaPlayerMsg::SkipChapter(forward));
+            });
+            controls.append(&button);
         }
 
-        self.player = Some(player);
+        controls
     }
 
-    fn update_popover_content(&self) {
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
         // Update popover title with track name
-        self.popover.emit(PopoverMsg::UpdateTitle(self.track_title.clone()));
+        self.popover.emit(PopoverMsg::UpdateTitle(
+            privacy::redact(&self.track_title).to_string(),
+        ));
 
         // Build items list
-        let items = vec![
+        let mut items = Vec::new();
+        // Cover art gives the track away as much as its title
+        if let Some(texture) = self.album_art.as_ref().filter(|_| !privacy::is_enabled()) {
+            items.push(PopoverItem::Image {
+                texture: texture.clone(),
+                size: ALBUM_ART_SIZE,
+            });
+        }
+        items.extend([
             PopoverItem::DetailRow {
                 label: "Artist".to_string(),
-                value: self.track_artist.clone(),
+                value: privacy::redact(&self.track_artist).to_string(),
                 value_css: "media-detail".to_string(),
             },
             PopoverItem::DetailRow {
                 label: "Album".to_string(),
-                value: self.track_album.clone(),
+                value: privacy::redact(&self.track_album).to_string(),
                 value_css: "media-detail".to_string(),
             },
             PopoverItem::Separator,
@@ -235,8 +553,299 @@ impl MediaPlayer {
                 value: if self.is_playing { "Playing" } else { "Paused" }.to_string(),
                 value_css: "media-detail".to_string(),
             },
-        ];
+        ]);
+
+        // MPRIS has no field for cast targets, so only local sinks are known
+        if let Some(output) = self.output_target() {
+            items.push(PopoverItem::DetailRow {
+                label: "Output".to_string(),
+                value: output.to_string(),
+                value_css: "media-detail".to_string(),
+            });
+        }
+
+        if let Some(index) = self.current_chapter {
+            items.push(PopoverItem::DetailRow {
+                label: "Chapter".to_string(),
+                value: format!(
+                    "{}/{} · {}",
+                    index + 1,
+                    self.chapters.len(),
+                    privacy::redact(&self.chapters[index].title)
+                ),
+                value_css: "media-detail".to_string(),
+            });
+            items.push(PopoverItem::Custom(
+                Self::chapter_controls(index, self.chapters.len(), sender).upcast(),
+            ));
+        }
+
+        if let Some((_, title)) = &self.next_track {
+            items.push(PopoverItem::DetailRow {
+                label: "Next".to_string(),
+                value: privacy::redact(title).to_string(),
+                value_css: "media-detail".to_string(),
+            });
+        }
 
         self.popover.emit(PopoverMsg::UpdateItems(items));
     }
 }
+
+/// Find the active player and read what the widget shows, blocking on D-Bus
+///
+/// `next_track` is the queued track already known, refetched only when the
+/// queue moved.
+fn read_player(next_track: Option<(TrackID, String)>) -> Option<PlayerState> {
+    let player_finder = match PlayerFinder::new() {
+        Ok(finder) => finder,
+        Err(e) => {
+            health::report(health::MPRIS, e);
+            return None;
+        }
+    };
+
+    // Get the first active player
+    let player = match player_finder.find_active() {
+        Ok(player) => {
+            health::clear(health::MPRIS);
+            player
+        }
+        Err(e) => {
+            // No player running is fine, a broken bus isn't
+            match e {
+                FindingError::DBusError(e) => health::report(health::MPRIS, e),
+                _ => health::clear(health::MPRIS),
+            }
+            return None;
+        }
+    };
+
+    let metadata = player.get_metadata().ok();
+    let track = metadata.as_ref().map(|metadata| Track {
+        title: metadata
+            .title()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "Unknown Track".to_string()),
+        artist: metadata
+            .artists()
+            .and_then(|artists| artists.first().map(|s| s.to_string()))
+            .unwrap_or_else(|| "Unknown Artist".to_string()),
+        album: metadata
+            .album_name()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "Unknown Album".to_string()),
+        art_url: metadata.art_url().map(str::to_string),
+        chapters: parse_chapters(metadata),
+    });
+
+    let position = match &track {
+        Some(track) if !track.chapters.is_empty() => player.get_position().ok(),
+        _ => None,
+    };
+    let track_id = metadata.and_then(|metadata| metadata.track_id());
+
+    Some(PlayerState {
+        player: PlayerName {
+            unique: player.unique_name().to_string(),
+            identity: player.identity().to_string(),
+            short: player.bus_name_player_name_part().to_string(),
+        },
+        track,
+        is_playing: player
+            .get_playback_status()
+            .ok()
+            .map(|status| matches!(status, PlaybackStatus::Playing)),
+        next_track: read_next_track(&player, track_id.clone(), next_track),
+        track_id,
+        position,
+    })
+}
+
+/// The track queued after `current` in the player's TrackList
+fn read_next_track(
+    player: &Player,
+    current: Option<TrackID>,
+    known: Option<(TrackID, String)>,
+) -> Option<(TrackID, String)> {
+    let next_id = current.and_then(|current| {
+        let tracks = player.checked_get_track_list().ok()??;
+        let index = tracks.ids().iter().position(|id| *id == current)?;
+        tracks.get(index + 1).cloned()
+    })?;
+
+    // Only fetch metadata when the queue moved
+    if let Some(known) = known.filter(|(id, _)| *id == next_id) {
+        return Some(known);
+    }
+
+    player
+        .get_tracks_metadata(std::slice::from_ref(&next_id))
+        .ok()
+        .and_then(|tracks| tracks.into_iter().next())
+        .map(|metadata| {
+            let title = metadata.title().unwrap_or("Unknown Track").to_string();
+            (next_id, title)
+        })
+}
+
+/// Run `f` on the player with the unique bus name `unique_name`, `None` when
+/// it's gone
+fn with_player<T>(unique_name: &str, f: impl FnOnce(&Player) -> T) -> Option<T> {
+    let players = PlayerFinder::new().ok()?.find_all().ok()?;
+    let player = players
+        .iter()
+        .find(|player| player.unique_name() == unique_name)?;
+    Some(f(player))
+}
+
+/// Jump to the chapter after or before `current`
+fn skip_chapter(
+    player: &Player,
+    track_id: &TrackID,
+    chapters: &[Chapter],
+    current: usize,
+    forward: bool,
+) {
+    let target = if forward {
+        current + 1
+    } else {
+        // Like the previous-track button: restart the chapter unless
+        // it only just began
+        let started = chapters[current].start;
+        let position = player.get_position().unwrap_or_default();
+        if position.saturating_sub(started) > Duration::from_secs(3) {
+            current
+        } else {
+            current.saturating_sub(1)
+        }
+    };
+
+    let Some(chapter) = chapters.get(target) else {
+        return;
+    };
+    if let Err(e) = player.set_position(track_id.clone(), &chapter.start) {
+        eprintln!("Failed to skip chapter: {}", e);
+    }
+}
+
+/// Seek the player by one `step` and describe the new position
+fn seek(player: &Player, step: Duration, forward: bool) -> Option<String> {
+    let step = step.as_micros() as i64;
+    let offset = if forward { step } else { -step };
+
+    match player.checked_seek(offset) {
+        Ok(true) => {}
+        Ok(false) => return None,
+        Err(e) => {
+            eprintln!("Failed to seek: {}", e);
+            return None;
+        }
+    }
+
+    let position = format_position(player.get_position().ok()?);
+    let length = player
+        .get_metadata()
+        .ok()
+        .and_then(|metadata| metadata.length());
+    Some(match length {
+        Some(length) => format!("{} / {}", position, format_position(length)),
+        None => position,
+    })
+}
+
+async fn player_pid(unique_name: &str) -> zbus::Result<u32> {
+    let connection = bus::session().await?;
+    let proxy = zbus::fdo::DBusProxy::new(&connection).await?;
+    let name = zbus::names::BusName::try_from(unique_name)?;
+    Ok(proxy.get_connection_unix_process_id(name).await?)
+}
+
+/// Chapters listed one per line in `xesam:comment`, e.g. `12:30 Interview`
+///
+/// Players have no standard chapter field, but podcast and audiobook
+/// players commonly put the chapter list in the comment.
+fn parse_chapters(metadata: &mpris_dbus::Metadata) -> Vec<Chapter> {
+    let Some(comments) = metadata
+        .get("xesam:comment")
+        .and_then(|value| value.as_str_array())
+    else {
+        return Vec::new();
+    };
+
+    let mut chapters: Vec<Chapter> = comments
+        .iter()
+        .flat_map(|comment| comment.lines())
+        .filter_map(|line| {
+            let (timestamp, title) = line.trim().split_once(char::is_whitespace)?;
+            let title = title.trim_start_matches([' ', '-', '–']).trim();
+            if title.is_empty() {
+                return None;
+            }
+            Some(Chapter {
+                start: parse_timestamp(timestamp)?,
+                title: title.to_string(),
+            })
+        })
+        .collect();
+
+    // A single timestamp is more likely a mention than a chapter list
+    if chapters.len() < 2 {
+        return Vec::new();
+    }
+    chapters.sort_by_key(|chapter| chapter.start);
+    chapters
+}
+
+/// Load cover art from a `file://` URL, fetching remote art would block the
+/// main loop
+fn load_album_art(url: &str) -> Option<gdk::Texture> {
+    if !url.starts_with("file://") {
+        return None;
+    }
+    gdk::Texture::from_file(&gtk::gio::File::for_uri(url))
+        .map_err(|e| eprintln!("Failed to load album art {}: {}", url, e))
+        .ok()
+}
+
+/// `m:ss` or `h:mm:ss`
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let parts: Vec<&str> = timestamp.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+
+    let mut secs = 0u64;
+    for part in parts {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(Duration::from_secs(secs))
+}
+
+/// `m:ss`, or `h:mm:ss` for long tracks
+fn format_position(position: Duration) -> String {
+    let secs = position.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+impl Snapshot for MediaPlayer {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "title": self.track_title,
+            "artist": self.track_artist,
+            "album": self.track_album,
+            "art_url": self.art_url,
+            "is_playing": self.is_playing,
+            "next": self.next_track.as_ref().map(|(_, title)| title),
+            "output": self.output_target(),
+        })
+    }
+}
@@ -0,0 +1,155 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+use std::thread;
+
+use super::poll;
+use super::snapshot::Snapshot;
+use crate::config::MicrophoneConfig;
+use crate::services::audio::{self, AudioState};
+
+pub struct Microphone {
+    config: MicrophoneConfig,
+    /// Description of the default source, if there is one
+    source: Option<String>,
+    muted: bool,
+    /// Apps currently recording from a microphone
+    recording: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum MicrophoneMsg {
+    AudioChanged(AudioState),
+    ToggleMute,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Microphone {
+    type Init = MicrophoneConfig;
+    type Input = MicrophoneMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_visible: model.config.enabled,
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            gtk::Button {
+                set_css_classes: &["microphone-button"],
+                #[watch]
+                set_sensitive: model.source.is_some(),
+                #[watch]
+                set_label: if model.muted || model.source.is_none() { "󰍭" } else { "󰍬" },
+                update_property: &[gtk::accessible::Property::Label("Mute microphone")],
+                #[watch]
+                update_state: &[gtk::accessible::State::Pressed(if model.muted {
+                    gtk::AccessibleTristate::True
+                } else {
+                    gtk::AccessibleTristate::False
+                })],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+                connect_clicked => MicrophoneMsg::ToggleMute,
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Microphone {
+            config,
+            source: None,
+            muted: false,
+            recording: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        if model.config.enabled {
+            let mut audio_rx = audio::subscribe();
+            relm4::spawn(async move {
+                loop {
+                    let state = audio_rx.borrow_and_update().clone();
+                    if !poll::deliver(&sender, MicrophoneMsg::AudioChanged(state)) {
+                        break;
+                    }
+                    if audio_rx.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            MicrophoneMsg::AudioChanged(state) => {
+                let source = state.default_source();
+                self.source = source.map(|source| source.description.clone());
+                self.muted = source.is_some_and(|source| source.mute);
+                self.recording = state
+                    .recording_apps()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+            }
+            MicrophoneMsg::ToggleMute => {
+                let mute = !self.muted;
+                // pactl reports the change back through the audio service
+                thread::spawn(move || {
+                    if let Err(e) = audio::set_default_source_mute(mute) {
+                        eprintln!("Failed to toggle the microphone: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl Microphone {
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["microphone-widget", "widget"];
+        if self.muted {
+            classes.push("microphone-muted");
+        } else if !self.recording.is_empty() {
+            // Live and in use, the case worth noticing
+            classes.push("microphone-in-use");
+        }
+        classes
+    }
+
+    fn tooltip(&self) -> String {
+        let Some(source) = &self.source else {
+            return "No microphone".to_string();
+        };
+        let state = if self.muted { "muted" } else { "live" };
+        if self.recording.is_empty() {
+            format!("{} ({})", source, state)
+        } else {
+            format!(
+                "{} ({})\nRecording: {}",
+                source,
+                state,
+                self.recording.join(", ")
+            )
+        }
+    }
+}
+
+impl Snapshot for Microphone {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "source": self.source,
+            "muted": self.muted,
+            "recording": self.recording,
+        })
+    }
+}
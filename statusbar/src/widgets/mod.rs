@@ -1,22 +1,86 @@
 // Widget modules
+pub mod battery;
+pub mod bluetooth;
+pub mod break_reminder;
+pub mod brightness;
 pub mod clock;
-pub mod workspace;
-pub mod window_title;
+pub mod countdown;
+pub mod custom;
+pub mod diagnostics;
+pub mod disk_alert;
+pub mod email;
+pub mod failed_units;
+pub mod focus_timer;
+pub mod gamemode;
+pub mod input_method;
+pub mod keyboard_layout;
+pub mod lua;
 pub mod media_player;
+pub mod microphone;
+pub mod night_light;
+pub mod notification_center;
+pub mod privacy_indicator;
 pub mod resources;
-pub mod wifi;
-pub mod battery;
+pub mod screen_time;
+pub mod taskbar;
+pub mod ticker;
+pub mod time_tracking;
 pub mod tray;
+pub mod updates;
+pub mod volume;
+pub mod vpn;
+pub mod weather;
+pub mod wifi;
+pub mod window_title;
+pub mod workspace;
 
 // Popover component module
 pub mod popover;
 
+// Shared right-click menu, settings dialog, icon lookup, polling, transitions
+// and tooltips
+pub mod context_menu;
+pub mod icons;
+pub mod motion;
+pub mod poll;
+pub mod settings;
+pub mod snapshot;
+pub mod tooltip;
+
 // Re-exports
+pub use battery::Battery;
+pub use bluetooth::Bluetooth;
+pub use break_reminder::BreakReminder;
+pub use brightness::Brightness;
 pub use clock::Clock;
-pub use workspace::WorkspaceWidget;
-pub use window_title::WindowTitle;
+pub use context_menu::{ContextAction, ContextMenu, InputEvent};
+pub use countdown::Countdown;
+pub use custom::Custom;
+pub use diagnostics::Diagnostics;
+pub use disk_alert::DiskAlert;
+pub use email::Email;
+pub use failed_units::FailedUnits;
+pub use focus_timer::FocusTimer;
+pub use gamemode::GameMode;
+pub use input_method::InputMethod;
+pub use keyboard_layout::KeyboardLayout;
+pub use lua::LuaWidgets;
 pub use media_player::MediaPlayer;
+pub use microphone::Microphone;
+pub use night_light::NightLight;
+pub use notification_center::NotificationCenter;
+pub use privacy_indicator::PrivacyIndicator;
 pub use resources::Resources;
-pub use wifi::WiFi;
-pub use battery::Battery;
+pub use screen_time::ScreenTime;
+pub use snapshot::Snapshot;
+pub use taskbar::Taskbar;
+pub use ticker::Ticker;
+pub use time_tracking::TimeTracking;
 pub use tray::SystemTray;
+pub use updates::Updates;
+pub use volume::Volume;
+pub use vpn::Vpn;
+pub use weather::Weather;
+pub use wifi::WiFi;
+pub use window_title::WindowTitle;
+pub use workspace::WorkspaceWidget;
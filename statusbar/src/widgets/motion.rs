@@ -0,0 +1,99 @@
+// Opt-in transitions for widgets appearing and the bar hiding
+//
+// Revealers take the `[theme.animations]` duration, or none while animations
+// are off; GTK skips them by itself under reduce motion.
+use gtk::prelude::*;
+use relm4::gtk;
+use std::cell::Cell;
+
+use crate::config::{AnimationConfig, BarPosition};
+
+thread_local! {
+    /// Transition length in milliseconds, 0 while animations are off
+    static DURATION: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Take the animation settings, before any revealer is made
+pub fn configure(config: &AnimationConfig) {
+    let duration = if config.enabled {
+        config.duration_ms
+    } else {
+        0
+    };
+    DURATION.with(|cell| cell.set(duration));
+}
+
+pub fn enabled() -> bool {
+    duration() > 0
+}
+
+/// Transition length in milliseconds for revealers
+pub fn duration() -> u32 {
+    DURATION.with(Cell::get)
+}
+
+/// A revealer showing its child, see [`hide_when_slid_out`]
+pub fn revealer(transition: gtk::RevealerTransitionType) -> gtk::Revealer {
+    let revealer = gtk::Revealer::builder()
+        .transition_type(transition)
+        .transition_duration(duration())
+        .reveal_child(true)
+        .build();
+    hide_when_slid_out(&revealer);
+    revealer
+}
+
+/// Hide `revealer`, or the window it fills, once its child has slid out, so
+/// it takes no space
+pub fn hide_when_slid_out(revealer: &gtk::Revealer) {
+    revealer.connect_child_revealed_notify(|revealer| {
+        if revealer.is_child_revealed() || revealer.reveals_child() {
+            return;
+        }
+        match revealer.parent().and_downcast::<gtk::Window>() {
+            Some(window) => window.set_visible(false),
+            None => revealer.set_visible(false),
+        }
+    });
+}
+
+/// Slide the bar in from the screen edge it sits on
+pub fn bar_transition(position: BarPosition) -> gtk::RevealerTransitionType {
+    match position {
+        BarPosition::Top => gtk::RevealerTransitionType::SlideDown,
+        BarPosition::Bottom => gtk::RevealerTransitionType::SlideUp,
+        BarPosition::Left => gtk::RevealerTransitionType::SlideRight,
+        BarPosition::Right => gtk::RevealerTransitionType::SlideLeft,
+    }
+}
+
+/// Sliding in and out, for `#[watch]` in a view
+pub trait Slide {
+    fn slide(&self, shown: bool);
+}
+
+impl Slide for gtk::Revealer {
+    fn slide(&self, shown: bool) {
+        if shown == self.reveals_child() {
+            return;
+        }
+        // Made visible first, so the revealer is mapped and animates
+        if shown {
+            self.set_visible(true);
+        }
+        self.set_reveal_child(shown);
+    }
+}
+
+/// Show or hide a bar window, sliding its contents when they sit in a
+/// [`revealer`]
+pub fn set_window_visible(window: &gtk::Window, visible: bool) {
+    let Some(revealer) = window.child().and_downcast::<gtk::Revealer>() else {
+        window.set_visible(visible);
+        return;
+    };
+    if visible {
+        window.set_visible(true);
+    }
+    revealer.slide(visible);
+}
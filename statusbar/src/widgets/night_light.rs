@@ -0,0 +1,158 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll::{self, Poll};
+use super::snapshot::Snapshot;
+use crate::config::NightLightConfig;
+use crate::services::night_light;
+
+/// How often the filter process is checked for having exited
+const CHECK_INTERVAL_SECONDS: u32 = 5;
+
+pub struct NightLight {
+    config: NightLightConfig,
+    active: bool,
+    error: Option<String>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum NightLightMsg {
+    Toggle,
+    /// Notice a filter that exited on its own
+    CheckRunning,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for NightLight {
+    type Init = NightLightConfig;
+    type Input = NightLightMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_visible: model.config.enabled,
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            gtk::Button {
+                set_css_classes: &["night-light-button"],
+                #[watch]
+                set_label: if model.active { "󰖔" } else { "󰖙" },
+                update_property: &[gtk::accessible::Property::Label("Night light")],
+                #[watch]
+                update_state: &[gtk::accessible::State::Pressed(if model.active {
+                    gtk::AccessibleTristate::True
+                } else {
+                    gtk::AccessibleTristate::False
+                })],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+                connect_clicked => NightLightMsg::Toggle,
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = NightLight {
+            config,
+            active: false,
+            error: None,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        if !model.config.enabled {
+            // Disabling the widget from the menu also turns the filter off
+            night_light::stop();
+            return ComponentParts { model, widgets };
+        }
+
+        // A relaunch after a config change restarts the filter with new settings
+        if model.config.autostart || night_light::is_running() {
+            sender.input(NightLightMsg::Toggle);
+        }
+
+        model
+            .timers
+            .push(poll::every(CHECK_INTERVAL_SECONDS, move || {
+                sender.input(NightLightMsg::CheckRunning)
+            }));
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            NightLightMsg::Toggle => {
+                if self.active {
+                    night_light::stop();
+                    self.active = false;
+                    return;
+                }
+                match night_light::start(&self.config) {
+                    Ok(()) => {
+                        self.active = true;
+                        self.error = None;
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        self.error = Some(e);
+                    }
+                }
+            }
+            NightLightMsg::CheckRunning => {
+                if self.active && !night_light::is_running() {
+                    self.active = false;
+                    self.error = Some(format!("{} exited", self.config.backend.name()));
+                }
+            }
+        }
+    }
+}
+
+impl NightLight {
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["night-light-widget", "widget"];
+        if self.active {
+            classes.push("night-light-active");
+        }
+        classes
+    }
+
+    fn tooltip(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!("Night light: {}", error);
+        }
+        if !self.active {
+            return "Night light off".to_string();
+        }
+        let follows_sun = self.config.latitude.is_some() && self.config.longitude.is_some();
+        if follows_sun {
+            format!(
+                "Night light on ({}K after sunset)",
+                self.config.night_temperature
+            )
+        } else {
+            format!("Night light on ({}K)", self.config.night_temperature)
+        }
+    }
+}
+
+impl Snapshot for NightLight {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "active": self.active,
+            "backend": self.config.backend.name(),
+            "error": self.error,
+        })
+    }
+}
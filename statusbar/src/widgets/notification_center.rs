@@ -0,0 +1,542 @@
+use chrono::{DateTime, Local};
+use gtk::prelude::*;
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::NotificationsConfig;
+use crate::headless;
+use crate::services::notifications::{self, Notification};
+use crate::services::privacy;
+
+/// Gap between the bar and the banners, and between banners
+const BANNER_MARGIN: i32 = 8;
+const BANNER_WIDTH: i32 = 360;
+
+pub struct NotificationCenter {
+    config: NotificationsConfig,
+    history: Vec<Notification>,
+    /// Notifications newer than this count as unread
+    last_opened: DateTime<Local>,
+    popover: Controller<PopoverComponent>,
+    banners: Option<Banners>,
+    do_not_disturb: bool,
+}
+
+#[derive(Debug)]
+pub enum NotificationCenterMsg {
+    HistoryChanged(Vec<Notification>),
+    TogglePopover,
+    PrivacyChanged,
+    Dismiss(u32),
+    ClearAll,
+    /// Run an action of a notification, by key
+    Invoke(u32, String),
+    /// Close the banner of a notification, keeping it in the history
+    CloseBanner(u32),
+    BannerExpired(u32),
+    DoNotDisturbChanged(bool),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for NotificationCenter {
+    type Init = NotificationsConfig;
+    type Input = NotificationCenterMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["notification-center-widget", "widget"],
+            set_visible: model.config.enabled,
+            #[watch]
+            set_class_active: ("notification-center-unread", model.unread() > 0),
+
+            #[name = "bell_button"]
+            gtk::Button {
+                set_css_classes: &["notification-center-button"],
+                connect_clicked => NotificationCenterMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Notifications")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_css_classes: &["notification-center-icon"],
+                        #[watch]
+                        set_label: model.icon(),
+                    },
+
+                    gtk::Label {
+                        set_css_classes: &["notification-center-count"],
+                        #[watch]
+                        set_visible: model.unread() > 0,
+                        #[watch]
+                        set_label: &model.unread().to_string(),
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Notifications".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(BANNER_WIDTH),
+                scrollable: true,
+                max_height: Some(540),
+                ..Default::default()
+            })
+            .detach();
+
+        // Banners need a layer shell to sit on
+        let banners =
+            (config.daemon && !headless::is_enabled()).then(|| Banners::new(config.max_banners));
+
+        let model = NotificationCenter {
+            config,
+            history: Vec::new(),
+            last_opened: Local::now(),
+            popover,
+            banners,
+            do_not_disturb: notifications::is_do_not_disturb(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.bell_button.clone().upcast::<gtk::Widget>());
+
+        if !model.config.enabled && model.banners.is_none() {
+            return ComponentParts { model, widgets };
+        }
+
+        let mut privacy_rx = privacy::subscribe();
+        let privacy_sender = sender.clone();
+        relm4::spawn(async move {
+            while privacy_rx.changed().await.is_ok() {
+                if !poll::deliver(&privacy_sender, NotificationCenterMsg::PrivacyChanged) {
+                    break;
+                }
+            }
+        });
+
+        let mut do_not_disturb_rx = notifications::subscribe_do_not_disturb();
+        let do_not_disturb_sender = sender.clone();
+        relm4::spawn(async move {
+            while do_not_disturb_rx.changed().await.is_ok() {
+                let enabled = *do_not_disturb_rx.borrow();
+                if !poll::deliver(
+                    &do_not_disturb_sender,
+                    NotificationCenterMsg::DoNotDisturbChanged(enabled),
+                ) {
+                    break;
+                }
+            }
+        });
+
+        let mut notifications_rx = notifications::subscribe();
+        relm4::spawn(async move {
+            loop {
+                let history = notifications_rx.borrow_and_update().clone();
+                if !poll::deliver(&sender, NotificationCenterMsg::HistoryChanged(history)) {
+                    break;
+                }
+                if notifications_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            NotificationCenterMsg::HistoryChanged(history) => {
+                self.history = history;
+                // Another daemon may already own the name and show its own
+                if let Some(banners) = self
+                    .banners
+                    .as_mut()
+                    .filter(|_| notifications::is_serving())
+                {
+                    banners.sync(&self.history, &self.config, &sender);
+                }
+                self.update_popover_content(&sender);
+            }
+            NotificationCenterMsg::TogglePopover => {
+                self.last_opened = Local::now();
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+            NotificationCenterMsg::PrivacyChanged => {
+                self.update_popover_content(&sender);
+            }
+            NotificationCenterMsg::Dismiss(id) => notifications::dismiss(id),
+            NotificationCenterMsg::ClearAll => notifications::clear(),
+            NotificationCenterMsg::Invoke(id, key) => notifications::invoke_action(id, &key),
+            NotificationCenterMsg::CloseBanner(id) => {
+                if let Some(banners) = &mut self.banners {
+                    banners.remove(id);
+                }
+            }
+            NotificationCenterMsg::BannerExpired(id) => {
+                if self
+                    .banners
+                    .as_mut()
+                    .is_some_and(|banners| banners.remove(id))
+                {
+                    notifications::expire(id);
+                }
+            }
+            NotificationCenterMsg::DoNotDisturbChanged(enabled) => {
+                self.do_not_disturb = enabled;
+            }
+        }
+    }
+}
+
+impl NotificationCenter {
+    fn unread(&self) -> usize {
+        self.history
+            .iter()
+            .filter(|notification| notification.time > self.last_opened)
+            .count()
+    }
+
+    fn icon(&self) -> &'static str {
+        if self.do_not_disturb {
+            "󰂛"
+        } else if self.unread() > 0 {
+            "󰂚"
+        } else {
+            "󰂜"
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        let unread = match self.unread() {
+            0 => "No new notifications".to_string(),
+            1 => "1 new notification".to_string(),
+            count => format!("{} new notifications", count),
+        };
+        if self.do_not_disturb {
+            format!("{}\nDo not disturb", unread)
+        } else {
+            unread
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        let count = gtk::Label::builder()
+            .label(format!("{} in history", self.history.len()))
+            .css_classes(vec!["detail-label"])
+            .halign(gtk::Align::Start)
+            .hexpand(true)
+            .build();
+        let clear_button = gtk::Button::builder()
+            .label("Clear all")
+            .css_classes(vec!["notification-center-clear"])
+            .sensitive(!self.history.is_empty())
+            .build();
+        let clear_sender = sender.clone();
+        clear_button.connect_clicked(move |_| clear_sender.input(NotificationCenterMsg::ClearAll));
+        header.append(&count);
+        header.append(&clear_button);
+
+        let list = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        if self.history.is_empty() {
+            let empty = gtk::Label::builder()
+                .label("No notifications")
+                .css_classes(vec!["notification-center-empty"])
+                .build();
+            list.append(&empty);
+        }
+        for notification in &self.history {
+            list.append(&notification_card(notification, false, sender));
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(vec![
+            PopoverItem::Custom(header.upcast()),
+            PopoverItem::Separator,
+            PopoverItem::Custom(list.upcast()),
+        ]));
+    }
+}
+
+/// Card showing one notification, in the popover or as a banner
+///
+/// Clicking a card runs its default action. The close button dismisses a
+/// notification from the history but only hides a banner.
+fn notification_card(
+    notification: &Notification,
+    banner: bool,
+    sender: &ComponentSender<NotificationCenter>,
+) -> gtk::Box {
+    let id = notification.id;
+    let card = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(2)
+        .css_classes(vec!["notification-card"])
+        .build();
+    if notification.critical {
+        card.add_css_class("notification-critical");
+    }
+
+    let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    if !notification.app_icon.is_empty() {
+        let icon = if notification.app_icon.starts_with('/') {
+            gtk::Image::from_file(&notification.app_icon)
+        } else {
+            gtk::Image::from_icon_name(&notification.app_icon)
+        };
+        icon.set_pixel_size(16);
+        header.append(&icon);
+    }
+    let source = gtk::Label::builder()
+        .label(format!(
+            "{} · {}",
+            notification.app_name,
+            notification.time.format("%H:%M")
+        ))
+        .css_classes(vec!["notification-source"])
+        .halign(gtk::Align::Start)
+        .hexpand(true)
+        .build();
+    let close = gtk::Button::builder()
+        .label("×")
+        .css_classes(vec!["notification-close"])
+        .tooltip_text(if banner { "Hide" } else { "Dismiss" })
+        .build();
+    let close_sender = sender.clone();
+    close.connect_clicked(move |_| {
+        close_sender.input(if banner {
+            NotificationCenterMsg::CloseBanner(id)
+        } else {
+            NotificationCenterMsg::Dismiss(id)
+        });
+    });
+    header.append(&source);
+    header.append(&close);
+    card.append(&header);
+
+    let summary = gtk::Label::builder()
+        .label(privacy::redact(&notification.summary))
+        .css_classes(vec!["notification-summary"])
+        .halign(gtk::Align::Start)
+        .xalign(0.0)
+        .wrap(true)
+        .build();
+    card.append(&summary);
+    if !notification.body.is_empty() {
+        let body = gtk::Label::builder()
+            .label(privacy::redact(&notification.body))
+            .css_classes(vec!["notification-body"])
+            .halign(gtk::Align::Start)
+            .xalign(0.0)
+            .wrap(true)
+            .build();
+        card.append(&body);
+    }
+
+    let buttons: Vec<&(String, String)> = notification
+        .actions
+        .iter()
+        .filter(|(key, _)| key != "default")
+        .collect();
+    if !buttons.is_empty() {
+        let actions = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        for (key, label) in buttons {
+            let button = gtk::Button::builder()
+                .label(label)
+                .css_classes(vec!["notification-action"])
+                .hexpand(true)
+                .build();
+            let action_sender = sender.clone();
+            let key = key.clone();
+            button.connect_clicked(move |_| {
+                action_sender.input(NotificationCenterMsg::Invoke(id, key.clone()));
+            });
+            actions.append(&button);
+        }
+        card.append(&actions);
+    }
+
+    if notification.actions.iter().any(|(key, _)| key == "default") {
+        let click = gtk::GestureClick::new();
+        let click_sender = sender.clone();
+        click.connect_released(move |_, _, _, _| {
+            click_sender.input(NotificationCenterMsg::Invoke(id, "default".to_string()));
+        });
+        card.add_controller(click);
+    }
+
+    card
+}
+
+/// Layer-shell window stacking banners below the bar
+struct Banners {
+    window: gtk::Window,
+    list: gtk::Box,
+    /// Visible banners by notification id, oldest first
+    cards: Vec<(u32, gtk::Box)>,
+    /// Newest notification already shown, so each is bannered once
+    last_shown: DateTime<Local>,
+    max_banners: usize,
+}
+
+impl Banners {
+    fn new(max_banners: usize) -> Self {
+        let window = gtk::Window::new();
+        window.init_layer_shell();
+        window.set_layer(Layer::Overlay);
+        window.set_namespace(Some("statusbar-notifications"));
+        // Anchored to the top, the compositor places it below the bar's zone
+        window.set_anchor(Edge::Top, true);
+        window.set_anchor(Edge::Right, true);
+        window.set_margin(Edge::Top, BANNER_MARGIN);
+        window.set_margin(Edge::Right, BANNER_MARGIN);
+        window.set_css_classes(&["notification-banners"]);
+
+        let list = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(BANNER_MARGIN)
+            .width_request(BANNER_WIDTH)
+            .build();
+        window.set_child(Some(&list));
+
+        Self {
+            window,
+            list,
+            cards: Vec::new(),
+            last_shown: Local::now(),
+            max_banners,
+        }
+    }
+
+    /// Show banners for new notifications and drop those no longer listed
+    fn sync(
+        &mut self,
+        history: &[Notification],
+        config: &NotificationsConfig,
+        sender: &ComponentSender<NotificationCenter>,
+    ) {
+        let closed: Vec<u32> = self
+            .cards
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| !history.iter().any(|notification| notification.id == *id))
+            .collect();
+        for id in closed {
+            self.remove(id);
+        }
+
+        // History is newest first, banners stack oldest first
+        let fresh: Vec<&Notification> = history
+            .iter()
+            .filter(|notification| notification.time > self.last_shown)
+            .collect();
+        // Only critical notifications get through do not disturb
+        let do_not_disturb = notifications::is_do_not_disturb();
+        for notification in fresh.into_iter().rev() {
+            if !do_not_disturb || notification.critical {
+                self.show(notification, config, sender);
+            }
+        }
+        if let Some(newest) = history.first() {
+            self.last_shown = newest.time;
+        }
+    }
+
+    fn show(
+        &mut self,
+        notification: &Notification,
+        config: &NotificationsConfig,
+        sender: &ComponentSender<NotificationCenter>,
+    ) {
+        // A replaced notification takes over its old banner's place
+        let card = notification_card(notification, true, sender);
+        card.add_css_class("notification-banner");
+        match self.cards.iter().position(|(id, _)| *id == notification.id) {
+            Some(index) => {
+                let old = std::mem::replace(&mut self.cards[index].1, card.clone());
+                self.list.insert_child_after(&card, Some(&old));
+                self.list.remove(&old);
+            }
+            None => {
+                self.list.append(&card);
+                self.cards.push((notification.id, card));
+            }
+        }
+
+        while self.cards.len() > self.max_banners.max(1) {
+            let (_, oldest) = self.cards.remove(0);
+            self.list.remove(&oldest);
+        }
+
+        let timeout_ms = match notification.expire_timeout {
+            _ if notification.critical => None,
+            0 => None,
+            ms if ms > 0 => Some(ms as u64),
+            _ => Some(config.banner_seconds * 1000),
+        };
+        if let Some(ms) = timeout_ms {
+            let id = notification.id;
+            let expire_sender = sender.clone();
+            gtk::glib::timeout_add_local_once(std::time::Duration::from_millis(ms), move || {
+                expire_sender.input(NotificationCenterMsg::BannerExpired(id));
+            });
+        }
+
+        self.window.set_visible(true);
+    }
+
+    /// Hide a banner, returning whether it was still shown
+    fn remove(&mut self, id: u32) -> bool {
+        let Some(index) = self.cards.iter().position(|(card_id, _)| *card_id == id) else {
+            return false;
+        };
+        let (_, card) = self.cards.remove(index);
+        self.list.remove(&card);
+        if self.cards.is_empty() {
+            // An empty surface would still take input
+            self.window.set_visible(false);
+        }
+        true
+    }
+}
+
+impl Snapshot for NotificationCenter {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "daemon": notifications::is_serving(),
+            "count": self.history.len(),
+            "unread": self.unread(),
+            "do_not_disturb": self.do_not_disturb,
+            "banners": self.banners.as_ref().map_or(0, |banners| banners.cards.len()),
+            "history": self.history.iter().map(|notification| json!({
+                "id": notification.id,
+                "app_name": notification.app_name,
+                "summary": notification.summary,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
@@ -0,0 +1,104 @@
+// Polling timers that back off while nobody is looking at the bar
+use gtk::glib;
+use relm4::{Component, ComponentSender, gtk};
+use std::rc::Rc;
+
+use crate::services::power::{self, Activity};
+
+/// While the session is idle, only one tick in this many runs
+const IDLE_SLOWDOWN: u32 = 10;
+
+/// Timers and listeners set up by [`every`] or [`on_resume`], stopped when
+/// dropped
+///
+/// Widgets keep it in their model, so a relaunched widget takes its polling
+/// with it rather than leaving it firing at a component that is gone.
+#[must_use = "polling stops as soon as this is dropped"]
+pub struct Poll {
+    timer: Option<glib::SourceId>,
+    resume: glib::JoinHandle<()>,
+}
+
+impl Drop for Poll {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.remove();
+        }
+        self.resume.abort();
+    }
+}
+
+/// Call `tick` every `seconds`, less often while the session is idle and not
+/// at all while it sleeps or the bars are hidden
+///
+/// `tick` also runs right after a wake-up or the session turning active, so
+/// nothing stays stale until the next tick.
+pub fn every(seconds: u32, tick: impl Fn() + 'static) -> Poll {
+    let tick = Rc::new(tick);
+
+    let timer_tick = tick.clone();
+    let mut skipped = 0;
+    let timer = glib::timeout_add_seconds_local(seconds, move || {
+        match power::activity() {
+            Activity::Active => timer_tick(),
+            Activity::Idle => {
+                skipped += 1;
+                if skipped >= IDLE_SLOWDOWN {
+                    skipped = 0;
+                    timer_tick();
+                }
+            }
+            Activity::Paused => {}
+        }
+        glib::ControlFlow::Continue
+    });
+
+    let mut poll = on_resume(move || tick());
+    poll.timer = Some(timer);
+    poll
+}
+
+/// Call `f` whenever polling picks up again: on waking from suspend, even if
+/// the session is still idle, and when it turns active or the bars reappear
+pub fn on_resume(f: impl Fn() + 'static) -> Poll {
+    let mut power_rx = power::subscribe();
+    let resume = glib::spawn_future_local(async move {
+        let mut previous = power_rx.borrow_and_update().activity();
+        while power_rx.changed().await.is_ok() {
+            let activity = power_rx.borrow_and_update().activity();
+            if activity > previous {
+                f();
+            }
+            previous = activity;
+        }
+    });
+    Poll {
+        timer: None,
+        resume,
+    }
+}
+
+/// Pass `msg` to the component behind `sender`, false once it has shut down
+///
+/// Loops feeding a widget from a subscription or a thread stop on false, so
+/// they end with the widget instead of outliving a relaunch.
+pub fn deliver<C: Component>(sender: &ComponentSender<C>, msg: C::Input) -> bool {
+    sender.input_sender().send(msg).is_ok()
+}
+
+/// Call `f` once the bar has drawn its first frame
+///
+/// For widgets whose first update is slow, so they don't hold up the bar
+/// appearing; anything more urgent on the main loop, redraws included, goes
+/// first.
+pub fn after_first_frame(f: impl FnOnce() + 'static) {
+    glib::idle_add_local_full(glib::Priority::LOW, {
+        let mut f = Some(f);
+        move || {
+            if let Some(f) = f.take() {
+                f();
+            }
+            glib::ControlFlow::Break
+        }
+    });
+}
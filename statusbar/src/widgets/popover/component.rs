@@ -1,21 +1,49 @@
 use gtk::prelude::*;
+use gtk4_layer_shell::{Edge, KeyboardMode, LayerShell};
+use relm4::factory::FactoryVecDeque;
 use relm4::{gtk::Widget, prelude::*};
+use std::cell::Cell;
+use std::rc::Rc;
 
-use super::models::{PopoverInit, PopoverItem, PopoverMsg};
+use super::list::{self, ListRowItem};
+use super::models::{
+    ListRow, PopoverInit, PopoverItem, PopoverMsg, PopoverOutput, PopoverPosition,
+};
+
+/// Tallest a list item grows before it scrolls
+const LIST_MAX_HEIGHT: i32 = 400;
+
+/// Tallest scrollable content grows without a `max_height`
+const DEFAULT_MAX_HEIGHT: i32 = 480;
 
 /// Reusable popover component with reactive updates
 pub struct PopoverComponent {
     title: String,
     items: Vec<PopoverItem>,
+    /// Widget shown for each of `items`
+    widgets: Vec<gtk::Widget>,
     content_box: gtk::Box,
     root: gtk::Popover,
+    position: PopoverPosition,
+    /// Rows of the list item, kept across updates so they change in place
+    list: FactoryVecDeque<ListRowItem>,
+    list_window: gtk::ScrolledWindow,
+    /// All rows of the list item, before filtering
+    rows: Vec<ListRow>,
+    search_entry: gtk::SearchEntry,
+    query: String,
+    /// Kept to report button clicks from rendered items
+    sender: ComponentSender<Self>,
+    /// Set while items are re-rendered, so programmatic changes to sliders
+    /// and switches aren't reported as user input
+    suppress_output: Rc<Cell<bool>>,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for PopoverComponent {
     type Init = PopoverInit;
     type Input = PopoverMsg;
-    type Output = ();
+    type Output = PopoverOutput;
 
     view! {
         #[root]
@@ -25,7 +53,7 @@ impl SimpleComponent for PopoverComponent {
     fn init(
         init: Self::Init,
         root: Self::Root,
-        _sender: ComponentSender<Self>,
+        sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         // Create the popover content manually
         let main_box = gtk::Box::builder()
@@ -54,16 +82,84 @@ impl SimpleComponent for PopoverComponent {
             .orientation(gtk::Orientation::Vertical)
             .spacing(4)
             .build();
-        main_box.append(&content_box);
+        if init.scrollable {
+            let scrolled = gtk::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk::PolicyType::Never)
+                .propagate_natural_height(true)
+                .max_content_height(init.max_height.unwrap_or(DEFAULT_MAX_HEIGHT))
+                .child(&content_box)
+                .build();
+            main_box.append(&scrolled);
+        } else {
+            main_box.append(&content_box);
+        }
+
+        root.set_has_arrow(init.has_arrow);
+        // Screen readers announce the title when the popover opens
+        root.update_property(&[gtk::accessible::Property::Label(&init.title)]);
 
         // Set the main box as the popover child
         root.set_child(Some(&main_box));
 
+        let list_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(4)
+            .css_classes(vec!["popover-list"])
+            .build();
+        let list = FactoryVecDeque::builder()
+            .launch(list_box)
+            .forward(sender.output_sender(), PopoverOutput::Action);
+        let list_window = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(LIST_MAX_HEIGHT)
+            .child(list.widget())
+            .build();
+
+        let search_entry = gtk::SearchEntry::builder()
+            .css_classes(vec!["popover-search"])
+            .build();
+        let filter_sender = sender.clone();
+        search_entry.connect_search_changed(move |entry| {
+            filter_sender.input(PopoverMsg::Filter(entry.text().to_string()));
+        });
+
+        // Lets owners refresh only while their popover is visible
+        let opened_sender = sender.clone();
+        let opened_entry = search_entry.clone();
+        root.connect_show(move |popover| {
+            // The search entry needs the keyboard, which the bar normally
+            // doesn't take
+            if opened_entry.parent().is_some() {
+                set_keyboard_focusable(popover, true);
+                opened_entry.grab_focus();
+            }
+            let _ = opened_sender.output(PopoverOutput::Opened);
+        });
+        let closed_sender = sender.clone();
+        let closed_entry = search_entry.clone();
+        root.connect_closed(move |popover| {
+            if closed_entry.parent().is_some() {
+                set_keyboard_focusable(popover, false);
+                closed_entry.set_text("");
+            }
+            let _ = closed_sender.output(PopoverOutput::Closed);
+        });
+
         let model = PopoverComponent {
             title: init.title.clone(),
             items: Vec::new(),
+            widgets: Vec::new(),
             content_box: content_box.clone(),
             root: root.clone(),
+            position: init.position,
+            list,
+            list_window,
+            rows: Vec::new(),
+            search_entry,
+            query: String::new(),
+            sender,
+            suppress_output: Rc::new(Cell::new(false)),
         };
 
         let widgets = view_output!();
@@ -80,6 +176,8 @@ impl SimpleComponent for PopoverComponent {
         match msg {
             PopoverMsg::UpdateTitle(title) => {
                 self.title = title;
+                self.root
+                    .update_property(&[gtk::accessible::Property::Label(&self.title)]);
                 // Update title label if needed
                 if let Some(child) = self.root.child() {
                     if let Ok(main_box) = child.downcast::<gtk::Box>() {
@@ -92,51 +190,277 @@ impl SimpleComponent for PopoverComponent {
                 }
             }
             PopoverMsg::UpdateItems(items) => {
-                self.items = items;
-                self.render_items();
+                let rows = items.iter().find_map(|item| match item {
+                    PopoverItem::List(rows) => Some(rows),
+                    _ => None,
+                });
+                if let Some(rows) = rows {
+                    self.rows = rows.clone();
+                    self.filter_rows();
+                }
+                self.render_items(items);
             }
             PopoverMsg::Toggle => {
                 if self.root.is_visible() {
                     self.root.popdown();
                 } else {
+                    self.root.set_position(self.position_type());
                     self.root.popup();
                 }
             }
+            PopoverMsg::Filter(query) => {
+                self.query = query;
+                self.filter_rows();
+            }
         }
     }
 }
 
 impl PopoverComponent {
-    /// Render all items into the content box
-    fn render_items(&self) {
-        // Clear existing content
-        while let Some(child) = self.content_box.first_child() {
-            self.content_box.remove(&child);
+    /// Bring the content box in line with `items`, keeping widgets whose item
+    /// is unchanged and updating simple rows in place
+    fn render_items(&mut self, items: Vec<PopoverItem>) {
+        // Setting a slider or switch to its new value is not a user change
+        self.suppress_output.set(true);
+        let widgets: Vec<gtk::Widget> = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let old = self.items.get(index).zip(self.widgets.get(index));
+                match old {
+                    Some((old, widget))
+                        if old == item || Self::update_widget(widget, old, item) =>
+                    {
+                        widget.clone()
+                    }
+                    _ => self.create_widget(item),
+                }
+            })
+            .collect();
+        self.suppress_output.set(false);
+
+        let mut child = self.content_box.first_child();
+        while let Some(widget) = child {
+            child = widget.next_sibling();
+            if !widgets.contains(&widget) {
+                self.content_box.remove(&widget);
+            }
         }
 
-        // Add new items
-        for item in &self.items {
-            match item {
+        let mut previous: Option<&gtk::Widget> = None;
+        for widget in &widgets {
+            if widget.parent().as_ref() == Some(self.content_box.upcast_ref::<gtk::Widget>()) {
+                self.content_box.reorder_child_after(widget, previous);
+            } else {
+                self.content_box.insert_child_after(widget, previous);
+            }
+            previous = Some(widget);
+        }
+
+        let description = Self::describe(&items);
+        self.root
+            .update_property(&[gtk::accessible::Property::Description(&description)]);
+        self.items = items;
+        self.widgets = widgets;
+    }
+
+    /// Read the details and progress of `items` out as one sentence, which
+    /// screen readers announce after the title
+    fn describe(items: &[PopoverItem]) -> String {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                PopoverItem::DetailRow { label, value, .. } => Some(format!("{} {}", label, value)),
+                PopoverItem::Progress {
+                    label, fraction, ..
+                } => Some(format!(
+                    "{} {:.0}%",
+                    label,
+                    fraction.clamp(0.0, 1.0) * 100.0
+                )),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Show the list rows matching the search query
+    fn filter_rows(&mut self) {
+        let rows: Vec<ListRow> = self
+            .rows
+            .iter()
+            .filter(|row| list::matches(row, &self.query))
+            .cloned()
+            .collect();
+        list::update_rows(&mut self.list, &rows);
+    }
+
+    /// Update a widget rendered for `old` to show `new` instead, returning
+    /// false when it has to be rebuilt
+    fn update_widget(widget: &gtk::Widget, old: &PopoverItem, new: &PopoverItem) -> bool {
+        match (old, new) {
+            (
+                PopoverItem::DetailRow {
+                    value_css: old_css, ..
+                },
                 PopoverItem::DetailRow {
                     label,
                     value,
                     value_css,
-                } => {
-                    let row = Self::create_detail_row(label, value, value_css);
-                    self.content_box.append(&row);
-                }
-                PopoverItem::Separator => {
-                    let separator = gtk::Separator::builder()
-                        .orientation(gtk::Orientation::Horizontal)
-                        .margin_top(8)
-                        .margin_bottom(8)
-                        .build();
-                    self.content_box.append(&separator);
-                }
-                PopoverItem::Custom(widget) => {
-                    self.content_box.append(widget);
+                },
+            ) => Self::update_detail_row(widget, label, value, old_css, value_css),
+            (
+                PopoverItem::Progress { css: old_css, .. },
+                PopoverItem::Progress {
+                    label,
+                    fraction,
+                    css,
+                },
+            ) => {
+                let fraction = fraction.clamp(0.0, 1.0);
+                let (Some(row), Some(bar)) = (
+                    widget.first_child(),
+                    widget
+                        .last_child()
+                        .and_then(|bar| bar.downcast::<gtk::ProgressBar>().ok()),
+                ) else {
+                    return false;
+                };
+                let percent = format!("{:.0}%", fraction * 100.0);
+                if !Self::update_detail_row(&row, label, &percent, "", "") {
+                    return false;
                 }
+                bar.set_fraction(fraction);
+                widget.remove_css_class(old_css);
+                widget.add_css_class(css);
+                true
+            }
+            (
+                PopoverItem::Slider {
+                    id: old_id,
+                    min: old_min,
+                    max: old_max,
+                    ..
+                },
+                PopoverItem::Slider {
+                    id,
+                    min,
+                    max,
+                    value,
+                },
+            ) if old_id == id && old_min == min && old_max == max => {
+                let Some(slider) = widget.downcast_ref::<gtk::Scale>() else {
+                    return false;
+                };
+                slider.set_value(*value);
+                true
+            }
+            (PopoverItem::Switch { id: old_id, .. }, PopoverItem::Switch { id, label, state })
+                if old_id == id =>
+            {
+                let label_widget = widget
+                    .first_child()
+                    .and_then(|label| label.downcast::<gtk::Label>().ok());
+                let switch = widget
+                    .last_child()
+                    .and_then(|switch| switch.downcast::<gtk::Switch>().ok());
+                let (Some(label_widget), Some(switch)) = (label_widget, switch) else {
+                    return false;
+                };
+                label_widget.set_label(label);
+                switch.set_active(*state);
+                true
+            }
+            // Rows were already updated in place by the factory
+            (PopoverItem::List(_), PopoverItem::List(_)) => true,
+            (PopoverItem::SearchEntry { .. }, PopoverItem::SearchEntry { placeholder }) => {
+                let Some(entry) = widget.downcast_ref::<gtk::SearchEntry>() else {
+                    return false;
+                };
+                entry.set_property("placeholder-text", placeholder);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Update the texts and value class of a row from [`Self::create_detail_row`]
+    fn update_detail_row(
+        row: &gtk::Widget,
+        label: &str,
+        value: &str,
+        old_css: &str,
+        value_css: &str,
+    ) -> bool {
+        let label_widget = row
+            .first_child()
+            .and_then(|label| label.downcast::<gtk::Label>().ok());
+        let value_widget = row
+            .last_child()
+            .and_then(|value| value.downcast::<gtk::Label>().ok());
+        let (Some(label_widget), Some(value_widget)) = (label_widget, value_widget) else {
+            return false;
+        };
+        label_widget.set_label(label);
+        value_widget.set_label(value);
+        if old_css != value_css {
+            value_widget.remove_css_class(old_css);
+            value_widget.add_css_class(value_css);
+        }
+        true
+    }
+
+    /// Create the widget for a single item
+    fn create_widget(&self, item: &PopoverItem) -> gtk::Widget {
+        match item {
+            PopoverItem::DetailRow {
+                label,
+                value,
+                value_css,
+            } => Self::create_detail_row(label, value, value_css).upcast(),
+            PopoverItem::Button {
+                label,
+                icon,
+                css,
+                action_id,
+            } => self
+                .create_button(label, icon.as_deref(), css, action_id)
+                .upcast(),
+            PopoverItem::Progress {
+                label,
+                fraction,
+                css,
+            } => Self::create_progress(label, *fraction, css).upcast(),
+            PopoverItem::Image { texture, size } => gtk::Image::builder()
+                .paintable(texture)
+                .pixel_size(*size)
+                .halign(gtk::Align::Center)
+                .css_classes(vec!["popover-image"])
+                .build()
+                .upcast(),
+            PopoverItem::Slider {
+                id,
+                min,
+                max,
+                value,
+            } => self.create_slider(id, *min, *max, *value).upcast(),
+            PopoverItem::Switch { id, label, state } => {
+                self.create_switch(id, label, *state).upcast()
             }
+            PopoverItem::List(_) => self.list_window.clone().upcast(),
+            PopoverItem::SearchEntry { placeholder } => {
+                // The setter needs GTK 4.10, the property is older
+                self.search_entry
+                    .set_property("placeholder-text", placeholder);
+                self.search_entry.clone().upcast()
+            }
+            PopoverItem::Separator => gtk::Separator::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .margin_top(8)
+                .margin_bottom(8)
+                .build()
+                .upcast(),
+            PopoverItem::Custom(widget) => widget.clone(),
         }
     }
 
@@ -166,7 +490,173 @@ impl PopoverComponent {
         row
     }
 
+    /// Create a label and percentage above a progress bar
+    fn create_progress(label: &str, fraction: f64, css: &str) -> gtk::Box {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let progress = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(4)
+            .css_classes(vec!["popover-progress", css])
+            .build();
+
+        let row = Self::create_detail_row(
+            label,
+            &format!("{:.0}%", fraction * 100.0),
+            "progress-value",
+        );
+        let bar = gtk::ProgressBar::builder().fraction(fraction).build();
+
+        progress.append(&row);
+        progress.append(&bar);
+        progress
+    }
+
+    /// Create a button that reports its action id when clicked
+    fn create_button(
+        &self,
+        label: &str,
+        icon: Option<&str>,
+        css: &str,
+        action_id: &str,
+    ) -> gtk::Button {
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(8)
+            .build();
+
+        if let Some(icon) = icon {
+            content.append(&gtk::Image::from_icon_name(icon));
+        }
+
+        let label_widget = gtk::Label::builder()
+            .label(label)
+            .halign(gtk::Align::Start)
+            .build();
+        content.append(&label_widget);
+
+        let button = gtk::Button::builder()
+            .child(&content)
+            .css_classes(vec!["popover-button", css])
+            .build();
+        button.update_property(&[gtk::accessible::Property::Label(label)]);
+
+        let sender = self.sender.clone();
+        let action_id = action_id.to_string();
+        button.connect_clicked(move |_| {
+            // Nobody listens to popovers launched with `detach()`
+            let _ = sender.output(PopoverOutput::Action(action_id.clone()));
+        });
+        button
+    }
+
+    /// Create a slider that reports its value as it moves
+    fn create_slider(&self, id: &str, min: f64, max: f64, value: f64) -> gtk::Scale {
+        let slider = gtk::Scale::with_range(gtk::Orientation::Horizontal, min, max, 1.0);
+        slider.set_value(value);
+        slider.set_hexpand(true);
+        slider.set_css_classes(&["popover-slider"]);
+
+        let sender = self.sender.clone();
+        let suppress_output = self.suppress_output.clone();
+        let id = id.to_string();
+        slider.connect_value_changed(move |slider| {
+            if suppress_output.get() {
+                return;
+            }
+            let _ = sender.output(PopoverOutput::SliderChanged {
+                id: id.clone(),
+                value: slider.value(),
+            });
+        });
+        slider
+    }
+
+    /// Create a label and switch row that reports the new state when flipped
+    fn create_switch(&self, id: &str, label: &str, state: bool) -> gtk::Box {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(8)
+            .css_classes(vec!["detail-row"])
+            .build();
+
+        let label_widget = gtk::Label::builder()
+            .label(label)
+            .css_classes(vec!["detail-label"])
+            .halign(gtk::Align::Start)
+            .hexpand(true)
+            .build();
+
+        let switch = gtk::Switch::builder()
+            .active(state)
+            .valign(gtk::Align::Center)
+            .css_classes(vec!["popover-switch"])
+            .build();
+
+        let sender = self.sender.clone();
+        let suppress_output = self.suppress_output.clone();
+        let id = id.to_string();
+        switch.connect_active_notify(move |switch| {
+            if suppress_output.get() {
+                return;
+            }
+            let _ = sender.output(PopoverOutput::SwitchToggled {
+                id: id.clone(),
+                state: switch.is_active(),
+            });
+        });
+
+        row.append(&label_widget);
+        row.append(&switch);
+        row
+    }
+
+    /// Resolve [`PopoverPosition::Auto`] against the bar's anchors
+    fn position_type(&self) -> gtk::PositionType {
+        match self.position {
+            PopoverPosition::Top => gtk::PositionType::Top,
+            PopoverPosition::Bottom => gtk::PositionType::Bottom,
+            PopoverPosition::Auto => {
+                let window = self
+                    .root
+                    .parent()
+                    .and_then(|parent| parent.root())
+                    .and_then(|root| root.downcast::<gtk::Window>().ok());
+                let Some(window) = window else {
+                    return gtk::PositionType::Bottom;
+                };
+                // A bar is anchored to its own edge but never the opposite one
+                let anchored =
+                    |edge, opposite| window.is_anchor(edge) && !window.is_anchor(opposite);
+                if anchored(Edge::Bottom, Edge::Top) {
+                    gtk::PositionType::Top
+                } else if anchored(Edge::Left, Edge::Right) {
+                    gtk::PositionType::Right
+                } else if anchored(Edge::Right, Edge::Left) {
+                    gtk::PositionType::Left
+                } else {
+                    gtk::PositionType::Bottom
+                }
+            }
+        }
+    }
+
     pub fn set_parent(&self, parent: &impl IsA<Widget>) {
         self.root.set_parent(parent);
     }
 }
+
+/// Let the bar take keyboard input while a text entry is shown
+///
+/// The bar normally takes none, so typing keeps going to the focused window.
+pub fn set_keyboard_focusable(widget: &impl IsA<Widget>, focusable: bool) {
+    let window = widget
+        .root()
+        .and_then(|root| root.downcast::<gtk::Window>().ok());
+    if let Some(window) = window {
+        window.set_keyboard_mode(if focusable {
+            KeyboardMode::OnDemand
+        } else {
+            KeyboardMode::None
+        });
+    }
+}
@@ -0,0 +1,160 @@
+use gtk::prelude::*;
+use relm4::factory::FactoryVecDeque;
+use relm4::prelude::*;
+
+use super::models::ListRow;
+
+/// Row of a popover list, rebuilt only when its data changes
+pub struct ListRowItem {
+    row: ListRow,
+    badges: gtk::Box,
+}
+
+#[derive(Debug)]
+pub enum ListRowMsg {
+    Clicked,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for ListRowItem {
+    type Init = ListRow;
+    type Input = ListRowMsg;
+    /// Action id of the clicked row
+    type Output = String;
+    type CommandOutput = ();
+    type ParentWidget = gtk::Box;
+
+    view! {
+        #[root]
+        gtk::Button {
+            set_css_classes: &["list-row"],
+            // The icon is a glyph, so name the row by its text
+            #[watch]
+            update_property: &[gtk::accessible::Property::Label(&self.accessible_label())],
+            connect_clicked => ListRowMsg::Clicked,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 12,
+
+                gtk::Label {
+                    #[watch]
+                    set_visible: self.row.icon.is_some(),
+                    #[watch]
+                    set_label: self.row.icon.as_deref().unwrap_or_default(),
+                    set_css_classes: &["list-row-icon"],
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_valign: gtk::Align::Center,
+                    set_hexpand: true,
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &self.row.title,
+                        set_halign: gtk::Align::Start,
+                        set_ellipsize: gtk::pango::EllipsizeMode::End,
+                        set_css_classes: &["list-row-title"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_visible: self.row.subtitle.is_some(),
+                        #[watch]
+                        set_label: self.row.subtitle.as_deref().unwrap_or_default(),
+                        set_halign: gtk::Align::Start,
+                        set_ellipsize: gtk::pango::EllipsizeMode::End,
+                        set_css_classes: &["list-row-subtitle"],
+                    },
+                },
+
+                append: &self.badges,
+            }
+        }
+    }
+
+    fn init_model(row: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+        let item = ListRowItem {
+            row,
+            badges: gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .valign(gtk::Align::Center)
+                .spacing(4)
+                .build(),
+        };
+        item.render_badges();
+        item
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: FactorySender<Self>) {
+        match msg {
+            ListRowMsg::Clicked => {
+                let _ = sender.output(self.row.action_id.clone());
+            }
+        }
+    }
+}
+
+impl ListRowItem {
+    fn set_row(&mut self, row: ListRow) {
+        let badges_changed = row.badges != self.row.badges;
+        self.row = row;
+        if badges_changed {
+            self.render_badges();
+        }
+    }
+
+    fn accessible_label(&self) -> String {
+        std::iter::once(self.row.title.as_str())
+            .chain(self.row.subtitle.as_deref())
+            .chain(self.row.badges.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn render_badges(&self) {
+        while let Some(child) = self.badges.first_child() {
+            self.badges.remove(&child);
+        }
+        for badge in &self.row.badges {
+            let label = gtk::Label::builder()
+                .label(badge)
+                .css_classes(vec!["list-row-badge"])
+                .build();
+            self.badges.append(&label);
+        }
+    }
+}
+
+/// Whether the characters of `query` appear in order in the row's title or
+/// subtitle, ignoring case and spaces
+pub fn matches(row: &ListRow, query: &str) -> bool {
+    let fuzzy = |text: &str| {
+        let mut text = text.chars().flat_map(char::to_lowercase);
+        query
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .all(|c| text.any(|t| t == c))
+    };
+    fuzzy(&row.title) || row.subtitle.as_deref().is_some_and(fuzzy)
+}
+
+/// Bring the list in line with `rows`, touching only rows that changed
+pub fn update_rows(list: &mut FactoryVecDeque<ListRowItem>, rows: &[ListRow]) {
+    let mut guard = list.guard();
+    for (index, row) in rows.iter().enumerate() {
+        if index >= guard.len() {
+            guard.push_back(row.clone());
+        } else if guard.get(index).is_some_and(|item| item.row != *row) {
+            // Only rows borrowed mutably are re-rendered
+            if let Some(item) = guard.get_mut(index) {
+                item.set_row(row.clone());
+            }
+        }
+    }
+    while guard.len() > rows.len() {
+        guard.pop_back();
+    }
+}
@@ -1,5 +1,6 @@
 mod component;
+mod list;
 mod models;
 
-pub use component::PopoverComponent;
-pub use models::{PopoverInit, PopoverItem, PopoverMsg};
+pub use component::{PopoverComponent, set_keyboard_focusable};
+pub use models::{ListRow, PopoverInit, PopoverItem, PopoverMsg, PopoverOutput};
@@ -1,4 +1,4 @@
-use relm4::gtk;
+use relm4::gtk::{self, gdk};
 
 /// Initialization data for PopoverComponent
 #[derive(Clone)]
@@ -8,10 +8,42 @@ pub struct PopoverInit {
     pub margin: i32,
     pub spacing: i32,
     pub width: Option<i32>,
+    pub position: PopoverPosition,
+    pub has_arrow: bool,
+    /// Wrap the items in a ScrolledWindow instead of growing with them
+    pub scrollable: bool,
+    /// Tallest the items grow before scrolling, when `scrollable`
+    pub max_height: Option<i32>,
+}
+
+impl Default for PopoverInit {
+    fn default() -> Self {
+        Self {
+            parent: None,
+            title: String::new(),
+            margin: 12,
+            spacing: 8,
+            width: None,
+            position: PopoverPosition::Auto,
+            has_arrow: true,
+            scrollable: false,
+            max_height: None,
+        }
+    }
+}
+
+/// Side of the parent widget the popover opens on
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PopoverPosition {
+    Top,
+    Bottom,
+    /// Away from the screen edge the bar is anchored to
+    #[default]
+    Auto,
 }
 
 /// Items that can be displayed in the popover
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum PopoverItem {
     /// Two-column detail row with label and value
     DetailRow {
@@ -19,12 +51,60 @@ pub enum PopoverItem {
         value: String,
         value_css: String,
     },
+    /// Full-width button, reports `action_id` through [`PopoverOutput`] when
+    /// clicked
+    Button {
+        label: String,
+        /// Icon name from the icon theme, shown before the label
+        icon: Option<String>,
+        css: String,
+        action_id: String,
+    },
+    /// Labelled bar filled to `fraction`, between 0 and 1
+    Progress {
+        label: String,
+        fraction: f64,
+        css: String,
+    },
+    /// Centered image, `size` pixels wide and high
+    Image { texture: gdk::Texture, size: i32 },
+    /// Horizontal slider, reports new values through [`PopoverOutput`]
+    Slider {
+        id: String,
+        min: f64,
+        max: f64,
+        value: f64,
+    },
+    /// Labelled on/off switch, reports new states through [`PopoverOutput`]
+    Switch {
+        id: String,
+        label: String,
+        state: bool,
+    },
+    /// Scrollable list of clickable rows, updated in place as rows change.
+    /// A popover shows at most one list.
+    List(Vec<ListRow>),
+    /// Search field filtering the list item as you type
+    SearchEntry { placeholder: String },
     /// Horizontal separator line
     Separator,
     /// Custom widget for special cases (e.g., Resources config section)
     Custom(gtk::Widget),
 }
 
+/// Row of a [`PopoverItem::List`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListRow {
+    /// Glyph shown before the title
+    pub icon: Option<String>,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Short tags after the title, e.g. `Connected`
+    pub badges: Vec<String>,
+    /// Reported through [`PopoverOutput::Action`] when the row is clicked
+    pub action_id: String,
+}
+
 /// Messages for PopoverComponent
 #[derive(Debug, Clone)]
 pub enum PopoverMsg {
@@ -34,4 +114,22 @@ pub enum PopoverMsg {
     UpdateItems(Vec<PopoverItem>),
     /// Toggle popover visibility
     Toggle,
+    /// Show only list rows fuzzily matching the query, sent by the search
+    /// entry item
+    Filter(String),
+}
+
+/// Output messages from PopoverComponent
+#[derive(Debug, Clone)]
+pub enum PopoverOutput {
+    /// A button item was clicked, with its action id
+    Action(String),
+    /// A slider item was moved
+    SliderChanged { id: String, value: f64 },
+    /// A switch item was flipped
+    SwitchToggled { id: String, state: bool },
+    /// The popover was shown
+    Opened,
+    /// The popover was hidden
+    Closed,
 }
@@ -0,0 +1,190 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::PrivacyConfig;
+use crate::services::privacy_indicator::{self, CaptureState};
+
+pub struct PrivacyIndicator {
+    config: PrivacyConfig,
+    capture: CaptureState,
+    popover: Controller<PopoverComponent>,
+}
+
+#[derive(Debug)]
+pub enum PrivacyIndicatorMsg {
+    CaptureChanged(CaptureState),
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for PrivacyIndicator {
+    type Init = PrivacyConfig;
+    type Input = PrivacyIndicatorMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["privacy-indicator-widget", "widget"],
+            #[watch]
+            set_visible: model.config.enabled && model.capture.is_capturing(),
+
+            #[name = "privacy_button"]
+            gtk::Button {
+                set_css_classes: &["privacy-indicator-button"],
+                connect_clicked => PrivacyIndicatorMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Privacy")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰄀",
+                        set_css_classes: &["privacy-indicator-icon", "privacy-camera"],
+                        #[watch]
+                        set_visible: !model.capture.camera.is_empty(),
+                    },
+
+                    gtk::Label {
+                        set_label: "󰍬",
+                        set_css_classes: &["privacy-indicator-icon", "privacy-microphone"],
+                        #[watch]
+                        set_visible: !model.capture.microphone.is_empty(),
+                    },
+
+                    gtk::Label {
+                        set_label: "󰍹",
+                        set_css_classes: &["privacy-indicator-icon", "privacy-screen"],
+                        #[watch]
+                        set_visible: model.capture.is_sharing_screen(),
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Privacy".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(260),
+                ..Default::default()
+            })
+            .detach();
+
+        let model = PrivacyIndicator {
+            config,
+            capture: CaptureState::default(),
+            popover,
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.privacy_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let mut capture_rx = privacy_indicator::subscribe();
+            relm4::spawn(async move {
+                loop {
+                    let state = capture_rx.borrow_and_update().clone();
+                    if !poll::deliver(&sender, PrivacyIndicatorMsg::CaptureChanged(state)) {
+                        break;
+                    }
+                    if capture_rx.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            PrivacyIndicatorMsg::CaptureChanged(state) => {
+                self.capture = state;
+                self.update_popover_content();
+            }
+            PrivacyIndicatorMsg::TogglePopover => {
+                self.update_popover_content();
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl PrivacyIndicator {
+    /// What is captured, each with the apps doing it
+    fn captures(&self) -> [(&'static str, &[String]); 3] {
+        [
+            ("Camera", &self.capture.camera),
+            ("Microphone", &self.capture.microphone),
+            ("Screen", &self.capture.screen),
+        ]
+    }
+
+    fn tooltip(&self) -> String {
+        self.captures()
+            .into_iter()
+            .filter(|(_, apps)| !apps.is_empty())
+            .map(|(kind, apps)| format!("{}: {}", kind, apps.join(", ")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn update_popover_content(&self) {
+        let mut items = Vec::new();
+        for (kind, apps) in self.captures() {
+            if apps.is_empty() {
+                continue;
+            }
+            if !items.is_empty() {
+                items.push(PopoverItem::Separator);
+            }
+            for app in apps {
+                items.push(PopoverItem::DetailRow {
+                    label: kind.to_string(),
+                    value: app.clone(),
+                    value_css: "privacy-indicator-app".to_string(),
+                });
+            }
+        }
+        if items.is_empty() {
+            items.push(PopoverItem::DetailRow {
+                label: "Nothing is being captured".to_string(),
+                value: String::new(),
+                value_css: "privacy-indicator-app".to_string(),
+            });
+        }
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for PrivacyIndicator {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "camera": self.capture.camera,
+            "microphone": self.capture.microphone,
+            "screen": self.capture.screen,
+        })
+    }
+}
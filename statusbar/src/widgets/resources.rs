@@ -1,8 +1,61 @@
+use gtk::glib;
 use gtk::prelude::*;
+use procfs::{CpuPressure, Current, IoPressure, MemoryPressure, PressureRecord};
 use relm4::prelude::*;
+use serde_json::json;
+use std::time::Instant;
 use sysinfo::{Components, Disks, Networks, System};
 
-use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg, PopoverOutput};
+use super::snapshot::Snapshot;
+use crate::config::ResourcesConfig;
+
+/// CPU/MEM refresh interval for the bar labels
+const SUMMARY_INTERVAL_SECS: u32 = 2;
+/// Full refresh interval while the popover is open
+const DETAIL_INTERVAL_SECS: u32 = 1;
+
+/// PSI "some"/"full" stall percentages (10 second average) for one resource
+#[derive(Debug, Clone)]
+struct PressureStat {
+    resource: &'static str,
+    some: f32,
+    full: f32,
+}
+
+impl PressureStat {
+    fn new(resource: &'static str, some: &PressureRecord, full: &PressureRecord) -> Self {
+        Self {
+            resource,
+            some: some.avg10,
+            full: full.avg10,
+        }
+    }
+}
+
+/// sysinfo's handles on the system, slow to set up so loaded in the background
+#[derive(Debug)]
+pub struct Probes {
+    system: System,
+    networks: Networks,
+    components: Components,
+    disks: Disks,
+}
+
+impl Probes {
+    fn load() -> Self {
+        let mut system = System::new_all();
+        system.refresh_all();
+
+        Self {
+            system,
+            networks: Networks::new_with_refreshed_list(),
+            components: Components::new_with_refreshed_list(),
+            disks: Disks::new_with_refreshed_list(),
+        }
+    }
+}
 
 pub struct Resources {
     cpu_usage: f32,
@@ -12,30 +65,38 @@ pub struct Resources {
     memory_total: u64,
     memory_label: String,
     memory_label_widget: gtk::Label,
-    network_rx: u64,
-    network_tx: u64,
+    /// Bytes per second, None until two samples were taken
+    network_rx: Option<u64>,
+    network_tx: Option<u64>,
+    /// When the network counters were last read, None while the popover
+    /// is closed so the traffic in between isn't counted
+    network_sampled: Option<Instant>,
     disk_read: u64,
     disk_write: u64,
     gpu_usage: f32,
     temperatures: Vec<(String, f32)>,
-    system: System,
-    networks: Networks,
-    components: Components,
-    disks: Disks,
+    pressure: Vec<PressureStat>,
+    /// None until loaded
+    probes: Option<Probes>,
     popover: Controller<PopoverComponent>,
+    detail_timer: Option<glib::SourceId>,
+    timers: Vec<Poll>,
 }
 
 #[derive(Debug, Clone)]
 pub enum ResourcesMsg {
     Update,
+    UpdateDetails,
     TogglePopover,
+    Popover(PopoverOutput),
 }
 
 #[relm4::component(pub)]
-impl SimpleComponent for Resources {
-    type Init = ();
+impl Component for Resources {
+    type Init = ResourcesConfig;
     type Input = ResourcesMsg;
     type Output = ();
+    type CommandOutput = Probes;
 
     view! {
         gtk::Box {
@@ -51,8 +112,9 @@ impl SimpleComponent for Resources {
                     set_orientation: gtk::Orientation::Horizontal,
                     set_spacing: 8,
 
-                    // CPU monitor (always visible)
+                    // CPU monitor
                     gtk::Box {
+                        set_visible: config.show_cpu,
                         set_orientation: gtk::Orientation::Vertical,
                         set_spacing: 0,
                         set_css_classes: &["resource-item"],
@@ -69,8 +131,9 @@ impl SimpleComponent for Resources {
                         }
                     },
 
-                    // Memory monitor (always visible)
+                    // Memory monitor
                     gtk::Box {
+                        set_visible: config.show_memory,
                         set_orientation: gtk::Orientation::Vertical,
                         set_spacing: 0,
                         set_css_classes: &["resource-item"],
@@ -92,17 +155,10 @@ impl SimpleComponent for Resources {
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let mut system = System::new_all();
-        system.refresh_all();
-
-        let networks = Networks::new_with_refreshed_list();
-        let components = Components::new_with_refreshed_list();
-        let disks = Disks::new_with_refreshed_list();
-
         let widgets = view_output!();
 
         // Initialize the popover component
@@ -113,92 +169,168 @@ impl SimpleComponent for Resources {
                 margin: 16,
                 spacing: 12,
                 width: Some(350),
+                ..Default::default()
             })
-            .detach();
+            .forward(sender.input_sender(), ResourcesMsg::Popover);
 
-        let model = Resources {
+        let mut model = Resources {
             cpu_usage: 0.0,
             cpu_label: "0%".to_string(),
             cpu_label_widget: widgets.cpu_label_widget.clone(),
             memory_used: 0,
-            memory_total: system.total_memory(),
+            memory_total: 0,
             memory_label: "0.0G (0%)".to_string(),
             memory_label_widget: widgets.memory_label_widget.clone(),
-            network_rx: 0,
-            network_tx: 0,
+            network_rx: None,
+            network_tx: None,
+            network_sampled: None,
             disk_read: 0,
             disk_write: 0,
             gpu_usage: 0.0,
             temperatures: Vec::new(),
-            system,
-            networks,
-            components,
-            disks,
+            pressure: Vec::new(),
+            probes: None,
             popover,
+            detail_timer: None,
+            timers: Vec::new(),
         };
 
-        // Update every 2 seconds
+        // Update the bar labels every 2 seconds
         let sender_clone = sender.clone();
-        gtk::glib::timeout_add_seconds_local(2, move || {
-            sender_clone.input(ResourcesMsg::Update);
-            gtk::glib::ControlFlow::Continue
-        });
+        model
+            .timers
+            .push(poll::every(SUMMARY_INTERVAL_SECS, move || {
+                sender_clone.input(ResourcesMsg::Update)
+            }));
 
-        // Initial update
-        sender.input(ResourcesMsg::Update);
+        // The first update follows once sysinfo has looked around
+        sender.spawn_oneshot_command(Probes::load);
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
         match msg {
+            // Nothing to show until the probes are loaded
+            ResourcesMsg::Update | ResourcesMsg::UpdateDetails if self.probes.is_none() => {}
             ResourcesMsg::Update => {
-                self.refresh_stats();
+                // The detail timer already refreshes everything while open
+                if self.detail_timer.is_none() {
+                    self.refresh_summary();
+                    self.update_labels();
+                }
+            }
+            ResourcesMsg::UpdateDetails => {
+                self.refresh_summary();
+                self.refresh_details();
+                self.update_labels();
                 self.update_popover_content();
-
-                // Manually update the labels
-                self.cpu_label_widget.set_label(&self.cpu_label);
-                self.memory_label_widget.set_label(&self.memory_label);
             }
             ResourcesMsg::TogglePopover => {
                 self.popover.emit(PopoverMsg::Toggle);
             }
+            // Only sample temperatures, disks and network while the popover is visible
+            ResourcesMsg::Popover(PopoverOutput::Opened) => {
+                if self.detail_timer.is_none() {
+                    sender.input(ResourcesMsg::UpdateDetails);
+
+                    let sender_clone = sender.clone();
+                    self.detail_timer = Some(glib::timeout_add_seconds_local(
+                        DETAIL_INTERVAL_SECS,
+                        move || {
+                            sender_clone.input(ResourcesMsg::UpdateDetails);
+                            glib::ControlFlow::Continue
+                        },
+                    ));
+                }
+            }
+            ResourcesMsg::Popover(PopoverOutput::Closed) => {
+                if let Some(timer) = self.detail_timer.take() {
+                    timer.remove();
+                }
+                self.network_sampled = None;
+                self.network_rx = None;
+                self.network_tx = None;
+            }
+            ResourcesMsg::Popover(_) => {}
+        }
+    }
+
+    fn update_cmd(
+        &mut self,
+        probes: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        self.probes = Some(probes);
+        match self.detail_timer {
+            Some(_) => sender.input(ResourcesMsg::UpdateDetails),
+            None => sender.input(ResourcesMsg::Update),
+        }
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if let Some(timer) = self.detail_timer.take() {
+            timer.remove();
         }
     }
 }
 
 impl Resources {
-    fn refresh_stats(&mut self) {
+    fn update_labels(&self) {
+        // Manually update the labels
+        self.cpu_label_widget.set_label(&self.cpu_label);
+        self.memory_label_widget.set_label(&self.memory_label);
+    }
+
+    /// Refresh the CPU and memory figures shown in the bar
+    fn refresh_summary(&mut self) {
+        let Some(probes) = self.probes.as_mut() else {
+            return;
+        };
+
         // Refresh CPU and memory
-        self.system.refresh_cpu_all();
-        self.system.refresh_memory();
+        probes.system.refresh_cpu_all();
+        probes.system.refresh_memory();
 
         // Calculate average CPU usage
-        self.cpu_usage = self.system.global_cpu_usage();
+        self.cpu_usage = probes.system.global_cpu_usage();
         self.cpu_label = format!("{:.0}%", self.cpu_usage);
 
         // Get memory usage
-        self.memory_used = self.system.used_memory();
-        self.memory_total = self.system.total_memory();
+        self.memory_used = probes.system.used_memory();
+        self.memory_total = probes.system.total_memory();
         self.memory_label = Self::format_memory_compact(self.memory_used);
+    }
+
+    /// Refresh the popover-only figures (network, disks, sensors)
+    fn refresh_details(&mut self) {
+        let Some(probes) = self.probes.as_mut() else {
+            return;
+        };
 
         // Refresh network stats
-        self.networks.refresh(false);
+        let now = Instant::now();
+        probes.networks.refresh(false);
 
-        // Calculate total network traffic
+        // Calculate total network traffic since the last refresh
         let mut total_rx = 0;
         let mut total_tx = 0;
 
-        for (_interface_name, network) in self.networks.iter() {
+        for (_interface_name, network) in probes.networks.iter() {
             total_rx += network.received();
             total_tx += network.transmitted();
         }
 
-        self.network_rx = total_rx;
-        self.network_tx = total_tx;
+        // The first sample only sets the starting point
+        if let Some(previous) = self.network_sampled.replace(now) {
+            let seconds = now.duration_since(previous).as_secs_f64().max(f64::EPSILON);
+            self.network_rx = Some((total_rx as f64 / seconds) as u64);
+            self.network_tx = Some((total_tx as f64 / seconds) as u64);
+        }
 
         // Refresh disk stats
-        self.disks.refresh(true);
+        probes.disks.refresh(true);
 
         // Note: sysinfo doesn't directly provide disk I/O rates, these would be cumulative
         // For now, we'll show placeholder values
@@ -206,15 +338,30 @@ impl Resources {
         self.disk_write = 0;
 
         // Refresh temperature sensors
-        self.components.refresh(true);
+        probes.components.refresh(true);
         self.temperatures.clear();
-        for component in self.components.iter() {
+        for component in probes.components.iter() {
             if let Some(temp) = component.temperature() {
                 self.temperatures
                     .push((component.label().to_string(), temp));
             }
         }
 
+        // Refresh pressure stall information (missing on kernels without PSI)
+        self.pressure.clear();
+        if let Ok(cpu) = CpuPressure::current() {
+            self.pressure
+                .push(PressureStat::new("CPU", &cpu.some, &cpu.full));
+        }
+        if let Ok(memory) = MemoryPressure::current() {
+            self.pressure
+                .push(PressureStat::new("Memory", &memory.some, &memory.full));
+        }
+        if let Ok(io) = IoPressure::current() {
+            self.pressure
+                .push(PressureStat::new("IO", &io.some, &io.full));
+        }
+
         // GPU usage would require external tools/libraries
         self.gpu_usage = 0.0;
     }
@@ -230,48 +377,58 @@ impl Resources {
         });
 
         // Memory details
-        let mem_percent = (self.memory_used as f64 / self.memory_total as f64) * 100.0;
-        items.push(PopoverItem::DetailRow {
-            label: "Memory".to_string(),
-            value: format!(
-                "{:.2}G / {:.2}G ({:.0}%)",
+        items.push(PopoverItem::Progress {
+            label: format!(
+                "Memory · {:.2}G / {:.2}G",
                 self.memory_used as f64 / 1_073_741_824.0,
                 self.memory_total as f64 / 1_073_741_824.0,
-                mem_percent
             ),
-            value_css: "memory-detail".to_string(),
+            fraction: self.memory_used as f64 / self.memory_total as f64,
+            css: "memory-progress".to_string(),
         });
 
         // Network details
         items.push(PopoverItem::DetailRow {
             label: "Network RX".to_string(),
-            value: Self::format_bytes(self.network_rx),
+            value: Self::format_rate(self.network_rx),
             value_css: "network-detail".to_string(),
         });
         items.push(PopoverItem::DetailRow {
             label: "Network TX".to_string(),
-            value: Self::format_bytes(self.network_tx),
+            value: Self::format_rate(self.network_tx),
             value_css: "network-detail".to_string(),
         });
 
         // Disk usage
-        for disk in self.disks.iter() {
+        let disks = self.probes.iter().flat_map(|probes| probes.disks.iter());
+        for disk in disks {
             let disk_name = disk.name().to_string_lossy();
             let available = disk.available_space();
             let total = disk.total_space();
-            let used_percent = ((total - available) as f64 / total as f64) * 100.0;
-            items.push(PopoverItem::DetailRow {
-                label: format!("Disk ({})", disk_name),
-                value: format!(
-                    "{:.1}G / {:.1}G ({:.0}%)",
+            items.push(PopoverItem::Progress {
+                label: format!(
+                    "Disk ({}) · {:.1}G / {:.1}G",
+                    disk_name,
                     (total - available) as f64 / 1_073_741_824.0,
                     total as f64 / 1_073_741_824.0,
-                    used_percent
                 ),
-                value_css: "disk-detail".to_string(),
+                fraction: (total - available) as f64 / total as f64,
+                css: "disk-progress".to_string(),
             });
         }
 
+        // Pressure stall information
+        if !self.pressure.is_empty() {
+            items.push(PopoverItem::Separator);
+            for stat in &self.pressure {
+                items.push(PopoverItem::DetailRow {
+                    label: format!("{} Pressure", stat.resource),
+                    value: format!("some {:.1}% / full {:.1}%", stat.some, stat.full),
+                    value_css: Self::pressure_css(stat.some).to_string(),
+                });
+            }
+        }
+
         // Temperature sensors
         if !self.temperatures.is_empty() {
             items.push(PopoverItem::Separator);
@@ -298,6 +455,16 @@ impl Resources {
         self.popover.emit(PopoverMsg::UpdateItems(items));
     }
 
+    fn pressure_css(some: f32) -> &'static str {
+        if some >= 25.0 {
+            "pressure-high"
+        } else if some >= 5.0 {
+            "pressure-medium"
+        } else {
+            "pressure-detail"
+        }
+    }
+
     fn format_memory_short(used: u64, total: u64) -> String {
         let used_gb = used as f64 / 1_073_741_824.0;
         let percent = (used as f64 / total as f64) * 100.0;
@@ -324,4 +491,32 @@ impl Resources {
             format!("{}B", bytes)
         }
     }
+
+    fn format_rate(bytes_per_second: Option<u64>) -> String {
+        match bytes_per_second {
+            Some(bytes) => format!("{}/s", Self::format_bytes(bytes)),
+            None => "…".to_string(),
+        }
+    }
+}
+
+impl Snapshot for Resources {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "cpu_usage": self.cpu_usage,
+            "memory_used": self.memory_used,
+            "memory_total": self.memory_total,
+            "network_rx": self.network_rx,
+            "network_tx": self.network_tx,
+            "disk_read": self.disk_read,
+            "disk_write": self.disk_write,
+            "gpu_usage": self.gpu_usage,
+            "temperatures": self.temperatures,
+            "pressure": self.pressure.iter().map(|stat| json!({
+                "resource": stat.resource,
+                "some": stat.some,
+                "full": stat.full,
+            })).collect::<Vec<_>>(),
+        })
+    }
 }
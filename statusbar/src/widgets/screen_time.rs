@@ -0,0 +1,345 @@
+use chrono::{Local, NaiveDate};
+use gtk::glib;
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::ScreenTimeConfig;
+use crate::services::compositor::{self, Event};
+use crate::services::idle;
+
+/// Save accumulated usage to disk every minute
+const SAVE_INTERVAL_SECS: u64 = 60;
+
+/// Per-app focus time for a single day, persisted as TOML
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DailyUsage {
+    apps: HashMap<String, u64>,
+}
+
+impl DailyUsage {
+    fn path(date: NaiveDate) -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| {
+            dir.join("statusbar")
+                .join("screen-time")
+                .join(format!("{}.toml", date.format("%Y-%m-%d")))
+        })
+    }
+
+    fn load(date: NaiveDate) -> Self {
+        Self::path(date)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, date: NaiveDate) {
+        let Some(path) = Self::path(date) else {
+            return;
+        };
+
+        if let Some(Err(e)) = path.parent().map(fs::create_dir_all) {
+            eprintln!("Failed to create the folder of {}: {}", path.display(), e);
+            return;
+        }
+
+        match toml::to_string(self) {
+            Ok(contents) => {
+                if let Err(e) = fs::write(&path, contents) {
+                    eprintln!("Failed to save screen time to {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize screen time: {}", e),
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.apps.values().sum()
+    }
+}
+
+pub struct ScreenTime {
+    config: ScreenTimeConfig,
+    date: NaiveDate,
+    usage: DailyUsage,
+    yesterday_total: u64,
+    current_app: Option<String>,
+    is_idle: bool,
+    unsaved_secs: u64,
+    popover: Controller<PopoverComponent>,
+    /// Counts focus time every second, removed on shutdown
+    tick_timer: Option<glib::SourceId>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ScreenTimeMsg {
+    Tick,
+    FocusChanged(Option<String>),
+    IdleChanged(bool),
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for ScreenTime {
+    type Init = ScreenTimeConfig;
+    type Input = ScreenTimeMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["screen-time-widget", "widget"],
+            set_visible: model.config.enabled,
+
+            #[name = "screen_time_button"]
+            gtk::Button {
+                set_css_classes: &["screen-time-button"],
+                connect_clicked => ScreenTimeMsg::TogglePopover,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&format!("Screen time {}", Self::format_duration(model.usage.total())))],
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰔛",
+                        set_css_classes: &["screen-time-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &Self::format_duration(model.usage.total()),
+                        set_css_classes: &["screen-time-label"],
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Create popover without parent (will be set after widgets are created)
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Screen Time".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(300),
+                ..Default::default()
+            })
+            .detach();
+
+        let date = Local::now().date_naive();
+        let yesterday_total = date
+            .pred_opt()
+            .map(|yesterday| DailyUsage::load(yesterday).total())
+            .unwrap_or(0);
+
+        let mut model = ScreenTime {
+            config,
+            date,
+            usage: DailyUsage::load(date),
+            yesterday_total,
+            current_app: None,
+            is_idle: false,
+            unsaved_secs: 0,
+            popover,
+            tick_timer: None,
+        };
+
+        let widgets = view_output!();
+
+        // Set parent widget after widgets are created
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.screen_time_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            // Attribute one second of focus time per tick
+            let sender_clone = sender.clone();
+            model.tick_timer = Some(glib::timeout_add_seconds_local(1, move || {
+                sender_clone.input(ScreenTimeMsg::Tick);
+                glib::ControlFlow::Continue
+            }));
+
+            // Idle time is not attributed to the focused app
+            let mut idle_rx = idle::subscribe();
+            let sender_clone = sender.clone();
+            relm4::spawn(async move {
+                while idle_rx.changed().await.is_ok() {
+                    let is_idle = *idle_rx.borrow();
+                    if !poll::deliver(&sender_clone, ScreenTimeMsg::IdleChanged(is_idle)) {
+                        break;
+                    }
+                }
+            });
+
+            Self::listen_compositor_events(sender.clone());
+
+            // Request initial focused window
+            thread::spawn(move || {
+                sender.input(ScreenTimeMsg::FocusChanged(Self::get_focused_app()));
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            ScreenTimeMsg::Tick => {
+                self.tick();
+            }
+            ScreenTimeMsg::FocusChanged(app_id) => {
+                self.current_app = app_id;
+            }
+            ScreenTimeMsg::IdleChanged(is_idle) => {
+                self.is_idle = is_idle;
+            }
+            ScreenTimeMsg::TogglePopover => {
+                self.update_popover_content();
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if let Some(timer) = self.tick_timer.take() {
+            timer.remove();
+        }
+        // Keep the time counted since the last periodic save
+        if self.unsaved_secs > 0 {
+            self.usage.save(self.date);
+            self.unsaved_secs = 0;
+        }
+    }
+}
+
+impl ScreenTime {
+    fn tick(&mut self) {
+        // Start a new day at midnight
+        let today = Local::now().date_naive();
+        if today != self.date {
+            self.usage.save(self.date);
+            self.yesterday_total = self.usage.total();
+            self.usage = DailyUsage::default();
+            self.date = today;
+            self.unsaved_secs = 0;
+        }
+
+        if self.is_idle {
+            return;
+        }
+
+        if let Some(app) = &self.current_app {
+            *self.usage.apps.entry(app.clone()).or_insert(0) += 1;
+            self.unsaved_secs += 1;
+        }
+
+        if self.unsaved_secs >= SAVE_INTERVAL_SECS {
+            self.usage.save(self.date);
+            self.unsaved_secs = 0;
+            self.update_popover_content();
+        }
+    }
+
+    fn update_popover_content(&self) {
+        let mut apps: Vec<(&String, &u64)> = self.usage.apps.iter().collect();
+        apps.sort_by(|a, b| b.1.cmp(a.1));
+
+        let mut items: Vec<PopoverItem> = apps
+            .into_iter()
+            .take(self.config.max_apps)
+            .map(|(app, secs)| PopoverItem::DetailRow {
+                label: app.clone(),
+                value: Self::format_duration(*secs),
+                value_css: "screen-time-detail".to_string(),
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(PopoverItem::DetailRow {
+                label: "No activity yet".to_string(),
+                value: String::new(),
+                value_css: "screen-time-detail".to_string(),
+            });
+        }
+
+        items.push(PopoverItem::Separator);
+        items.push(PopoverItem::DetailRow {
+            label: "Today".to_string(),
+            value: Self::format_duration(self.usage.total()),
+            value_css: "screen-time-detail".to_string(),
+        });
+        items.push(PopoverItem::DetailRow {
+            label: "Yesterday".to_string(),
+            value: Self::format_duration(self.yesterday_total),
+            value_css: "screen-time-detail".to_string(),
+        });
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+
+    fn format_duration(secs: u64) -> String {
+        let hours = secs / 3600;
+        let minutes = (secs % 3600) / 60;
+
+        if hours > 0 {
+            format!("{}h {:02}m", hours, minutes)
+        } else {
+            format!("{}m", minutes)
+        }
+    }
+
+    fn listen_compositor_events(sender: ComponentSender<Self>) {
+        let mut events = compositor::subscribe();
+        thread::spawn(move || {
+            while let Some(event) = events.blocking_next() {
+                let app_id = match event {
+                    Event::WindowFocusChanged(_) | Event::WindowClosed(_) => {
+                        Self::get_focused_app()
+                    }
+                    Event::WindowOpenedOrChanged(window) if window.is_focused => {
+                        Some(window.app_id.unwrap_or_else(|| "unknown".to_string()))
+                    }
+                    _ => continue,
+                };
+                if !poll::deliver(&sender, ScreenTimeMsg::FocusChanged(app_id)) {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn get_focused_app() -> Option<String> {
+        let window = compositor::focused_window().ok()??;
+        Some(window.app_id.unwrap_or_else(|| "unknown".to_string()))
+    }
+}
+
+impl Snapshot for ScreenTime {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "date": self.date.to_string(),
+            "current_app": self.current_app,
+            "today_total": self.usage.total(),
+            "yesterday_total": self.yesterday_total,
+            "apps": self.usage.apps,
+        })
+    }
+}
@@ -0,0 +1,310 @@
+use gtk::prelude::*;
+use relm4::gtk;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::services::secrets;
+
+/// Lowest and highest number a spin button accepts, wide enough for
+/// negative coordinates and margins
+const SPIN_RANGE: (f64, f64) = (i32::MIN as f64, i32::MAX as f64);
+
+/// Input widget for a single config key
+enum Editor {
+    Text(gtk::Entry),
+    Integer(gtk::SpinButton),
+    Float(gtk::SpinButton),
+    Toggle(gtk::Switch),
+}
+
+impl Editor {
+    /// Pick an editor for `value`, or `None` for lists and nested tables
+    fn new(value: &toml::Value) -> Option<Self> {
+        match value {
+            toml::Value::String(text) => {
+                let entry = gtk::Entry::builder()
+                    .text(text.as_str())
+                    .hexpand(true)
+                    .build();
+                Some(Self::Text(entry))
+            }
+            toml::Value::Integer(number) => {
+                let spin = gtk::SpinButton::with_range(SPIN_RANGE.0, SPIN_RANGE.1, 1.0);
+                spin.set_value(*number as f64);
+                Some(Self::Integer(spin))
+            }
+            toml::Value::Float(number) => {
+                let spin = gtk::SpinButton::with_range(SPIN_RANGE.0, SPIN_RANGE.1, 0.1);
+                // Enough digits that re-reading the text keeps the value
+                spin.set_digits(decimals(*number).clamp(2, 6));
+                spin.set_value(*number);
+                Some(Self::Float(spin))
+            }
+            toml::Value::Boolean(enabled) => {
+                let switch = gtk::Switch::builder()
+                    .active(*enabled)
+                    .halign(gtk::Align::End)
+                    .valign(gtk::Align::Center)
+                    .build();
+                Some(Self::Toggle(switch))
+            }
+            _ => None,
+        }
+    }
+
+    fn widget(&self) -> gtk::Widget {
+        match self {
+            Self::Text(entry) => entry.clone().upcast(),
+            Self::Integer(spin) | Self::Float(spin) => spin.clone().upcast(),
+            Self::Toggle(switch) => switch.clone().upcast(),
+        }
+    }
+
+    fn value(&self) -> toml_edit::Value {
+        match self {
+            Self::Text(entry) => entry.text().as_str().into(),
+            Self::Integer(spin) => (spin.value_as_int() as i64).into(),
+            Self::Float(spin) => spin.value().into(),
+            Self::Toggle(switch) => switch.is_active().into(),
+        }
+    }
+
+    /// Whether the value was edited away from `original`
+    fn changed(&self, original: &toml::Value) -> bool {
+        match self {
+            Self::Text(entry) => original.as_str() != Some(entry.text().as_str()),
+            Self::Integer(spin) => original.as_integer() != Some(spin.value_as_int() as i64),
+            Self::Float(spin) => original.as_float() != Some(spin.value()),
+            Self::Toggle(switch) => original.as_bool() != Some(switch.is_active()),
+        }
+    }
+}
+
+/// Open a dialog editing the `[section]` of the config file
+///
+/// `on_saved` runs after the section has been written, so the caller can
+/// reload the widget with the new values.
+pub fn open(section: &'static str, on_saved: impl Fn() + 'static) {
+    let window = gtk::Window::builder()
+        .title(format!("{} settings", humanize(section)))
+        .default_width(360)
+        .resizable(false)
+        .css_classes(vec!["settings-dialog"])
+        .build();
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(6)
+        .column_spacing(12)
+        .build();
+
+    let mut editors = Vec::new();
+    if let Some(values) = Config::load().section(section) {
+        for (key, value) in &values {
+            let Some(editor) = Editor::new(value) else {
+                continue;
+            };
+
+            let label = gtk::Label::builder()
+                .label(humanize(key))
+                .halign(gtk::Align::Start)
+                .hexpand(true)
+                .css_classes(vec!["detail-label"])
+                .build();
+
+            let row = editors.len() as i32;
+            grid.attach(&label, 0, row, 1, 1);
+            grid.attach(&editor.widget(), 1, row, 1, 1);
+            editors.push((key.clone(), value.clone(), editor));
+        }
+    }
+
+    if editors.is_empty() {
+        let label = gtk::Label::builder()
+            .label("This widget has no settings")
+            .css_classes(vec!["detail-label"])
+            .build();
+        content.append(&label);
+    } else {
+        content.append(&grid);
+    }
+
+    let error_label = gtk::Label::builder()
+        .wrap(true)
+        .xalign(0.0)
+        .visible(false)
+        .css_classes(vec!["settings-error"])
+        .build();
+    content.append(&error_label);
+
+    // Action buttons
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    buttons.set_halign(gtk::Align::End);
+
+    let open_file_button = gtk::Button::with_label("Open config file");
+    open_file_button.connect_clicked(|_| open_config_file());
+    buttons.append(&open_file_button);
+
+    let cancel_button = gtk::Button::with_label("Cancel");
+    let window_clone = window.clone();
+    cancel_button.connect_clicked(move |_| window_clone.close());
+    buttons.append(&cancel_button);
+
+    if !editors.is_empty() {
+        let save_button = gtk::Button::with_label("Save");
+        save_button.add_css_class("suggested-action");
+
+        let window_clone = window.clone();
+        save_button.connect_clicked(move |_| {
+            // Untouched keys stay out of the file, defaults included
+            let values = editors
+                .iter()
+                .filter(|(_, original, editor)| editor.changed(original))
+                .map(|(key, _, editor)| (key.clone(), editor.value()))
+                .collect();
+
+            match Config::save_section(section, values) {
+                Ok(()) => {
+                    on_saved();
+                    window_clone.close();
+                }
+                Err(e) => {
+                    error_label.set_label(&e);
+                    error_label.set_visible(true);
+                }
+            }
+        });
+        buttons.append(&save_button);
+    }
+
+    content.append(&buttons);
+    window.set_child(Some(&content));
+    window.present();
+}
+
+/// Ask for the password or token of `account` and store it in the keyring
+pub fn ask_secret(account: &str) {
+    let window = gtk::Window::builder()
+        .title(format!("Credentials for {}", account))
+        .default_width(360)
+        .resizable(false)
+        .css_classes(vec!["settings-dialog"])
+        .build();
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let label = gtk::Label::builder()
+        .label(format!(
+            "Enter the password or token for {}. It is kept in your keyring, not in the config file.",
+            account
+        ))
+        .wrap(true)
+        .xalign(0.0)
+        .css_classes(vec!["detail-label"])
+        .build();
+    content.append(&label);
+
+    let entry = gtk::PasswordEntry::builder()
+        .show_peek_icon(true)
+        .activates_default(true)
+        .build();
+    content.append(&entry);
+
+    let error_label = gtk::Label::builder()
+        .wrap(true)
+        .xalign(0.0)
+        .visible(false)
+        .css_classes(vec!["settings-error"])
+        .build();
+    content.append(&error_label);
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    buttons.set_halign(gtk::Align::End);
+
+    let cancel_button = gtk::Button::with_label("Not now");
+    let window_clone = window.clone();
+    cancel_button.connect_clicked(move |_| window_clone.close());
+    buttons.append(&cancel_button);
+
+    let save_button = gtk::Button::with_label("Save");
+    save_button.add_css_class("suggested-action");
+    let account = account.to_string();
+    let window_clone = window.clone();
+    save_button.connect_clicked(move |button| {
+        let secret = entry.text().to_string();
+        if secret.is_empty() {
+            return;
+        }
+        button.set_sensitive(false);
+
+        let account = account.clone();
+        let button = button.clone();
+        let window = window_clone.clone();
+        let error_label = error_label.clone();
+        gtk::glib::spawn_future_local(async move {
+            // The keyring is reached over the bus on relm4's runtime
+            let stored = relm4::spawn(async move { secrets::store(&account, &secret).await })
+                .await
+                .unwrap_or_else(|e| Err(format!("Failed to store the secret: {}", e)));
+            match stored {
+                Ok(()) => window.close(),
+                Err(e) => {
+                    error_label.set_label(&e);
+                    error_label.set_visible(true);
+                    button.set_sensitive(true);
+                }
+            }
+        });
+    });
+    buttons.append(&save_button);
+
+    content.append(&buttons);
+    window.set_child(Some(&content));
+    window.set_default_widget(Some(&save_button));
+    window.present();
+}
+
+/// Open the config file in the user's default editor
+pub fn open_config_file() {
+    let Some(path) = Config::path() else {
+        return;
+    };
+
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(&path, "");
+    }
+
+    if let Err(e) = Command::new("xdg-open").arg(&path).spawn() {
+        eprintln!("Failed to open {}: {}", path.display(), e);
+    }
+}
+
+/// How many digits `number` has after the decimal point
+fn decimals(number: f64) -> u32 {
+    number
+        .to_string()
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len() as u32)
+}
+
+/// `interval_minutes` -> `Interval minutes`
+fn humanize(key: &str) -> String {
+    let text = key.replace('_', " ");
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
@@ -0,0 +1,4 @@
+/// JSON view of a widget's state for `statusbar dump-state`
+pub trait Snapshot {
+    fn snapshot(&self) -> serde_json::Value;
+}
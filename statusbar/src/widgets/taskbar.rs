@@ -0,0 +1,353 @@
+use gtk::prelude::*;
+use relm4::factory::FactoryVecDeque;
+use relm4::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::thread;
+
+use super::icons::app_icon;
+use super::poll;
+use super::snapshot::Snapshot;
+use crate::config::TaskbarConfig;
+use crate::services::compositor::{self, Event, Window, Workspace};
+use crate::services::privacy;
+
+const ICON_SIZE: i32 = 16;
+
+// Taskbar button factory component
+#[derive(Debug, Clone)]
+pub struct TaskbarButton {
+    window: Window,
+    show_title: bool,
+    max_title_length: usize,
+}
+
+#[derive(Debug)]
+pub enum TaskbarButtonMsg {
+    Clicked,
+}
+
+#[derive(Debug)]
+pub enum TaskbarButtonOutput {
+    Focus(u64),
+    Close(u64),
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for TaskbarButton {
+    type Init = TaskbarButton;
+    type Input = TaskbarButtonMsg;
+    type Output = TaskbarButtonOutput;
+    type CommandOutput = ();
+    type ParentWidget = gtk::Box;
+
+    view! {
+        #[root]
+        gtk::Button {
+            set_css_classes: &self.css_classes(),
+            set_tooltip_text: Some(self.title()),
+            update_property: &[gtk::accessible::Property::Label(self.title())],
+
+            connect_clicked => TaskbarButtonMsg::Clicked,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 4,
+
+                gtk::Image {
+                    set_from_gicon: &app_icon(self.window.app_id.as_deref().unwrap_or_default()),
+                    set_pixel_size: ICON_SIZE,
+                },
+
+                gtk::Label {
+                    set_visible: self.show_title,
+                    set_label: self.title(),
+                    set_css_classes: &["taskbar-title"],
+                    set_ellipsize: gtk::pango::EllipsizeMode::End,
+                    set_max_width_chars: self.max_title_length as i32,
+                }
+            }
+        }
+    }
+
+    fn init_model(button: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+        button
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &gtk::Widget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+
+        // Middle click closes the window, like in most taskbars
+        let click = gtk::GestureClick::new();
+        click.set_button(gtk::gdk::BUTTON_MIDDLE);
+        let id = self.window.id;
+        click.connect_released(move |_, _, _, _| {
+            let _ = sender.output(TaskbarButtonOutput::Close(id));
+        });
+        root.add_controller(click);
+
+        widgets
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: FactorySender<Self>) {
+        match msg {
+            TaskbarButtonMsg::Clicked => {
+                let _ = sender.output(TaskbarButtonOutput::Focus(self.window.id));
+            }
+        }
+    }
+}
+
+impl TaskbarButton {
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["taskbar-button"];
+        if self.window.is_focused {
+            classes.push("taskbar-focused");
+        }
+        classes
+    }
+
+    fn title(&self) -> &str {
+        let title = self.window.title.as_deref().unwrap_or_default();
+        privacy::redact(title)
+    }
+}
+
+// Main taskbar widget
+pub struct Taskbar {
+    config: TaskbarConfig,
+    buttons: FactoryVecDeque<TaskbarButton>,
+    /// Workspace whose windows are shown
+    focused_workspace: Option<u64>,
+    /// Open windows by id
+    windows: HashMap<u64, Window>,
+    connected: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum TaskbarMsg {
+    UpdateWorkspaces(Vec<Workspace>),
+    WorkspaceFocused(u64),
+    UpdateWindows(Vec<Window>),
+    WindowChanged(Window),
+    WindowClosed(u64),
+    FocusChanged(Option<u64>),
+    ConnectionChanged(bool),
+    PrivacyChanged,
+    Focus(u64),
+    Close(u64),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Taskbar {
+    type Init = TaskbarConfig;
+    type Input = TaskbarMsg;
+    type Output = ();
+
+    view! {
+        #[root]
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_visible: model.config.enabled,
+            set_css_classes: &["taskbar-widget", "widget"],
+            #[watch]
+            set_class_active: ("compositor-disconnected", !model.connected),
+            #[watch]
+            set_tooltip_text: (!model.connected).then_some("Lost connection to the compositor"),
+
+            #[local_ref]
+            buttons_box -> gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 2,
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let buttons = FactoryVecDeque::builder()
+            .launch(gtk::Box::default())
+            .forward(sender.input_sender(), |output| match output {
+                TaskbarButtonOutput::Focus(id) => TaskbarMsg::Focus(id),
+                TaskbarButtonOutput::Close(id) => TaskbarMsg::Close(id),
+            });
+
+        let model = Taskbar {
+            config,
+            buttons,
+            focused_workspace: None,
+            windows: HashMap::new(),
+            connected: true,
+        };
+
+        let buttons_box = model.buttons.widget();
+        let widgets = view_output!();
+
+        if !model.config.enabled {
+            return ComponentParts { model, widgets };
+        }
+
+        Self::listen_compositor_events(sender.clone());
+
+        // Dim the widget while the compositor is unreachable
+        let mut connection_rx = compositor::connection();
+        let connection_sender = sender.clone();
+        relm4::spawn(async move {
+            while connection_rx.changed().await.is_ok() {
+                let connected = *connection_rx.borrow();
+                if !poll::deliver(&connection_sender, TaskbarMsg::ConnectionChanged(connected)) {
+                    break;
+                }
+            }
+        });
+
+        // Re-render titles when the privacy screen is toggled
+        let mut privacy_rx = privacy::subscribe();
+        let privacy_sender = sender.clone();
+        relm4::spawn(async move {
+            while privacy_rx.changed().await.is_ok() {
+                if !poll::deliver(&privacy_sender, TaskbarMsg::PrivacyChanged) {
+                    break;
+                }
+            }
+        });
+
+        // Request initial workspace and window state
+        thread::spawn(move || {
+            if let Ok(workspaces) = compositor::workspaces() {
+                sender.input(TaskbarMsg::UpdateWorkspaces(workspaces));
+            }
+            if let Ok(windows) = compositor::windows() {
+                sender.input(TaskbarMsg::UpdateWindows(windows));
+            }
+        });
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            TaskbarMsg::UpdateWorkspaces(workspaces) => {
+                self.focused_workspace = workspaces
+                    .iter()
+                    .find(|workspace| workspace.is_focused)
+                    .map(|workspace| workspace.id);
+                self.rebuild_buttons();
+            }
+            TaskbarMsg::WorkspaceFocused(id) => {
+                self.focused_workspace = Some(id);
+                self.rebuild_buttons();
+            }
+            TaskbarMsg::UpdateWindows(windows) => {
+                self.windows = windows
+                    .into_iter()
+                    .map(|window| (window.id, window))
+                    .collect();
+                self.rebuild_buttons();
+            }
+            TaskbarMsg::WindowChanged(window) => {
+                if window.is_focused {
+                    self.set_focused_window(Some(window.id));
+                }
+                self.windows.insert(window.id, window);
+                self.rebuild_buttons();
+            }
+            TaskbarMsg::WindowClosed(id) => {
+                self.windows.remove(&id);
+                self.rebuild_buttons();
+            }
+            TaskbarMsg::FocusChanged(id) => {
+                self.set_focused_window(id);
+                self.rebuild_buttons();
+            }
+            TaskbarMsg::ConnectionChanged(connected) => {
+                self.connected = connected;
+            }
+            TaskbarMsg::PrivacyChanged => self.rebuild_buttons(),
+            TaskbarMsg::Focus(id) => {
+                if let Err(e) = compositor::focus_window(id) {
+                    eprintln!("Failed to focus window: {}", e);
+                }
+            }
+            TaskbarMsg::Close(id) => {
+                if let Err(e) = compositor::close_window(id) {
+                    eprintln!("Failed to close window: {}", e);
+                }
+            }
+        }
+    }
+}
+
+impl Taskbar {
+    fn set_focused_window(&mut self, id: Option<u64>) {
+        for window in self.windows.values_mut() {
+            window.is_focused = Some(window.id) == id;
+        }
+    }
+
+    fn rebuild_buttons(&mut self) {
+        // Window ids grow with opening order, so buttons follow it too
+        let mut windows: Vec<&Window> = self
+            .windows
+            .values()
+            .filter(|window| {
+                window.workspace_id.is_some() && window.workspace_id == self.focused_workspace
+            })
+            .collect();
+        windows.sort_by_key(|window| window.id);
+
+        let mut guard = self.buttons.guard();
+        guard.clear();
+        for window in windows {
+            guard.push_back(TaskbarButton {
+                window: window.clone(),
+                show_title: self.config.show_titles,
+                max_title_length: self.config.max_title_length,
+            });
+        }
+    }
+
+    fn listen_compositor_events(sender: ComponentSender<Self>) {
+        let mut events = compositor::subscribe();
+        thread::spawn(move || {
+            while let Some(event) = events.blocking_next() {
+                let msg = match event {
+                    Event::WorkspacesChanged(workspaces) => {
+                        TaskbarMsg::UpdateWorkspaces(workspaces)
+                    }
+                    Event::WorkspaceActivated { id, focused: true } => {
+                        TaskbarMsg::WorkspaceFocused(id)
+                    }
+                    Event::WindowsChanged(windows) => TaskbarMsg::UpdateWindows(windows),
+                    Event::WindowOpenedOrChanged(window) => TaskbarMsg::WindowChanged(window),
+                    Event::WindowClosed(id) => TaskbarMsg::WindowClosed(id),
+                    Event::WindowFocusChanged(id) => TaskbarMsg::FocusChanged(id),
+                    _ => continue,
+                };
+                if !poll::deliver(&sender, msg) {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+impl Snapshot for Taskbar {
+    fn snapshot(&self) -> serde_json::Value {
+        let windows: Vec<&Window> = self.buttons.iter().map(|button| &button.window).collect();
+        json!({
+            "enabled": self.config.enabled,
+            "focused_workspace": self.focused_workspace,
+            "windows": windows,
+        })
+    }
+}
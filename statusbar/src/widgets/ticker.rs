@@ -0,0 +1,316 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::TickerConfig;
+use crate::services::ticker::Quote;
+
+/// Points in a popover price chart
+const CHART_WIDTH: usize = 24;
+
+/// Chart levels, lowest first
+const CHART_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub struct Ticker {
+    config: TickerConfig,
+    /// Last result per symbol, in config order
+    quotes: Vec<Option<Result<Quote, String>>>,
+    /// Symbol shown in the bar
+    index: usize,
+    fetching: bool,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum TickerMsg {
+    Fetch,
+    Fetched(Vec<Result<Quote, String>>),
+    /// Show the next symbol
+    Cycle,
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Ticker {
+    type Init = TickerConfig;
+    type Input = TickerMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            #[watch]
+            set_visible: model.config.enabled && model.current().is_some(),
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            #[name = "ticker_button"]
+            gtk::Button {
+                set_css_classes: &["ticker-button"],
+                connect_clicked => TickerMsg::TogglePopover,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: model.current().map_or("", |quote| quote.symbol.as_str()),
+                        set_css_classes: &["ticker-symbol"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.current().map(format_quote).unwrap_or_default(),
+                        set_css_classes: &["ticker-price"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: config.provider.name().to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(300),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut model = Ticker {
+            quotes: vec![None; config.symbols.len()],
+            config,
+            index: 0,
+            fetching: false,
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.ticker_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled && !model.config.symbols.is_empty() {
+            let fetch_sender = sender.clone();
+            let interval = (model.config.interval_minutes.max(1) * 60) as u32;
+            model.timers.push(poll::every(interval, move || {
+                fetch_sender.input(TickerMsg::Fetch)
+            }));
+
+            if model.config.symbols.len() > 1 {
+                let cycle_sender = sender.clone();
+                let cycle = model.config.cycle_seconds.max(1) as u32;
+                model.timers.push(poll::every(cycle, move || {
+                    cycle_sender.input(TickerMsg::Cycle)
+                }));
+            }
+
+            sender.input(TickerMsg::Fetch);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            TickerMsg::Fetch => {
+                if self.fetching {
+                    return;
+                }
+                self.fetching = true;
+
+                let provider = self.config.provider;
+                let symbols = self.config.symbols.clone();
+                let currency = self.config.currency.clone();
+                relm4::spawn(async move {
+                    let mut results = Vec::new();
+                    for symbol in &symbols {
+                        results.push(provider.quote(symbol, &currency).await);
+                    }
+                    sender.input(TickerMsg::Fetched(results));
+                });
+            }
+            TickerMsg::Fetched(results) => {
+                self.fetching = false;
+                for (symbol, result) in self.config.symbols.iter().zip(&results) {
+                    if let Err(e) = result {
+                        eprintln!("Failed to fetch {}: {}", symbol, e);
+                    }
+                }
+                self.quotes = results.into_iter().map(Some).collect();
+                if self.current().is_none() {
+                    self.cycle();
+                }
+                self.update_popover_content();
+            }
+            TickerMsg::Cycle => self.cycle(),
+            TickerMsg::TogglePopover => {
+                self.update_popover_content();
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl Ticker {
+    fn quote(&self, index: usize) -> Option<&Quote> {
+        self.quotes.get(index)?.as_ref()?.as_ref().ok()
+    }
+
+    fn current(&self) -> Option<&Quote> {
+        self.quote(self.index)
+    }
+
+    /// Move on to the next symbol that has a quote
+    fn cycle(&mut self) {
+        let count = self.quotes.len();
+        let next = (1..=count)
+            .map(|step| (self.index + step) % count)
+            .find(|&index| self.quote(index).is_some());
+        if let Some(index) = next {
+            self.index = index;
+        }
+    }
+
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["ticker-widget", "widget"];
+        if let Some(quote) = self.current() {
+            classes.push(change_class(quote));
+        }
+        classes
+    }
+
+    fn update_popover_content(&self) {
+        let items = self
+            .config
+            .symbols
+            .iter()
+            .zip(&self.quotes)
+            .map(|(symbol, quote)| match quote {
+                Some(Ok(quote)) => PopoverItem::Custom(quote_row(quote).upcast()),
+                Some(Err(e)) => PopoverItem::DetailRow {
+                    label: symbol.clone(),
+                    value: e.clone(),
+                    value_css: "ticker-detail-error".to_string(),
+                },
+                None => PopoverItem::DetailRow {
+                    label: symbol.clone(),
+                    value: "…".to_string(),
+                    value_css: "ticker-detail".to_string(),
+                },
+            })
+            .collect();
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+fn change_class(quote: &Quote) -> &'static str {
+    if quote.change_percent < 0.0 {
+        "ticker-down"
+    } else {
+        "ticker-up"
+    }
+}
+
+/// Price and day change, e.g. `189.20 ▲1.25%`
+fn format_quote(quote: &Quote) -> String {
+    let arrow = if quote.change_percent < 0.0 {
+        '▼'
+    } else {
+        '▲'
+    };
+    format!(
+        "{:.2} {}{:.2}%",
+        quote.price,
+        arrow,
+        quote.change_percent.abs()
+    )
+}
+
+/// Symbol, the day's chart and the quote side by side
+fn quote_row(quote: &Quote) -> gtk::Box {
+    let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    row.set_css_classes(&["ticker-row", change_class(quote)]);
+
+    let symbol = gtk::Label::new(Some(&quote.symbol));
+    symbol.set_halign(gtk::Align::Start);
+    symbol.set_hexpand(true);
+    symbol.set_css_classes(&["ticker-row-symbol"]);
+    row.append(&symbol);
+
+    let chart = gtk::Label::new(Some(&sparkline(&quote.history)));
+    chart.set_css_classes(&["ticker-chart"]);
+    row.append(&chart);
+
+    let price = gtk::Label::new(Some(&format_quote(quote)));
+    price.set_tooltip_text(Some(&quote.currency));
+    price.set_css_classes(&["ticker-row-price"]);
+    row.append(&price);
+
+    row
+}
+
+/// Draw prices with block characters, averaging them into at most
+/// [`CHART_WIDTH`] points
+fn sparkline(history: &[f64]) -> String {
+    if history.is_empty() {
+        return String::new();
+    }
+    let bucket = history.len().div_ceil(CHART_WIDTH);
+    let points: Vec<f64> = history
+        .chunks(bucket)
+        .map(|chunk| chunk.iter().sum::<f64>() / chunk.len() as f64)
+        .collect();
+
+    let min = points.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = points.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let top = (CHART_BLOCKS.len() - 1) as f64;
+    points
+        .iter()
+        .map(|point| {
+            // A flat day sits in the middle
+            let level = if max > min {
+                (point - min) / (max - min) * top
+            } else {
+                top / 2.0
+            };
+            CHART_BLOCKS[level.round() as usize]
+        })
+        .collect()
+}
+
+impl Snapshot for Ticker {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "provider": self.config.provider.name(),
+            "current": self.current().map(|quote| &quote.symbol),
+            "quotes": self
+                .config
+                .symbols
+                .iter()
+                .zip(&self.quotes)
+                .map(|(symbol, quote)| json!({
+                    "symbol": symbol,
+                    "quote": quote.as_ref().and_then(|result| result.as_ref().ok()),
+                    "error": quote.as_ref().and_then(|result| result.as_ref().err()),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
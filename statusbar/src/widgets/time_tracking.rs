@@ -0,0 +1,354 @@
+use chrono::{Local, Utc};
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+
+use super::poll::{self, Poll};
+use super::popover::{
+    PopoverComponent, PopoverInit, PopoverItem, PopoverMsg, set_keyboard_focusable,
+};
+use super::snapshot::Snapshot;
+use crate::config::TimeTrackingConfig;
+use crate::services::time_tracking::{Running, Task, Tracking};
+
+pub struct TimeTracking {
+    config: TimeTrackingConfig,
+    /// None until the first check
+    tracking: Option<Tracking>,
+    /// A start or stop is in flight
+    busy: bool,
+    error: Option<String>,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum TimeTrackingMsg {
+    Refresh,
+    StatusChanged(Result<Tracking, String>),
+    Start(Task),
+    Stop,
+    ActionDone(Result<(), String>),
+    /// Redraw the elapsed time
+    Tick,
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for TimeTracking {
+    type Init = TimeTrackingConfig;
+    type Input = TimeTrackingMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_visible: model.config.enabled,
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            #[name = "time_tracking_button"]
+            gtk::Button {
+                set_css_classes: &["time-tracking-button"],
+                connect_clicked => TimeTrackingMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Time tracking")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󱎫",
+                        set_css_classes: &["time-tracking-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_visible: model.running().is_some(),
+                        #[watch]
+                        set_label: &model.label(),
+                        set_css_classes: &["time-tracking-label"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: config.backend.name().to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(280),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut model = TimeTracking {
+            config,
+            tracking: None,
+            busy: false,
+            error: None,
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.time_tracking_button.clone().upcast::<gtk::Widget>());
+
+        // Typing a new task needs the keyboard only while the popover is open
+        model
+            .popover
+            .widget()
+            .connect_closed(|popover| set_keyboard_focusable(popover, false));
+
+        if model.config.enabled {
+            let sender_clone = sender.clone();
+            let interval = model.config.interval_seconds.max(1) as u32;
+            model.timers.push(poll::every(interval, move || {
+                sender_clone.input(TimeTrackingMsg::Refresh)
+            }));
+
+            let tick_sender = sender.clone();
+            model.timers.push(poll::every(1, move || {
+                tick_sender.input(TimeTrackingMsg::Tick)
+            }));
+
+            sender.input(TimeTrackingMsg::Refresh);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            TimeTrackingMsg::Refresh => {
+                let backend = self.config.backend;
+                let max_recent = self.config.max_recent;
+                relm4::spawn(async move {
+                    let result = backend.status(max_recent).await;
+                    sender.input(TimeTrackingMsg::StatusChanged(result));
+                });
+            }
+            TimeTrackingMsg::StatusChanged(result) => match result {
+                Ok(tracking) => {
+                    let recovered = self.error.take().is_some();
+                    if recovered || self.tracking.as_ref() != Some(&tracking) {
+                        self.tracking = Some(tracking);
+                        self.update_popover_content(&sender);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to check time tracking: {}", e);
+                    if self.error.as_ref() != Some(&e) {
+                        self.error = Some(e);
+                        self.update_popover_content(&sender);
+                    }
+                }
+            },
+            TimeTrackingMsg::Start(task) => {
+                if self.busy || task.description.is_empty() {
+                    return;
+                }
+                let backend = self.config.backend;
+                let running = self.running().cloned();
+                self.run(&sender, backend.start(task, running));
+            }
+            TimeTrackingMsg::Stop => {
+                let Some(running) = self.running().cloned() else {
+                    return;
+                };
+                if self.busy {
+                    return;
+                }
+                let backend = self.config.backend;
+                self.run(&sender, backend.stop(running));
+            }
+            TimeTrackingMsg::ActionDone(result) => {
+                self.busy = false;
+                match result {
+                    // The error stays up until the next check
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        self.error = Some(e);
+                        self.update_popover_content(&sender);
+                    }
+                    Ok(()) => sender.input(TimeTrackingMsg::Refresh),
+                }
+            }
+            // The view reads the clock, redrawing is all that's needed
+            TimeTrackingMsg::Tick => {}
+            TimeTrackingMsg::TogglePopover => {
+                let popover = self.popover.widget();
+                if !popover.is_visible() {
+                    set_keyboard_focusable(popover, true);
+                }
+                self.update_popover_content(&sender);
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl TimeTracking {
+    fn running(&self) -> Option<&Running> {
+        self.tracking.as_ref()?.running.as_ref()
+    }
+
+    /// Run a start or stop, then refresh
+    fn run(
+        &mut self,
+        sender: &ComponentSender<Self>,
+        action: impl Future<Output = Result<(), String>> + Send + 'static,
+    ) {
+        self.busy = true;
+        self.update_popover_content(sender);
+        let sender = sender.clone();
+        relm4::spawn(async move {
+            sender.input(TimeTrackingMsg::ActionDone(action.await));
+        });
+    }
+
+    fn elapsed(running: &Running) -> String {
+        let minutes = (Utc::now() - running.start).num_minutes().max(0);
+        format!("{}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    fn label(&self) -> String {
+        let Some(running) = self.running() else {
+            return String::new();
+        };
+        let mut description: String = running
+            .task
+            .description
+            .chars()
+            .take(self.config.max_length)
+            .collect();
+        if description.len() < running.task.description.len() {
+            description.push('…');
+        }
+        format!("{} {}", description, Self::elapsed(running))
+    }
+
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["time-tracking-widget", "widget"];
+        if self.running().is_some() {
+            classes.push("time-tracking-running");
+        }
+        if self.error.is_some() {
+            classes.push("time-tracking-error");
+        }
+        classes
+    }
+
+    fn tooltip(&self) -> String {
+        match self.running() {
+            Some(running) => format!(
+                "{} since {}",
+                running.task.description,
+                running.start.with_timezone(&Local).format("%H:%M")
+            ),
+            None => "Not tracking".to_string(),
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let mut items = Vec::new();
+
+        if let Some(running) = self.running() {
+            items.push(PopoverItem::DetailRow {
+                label: running.task.description.clone(),
+                value: format!(
+                    "since {}",
+                    running.start.with_timezone(&Local).format("%H:%M")
+                ),
+                value_css: "time-tracking-detail".to_string(),
+            });
+            let stop = gtk::Button::builder()
+                .label("Stop")
+                .css_classes(vec!["time-tracking-stop"])
+                .sensitive(!self.busy)
+                .build();
+            let stop_sender = sender.clone();
+            stop.connect_clicked(move |_| stop_sender.input(TimeTrackingMsg::Stop));
+            items.push(PopoverItem::Custom(stop.upcast()));
+            items.push(PopoverItem::Separator);
+        }
+
+        let entry = gtk::Entry::builder()
+            .placeholder_text("Start a new task")
+            .css_classes(vec!["time-tracking-entry"])
+            .sensitive(!self.busy)
+            .build();
+        let entry_sender = sender.clone();
+        entry.connect_activate(move |entry| {
+            entry_sender.input(TimeTrackingMsg::Start(Task::new(
+                entry.text().trim().to_string(),
+            )));
+        });
+        items.push(PopoverItem::Custom(entry.upcast()));
+
+        // Switching to a recent task stops the running one
+        let recent = self
+            .tracking
+            .as_ref()
+            .map(|tracking| tracking.recent.as_slice())
+            .unwrap_or_default();
+        if !recent.is_empty() {
+            let list = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            for task in recent {
+                let button = gtk::Button::builder()
+                    .label(&task.description)
+                    .css_classes(vec!["time-tracking-task"])
+                    .sensitive(!self.busy)
+                    .build();
+                let task = task.clone();
+                let task_sender = sender.clone();
+                button.connect_clicked(move |_| {
+                    task_sender.input(TimeTrackingMsg::Start(task.clone()));
+                });
+                list.append(&button);
+            }
+            items.push(PopoverItem::Custom(list.upcast()));
+        }
+
+        if let Some(error) = &self.error {
+            items.push(PopoverItem::Separator);
+            items.push(PopoverItem::DetailRow {
+                label: "Error".to_string(),
+                value: error.clone(),
+                value_css: "time-tracking-detail-error".to_string(),
+            });
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for TimeTracking {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "backend": self.config.backend.name(),
+            "running": self.running().map(|running| json!({
+                "task": running.task,
+                "start": running.start.to_rfc3339(),
+                "elapsed": Self::elapsed(running),
+            })),
+            "recent": self.tracking.as_ref().map(|tracking| &tracking.recent),
+            "error": self.error,
+        })
+    }
+}
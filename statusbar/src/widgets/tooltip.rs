@@ -0,0 +1,55 @@
+// Pango markup tooltips filled in from user templates
+use gtk::{glib, pango};
+use relm4::gtk;
+
+/// Fill the `{name}` placeholders of a Pango markup `template`
+///
+/// Values are escaped, unknown placeholders stay as written and `{{`/`}}` give
+/// literal braces. Lines left blank are dropped, so a template can list
+/// details that aren't always known. A template that isn't valid markup is
+/// shown as plain text rather than not at all. Returns None when nothing is
+/// left to show.
+pub fn render(template: &str, values: &[(&str, &str)]) -> Option<String> {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        filled.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            filled.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+
+        let placeholder = tail.strip_prefix('{').and_then(|inner| {
+            let (name, _) = inner.split_once('}')?;
+            let (_, value) = values.iter().find(|(key, _)| *key == name)?;
+            Some((name.len() + 2, value))
+        });
+        match placeholder {
+            Some((len, value)) => {
+                filled.push_str(&glib::markup_escape_text(value));
+                rest = &tail[len..];
+            }
+            None => {
+                filled.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+
+    let markup = filled
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if markup.is_empty() {
+        return None;
+    }
+    match pango::parse_markup(&markup, '\0') {
+        Ok(_) => Some(markup),
+        Err(_) => Some(glib::markup_escape_text(&markup).to_string()),
+    }
+}
@@ -1,8 +1,13 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
+use serde_json::json;
+
+use crate::widgets::poll::{self, Poll};
+use crate::widgets::snapshot::Snapshot;
 
 pub struct SystemTray {
     items: Vec<TrayItem>,
+    timers: Vec<Poll>,
 }
 
 #[derive(Debug, Clone)]
@@ -38,16 +43,18 @@ impl SimpleComponent for SystemTray {
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = SystemTray { items: Vec::new() };
+        let mut model = SystemTray {
+            items: Vec::new(),
+            timers: Vec::new(),
+        };
 
         let widgets = view_output!();
 
         // Update every 10 seconds to check for new tray items
         let sender_clone = sender.clone();
-        gtk::glib::timeout_add_seconds_local(10, move || {
-            sender_clone.input(SystemTrayMsg::Update);
-            gtk::glib::ControlFlow::Continue
-        });
+        model.timers.push(poll::every(10, move || {
+            sender_clone.input(SystemTrayMsg::Update)
+        }));
 
         // Initial update
         sender.input(SystemTrayMsg::Update);
@@ -74,3 +81,15 @@ impl SystemTray {
         // This requires additional dependencies like zbus
     }
 }
+
+impl Snapshot for SystemTray {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "items": self.items.iter().map(|item| json!({
+                "id": item.id,
+                "icon": item.icon,
+                "tooltip": item.tooltip,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use chrono::{DateTime, Local};
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+use std::process::Command;
+use std::thread;
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::UpdatesConfig;
+use crate::services::updates::{self, Update};
+
+pub struct Updates {
+    config: UpdatesConfig,
+    updates: Vec<Update>,
+    /// Backends that failed during the last check
+    errors: Vec<String>,
+    checking: bool,
+    /// The updater command is running
+    updating: bool,
+    checked_at: Option<DateTime<Local>>,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum UpdatesMsg {
+    Check,
+    Checked(Vec<Update>, Vec<String>),
+    RunUpdater,
+    UpdaterExited,
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Updates {
+    type Init = UpdatesConfig;
+    type Input = UpdatesMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            // Only worth the space while there is something to install
+            #[watch]
+            set_visible: model.config.enabled && (!model.updates.is_empty() || model.updating),
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            #[name = "updates_button"]
+            gtk::Button {
+                set_css_classes: &["updates-button"],
+                connect_clicked => UpdatesMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("Updates")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_label: "󰚰",
+                        set_css_classes: &["updates-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.updates.len().to_string(),
+                        set_css_classes: &["updates-count"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Updates".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(320),
+                scrollable: true,
+                max_height: Some(420),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut model = Updates {
+            config,
+            updates: Vec::new(),
+            errors: Vec::new(),
+            checking: false,
+            updating: false,
+            checked_at: None,
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.updates_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let sender_clone = sender.clone();
+            let interval = (model.config.interval_minutes.max(1) * 60) as u32;
+            model.timers.push(poll::every(interval, move || {
+                sender_clone.input(UpdatesMsg::Check)
+            }));
+            sender.input(UpdatesMsg::Check);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            UpdatesMsg::Check => {
+                if self.checking || self.updating {
+                    return;
+                }
+                self.checking = true;
+                self.update_popover_content(&sender);
+
+                // Package managers can take a while, so check off the main thread
+                let backends = self.config.backends.clone();
+                thread::spawn(move || {
+                    let (updates, errors) = updates::check_all(&backends);
+                    sender.input(UpdatesMsg::Checked(updates, errors));
+                });
+            }
+            UpdatesMsg::Checked(updates, errors) => {
+                for e in &errors {
+                    eprintln!("Failed to check for updates: {}", e);
+                }
+                self.checking = false;
+                self.updates = updates;
+                self.errors = errors;
+                self.checked_at = Some(Local::now());
+                self.update_popover_content(&sender);
+            }
+            UpdatesMsg::RunUpdater => {
+                if self.updating || self.config.updater_command.is_empty() {
+                    return;
+                }
+                self.updating = true;
+                self.popover.emit(PopoverMsg::Toggle);
+
+                let command = self.config.updater_command.clone();
+                thread::spawn(move || {
+                    match Command::new("sh").arg("-c").arg(&command).status() {
+                        Ok(status) if !status.success() => {
+                            eprintln!("Updater exited with {}", status);
+                        }
+                        Ok(_) => {}
+                        Err(e) => eprintln!("Failed to run the updater: {}", e),
+                    }
+                    sender.input(UpdatesMsg::UpdaterExited);
+                });
+            }
+            UpdatesMsg::UpdaterExited => {
+                self.updating = false;
+                sender.input(UpdatesMsg::Check);
+            }
+            UpdatesMsg::TogglePopover => {
+                self.update_popover_content(&sender);
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl Updates {
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["updates-widget", "widget"];
+        if self.updating {
+            classes.push("updates-running");
+        }
+        classes
+    }
+
+    fn tooltip(&self) -> String {
+        if self.updating {
+            return "Updating…".to_string();
+        }
+        match self.updates.len() {
+            1 => "1 update available".to_string(),
+            count => format!("{} updates available", count),
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let detail = |label: &str, value: String| PopoverItem::DetailRow {
+            label: label.to_string(),
+            value,
+            value_css: "updates-detail".to_string(),
+        };
+
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        let status = match (self.checking, self.checked_at) {
+            (true, _) => "Checking…".to_string(),
+            (false, Some(time)) => format!("Checked at {}", time.format("%H:%M")),
+            (false, None) => String::new(),
+        };
+        header.append(
+            &gtk::Label::builder()
+                .label(status)
+                .css_classes(vec!["detail-label"])
+                .halign(gtk::Align::Start)
+                .hexpand(true)
+                .build(),
+        );
+        let check_button = gtk::Button::builder()
+            .label("Check now")
+            .css_classes(vec!["updates-action"])
+            .sensitive(!self.checking && !self.updating)
+            .build();
+        let check_sender = sender.clone();
+        check_button.connect_clicked(move |_| check_sender.input(UpdatesMsg::Check));
+        header.append(&check_button);
+        if !self.config.updater_command.is_empty() {
+            let update_button = gtk::Button::builder()
+                .label("Update")
+                .css_classes(vec!["updates-action", "updates-run"])
+                .sensitive(!self.updates.is_empty() && !self.updating)
+                .build();
+            let update_sender = sender.clone();
+            update_button.connect_clicked(move |_| update_sender.input(UpdatesMsg::RunUpdater));
+            header.append(&update_button);
+        }
+
+        let list = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        for update in &self.updates {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+            row.append(
+                &gtk::Label::builder()
+                    .label(&update.name)
+                    .css_classes(vec!["updates-package"])
+                    .halign(gtk::Align::Start)
+                    .hexpand(true)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .tooltip_text(update.backend.name())
+                    .build(),
+            );
+            let version = match &update.current {
+                Some(current) => format!("{} → {}", current, update.new),
+                None => update.new.clone(),
+            };
+            row.append(
+                &gtk::Label::builder()
+                    .label(version)
+                    .css_classes(vec!["updates-version"])
+                    .halign(gtk::Align::End)
+                    .ellipsize(gtk::pango::EllipsizeMode::Start)
+                    .max_width_chars(24)
+                    .build(),
+            );
+            list.append(&row);
+        }
+        let mut items = vec![PopoverItem::Custom(header.upcast()), PopoverItem::Separator];
+        if self.updates.is_empty() {
+            items.push(detail("Up to date", String::new()));
+        } else {
+            items.push(PopoverItem::Custom(list.upcast()));
+        }
+        for error in &self.errors {
+            items.push(detail("Error", error.clone()));
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for Updates {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "updates": self.updates,
+            "errors": self.errors,
+            "checking": self.checking,
+            "updating": self.updating,
+        })
+    }
+}
@@ -0,0 +1,403 @@
+use gtk::prelude::*;
+use gtk::{gdk, glib};
+use relm4::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::thread;
+
+use super::icons::app_icon;
+use super::poll;
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::VolumeConfig;
+use crate::services::audio::{self, AudioState, Sink, SinkInput, Stream};
+
+const ICON_SIZE: i32 = 16;
+
+/// What a slider or mute toggle acts on
+#[derive(Debug, Clone, Copy)]
+pub enum Target {
+    /// The default sink, whichever it currently is
+    Master,
+    SinkInput(u32),
+}
+
+/// Slider and mute toggle of one stream in the mixer
+///
+/// Rows are kept across audio changes and updated in place, so a slider
+/// being dragged isn't replaced under the pointer.
+struct StreamRow {
+    row: gtk::Box,
+    icon: gtk::Image,
+    label: gtk::Label,
+    scale: gtk::Scale,
+    scale_handler: glib::SignalHandlerId,
+    mute: gtk::ToggleButton,
+    mute_handler: glib::SignalHandlerId,
+}
+
+impl StreamRow {
+    fn new(target: Target, sender: &ComponentSender<Volume>) -> Self {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(8)
+            .css_classes(vec!["volume-row"])
+            .build();
+
+        let icon = gtk::Image::builder().pixel_size(ICON_SIZE).build();
+        let label = gtk::Label::builder()
+            .css_classes(vec!["volume-stream-name"])
+            .halign(gtk::Align::Start)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .max_width_chars(16)
+            .width_chars(16)
+            .xalign(0.0)
+            .build();
+
+        let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0);
+        scale.set_hexpand(true);
+        scale.set_width_request(160);
+        scale.add_css_class("volume-scale");
+        let scale_sender = sender.clone();
+        let scale_handler = scale.connect_value_changed(move |scale| {
+            let percent = scale.value().round() as u32;
+            scale_sender.input(VolumeMsg::SetVolume(target, percent));
+        });
+
+        let mute = gtk::ToggleButton::builder()
+            .css_classes(vec!["volume-mute"])
+            .tooltip_text("Mute")
+            .build();
+        let mute_sender = sender.clone();
+        let mute_handler = mute.connect_toggled(move |button| {
+            mute_sender.input(VolumeMsg::SetMute(target, button.is_active()));
+        });
+
+        row.append(&icon);
+        row.append(&label);
+        row.append(&scale);
+        row.append(&mute);
+
+        Self {
+            row,
+            icon,
+            label,
+            scale,
+            scale_handler,
+            mute,
+            mute_handler,
+        }
+    }
+
+    /// Show the current state without echoing it back to the server
+    fn update(&self, name: &str, percent: u32, muted: bool) {
+        self.label.set_label(name);
+        self.label.set_tooltip_text(Some(name));
+
+        self.scale.block_signal(&self.scale_handler);
+        self.scale.set_value(percent as f64);
+        self.scale.unblock_signal(&self.scale_handler);
+
+        self.mute.block_signal(&self.mute_handler);
+        self.mute.set_active(muted);
+        self.mute.unblock_signal(&self.mute_handler);
+        self.mute.set_label(volume_icon(percent, muted));
+        self.row.set_class_active("volume-row-muted", muted);
+    }
+}
+
+fn volume_icon(percent: u32, muted: bool) -> &'static str {
+    match percent {
+        _ if muted => "󰝟",
+        0 => "󰝟",
+        1..=33 => "󰕿",
+        34..=66 => "󰖀",
+        _ => "󰕾",
+    }
+}
+
+pub struct Volume {
+    config: VolumeConfig,
+    /// The default sink
+    sink: Option<Sink>,
+    /// Playback streams of applications
+    streams: Vec<SinkInput>,
+    popover: Controller<PopoverComponent>,
+    master: StreamRow,
+    /// Box holding one row per stream
+    mixer: gtk::Box,
+    rows: HashMap<u32, StreamRow>,
+    empty_label: gtk::Label,
+}
+
+#[derive(Debug)]
+pub enum VolumeMsg {
+    AudioChanged(AudioState),
+    TogglePopover,
+    /// Scroll on the bar, `true` for louder
+    Scroll(bool),
+    SetVolume(Target, u32),
+    SetMute(Target, bool),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Volume {
+    type Init = VolumeConfig;
+    type Input = VolumeMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_css_classes: &["volume-widget", "widget"],
+            set_visible: model.config.enabled,
+            #[watch]
+            set_class_active: ("volume-muted", model.muted()),
+
+            #[name = "volume_button"]
+            gtk::Button {
+                set_css_classes: &["volume-button"],
+                #[watch]
+                set_sensitive: model.sink.is_some(),
+                #[watch]
+                set_tooltip_text: Some(
+                    model.sink.as_ref().map_or("No output device", |sink| sink.description.as_str()),
+                ),
+                connect_clicked => VolumeMsg::TogglePopover,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&format!(
+                    "Volume {}%{}",
+                    model.percent(),
+                    if model.muted() { ", muted" } else { "" },
+                ))],
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        set_css_classes: &["volume-icon"],
+                        #[watch]
+                        set_label: volume_icon(model.percent(), model.muted()),
+                    },
+
+                    gtk::Label {
+                        set_css_classes: &["volume-label"],
+                        #[watch]
+                        set_label: &format!("{}%", model.percent()),
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Volume".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(320),
+                ..Default::default()
+            })
+            .detach();
+
+        let mixer = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(4)
+            .css_classes(vec!["volume-mixer"])
+            .build();
+        let empty_label = gtk::Label::builder()
+            .label("No applications are playing audio")
+            .css_classes(vec!["volume-empty"])
+            .halign(gtk::Align::Start)
+            .build();
+        mixer.append(&empty_label);
+
+        let master = StreamRow::new(Target::Master, &sender);
+        master.icon.set_icon_name(Some("audio-speakers"));
+
+        // The rows update in place, so the items are only set once
+        popover.emit(PopoverMsg::UpdateItems(vec![
+            PopoverItem::Custom(master.row.clone().upcast()),
+            PopoverItem::Separator,
+            PopoverItem::Custom(mixer.clone().upcast()),
+        ]));
+
+        let model = Volume {
+            config,
+            sink: None,
+            streams: Vec::new(),
+            popover,
+            master,
+            mixer,
+            rows: HashMap::new(),
+            empty_label,
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.volume_button.clone().upcast::<gtk::Widget>());
+
+        if !model.config.enabled {
+            return ComponentParts { model, widgets };
+        }
+
+        // Plain scrolling changes the output volume
+        let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        let scroll_sender = sender.clone();
+        scroll.connect_scroll(move |controller, _, dy| {
+            if controller
+                .current_event_state()
+                .contains(gdk::ModifierType::SHIFT_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+            if dy != 0.0 {
+                scroll_sender.input(VolumeMsg::Scroll(dy < 0.0));
+            }
+            glib::Propagation::Stop
+        });
+        root.add_controller(scroll);
+
+        let mut audio_rx = audio::subscribe();
+        relm4::spawn(async move {
+            loop {
+                let state = audio_rx.borrow_and_update().clone();
+                if !poll::deliver(&sender, VolumeMsg::AudioChanged(state)) {
+                    break;
+                }
+                if audio_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            VolumeMsg::AudioChanged(state) => {
+                self.sink = state.default_sink().cloned();
+                self.streams = state.sink_inputs;
+                self.sync_rows(&sender);
+            }
+            VolumeMsg::TogglePopover => {
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+            VolumeMsg::Scroll(up) => {
+                let percent = if up {
+                    (self.percent() + self.config.step).min(100)
+                } else {
+                    self.percent().saturating_sub(self.config.step)
+                };
+                self.apply(Target::Master, move |stream| {
+                    audio::set_volume(stream, percent)
+                });
+            }
+            VolumeMsg::SetVolume(target, percent) => {
+                self.apply(target, move |stream| audio::set_volume(stream, percent));
+            }
+            VolumeMsg::SetMute(target, mute) => {
+                self.apply(target, move |stream| audio::set_mute(stream, mute));
+            }
+        }
+    }
+}
+
+impl Volume {
+    fn percent(&self) -> u32 {
+        self.sink.as_ref().map_or(0, Sink::volume_percent)
+    }
+
+    fn muted(&self) -> bool {
+        self.sink.as_ref().is_some_and(|sink| sink.mute)
+    }
+
+    /// Run a pactl change off the main thread
+    ///
+    /// pactl reports the result back through the audio service.
+    fn apply(
+        &self,
+        target: Target,
+        change: impl FnOnce(Stream) -> Result<(), String> + Send + 'static,
+    ) {
+        let stream = match target {
+            Target::Master => match &self.sink {
+                Some(sink) => Stream::Sink(sink.index),
+                None => return,
+            },
+            Target::SinkInput(index) => Stream::SinkInput(index),
+        };
+        thread::spawn(move || {
+            if let Err(e) = change(stream) {
+                eprintln!("Failed to change volume: {}", e);
+            }
+        });
+    }
+
+    /// Bring the master row and the per-stream rows up to date
+    fn sync_rows(&mut self, sender: &ComponentSender<Self>) {
+        if let Some(sink) = &self.sink {
+            self.master
+                .update(&sink.description, sink.volume_percent(), sink.mute);
+        }
+        self.master.row.set_sensitive(self.sink.is_some());
+
+        let streams = &self.streams;
+        let mixer = &self.mixer;
+        self.rows.retain(|index, row| {
+            let open = streams.iter().any(|stream| stream.index == *index);
+            if !open {
+                mixer.remove(&row.row);
+            }
+            open
+        });
+
+        for stream in streams {
+            let row = self.rows.entry(stream.index).or_insert_with(|| {
+                let row = StreamRow::new(Target::SinkInput(stream.index), sender);
+                row.icon
+                    .set_from_gicon(&app_icon(stream.icon_name().unwrap_or_default()));
+                mixer.append(&row.row);
+                row
+            });
+            row.update(stream.app_name(), stream.volume_percent(), stream.mute);
+            row.row.set_tooltip_text(stream.media_name());
+        }
+        self.empty_label.set_visible(streams.is_empty());
+    }
+}
+
+impl Snapshot for Volume {
+    fn snapshot(&self) -> serde_json::Value {
+        let streams: Vec<serde_json::Value> = self
+            .streams
+            .iter()
+            .map(|stream| {
+                json!({
+                    "index": stream.index,
+                    "app": stream.app_name(),
+                    "volume": stream.volume_percent(),
+                    "mute": stream.mute,
+                })
+            })
+            .collect();
+        json!({
+            "enabled": self.config.enabled,
+            "sink": self.sink.as_ref().map(|sink| &sink.description),
+            "volume": self.percent(),
+            "mute": self.muted(),
+            "streams": streams,
+        })
+    }
+}
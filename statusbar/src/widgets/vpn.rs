@@ -0,0 +1,335 @@
+use gtk::glib;
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde_json::json;
+use std::thread;
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::VpnConfig;
+use crate::services::vpn::VpnStatus;
+
+pub struct Vpn {
+    config: VpnConfig,
+    /// None until the daemon answers, or while it can't be reached
+    status: Option<VpnStatus>,
+    /// An action is in flight
+    busy: bool,
+    error: Option<String>,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum VpnMsg {
+    Refresh,
+    StatusChanged(Result<VpnStatus, String>),
+    SetConnected(bool),
+    SetExitNode(Option<String>),
+    ActionDone(Result<(), String>),
+    TogglePopover,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Vpn {
+    type Init = VpnConfig;
+    type Input = VpnMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_visible: model.config.enabled,
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            #[name = "vpn_button"]
+            gtk::Button {
+                set_css_classes: &["vpn-button"],
+                connect_clicked => VpnMsg::TogglePopover,
+                update_property: &[gtk::accessible::Property::Label("VPN")],
+                #[watch]
+                set_tooltip_text: Some(&model.tooltip()),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: if model.connected() { "󰦝" } else { "󰦞" },
+                        set_css_classes: &["vpn-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_visible: model.exit_node().is_some(),
+                        #[watch]
+                        set_label: model.exit_node().unwrap_or_default(),
+                        set_css_classes: &["vpn-label"],
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: config.backend.name().to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(280),
+                ..Default::default()
+            })
+            .detach();
+
+        let mut model = Vpn {
+            config,
+            status: None,
+            busy: false,
+            error: None,
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.vpn_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let sender_clone = sender.clone();
+            let interval = model.config.interval_seconds.max(1) as u32;
+            model.timers.push(poll::every(interval, move || {
+                sender_clone.input(VpnMsg::Refresh)
+            }));
+            sender.input(VpnMsg::Refresh);
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            VpnMsg::Refresh => {
+                let backend = self.config.backend;
+                thread::spawn(move || {
+                    sender.input(VpnMsg::StatusChanged(backend.status()));
+                });
+            }
+            VpnMsg::StatusChanged(result) => {
+                let status = match result {
+                    Ok(status) => Some(status),
+                    Err(e) => {
+                        // Only worth logging the first time it goes away
+                        if self.status.is_some() {
+                            eprintln!("Failed to get VPN status: {}", e);
+                        }
+                        None
+                    }
+                };
+                if status != self.status {
+                    self.status = status;
+                    self.update_popover_content(&sender);
+                }
+            }
+            VpnMsg::SetConnected(connected) => {
+                let backend = self.config.backend;
+                self.run(&sender, move || backend.set_connected(connected));
+            }
+            VpnMsg::SetExitNode(id) => {
+                let backend = self.config.backend;
+                self.run(&sender, move || backend.set_exit_node(id.as_deref()));
+            }
+            VpnMsg::ActionDone(result) => {
+                self.busy = false;
+                self.error = result.err();
+                if let Some(e) = &self.error {
+                    eprintln!("{}", e);
+                }
+                self.update_popover_content(&sender);
+                sender.input(VpnMsg::Refresh);
+            }
+            VpnMsg::TogglePopover => {
+                self.update_popover_content(&sender);
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+        }
+    }
+}
+
+impl Vpn {
+    fn connected(&self) -> bool {
+        self.status.as_ref().is_some_and(|status| status.connected)
+    }
+
+    fn exit_node(&self) -> Option<&str> {
+        self.status
+            .as_ref()
+            .filter(|status| status.connected)
+            .and_then(|status| status.exit_node.as_deref())
+    }
+
+    /// Run an action off the main thread, then refresh
+    fn run(
+        &mut self,
+        sender: &ComponentSender<Self>,
+        action: impl FnOnce() -> Result<(), String> + Send + 'static,
+    ) {
+        if self.busy {
+            return;
+        }
+        self.busy = true;
+        self.update_popover_content(sender);
+        let sender = sender.clone();
+        thread::spawn(move || sender.input(VpnMsg::ActionDone(action())));
+    }
+
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["vpn-widget", "widget"];
+        if self.connected() {
+            classes.push("vpn-connected");
+        }
+        classes
+    }
+
+    fn tooltip(&self) -> String {
+        let name = self.config.backend.name();
+        match &self.status {
+            None => format!("{} is not running", name),
+            Some(status) if status.connected => match &status.exit_node {
+                Some(exit_node) => format!("{} connected via {}", name, exit_node),
+                None => format!("{} connected", name),
+            },
+            Some(status) => format!("{}: {}", name, status.state),
+        }
+    }
+
+    fn update_popover_content(&self, sender: &ComponentSender<Self>) {
+        let detail = |label: &str, value: String| PopoverItem::DetailRow {
+            label: label.to_string(),
+            value,
+            value_css: "vpn-detail".to_string(),
+        };
+
+        let Some(status) = &self.status else {
+            self.popover.emit(PopoverMsg::UpdateItems(vec![detail(
+                "Not running",
+                String::new(),
+            )]));
+            return;
+        };
+
+        let mut items = vec![PopoverItem::Custom(self.create_header(status, sender))];
+        if let Some(exit_node) = &status.exit_node {
+            items.push(detail("Exit node", exit_node.clone()));
+        }
+
+        if !status.exit_nodes.is_empty() {
+            items.push(PopoverItem::Separator);
+            let list = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            let current = status.exit_node.as_deref();
+            list.append(&self.exit_node_button("No exit node", None, current.is_none(), sender));
+            for node in &status.exit_nodes {
+                let button = self.exit_node_button(
+                    &node.name,
+                    Some(node.id.clone()),
+                    current == Some(node.name.as_str()),
+                    sender,
+                );
+                button.set_sensitive(node.online && !self.busy);
+                list.append(&button);
+            }
+            items.push(PopoverItem::Custom(list.upcast()));
+        }
+
+        if !status.peers.is_empty() {
+            items.push(PopoverItem::Separator);
+            for peer in &status.peers {
+                items.push(PopoverItem::DetailRow {
+                    label: peer.name.clone(),
+                    value: peer.ip.clone(),
+                    value_css: if peer.online {
+                        "vpn-peer"
+                    } else {
+                        "vpn-peer-offline"
+                    }
+                    .to_string(),
+                });
+            }
+        }
+
+        if let Some(error) = &self.error {
+            items.push(PopoverItem::Separator);
+            items.push(detail("Error", error.clone()));
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+
+    /// Daemon state with the connect switch
+    fn create_header(&self, status: &VpnStatus, sender: &ComponentSender<Self>) -> gtk::Widget {
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        header.set_css_classes(&["vpn-header"]);
+
+        let label = gtk::Label::new(Some(&status.state));
+        label.set_halign(gtk::Align::Start);
+        label.set_hexpand(true);
+        label.set_css_classes(&["vpn-state"]);
+        header.append(&label);
+
+        let switch = gtk::Switch::new();
+        switch.set_active(status.connected);
+        switch.set_sensitive(!self.busy);
+        switch.set_valign(gtk::Align::Center);
+        let switch_sender = sender.clone();
+        switch.connect_state_set(move |_, connected| {
+            switch_sender.input(VpnMsg::SetConnected(connected));
+            glib::Propagation::Proceed
+        });
+        header.append(&switch);
+
+        header.upcast()
+    }
+
+    fn exit_node_button(
+        &self,
+        name: &str,
+        id: Option<String>,
+        current: bool,
+        sender: &ComponentSender<Self>,
+    ) -> gtk::Button {
+        let button = gtk::Button::with_label(name);
+        button.set_css_classes(if current {
+            &["vpn-exit-node", "vpn-exit-node-current"]
+        } else {
+            &["vpn-exit-node"]
+        });
+        button.set_sensitive(!self.busy);
+        let exit_sender = sender.clone();
+        button.connect_clicked(move |_| {
+            exit_sender.input(VpnMsg::SetExitNode(id.clone()));
+        });
+        button
+    }
+}
+
+impl Snapshot for Vpn {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "backend": self.config.backend.name(),
+            "status": self.status,
+            "error": self.error,
+        })
+    }
+}
@@ -0,0 +1,370 @@
+use chrono::{DateTime, Local, TimeZone};
+use gtk::prelude::*;
+use relm4::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::poll::{self, Poll};
+use super::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use super::snapshot::Snapshot;
+use crate::config::WeatherConfig;
+use crate::services::{http, timedate};
+
+/// Conditions from the last successful fetch, persisted as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conditions {
+    pub area: String,
+    pub description: String,
+    /// wttr.in (WWO) weather code, e.g. 113 for clear sky
+    pub code: u32,
+    pub temperature_c: i32,
+    pub feels_like_c: i32,
+    pub humidity: u32,
+    pub wind_kmph: u32,
+    /// Unix timestamp of the fetch
+    pub fetched_at: i64,
+}
+
+impl Conditions {
+    fn cache_path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("statusbar").join("weather.json"))
+    }
+
+    fn load_cached() -> Option<Self> {
+        let contents = fs::read_to_string(Self::cache_path()?).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::cache_path() else {
+            return;
+        };
+
+        if let Some(Err(e)) = path.parent().map(fs::create_dir_all) {
+            eprintln!("Failed to create the folder of {}: {}", path.display(), e);
+            return;
+        }
+
+        match serde_json::to_string(self) {
+            Ok(contents) => {
+                if let Err(e) = fs::write(&path, contents) {
+                    eprintln!("Failed to cache weather to {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize weather: {}", e),
+        }
+    }
+
+    /// Read the `format=j1` reply of wttr.in
+    fn parse(reply: &Value) -> Option<Self> {
+        let current = &reply["current_condition"][0];
+        let area = reply["nearest_area"][0]["areaName"][0]["value"]
+            .as_str()
+            .unwrap_or_default();
+
+        Some(Self {
+            area: area.to_string(),
+            description: current["weatherDesc"][0]["value"]
+                .as_str()?
+                .trim()
+                .to_string(),
+            code: parse_number(&current["weatherCode"])?,
+            temperature_c: parse_number(&current["temp_C"])?,
+            feels_like_c: parse_number(&current["FeelsLikeC"])?,
+            humidity: parse_number(&current["humidity"])?,
+            wind_kmph: parse_number(&current["windspeedKmph"])?,
+            fetched_at: Local::now().timestamp(),
+        })
+    }
+
+    fn fetched_at(&self) -> Option<DateTime<Local>> {
+        Local.timestamp_opt(self.fetched_at, 0).single()
+    }
+
+    /// Time of the fetch, e.g. `14:05`
+    fn fetched_time(&self) -> String {
+        self.fetched_at()
+            .map(|time| time.format("%H:%M").to_string())
+            .unwrap_or_default()
+    }
+
+    /// wttr.in-style symbol for the weather code
+    fn icon(&self) -> &'static str {
+        match self.code {
+            113 => "☀",
+            116 => "⛅",
+            119 | 122 => "☁",
+            143 | 248 | 260 => "🌫",
+            200 | 386 | 389 | 392 | 395 => "⛈",
+            179 | 227 | 230 | 323..=338 | 368 | 371 => "❄",
+            182 | 185 | 281 | 284 | 311 | 314 | 317 | 350 | 362 | 365 | 374 | 377 => "🌨",
+            _ => "🌧",
+        }
+    }
+}
+
+/// wttr.in sends numbers as strings
+fn parse_number<T: FromStr>(value: &Value) -> Option<T> {
+    value.as_str()?.trim().parse().ok()
+}
+
+pub struct Weather {
+    config: WeatherConfig,
+    conditions: Option<Conditions>,
+    /// The last fetch failed, so `conditions` may be out of date
+    stale: bool,
+    fetching: bool,
+    error: Option<String>,
+    popover: Controller<PopoverComponent>,
+    timers: Vec<Poll>,
+}
+
+#[derive(Debug)]
+pub enum WeatherMsg {
+    Refresh,
+    Fetched(Result<Conditions, String>),
+    TogglePopover,
+    /// The system timezone changed, so fetch times need reformatting
+    TimeSettingsChanged,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Weather {
+    type Init = WeatherConfig;
+    type Input = WeatherMsg;
+    type Output = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 2,
+            set_visible: model.config.enabled,
+            #[watch]
+            set_css_classes: &model.css_classes(),
+
+            #[name = "weather_button"]
+            gtk::Button {
+                set_css_classes: &["weather-button"],
+                connect_clicked => WeatherMsg::TogglePopover,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&format!("Weather {}", model.temperature()))],
+                #[watch]
+                set_tooltip_text: model.error.as_deref(),
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 4,
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: model.conditions.as_ref().map_or("?", Conditions::icon),
+                        set_css_classes: &["weather-icon"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model.temperature(),
+                        set_css_classes: &["weather-label"],
+                    },
+
+                    gtk::Label {
+                        #[watch]
+                        set_visible: model.stale && model.conditions.is_some(),
+                        #[watch]
+                        set_label: &model.as_of(),
+                        set_css_classes: &["weather-as-of"],
+                    },
+                }
+            },
+
+            // Offered once fetching fails, instead of waiting for the next interval
+            gtk::Button {
+                #[watch]
+                set_visible: model.stale,
+                #[watch]
+                set_sensitive: !model.fetching,
+                set_label: "↻",
+                update_property: &[gtk::accessible::Property::Label("Refresh weather")],
+                set_tooltip_text: Some("Refresh"),
+                set_css_classes: &["weather-refresh"],
+                connect_clicked => WeatherMsg::Refresh,
+            },
+        }
+    }
+
+    fn init(
+        config: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let popover = PopoverComponent::builder()
+            .launch(PopoverInit {
+                parent: None,
+                title: "Weather".to_string(),
+                margin: 12,
+                spacing: 8,
+                width: Some(260),
+                ..Default::default()
+            })
+            .detach();
+
+        // Show the last known conditions until the first fetch completes
+        let conditions = Conditions::load_cached();
+        let mut model = Weather {
+            stale: conditions.is_some(),
+            conditions,
+            config,
+            fetching: false,
+            error: None,
+            popover,
+            timers: Vec::new(),
+        };
+
+        let widgets = view_output!();
+
+        model
+            .popover
+            .model()
+            .set_parent(&widgets.weather_button.clone().upcast::<gtk::Widget>());
+
+        if model.config.enabled {
+            let sender_clone = sender.clone();
+            let interval = (model.config.interval_minutes.max(1) * 60) as u32;
+            model.timers.push(poll::every(interval, move || {
+                sender_clone.input(WeatherMsg::Refresh)
+            }));
+            sender.input(WeatherMsg::Refresh);
+
+            let mut time_settings_rx = timedate::subscribe();
+            relm4::spawn(async move {
+                while time_settings_rx.changed().await.is_ok() {
+                    if !poll::deliver(&sender, WeatherMsg::TimeSettingsChanged) {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            WeatherMsg::Refresh => {
+                if self.fetching {
+                    return;
+                }
+                self.fetching = true;
+
+                let location = self.config.location.clone();
+                relm4::spawn(async move {
+                    sender.input(WeatherMsg::Fetched(Self::fetch(&location).await));
+                });
+            }
+            WeatherMsg::Fetched(result) => {
+                self.fetching = false;
+                match result {
+                    Ok(conditions) => {
+                        conditions.save();
+                        self.conditions = Some(conditions);
+                        self.stale = false;
+                        self.error = None;
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to fetch weather: {}", e);
+                        self.stale = true;
+                        self.error = Some(e);
+                    }
+                }
+                self.update_popover_content();
+            }
+            WeatherMsg::TogglePopover => {
+                self.update_popover_content();
+                self.popover.emit(PopoverMsg::Toggle);
+            }
+            WeatherMsg::TimeSettingsChanged => self.update_popover_content(),
+        }
+    }
+}
+
+impl Weather {
+    fn css_classes(&self) -> Vec<&'static str> {
+        let mut classes = vec!["weather-widget", "widget"];
+        if self.stale {
+            classes.push("weather-stale");
+        }
+        classes
+    }
+
+    fn temperature(&self) -> String {
+        match &self.conditions {
+            Some(conditions) => format!("{}°C", conditions.temperature_c),
+            None if self.fetching => "…".to_string(),
+            None => "—".to_string(),
+        }
+    }
+
+    fn as_of(&self) -> String {
+        self.conditions
+            .as_ref()
+            .map(|conditions| format!("as of {}", conditions.fetched_time()))
+            .unwrap_or_default()
+    }
+
+    async fn fetch(location: &str) -> Result<Conditions, String> {
+        // An empty location lets wttr.in locate us by IP
+        let url = format!("https://wttr.in/{}?format=j1", location.replace(' ', "+"));
+        let reply: Value = http::get_json(&url).await?;
+        Conditions::parse(&reply).ok_or_else(|| "Unexpected reply from wttr.in".to_string())
+    }
+
+    fn update_popover_content(&self) {
+        let detail = |label: &str, value: String| PopoverItem::DetailRow {
+            label: label.to_string(),
+            value,
+            value_css: "weather-detail".to_string(),
+        };
+
+        let mut items = Vec::new();
+        match &self.conditions {
+            Some(conditions) => {
+                if !conditions.area.is_empty() {
+                    items.push(detail("Location", conditions.area.clone()));
+                }
+                items.push(detail("Conditions", conditions.description.clone()));
+                items.push(detail(
+                    "Feels like",
+                    format!("{}°C", conditions.feels_like_c),
+                ));
+                items.push(detail("Humidity", format!("{}%", conditions.humidity)));
+                items.push(detail("Wind", format!("{} km/h", conditions.wind_kmph)));
+                items.push(PopoverItem::Separator);
+                items.push(detail(
+                    if self.stale { "Last known" } else { "Updated" },
+                    conditions.fetched_time(),
+                ));
+            }
+            None => items.push(detail("No weather data yet", String::new())),
+        }
+
+        if let Some(error) = &self.error {
+            items.push(detail("Error", error.clone()));
+        }
+
+        self.popover.emit(PopoverMsg::UpdateItems(items));
+    }
+}
+
+impl Snapshot for Weather {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "enabled": self.config.enabled,
+            "conditions": self.conditions,
+            "stale": self.stale,
+            "error": self.error,
+        })
+    }
+}
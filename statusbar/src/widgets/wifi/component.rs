@@ -1,23 +1,37 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use serde_json::json;
 
+use super::eap;
 use super::models::NetworkItem;
-use nm_dbus::NetworkManagerClient;
-use crate::widgets::popover::{PopoverComponent, PopoverInit, PopoverItem, PopoverMsg};
+use crate::config::WiFiConfig;
+use crate::services::{health, network, notify, privacy};
+use crate::widgets::poll::{self, Poll};
+use crate::widgets::popover::{
+    ListRow, PopoverComponent, PopoverInit, PopoverItem, PopoverMsg, PopoverOutput,
+};
+use crate::widgets::snapshot::Snapshot;
+use crate::widgets::tooltip;
+use nm_dbus::{AccessPoint, ActiveConnection, Security};
+use tokio::task::JoinHandle;
 
 pub struct WiFi {
+    config: WiFiConfig,
     ssid: String,
     signal_strength: i32,
     is_connected: bool,
     interface: String,
     ip_address: String,
+    /// Object path of the access point connected to
+    access_point: String,
     available_networks: Vec<NetworkItem>,
     is_scanning: bool,
+    /// Networks are only rescanned while the popover is open
+    popover_open: bool,
+    /// Follows the connected access point's strength while the popover is open
+    strength_watch: Option<JoinHandle<()>>,
     popover: Controller<PopoverComponent>,
-    nm_client: Arc<Mutex<Option<NetworkManagerClient>>>,
-    sender: Option<ComponentSender<Self>>,
+    timers: Vec<Poll>,
 }
 
 #[derive(Debug, Clone)]
@@ -25,17 +39,29 @@ pub enum WiFiMsg {
     Update,
     TogglePopover,
     ScanNetworks,
-    NetworksScanned(Vec<NetworkItem>),
     ConnectToNetwork(String),
     Disconnect,
+    PrivacyChanged,
+    /// New signal strength of the access point at the path
+    StrengthChanged(String, u8),
+    /// Interaction with a popover item
+    Popover(PopoverOutput),
+}
+
+/// Results of NetworkManager calls
+#[derive(Debug)]
+pub enum WiFiCommand {
+    Refreshed(Result<Option<ActiveConnection>, String>),
+    Scanned(Result<Vec<AccessPoint>, String>),
     ConnectionResult(Result<(), String>),
 }
 
 #[relm4::component(pub)]
-impl SimpleComponent for WiFi {
-    type Init = ();
+impl Component for WiFi {
+    type Init = WiFiConfig;
     type Input = WiFiMsg;
     type Output = ();
+    type CommandOutput = WiFiCommand;
 
     view! {
         gtk::Box {
@@ -48,20 +74,22 @@ impl SimpleComponent for WiFi {
                 connect_clicked => WiFiMsg::TogglePopover,
                 #[watch]
                 set_visible: model.is_connected,
+                #[watch]
+                update_property: &[gtk::accessible::Property::Label(&model.accessible_label())],
 
                 gtk::Label {
                     #[watch]
                     set_label: &Self::signal_icon(model.signal_strength),
                     set_css_classes: &["wifi-icon"],
                     #[watch]
-                    set_tooltip_text: Some(&model.ssid),
+                    set_tooltip_markup: model.tooltip().as_deref(),
                 }
             }
         }
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -73,20 +101,24 @@ impl SimpleComponent for WiFi {
                 margin: 12,
                 spacing: 8,
                 width: Some(320),
+                ..Default::default()
             })
-            .detach();
+            .forward(sender.input_sender(), WiFiMsg::Popover);
 
-        let model = WiFi {
+        let mut model = WiFi {
+            config,
             ssid: String::new(),
             signal_strength: 0,
             is_connected: false,
             interface: String::new(),
             ip_address: String::new(),
+            access_point: String::new(),
             available_networks: Vec::new(),
             is_scanning: false,
+            popover_open: false,
+            strength_watch: None,
             popover,
-            nm_client: Arc::new(Mutex::new(None)),
-            sender: Some(sender.clone()),
+            timers: Vec::new(),
         };
 
         let widgets = view_output!();
@@ -97,203 +129,206 @@ impl SimpleComponent for WiFi {
             .model()
             .set_parent(&widgets.wifi_button.clone().upcast::<gtk::Widget>());
 
-        // Initialize NetworkManager client asynchronously
-        let nm_client = model.nm_client.clone();
-        let sender_clone = sender.clone();
-        tokio::spawn(async move {
-            match NetworkManagerClient::new().await {
-                Ok(client) => {
-                    *nm_client.lock().await = Some(client);
-                    sender_clone.input(WiFiMsg::Update);
-                }
-                Err(e) => {
-                    eprintln!("Failed to initialize NetworkManager client: {}", e);
+        // Re-render when the privacy screen is toggled
+        let mut privacy_rx = privacy::subscribe();
+        let privacy_sender = sender.clone();
+        relm4::spawn(async move {
+            while privacy_rx.changed().await.is_ok() {
+                if !poll::deliver(&privacy_sender, WiFiMsg::PrivacyChanged) {
+                    break;
                 }
             }
         });
 
         // Update every 10 seconds (reduced frequency since we have D-Bus signals)
         let sender_clone = sender.clone();
-        gtk::glib::timeout_add_seconds_local(10, move || {
-            sender_clone.input(WiFiMsg::Update);
-            gtk::glib::ControlFlow::Continue
+        model
+            .timers
+            .push(poll::every(10, move || sender_clone.input(WiFiMsg::Update)));
+        let retry_sender = sender.clone();
+        health::on_retry(health::NETWORK, move || {
+            poll::deliver(&retry_sender, WiFiMsg::Update)
         });
+        sender.input(WiFiMsg::Update);
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
         match msg {
             WiFiMsg::Update => {
-                self.refresh_wifi_info(sender.clone());
+                sender.oneshot_command(async {
+                    WiFiCommand::Refreshed(network::active_connection().await)
+                });
+                if self.popover_open {
+                    sender.input(WiFiMsg::ScanNetworks);
+                }
             }
             WiFiMsg::TogglePopover => {
                 self.popover.emit(PopoverMsg::Toggle);
-                // Scan for networks when popover opens
-                sender.input(WiFiMsg::ScanNetworks);
             }
             WiFiMsg::ScanNetworks => {
                 if !self.is_scanning {
-                    self.scan_networks(sender.clone());
+                    self.is_scanning = true;
+                    sender.oneshot_command(async { WiFiCommand::Scanned(network::scan().await) });
                 }
             }
-            WiFiMsg::NetworksScanned(networks) => {
-                self.is_scanning = false;
-                self.available_networks = networks;
+            WiFiMsg::PrivacyChanged => {
+                self.update_popover_content();
+            }
+            WiFiMsg::StrengthChanged(path, strength) => {
+                if path != self.access_point {
+                    return;
+                }
+                self.signal_strength = i32::from(strength);
+                for network in &mut self.available_networks {
+                    if network.is_connected {
+                        network.strength = strength;
+                    }
+                }
                 self.update_popover_content();
             }
             WiFiMsg::ConnectToNetwork(ssid) => {
-                self.connect_to_network(ssid, sender.clone());
+                // 802.1X needs more than a password, so ask for it here
+                let enterprise = self
+                    .available_networks
+                    .iter()
+                    .any(|n| n.ssid == ssid && n.security == Security::Enterprise);
+                if enterprise {
+                    let sender = sender.clone();
+                    eap::ask(&ssid, move || sender.input(WiFiMsg::Update));
+                    return;
+                }
+                sender.oneshot_command(async move {
+                    WiFiCommand::ConnectionResult(network::connect(&ssid).await)
+                });
             }
             WiFiMsg::Disconnect => {
-                self.disconnect(sender.clone());
+                let interface = self.interface.clone();
+                sender.oneshot_command(async move {
+                    WiFiCommand::ConnectionResult(network::disconnect(&interface).await)
+                });
             }
-            WiFiMsg::ConnectionResult(result) => {
-                match result {
-                    Ok(_) => {
-                        // Refresh after successful connection
-                        sender.input(WiFiMsg::Update);
-                        sender.input(WiFiMsg::ScanNetworks);
-                    }
-                    Err(e) => {
-                        eprintln!("Connection error: {}", e);
+            WiFiMsg::Popover(PopoverOutput::Action(action_id)) => match action_id.as_str() {
+                "disconnect" => sender.input(WiFiMsg::Disconnect),
+                "refresh" => sender.input(WiFiMsg::ScanNetworks),
+                action_id => {
+                    if let Some(ssid) = action_id.strip_prefix("connect:") {
+                        sender.input(WiFiMsg::ConnectToNetwork(ssid.to_string()));
                     }
                 }
+            },
+            WiFiMsg::Popover(PopoverOutput::Opened) => {
+                self.popover_open = true;
+                self.watch_strength(&sender);
+                sender.input(WiFiMsg::ScanNetworks);
+            }
+            WiFiMsg::Popover(PopoverOutput::Closed) => {
+                self.popover_open = false;
+                self.watch_strength(&sender);
             }
+            WiFiMsg::Popover(_) => {}
         }
     }
-}
 
-impl WiFi {
-    fn refresh_wifi_info(&mut self, _sender: ComponentSender<Self>) {
-        let nm_client = self.nm_client.clone();
-
-        tokio::spawn(async move {
-            if let Some(client) = nm_client.lock().await.as_ref() {
-                match client.get_active_connection().await {
-                    Ok(Some(_conn)) => {
-                        // Will trigger update through message passing
-                        // For now, we'll just log it
-                    }
-                    Ok(None) => {
-                        // No active connection
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to get active connection: {}", e);
-                    }
+    fn update_cmd(
+        &mut self,
+        msg: Self::CommandOutput,
+        sender: ComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match msg {
+            WiFiCommand::Refreshed(Ok(connection)) => {
+                health::clear(health::NETWORK);
+                let access_point = self.access_point.clone();
+                self.set_connection(connection);
+                if self.access_point != access_point {
+                    self.watch_strength(&sender);
                 }
+                self.update_popover_content();
             }
-        });
-
-        // Temporary: Still use nmcli for current connection info
-        // This will be replaced with D-Bus signals in a future enhancement
-        self.refresh_wifi_info_nmcli();
-        self.update_popover_content();
-    }
-
-    fn refresh_wifi_info_nmcli(&mut self) {
-        // Fallback to nmcli for current connection (temporary)
-        if let Ok(output) = std::process::Command::new("nmcli")
-            .args(["-t", "-f", "ACTIVE,SSID,SIGNAL,DEVICE", "dev", "wifi"])
-            .output()
-        {
-            if let Ok(result) = String::from_utf8(output.stdout) {
-                for line in result.lines() {
-                    let parts: Vec<&str> = line.split(':').collect();
-                    if parts.len() >= 4 && parts[0] == "yes" {
-                        self.ssid = parts[1].to_string();
-                        self.signal_strength = parts[2].parse().unwrap_or(0);
-                        self.interface = parts[3].to_string();
-                        self.is_connected = true;
-
-                        // Get IP address
-                        if let Ok(ip_output) = std::process::Command::new("ip")
-                            .args(["-4", "addr", "show", &self.interface])
-                            .output()
-                        {
-                            if let Ok(ip_result) = String::from_utf8(ip_output.stdout) {
-                                for ip_line in ip_result.lines() {
-                                    if ip_line.trim().starts_with("inet ") {
-                                        if let Some(ip) = ip_line.trim().split_whitespace().nth(1) {
-                                            self.ip_address = ip.to_string();
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        return;
-                    }
-                }
+            WiFiCommand::Scanned(result) => {
+                self.is_scanning = false;
+                let access_points = result.unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    Vec::new()
+                });
+                self.available_networks = access_points
+                    .into_iter()
+                    .map(|ap| NetworkItem {
+                        is_connected: self.is_connected && ap.ssid == self.ssid,
+                        ssid: ap.ssid,
+                        strength: ap.strength,
+                        security: ap.security,
+                    })
+                    .collect();
+                self.update_popover_content();
+            }
+            WiFiCommand::ConnectionResult(Ok(())) => {
+                // Refresh after successful connection
+                sender.input(WiFiMsg::Update);
+                sender.input(WiFiMsg::ScanNetworks);
+            }
+            WiFiCommand::ConnectionResult(Err(e)) => {
+                // Clicked from the popover, so tell the user rather than the log
+                notify::send("Wi-Fi", &e);
+            }
+            WiFiCommand::Refreshed(Err(e)) => {
+                health::report(health::NETWORK, e);
             }
         }
-
-        // No active connection found
-        self.is_connected = false;
-        self.ssid = String::new();
-        self.signal_strength = 0;
-        self.interface = String::new();
-        self.ip_address = String::new();
     }
 
-    fn scan_networks(&mut self, sender: ComponentSender<Self>) {
-        self.is_scanning = true;
-        let nm_client = self.nm_client.clone();
-        let current_ssid = self.ssid.clone();
-
-        tokio::spawn(async move {
-            if let Some(client) = nm_client.lock().await.as_ref() {
-                match client.scan_networks().await {
-                    Ok(access_points) => {
-                        let networks: Vec<NetworkItem> = access_points
-                            .iter()
-                            .map(|ap| NetworkItem {
-                                ssid: ap.ssid.clone(),
-                                strength: ap.strength,
-                                is_secured: ap.is_secured,
-                                is_connected: ap.ssid == current_ssid,
-                            })
-                            .collect();
-
-                        sender.input(WiFiMsg::NetworksScanned(networks));
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to scan networks: {}", e);
-                        sender.input(WiFiMsg::NetworksScanned(Vec::new()));
-                    }
-                }
-            }
-        });
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if let Some(watch) = self.strength_watch.take() {
+            watch.abort();
+        }
     }
+}
 
-    fn connect_to_network(&self, ssid: String, sender: ComponentSender<Self>) {
-        let nm_client = self.nm_client.clone();
-
-        tokio::spawn(async move {
-            if let Some(client) = nm_client.lock().await.as_ref() {
-                let result = client
-                    .connect_to_network(&ssid)
-                    .await
-                    .map_err(|e| format!("Failed to connect: {}", e));
-
-                sender.input(WiFiMsg::ConnectionResult(result));
+impl WiFi {
+    fn set_connection(&mut self, connection: Option<ActiveConnection>) {
+        match connection {
+            Some(connection) => {
+                self.is_connected = true;
+                self.ssid = connection.ssid;
+                self.signal_strength = i32::from(connection.strength);
+                self.interface = connection.interface;
+                self.ip_address = connection.ip_address;
+                self.access_point = connection.access_point;
             }
-        });
+            None => {
+                self.is_connected = false;
+                self.ssid = String::new();
+                self.signal_strength = 0;
+                self.interface = String::new();
+                self.ip_address = String::new();
+                self.access_point = String::new();
+            }
+        }
     }
 
-    fn disconnect(&self, sender: ComponentSender<Self>) {
-        let nm_client = self.nm_client.clone();
-        let interface = self.interface.clone();
-
-        tokio::spawn(async move {
-            if let Some(client) = nm_client.lock().await.as_ref() {
-                let result = client
-                    .disconnect(&interface)
-                    .await
-                    .map_err(|e| format!("Failed to disconnect: {}", e));
+    /// Follow the connected access point's signal strength while the popover
+    /// is open, so it can be watched live; stop otherwise
+    fn watch_strength(&mut self, sender: &ComponentSender<Self>) {
+        if let Some(watch) = self.strength_watch.take() {
+            watch.abort();
+        }
+        if !self.popover_open || self.access_point.is_empty() {
+            return;
+        }
 
-                sender.input(WiFiMsg::ConnectionResult(result));
+        let path = self.access_point.clone();
+        let sender = sender.clone();
+        self.strength_watch = Some(relm4::spawn(async move {
+            let result = network::watch_strength(&path, |strength| {
+                sender.input(WiFiMsg::StrengthChanged(path.clone(), strength))
+            })
+            .await;
+            if let Err(e) = result {
+                eprintln!("{}", e);
             }
-        });
+        }));
     }
 
     fn update_popover_content(&self) {
@@ -303,7 +338,7 @@ impl WiFi {
         if self.is_connected {
             items.push(PopoverItem::DetailRow {
                 label: "Network".to_string(),
-                value: self.ssid.clone(),
+                value: privacy::redact(&self.ssid).to_string(),
                 value_css: "wifi-detail".to_string(),
             });
             items.push(PopoverItem::DetailRow {
@@ -320,16 +355,31 @@ impl WiFi {
             if !self.ip_address.is_empty() {
                 items.push(PopoverItem::DetailRow {
                     label: "IP Address".to_string(),
-                    value: self.ip_address.clone(),
+                    value: privacy::redact(&self.ip_address).to_string(),
                     value_css: "wifi-detail".to_string(),
                 });
             }
 
+            items.push(PopoverItem::Button {
+                label: "Disconnect".to_string(),
+                icon: Some("network-wireless-disconnected-symbolic".to_string()),
+                css: "disconnect-button".to_string(),
+                action_id: "disconnect".to_string(),
+            });
             items.push(PopoverItem::Separator);
         }
 
         // Available networks section
-        items.push(PopoverItem::Custom(self.create_networks_header()));
+        let header = gtk::Label::new(Some("Available Networks"));
+        header.set_halign(gtk::Align::Start);
+        header.set_css_classes(&["networks-title"]);
+        items.push(PopoverItem::Custom(header.upcast::<gtk::Widget>()));
+        items.push(PopoverItem::Button {
+            label: "Refresh networks".to_string(),
+            icon: Some("view-refresh-symbolic".to_string()),
+            css: "refresh-button".to_string(),
+            action_id: "refresh".to_string(),
+        });
 
         if self.is_scanning {
             let loading_label = gtk::Label::new(Some("Scanning..."));
@@ -340,118 +390,56 @@ impl WiFi {
             empty_label.set_css_classes(&["network-empty"]);
             items.push(PopoverItem::Custom(empty_label.upcast::<gtk::Widget>()));
         } else {
-            // Create scrollable network list
-            let scrolled = gtk::ScrolledWindow::new();
-            scrolled.set_min_content_height(300);
-            scrolled.set_max_content_height(600);
-            scrolled.set_vexpand(true);
-            scrolled.set_hexpand(true);
-            scrolled.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
-
-            let list_box = gtk::Box::new(gtk::Orientation::Vertical, 4);
-            list_box.set_css_classes(&["network-list"]);
-
-            for network in &self.available_networks {
-                list_box.append(&self.create_network_item(network));
-            }
-
-            scrolled.set_child(Some(&list_box));
-            items.push(PopoverItem::Custom(scrolled.upcast::<gtk::Widget>()));
-        }
-
-        self.popover.emit(PopoverMsg::UpdateItems(items));
-    }
-
-    fn create_networks_header(&self) -> gtk::Widget {
-        let header_box = gtk::Box::new(gtk::Orientation::Horizontal, 12);
-        header_box.set_css_classes(&["networks-header"]);
-
-        let label = gtk::Label::new(Some("Available Networks"));
-        label.set_halign(gtk::Align::Start);
-        label.set_hexpand(true);
-        label.set_css_classes(&["networks-title"]);
-
-        let disconnect_btn = gtk::Button::with_label("X");
-        disconnect_btn.set_css_classes(&["disconnect-button", "flat"]);
-        disconnect_btn.set_tooltip_text(Some("Disconnect"));
-
-        // Wire up disconnect button click handler
-        if let Some(sender) = &self.sender {
-            let sender_clone = sender.clone();
-            disconnect_btn.connect_clicked(move |_| {
-                sender_clone.input(WiFiMsg::Disconnect);
+            let rows = self
+                .available_networks
+                .iter()
+                .map(Self::network_row)
+                .collect();
+            items.push(PopoverItem::SearchEntry {
+                placeholder: "Filter networks".to_string(),
             });
+            items.push(PopoverItem::List(rows));
         }
 
-        let refresh_btn = gtk::Button::from_icon_name("view-refresh-symbolic");
-        refresh_btn.set_css_classes(&["refresh-button", "flat"]);
-        refresh_btn.set_tooltip_text(Some("Refresh networks"));
-
-        // Wire up refresh button click handler
-        if let Some(sender) = &self.sender {
-            let sender_clone = sender.clone();
-            refresh_btn.connect_clicked(move |_| {
-                sender_clone.input(WiFiMsg::ScanNetworks);
-            });
-        }
-
-        header_box.append(&label);
-        header_box.append(&disconnect_btn);
-        header_box.append(&refresh_btn);
-
-        header_box.upcast::<gtk::Widget>()
+        self.popover.emit(PopoverMsg::UpdateItems(items));
     }
 
-    fn create_network_item(&self, network: &NetworkItem) -> gtk::Widget {
-        let button = gtk::Button::new();
-        button.set_css_classes(&["network-item"]);
-
-        let content_box = gtk::Box::new(gtk::Orientation::Horizontal, 12);
-        content_box.set_margin_start(8);
-        content_box.set_margin_end(8);
-        content_box.set_margin_top(6);
-        content_box.set_margin_bottom(6);
-
-        // Signal icon
-        let signal_icon = gtk::Label::new(Some(network.signal_icon()));
-        signal_icon.set_css_classes(&["network-signal"]);
-
-        // SSID label
-        let ssid_label = gtk::Label::new(Some(&network.ssid));
-        ssid_label.set_halign(gtk::Align::Start);
-        ssid_label.set_hexpand(true);
-        ssid_label.set_css_classes(&["network-ssid"]);
-        ssid_label.set_ellipsize(gtk::pango::EllipsizeMode::End);
-
-        // Lock icon for secured networks
-        if network.is_secured {
-            let lock_icon = gtk::Label::new(Some(network.lock_icon()));
-            lock_icon.set_css_classes(&["network-lock"]);
-            content_box.append(&lock_icon);
+    fn network_row(network: &NetworkItem) -> ListRow {
+        let mut badges = Vec::new();
+        if network.security.is_secured() {
+            badges.push(network.lock_icon().to_string());
         }
-
-        // Connected badge
         if network.is_connected {
-            let connected_label = gtk::Label::new(Some("Connected"));
-            connected_label.set_css_classes(&["network-connected"]);
-            content_box.append(&connected_label);
+            badges.push("Connected".to_string());
         }
-
-        content_box.prepend(&ssid_label);
-        content_box.prepend(&signal_icon);
-
-        button.set_child(Some(&content_box));
-
-        // Wire up network item click handler to connect
-        if let Some(sender) = &self.sender {
-            let sender_clone = sender.clone();
-            let ssid = network.ssid.clone();
-            button.connect_clicked(move |_| {
-                sender_clone.input(WiFiMsg::ConnectToNetwork(ssid.clone()));
-            });
+        ListRow {
+            icon: Some(network.signal_icon().to_string()),
+            title: privacy::redact(&network.ssid).to_string(),
+            subtitle: Some(network.security.label().to_string()),
+            badges,
+            action_id: format!("connect:{}", network.ssid),
         }
+    }
 
-        button.upcast::<gtk::Widget>()
+    fn tooltip(&self) -> Option<String> {
+        tooltip::render(
+            &self.config.tooltip,
+            &[
+                ("ssid", privacy::redact(&self.ssid)),
+                ("signal", &self.signal_strength.to_string()),
+                ("ip", privacy::redact(&self.ip_address)),
+                ("interface", &self.interface),
+            ],
+        )
+    }
+
+    /// What screen readers announce for the glyph-only button
+    fn accessible_label(&self) -> String {
+        format!(
+            "Wi-Fi {}, signal {}%",
+            privacy::redact(&self.ssid),
+            self.signal_strength
+        )
     }
 
     fn signal_icon(strength: i32) -> &'static str {
@@ -464,3 +452,15 @@ impl WiFi {
         }
     }
 }
+
+impl Snapshot for WiFi {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "is_connected": self.is_connected,
+            "ssid": self.ssid,
+            "signal_strength": self.signal_strength,
+            "interface": self.interface,
+            "ip_address": self.ip_address,
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use gtk::prelude::*;
+use nm_dbus::{EapConfig, EapMethod, Phase2Auth};
+use relm4::gtk;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::services::network;
+
+/// Ask for the 802.1X settings of the enterprise network `ssid` and connect
+/// with them
+///
+/// `on_connected` runs once NetworkManager has taken the connection, so the
+/// caller can refresh.
+pub fn ask(ssid: &str, on_connected: impl Fn() + 'static) {
+    let window = gtk::Window::builder()
+        .title(format!("Connect to {}", ssid))
+        .default_width(360)
+        .resizable(false)
+        .css_classes(vec!["settings-dialog"])
+        .build();
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let label = gtk::Label::builder()
+        .label(format!(
+            "{} is an enterprise network. Enter the sign-in details from your organisation.",
+            ssid
+        ))
+        .wrap(true)
+        .xalign(0.0)
+        .css_classes(vec!["detail-label"])
+        .build();
+    content.append(&label);
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(6)
+        .column_spacing(12)
+        .build();
+
+    let method_labels: Vec<&str> = EapMethod::ALL.iter().map(|m| m.label()).collect();
+    let method = gtk::DropDown::from_strings(&method_labels);
+    let phase2_labels: Vec<&str> = Phase2Auth::ALL.iter().map(|p| p.label()).collect();
+    let phase2 = gtk::DropDown::from_strings(&phase2_labels);
+    let identity = gtk::Entry::builder().hexpand(true).build();
+    let anonymous_identity = gtk::Entry::builder()
+        .placeholder_text("Optional")
+        .hexpand(true)
+        .build();
+    let password = gtk::PasswordEntry::builder()
+        .show_peek_icon(true)
+        .activates_default(true)
+        .hexpand(true)
+        .build();
+    let ca_cert = gtk::Entry::builder()
+        .placeholder_text("System certificates")
+        .hexpand(true)
+        .build();
+
+    let rows: [(&str, gtk::Widget); 6] = [
+        ("Authentication", method.clone().upcast()),
+        ("Inner authentication", phase2.clone().upcast()),
+        ("Identity", identity.clone().upcast()),
+        ("Anonymous identity", anonymous_identity.clone().upcast()),
+        ("Password", password.clone().upcast()),
+        ("CA certificate", ca_cert.clone().upcast()),
+    ];
+    for (row, (text, editor)) in rows.iter().enumerate() {
+        let label = gtk::Label::builder()
+            .label(*text)
+            .halign(gtk::Align::Start)
+            .css_classes(vec!["detail-label"])
+            .build();
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(editor, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    let error_label = gtk::Label::builder()
+        .wrap(true)
+        .xalign(0.0)
+        .visible(false)
+        .css_classes(vec!["settings-error"])
+        .build();
+    content.append(&error_label);
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    buttons.set_halign(gtk::Align::End);
+
+    let cancel_button = gtk::Button::with_label("Cancel");
+    let window_clone = window.clone();
+    cancel_button.connect_clicked(move |_| window_clone.close());
+    buttons.append(&cancel_button);
+
+    let connect_button = gtk::Button::with_label("Connect");
+    connect_button.add_css_class("suggested-action");
+    let ssid = ssid.to_string();
+    let on_connected = Rc::new(on_connected);
+    let window_clone = window.clone();
+    connect_button.connect_clicked(move |button| {
+        let ca_cert = ca_cert.text();
+        let eap = EapConfig {
+            method: EapMethod::ALL[method.selected() as usize],
+            phase2: Phase2Auth::ALL[phase2.selected() as usize],
+            identity: identity.text().trim().to_string(),
+            anonymous_identity: anonymous_identity.text().trim().to_string(),
+            password: password.text().to_string(),
+            ca_cert: (!ca_cert.trim().is_empty()).then(|| PathBuf::from(ca_cert.trim())),
+        };
+        if eap.identity.is_empty() {
+            show_error(&error_label, "Enter the identity to sign in with");
+            identity.grab_focus();
+            return;
+        }
+        button.set_sensitive(false);
+
+        let ssid = ssid.clone();
+        let button = button.clone();
+        let window = window_clone.clone();
+        let error_label = error_label.clone();
+        let on_connected = on_connected.clone();
+        gtk::glib::spawn_future_local(async move {
+            // NetworkManager is reached over the bus on relm4's runtime
+            let connected =
+                relm4::spawn(async move { network::connect_enterprise(&ssid, &eap).await })
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Failed to connect: {}", e)));
+            match connected {
+                Ok(()) => {
+                    on_connected();
+                    window.close();
+                }
+                Err(e) => {
+                    show_error(&error_label, &e);
+                    button.set_sensitive(true);
+                }
+            }
+        });
+    });
+    buttons.append(&connect_button);
+
+    content.append(&buttons);
+    window.set_child(Some(&content));
+    window.set_default_widget(Some(&connect_button));
+    window.present();
+}
+
+fn show_error(error_label: &gtk::Label, message: &str) {
+    error_label.set_label(message);
+    error_label.set_visible(true);
+}
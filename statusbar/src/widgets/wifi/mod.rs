@@ -1,4 +1,5 @@
-mod models;
 mod component;
+mod eap;
+mod models;
 
 pub use component::WiFi;
@@ -1,9 +1,11 @@
+use nm_dbus::Security;
+
 /// Represents a WiFi network in the available networks list
 #[derive(Debug, Clone)]
 pub struct NetworkItem {
     pub ssid: String,
     pub strength: u8,
-    pub is_secured: bool,
+    pub security: Security,
     pub is_connected: bool,
 }
 
@@ -19,7 +21,7 @@ impl NetworkItem {
     }
 
     pub fn lock_icon(&self) -> &'static str {
-        if self.is_secured {
+        if self.security.is_secured() {
             "󰌾" // Lock icon for secured networks
         } else {
             "" // No icon for open networks
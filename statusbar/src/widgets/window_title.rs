@@ -1,21 +1,57 @@
+use gtk::gio;
 use gtk::prelude::*;
-use niri_ipc::{Event, Request, Response, socket::Socket};
+use regex::Regex;
 use relm4::prelude::*;
+use serde_json::json;
 use std::thread;
 
+use super::icons::app_icon;
+use super::poll;
+use super::snapshot::Snapshot;
+use crate::config::WindowTitleConfig;
+use crate::services::compositor::{self, Event, Window};
+use crate::services::privacy;
+
+const ICON_SIZE: i32 = 16;
+
+/// A compiled [`crate::config::TitleRule`]
+struct Rule {
+    app_id: Option<String>,
+    pattern: Regex,
+    replacement: String,
+}
+
 pub struct WindowTitle {
+    config: WindowTitleConfig,
+    rules: Vec<Rule>,
+    /// Title as reported by the compositor
     title: String,
+    /// Title after the rules, hide list and length limit
+    display_title: String,
     app_id: Option<String>,
+    /// Window the controls act on
+    window_id: Option<u64>,
+    floating: bool,
+    fullscreen: bool,
+    /// Icon of the app owning the title
+    icon: gio::Icon,
+    connected: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum WindowTitleMsg {
-    UpdateTitle(String, Option<String>),
+    /// The focused window, or `None` once no window has focus
+    UpdateWindow(Option<Window>),
+    PrivacyChanged,
+    ConnectionChanged(bool),
+    Close,
+    ToggleMaximized,
+    ToggleFloating,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for WindowTitle {
-    type Init = ();
+    type Init = WindowTitleConfig;
     type Input = WindowTitleMsg;
     type Output = ();
 
@@ -24,41 +60,129 @@ impl SimpleComponent for WindowTitle {
             set_orientation: gtk::Orientation::Horizontal,
             set_spacing: 8,
             set_css_classes: &["window-title-widget", "widget"],
+            #[watch]
+            set_class_active: ("compositor-disconnected", !model.connected),
+            #[watch]
+            set_tooltip_text: (!model.connected).then_some("Lost connection to the compositor"),
+            #[watch]
+            set_class_active: ("window-floating", model.floating),
+            #[watch]
+            set_class_active: ("window-fullscreen", model.fullscreen),
+
+            gtk::Image {
+                set_pixel_size: ICON_SIZE,
+                set_css_classes: &["window-title-icon"],
+                #[watch]
+                set_from_gicon: &model.icon,
+                #[watch]
+                set_visible: model.config.show_icon
+                    && model.app_id.is_some()
+                    && !model.display_title.is_empty(),
+            },
 
             gtk::Label {
                 #[watch]
-                set_label: &model.title,
+                set_label: privacy::redact(&model.display_title),
                 set_css_classes: &["window-title-label"],
                 set_ellipsize: gtk::pango::EllipsizeMode::End,
-                set_max_width_chars: 50,
+                set_max_width_chars: model.config.max_length as i32,
+            },
+
+            gtk::Label {
+                #[watch]
+                set_visible: model.state_glyph().is_some(),
+                #[watch]
+                set_label: model.state_glyph().unwrap_or_default(),
+                set_css_classes: &["window-state"],
+            },
+
+            // niri leaves decorations to the clients, and not all draw them
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 2,
+                set_css_classes: &["window-controls"],
+                #[watch]
+                set_visible: model.config.controls && model.window_id.is_some(),
+
+                gtk::Button {
+                    set_label: "󰖲",
+                    update_property: &[gtk::accessible::Property::Label("Float window")],
+                    set_tooltip_text: Some("Float"),
+                    set_css_classes: &["window-control", "window-control-float"],
+                    connect_clicked => WindowTitleMsg::ToggleFloating,
+                },
+
+                gtk::Button {
+                    set_label: "󰖯",
+                    update_property: &[gtk::accessible::Property::Label("Maximize window")],
+                    set_tooltip_text: Some("Maximize"),
+                    set_css_classes: &["window-control", "window-control-maximize"],
+                    connect_clicked => WindowTitleMsg::ToggleMaximized,
+                },
+
+                gtk::Button {
+                    set_label: "󰖭",
+                    update_property: &[gtk::accessible::Property::Label("Close window")],
+                    set_tooltip_text: Some("Close"),
+                    set_css_classes: &["window-control", "window-control-close"],
+                    connect_clicked => WindowTitleMsg::Close,
+                },
             }
         }
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let model = WindowTitle {
+            rules: compile_rules(&config),
+            config,
             title: String::from(""),
+            display_title: String::new(),
             app_id: None,
+            window_id: None,
+            floating: false,
+            fullscreen: false,
+            icon: gio::ThemedIcon::new("application-x-executable").upcast(),
+            connected: true,
         };
 
         let widgets = view_output!();
 
-        // Spawn thread to listen for niri events
-        let sender_clone = sender.clone();
-        thread::spawn(move || {
-            if let Err(e) = Self::listen_niri_events(sender_clone) {
-                eprintln!("Niri IPC error: {}", e);
+        Self::listen_compositor_events(sender.clone());
+
+        // Dim the widget while the compositor is unreachable
+        let mut connection_rx = compositor::connection();
+        let connection_sender = sender.clone();
+        relm4::spawn(async move {
+            while connection_rx.changed().await.is_ok() {
+                let connected = *connection_rx.borrow();
+                if !poll::deliver(
+                    &connection_sender,
+                    WindowTitleMsg::ConnectionChanged(connected),
+                ) {
+                    break;
+                }
+            }
+        });
+
+        // Re-render when the privacy screen is toggled
+        let mut privacy_rx = privacy::subscribe();
+        let privacy_sender = sender.clone();
+        relm4::spawn(async move {
+            while privacy_rx.changed().await.is_ok() {
+                if !poll::deliver(&privacy_sender, WindowTitleMsg::PrivacyChanged) {
+                    break;
+                }
             }
         });
 
         // Request initial focused window
         thread::spawn(move || {
-            if let Some((title, app_id)) = Self::get_focused_window() {
-                sender.input(WindowTitleMsg::UpdateTitle(title, app_id));
+            if let Some(window) = Self::get_focused_window() {
+                sender.input(WindowTitleMsg::UpdateWindow(Some(window)));
             }
         });
 
@@ -67,69 +191,135 @@ impl SimpleComponent for WindowTitle {
 
     fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
         match msg {
-            WindowTitleMsg::UpdateTitle(title, app_id) => {
-                self.title = title;
+            WindowTitleMsg::UpdateWindow(window) => {
+                let app_id = window.as_ref().and_then(|window| window.app_id.clone());
+                if let Some(id) = app_id.as_deref().filter(|_| app_id != self.app_id) {
+                    self.icon = app_icon(id);
+                }
+                self.window_id = window.as_ref().map(|window| window.id);
+                self.floating = window.as_ref().is_some_and(|window| window.is_floating);
+                self.fullscreen = window.as_ref().is_some_and(|window| window.is_fullscreen);
+                self.title = window.and_then(|window| window.title).unwrap_or_default();
                 self.app_id = app_id;
+                self.display_title = self.rewrite_title();
+            }
+            WindowTitleMsg::PrivacyChanged => {}
+            WindowTitleMsg::ConnectionChanged(connected) => {
+                self.connected = connected;
+            }
+            WindowTitleMsg::Close => self.control("close", compositor::close_window),
+            WindowTitleMsg::ToggleMaximized => {
+                self.control("maximize", compositor::toggle_maximized)
             }
+            WindowTitleMsg::ToggleFloating => self.control("float", compositor::toggle_floating),
         }
     }
 }
 
+/// Compile the configured rules, skipping invalid patterns
+fn compile_rules(config: &WindowTitleConfig) -> Vec<Rule> {
+    config
+        .rules
+        .iter()
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(pattern) => Some(Rule {
+                app_id: rule.app_id.clone(),
+                pattern,
+                replacement: rule.replacement.clone(),
+            }),
+            Err(e) => {
+                eprintln!("Invalid window title rule {:?}: {}", rule.pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
 impl WindowTitle {
-    fn listen_niri_events(sender: ComponentSender<Self>) -> Result<(), String> {
-        let mut socket = Socket::connect().map_err(|e| e.to_string())?;
-
-        let reply = socket
-            .send(Request::EventStream)
-            .map_err(|e| e.to_string())?;
-
-        if matches!(reply, Ok(Response::Handled)) {
-            let mut read_event = socket.read_events();
-
-            while let Ok(event) = read_event() {
-                match event {
-                    Event::WindowFocusChanged { id: _ } => {
-                        if let Some((title, app_id)) = Self::get_focused_window() {
-                            sender.input(WindowTitleMsg::UpdateTitle(title, app_id));
-                        }
-                    }
-                    Event::WindowsChanged { windows: _ } => {
-                        if let Some((title, app_id)) = Self::get_focused_window() {
-                            sender.input(WindowTitleMsg::UpdateTitle(title, app_id));
-                        }
-                    }
-                    Event::WindowOpenedOrChanged { window } => {
-                        if window.is_focused {
-                            sender.input(WindowTitleMsg::UpdateTitle(
-                                window.title.clone().unwrap_or_default(),
-                                window.app_id.clone(),
-                            ));
-                        }
-                    }
-                    Event::WindowClosed { id: _ } => {
-                        if let Some((title, app_id)) = Self::get_focused_window() {
-                            sender.input(WindowTitleMsg::UpdateTitle(title, app_id));
-                        } else {
-                            sender.input(WindowTitleMsg::UpdateTitle(String::new(), None));
-                        }
-                    }
-                    _ => {}
-                }
+    /// Glyph marking a fullscreen or floating window
+    fn state_glyph(&self) -> Option<&'static str> {
+        if self.fullscreen {
+            Some("󰊓")
+        } else if self.floating {
+            Some("󰖲")
+        } else {
+            None
+        }
+    }
+
+    /// Apply the hide list, rewrite rules and length limit to the title
+    fn rewrite_title(&self) -> String {
+        let app_id = self.app_id.as_deref();
+        if app_id.is_some_and(|app_id| self.config.hide.iter().any(|hidden| hidden == app_id)) {
+            return String::new();
+        }
+
+        let mut title = self.title.clone();
+        for rule in &self.rules {
+            if rule.app_id.is_none() || rule.app_id.as_deref() == app_id {
+                title = rule
+                    .pattern
+                    .replace_all(&title, rule.replacement.as_str())
+                    .into_owned();
             }
         }
 
-        Ok(())
+        let max_length = app_id
+            .and_then(|app_id| self.config.app_max_length.get(app_id))
+            .copied()
+            .unwrap_or(self.config.max_length);
+        if title.chars().count() > max_length {
+            title = title.chars().take(max_length.saturating_sub(1)).collect();
+            title.push('…');
+        }
+        title.trim().to_string()
     }
 
-    fn get_focused_window() -> Option<(String, Option<String>)> {
-        let mut socket = Socket::connect().ok()?;
-        let reply = socket.send(Request::FocusedWindow).ok()?;
-
-        match reply {
-            Ok(Response::FocusedWindow(Some(window))) => {
-                Some((window.title.unwrap_or_default(), window.app_id))
+    fn listen_compositor_events(sender: ComponentSender<Self>) {
+        let mut events = compositor::subscribe();
+        thread::spawn(move || {
+            while let Some(event) = events.blocking_next() {
+                let window = match event {
+                    Event::WindowFocusChanged(_) | Event::WindowsChanged(_) => {
+                        let Some(window) = Self::get_focused_window() else {
+                            continue;
+                        };
+                        Some(window)
+                    }
+                    Event::WindowOpenedOrChanged(window) if window.is_focused => Some(window),
+                    Event::WindowClosed(_) => Self::get_focused_window(),
+                    _ => continue,
+                };
+                if !poll::deliver(&sender, WindowTitleMsg::UpdateWindow(window)) {
+                    break;
+                }
             }
-            _ => None,
+        });
+    }
+
+    fn get_focused_window() -> Option<Window> {
+        compositor::focused_window().ok()?
+    }
+
+    /// Apply a window action to the focused window
+    fn control(&self, name: &str, action: fn(u64) -> Result<(), String>) {
+        let Some(id) = self.window_id else {
+            return;
+        };
+        if let Err(e) = action(id) {
+            eprintln!("Failed to {} window: {}", name, e);
         }
     }
 }
+
+impl Snapshot for WindowTitle {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({
+            "title": self.title,
+            "display_title": self.display_title,
+            "app_id": self.app_id,
+            "floating": self.floating,
+            "fullscreen": self.fullscreen,
+        })
+    }
+}
@@ -1,13 +1,30 @@
 use gtk::prelude::*;
-use niri_ipc::{Event, Request, Response, Workspace as NiriWorkspace, socket::Socket};
-use relm4::prelude::*;
 use relm4::factory::FactoryVecDeque;
+use relm4::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
 use std::thread;
 
+use super::icons::app_icon;
+use super::poll;
+use super::popover::set_keyboard_focusable;
+use super::snapshot::Snapshot;
+use crate::config::{WorkspaceBadges, WorkspaceConfig};
+use crate::services::compositor::{self, Event, Window, Workspace};
+
+/// App icons shown on a workspace button before collapsing into "+N"
+const MAX_BADGE_ICONS: usize = 3;
+const BADGE_ICON_SIZE: i32 = 12;
+
 // Workspace button factory component
 #[derive(Debug, Clone)]
 pub struct WorkspaceButton {
-    workspace: NiriWorkspace,
+    workspace: Workspace,
+    window_count: usize,
+    /// Distinct app ids of the windows on the workspace
+    app_ids: Vec<String>,
+    badges: WorkspaceBadges,
+    blink_urgent: bool,
 }
 
 #[derive(Debug)]
@@ -15,41 +32,102 @@ pub enum WorkspaceButtonMsg {
     Clicked,
 }
 
+#[derive(Debug)]
+pub enum WorkspaceButtonOutput {
+    Switch(u64),
+    /// Right-clicked to rename the workspace
+    Rename(u64),
+}
+
 #[relm4::factory(pub)]
 impl FactoryComponent for WorkspaceButton {
-    type Init = NiriWorkspace;
+    type Init = WorkspaceButton;
     type Input = WorkspaceButtonMsg;
-    type Output = u64;
+    type Output = WorkspaceButtonOutput;
     type CommandOutput = ();
     type ParentWidget = gtk::Box;
 
     view! {
         #[root]
         gtk::Button {
-            set_label: &self.workspace.idx.to_string(),
-
             #[watch]
-            set_css_classes: &Self::compute_css_classes(&self.workspace),
+            set_css_classes: &Self::compute_css_classes(&self.workspace, self.blink_urgent),
+            update_property: &[gtk::accessible::Property::Label(&format!("Workspace {}", self.label()))],
+            #[watch]
+            update_state: &[gtk::accessible::State::Pressed(if self.workspace.is_focused {
+                gtk::AccessibleTristate::True
+            } else {
+                gtk::AccessibleTristate::False
+            })],
 
             connect_clicked => WorkspaceButtonMsg::Clicked,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 2,
+                set_halign: gtk::Align::Center,
+
+                gtk::Label {
+                    set_label: &self.label(),
+                },
+
+                #[name = "badge"]
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 1,
+                    set_css_classes: &["workspace-badge"],
+                    set_visible: self.badges != WorkspaceBadges::None && self.window_count > 0,
+                }
+            }
         }
     }
 
-    fn init_model(workspace: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
-        Self { workspace }
+    fn init_model(button: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+        button
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &gtk::Widget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        self.fill_badge(&widgets.badge);
+
+        let click = gtk::GestureClick::new();
+        click.set_button(gtk::gdk::BUTTON_SECONDARY);
+        let id = self.workspace.id;
+        click.connect_pressed(move |gesture, _, _, _| {
+            // Keep the slot's context menu from opening as well
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            let _ = sender.output(WorkspaceButtonOutput::Rename(id));
+        });
+        root.add_controller(click);
+
+        widgets
     }
 
     fn update(&mut self, msg: Self::Input, sender: FactorySender<Self>) {
         match msg {
             WorkspaceButtonMsg::Clicked => {
-                let _ = sender.output(self.workspace.id);
+                let _ = sender.output(WorkspaceButtonOutput::Switch(self.workspace.id));
             }
         }
     }
 }
 
 impl WorkspaceButton {
-    fn compute_css_classes(workspace: &NiriWorkspace) -> Vec<&'static str> {
+    /// The workspace's name if it has one, otherwise its position
+    fn label(&self) -> String {
+        self.workspace
+            .name
+            .clone()
+            .unwrap_or_else(|| self.workspace.idx.to_string())
+    }
+
+    fn compute_css_classes(workspace: &Workspace, blink_urgent: bool) -> Vec<&'static str> {
         let mut classes = vec!["workspace-button"];
 
         if workspace.is_focused {
@@ -62,24 +140,74 @@ impl WorkspaceButton {
             classes.push("workspace-empty");
         }
 
+        // Set by the compositor while a window on the workspace requests attention
+        if workspace.is_urgent {
+            classes.push("workspace-urgent");
+            if blink_urgent {
+                classes.push("workspace-blink");
+            }
+        }
+
         classes
     }
+
+    fn fill_badge(&self, badge: &gtk::Box) {
+        match self.badges {
+            WorkspaceBadges::None => {}
+            WorkspaceBadges::Count => {
+                badge.append(&gtk::Label::new(Some(&self.window_count.to_string())));
+            }
+            WorkspaceBadges::Icons => {
+                for app_id in self.app_ids.iter().take(MAX_BADGE_ICONS) {
+                    let image = gtk::Image::from_gicon(&app_icon(app_id));
+                    image.set_pixel_size(BADGE_ICON_SIZE);
+                    image.set_tooltip_text(Some(app_id));
+                    badge.append(&image);
+                }
+                if self.app_ids.len() > MAX_BADGE_ICONS {
+                    let more = format!("+{}", self.app_ids.len() - MAX_BADGE_ICONS);
+                    badge.append(&gtk::Label::new(Some(&more)));
+                }
+            }
+        }
+    }
 }
 
 // Main workspace widget
 pub struct WorkspaceWidget {
     workspace_buttons: FactoryVecDeque<WorkspaceButton>,
+    badges: WorkspaceBadges,
+    hide_empty: bool,
+    blink_urgent: bool,
+    workspaces: Vec<Workspace>,
+    /// Open windows by id, used for badges
+    windows: HashMap<u64, Window>,
+    connected: bool,
+    show_overview_button: bool,
+    rename_popover: gtk::Popover,
+    rename_entry: gtk::Entry,
+    /// Workspace the rename popover is open for
+    renaming: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
 pub enum WorkspaceMsg {
-    UpdateWorkspaces(Vec<NiriWorkspace>),
+    UpdateWorkspaces(Vec<Workspace>),
+    UpdateWindows(Vec<Window>),
+    WindowChanged(Window),
+    WindowClosed(u64),
+    UrgencyChanged(u64, bool),
+    ConnectionChanged(bool),
     SwitchWorkspace(u64),
+    ToggleOverview,
+    StartRename(u64),
+    Rename(String),
+    RenameClosed,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for WorkspaceWidget {
-    type Init = ();
+    type Init = WorkspaceConfig;
     type Input = WorkspaceMsg;
     type Output = ();
 
@@ -89,6 +217,19 @@ impl SimpleComponent for WorkspaceWidget {
             set_orientation: gtk::Orientation::Horizontal,
             set_spacing: 4,
             set_css_classes: &["workspace-widget", "widget"],
+            #[watch]
+            set_class_active: ("compositor-disconnected", !model.connected),
+            #[watch]
+            set_tooltip_text: (!model.connected).then_some("Lost connection to the compositor"),
+
+            gtk::Button {
+                set_visible: model.show_overview_button,
+                set_label: "󰕰",
+                update_property: &[gtk::accessible::Property::Label("Overview")],
+                set_tooltip_text: Some("Overview"),
+                set_css_classes: &["workspace-overview-button"],
+                connect_clicked => WorkspaceMsg::ToggleOverview,
+            },
 
             #[local_ref]
             workspace_buttons_box -> gtk::Box {
@@ -99,36 +240,61 @@ impl SimpleComponent for WorkspaceWidget {
     }
 
     fn init(
-        _init: Self::Init,
+        config: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let workspace_buttons = FactoryVecDeque::builder()
             .launch(gtk::Box::default())
-            .forward(sender.input_sender(), |output| {
-                WorkspaceMsg::SwitchWorkspace(output)
+            .forward(sender.input_sender(), |output| match output {
+                WorkspaceButtonOutput::Switch(id) => WorkspaceMsg::SwitchWorkspace(id),
+                WorkspaceButtonOutput::Rename(id) => WorkspaceMsg::StartRename(id),
             });
 
+        let (rename_popover, rename_entry) = Self::create_rename_popover(&root, &sender);
+
         let model = WorkspaceWidget {
             workspace_buttons,
+            badges: config.badges,
+            hide_empty: config.hide_empty,
+            blink_urgent: config.blink_urgent,
+            workspaces: Vec::new(),
+            windows: HashMap::new(),
+            connected: true,
+            show_overview_button: config.overview_button && compositor::has_overview(),
+            rename_popover,
+            rename_entry,
+            renaming: None,
         };
 
         let workspace_buttons_box = model.workspace_buttons.widget();
         let widgets = view_output!();
 
-        // Spawn thread to listen for niri events
-        let sender_clone = sender.clone();
-        thread::spawn(move || {
-            if let Err(e) = Self::listen_niri_events(sender_clone) {
-                eprintln!("Niri IPC error: {}", e);
+        Self::listen_compositor_events(sender.clone());
+
+        // Dim the widget while the compositor is unreachable
+        let mut connection_rx = compositor::connection();
+        let connection_sender = sender.clone();
+        relm4::spawn(async move {
+            while connection_rx.changed().await.is_ok() {
+                let connected = *connection_rx.borrow();
+                if !poll::deliver(
+                    &connection_sender,
+                    WorkspaceMsg::ConnectionChanged(connected),
+                ) {
+                    break;
+                }
             }
         });
 
-        // Request initial workspace state
+        // Request initial workspace and window state
         thread::spawn(move || {
             if let Ok(workspaces) = Self::get_workspaces() {
                 sender.input(WorkspaceMsg::UpdateWorkspaces(workspaces));
             }
+            if let Ok(windows) = Self::get_windows() {
+                sender.input(WorkspaceMsg::UpdateWindows(windows));
+            }
         });
 
         ComponentParts { model, widgets }
@@ -137,77 +303,238 @@ impl SimpleComponent for WorkspaceWidget {
     fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
         match msg {
             WorkspaceMsg::UpdateWorkspaces(workspaces) => {
-                let mut guard = self.workspace_buttons.guard();
-                guard.clear();
-                for workspace in workspaces {
-                    guard.push_back(workspace);
+                self.workspaces = workspaces;
+                self.rebuild_buttons();
+            }
+            WorkspaceMsg::UpdateWindows(windows) => {
+                self.windows = windows
+                    .into_iter()
+                    .map(|window| (window.id, window))
+                    .collect();
+                self.rebuild_buttons();
+            }
+            WorkspaceMsg::WindowChanged(window) => {
+                // Title changes don't affect the badges
+                let moved = self.windows.get(&window.id).is_none_or(|old| {
+                    old.workspace_id != window.workspace_id || old.app_id != window.app_id
+                });
+                self.windows.insert(window.id, window);
+                if moved {
+                    self.rebuild_buttons();
                 }
             }
+            WorkspaceMsg::WindowClosed(id) => {
+                self.windows.remove(&id);
+                self.rebuild_buttons();
+            }
+            WorkspaceMsg::UrgencyChanged(id, urgent) => {
+                if let Some(workspace) = self.workspaces.iter_mut().find(|w| w.id == id) {
+                    workspace.is_urgent = urgent;
+                    self.rebuild_buttons();
+                }
+            }
+            WorkspaceMsg::ConnectionChanged(connected) => {
+                self.connected = connected;
+            }
             WorkspaceMsg::SwitchWorkspace(id) => {
                 if let Err(e) = Self::switch_to_workspace(id) {
                     eprintln!("Failed to switch workspace: {}", e);
                 }
             }
+            WorkspaceMsg::ToggleOverview => {
+                if let Err(e) = compositor::toggle_overview() {
+                    eprintln!("Failed to toggle the overview: {}", e);
+                }
+            }
+            WorkspaceMsg::StartRename(id) => self.start_rename(id),
+            WorkspaceMsg::Rename(name) => {
+                self.rename_popover.popdown();
+                let Some(id) = self.renaming.take() else {
+                    return;
+                };
+                // An empty entry clears the name
+                let name = name.trim();
+                let name = (!name.is_empty()).then_some(name);
+                if let Err(e) = compositor::rename_workspace(id, name) {
+                    eprintln!("Failed to rename workspace: {}", e);
+                }
+            }
+            WorkspaceMsg::RenameClosed => {
+                self.renaming = None;
+                set_keyboard_focusable(&self.rename_popover, false);
+            }
         }
     }
 }
 
 impl WorkspaceWidget {
-    fn listen_niri_events(sender: ComponentSender<Self>) -> Result<(), String> {
-        let mut socket = Socket::connect().map_err(|e| e.to_string())?;
+    fn rebuild_buttons(&mut self) {
+        // Window ids grow with opening order, so icons follow it too
+        let mut windows: Vec<&Window> = self.windows.values().collect();
+        windows.sort_by_key(|window| window.id);
+
+        let mut guard = self.workspace_buttons.guard();
+        guard.clear();
+        for workspace in &self.workspaces {
+            let on_workspace: Vec<&Window> = windows
+                .iter()
+                .copied()
+                .filter(|window| window.workspace_id == Some(workspace.id))
+                .collect();
+
+            let is_empty = on_workspace.is_empty() && workspace.active_window_id.is_none();
+            if self.hide_empty && is_empty && !workspace.is_focused {
+                continue;
+            }
+
+            let mut app_ids: Vec<String> = Vec::new();
+            for app_id in on_workspace
+                .iter()
+                .filter_map(|window| window.app_id.as_ref())
+            {
+                if !app_ids.contains(app_id) {
+                    app_ids.push(app_id.clone());
+                }
+            }
+
+            guard.push_back(WorkspaceButton {
+                workspace: workspace.clone(),
+                window_count: on_workspace.len(),
+                app_ids,
+                badges: self.badges,
+                blink_urgent: self.blink_urgent,
+            });
+        }
+    }
+
+    /// Popover with the entry for naming a workspace, reused for every button
+    ///
+    /// It hangs off the widget rather than a button, since buttons are
+    /// rebuilt whenever the compositor reports a change.
+    fn create_rename_popover(
+        root: &gtk::Box,
+        sender: &ComponentSender<Self>,
+    ) -> (gtk::Popover, gtk::Entry) {
+        let entry = gtk::Entry::builder()
+            .placeholder_text("Workspace name")
+            .css_classes(["workspace-rename-entry"])
+            .build();
+        let entry_sender = sender.clone();
+        entry.connect_activate(move |entry| {
+            entry_sender.input(WorkspaceMsg::Rename(entry.text().to_string()));
+        });
+
+        let popover = gtk::Popover::builder()
+            .child(&entry)
+            .css_classes(["workspace-rename"])
+            .build();
+        popover.set_parent(root);
+        let closed_sender = sender.clone();
+        popover.connect_closed(move |_| closed_sender.input(WorkspaceMsg::RenameClosed));
 
-        let reply = socket
-            .send(Request::EventStream)
-            .map_err(|e| e.to_string())?;
+        (popover, entry)
+    }
+
+    fn start_rename(&mut self, id: u64) {
+        let Some(workspace) = self.workspaces.iter().find(|w| w.id == id) else {
+            return;
+        };
+        self.renaming = Some(id);
+        self.rename_entry
+            .set_text(workspace.name.as_deref().unwrap_or_default());
+
+        // Messages cross threads, so the button is found by its position
+        let button = self
+            .workspace_buttons
+            .iter()
+            .position(|button| button.workspace.id == id)
+            .and_then(|index| {
+                std::iter::successors(self.workspace_buttons.widget().first_child(), |child| {
+                    child.next_sibling()
+                })
+                .nth(index)
+            });
+        let bounds = self
+            .rename_popover
+            .parent()
+            .zip(button)
+            .and_then(|(parent, button)| button.compute_bounds(&parent));
+        if let Some(bounds) = bounds {
+            self.rename_popover
+                .set_pointing_to(Some(&gtk::gdk::Rectangle::new(
+                    bounds.x() as i32,
+                    bounds.y() as i32,
+                    bounds.width() as i32,
+                    bounds.height() as i32,
+                )));
+        }
 
-        if matches!(reply, Ok(Response::Handled)) {
-            let mut read_event = socket.read_events();
+        set_keyboard_focusable(&self.rename_popover, true);
+        self.rename_popover.popup();
+        self.rename_entry.grab_focus();
+    }
 
-            while let Ok(event) = read_event() {
-                match event {
-                    Event::WorkspacesChanged { workspaces } => {
-                        let mut workspace_list: Vec<NiriWorkspace> = workspaces;
+    fn listen_compositor_events(sender: ComponentSender<Self>) {
+        let mut events = compositor::subscribe();
+        thread::spawn(move || {
+            while let Some(event) = events.blocking_next() {
+                let msg = match event {
+                    Event::WorkspacesChanged(workspaces) => {
+                        let mut workspace_list: Vec<Workspace> = workspaces;
                         workspace_list.sort_by_key(|w| w.idx);
-                        sender.input(WorkspaceMsg::UpdateWorkspaces(workspace_list));
+                        WorkspaceMsg::UpdateWorkspaces(workspace_list)
                     }
                     Event::WorkspaceActivated { id: _, focused: _ } => {
                         // Request fresh workspace state on activation
-                        if let Ok(workspaces) = Self::get_workspaces() {
-                            sender.input(WorkspaceMsg::UpdateWorkspaces(workspaces));
-                        }
+                        let Ok(workspaces) = Self::get_workspaces() else {
+                            continue;
+                        };
+                        WorkspaceMsg::UpdateWorkspaces(workspaces)
+                    }
+                    Event::WindowsChanged(windows) => WorkspaceMsg::UpdateWindows(windows),
+                    Event::WindowOpenedOrChanged(window) => WorkspaceMsg::WindowChanged(window),
+                    Event::WindowClosed(id) => WorkspaceMsg::WindowClosed(id),
+                    Event::WorkspaceUrgencyChanged { id, urgent } => {
+                        WorkspaceMsg::UrgencyChanged(id, urgent)
                     }
-                    _ => {}
+                    _ => continue,
+                };
+                if !poll::deliver(&sender, msg) {
+                    break;
                 }
             }
-        }
-
-        Ok(())
+        });
     }
 
-    fn get_workspaces() -> Result<Vec<NiriWorkspace>, String> {
-        let mut socket = Socket::connect().map_err(|e| e.to_string())?;
-        let reply = socket
-            .send(Request::Workspaces)
-            .map_err(|e| e.to_string())?;
+    fn get_workspaces() -> Result<Vec<Workspace>, String> {
+        let mut workspace_list = compositor::workspaces()?;
+        workspace_list.sort_by_key(|w| w.idx);
+        Ok(workspace_list)
+    }
 
-        match reply {
-            Ok(Response::Workspaces(workspaces)) => {
-                let mut workspace_list: Vec<NiriWorkspace> = workspaces.into_iter().collect();
-                workspace_list.sort_by_key(|w| w.idx);
-                Ok(workspace_list)
-            }
-            Ok(_) => Err("Unexpected response".to_string()),
-            Err(e) => Err(e),
-        }
+    fn get_windows() -> Result<Vec<Window>, String> {
+        compositor::windows()
     }
 
     fn switch_to_workspace(id: u64) -> Result<(), String> {
-        let mut socket = Socket::connect().map_err(|e| e.to_string())?;
-        let request = Request::Action(niri_ipc::Action::FocusWorkspace {
-            reference: niri_ipc::WorkspaceReferenceArg::Id(id),
-        });
+        compositor::focus_workspace(id)
+    }
+}
 
-        let _ = socket.send(request).map_err(|e| e.to_string())?;
-        Ok(())
+impl Snapshot for WorkspaceWidget {
+    fn snapshot(&self) -> serde_json::Value {
+        let workspaces: Vec<serde_json::Value> = self
+            .workspace_buttons
+            .iter()
+            .map(|button| {
+                json!({
+                    "workspace": button.workspace,
+                    "window_count": button.window_count,
+                    "app_ids": button.app_ids,
+                })
+            })
+            .collect();
+
+        json!({ "workspaces": workspaces })
     }
 }
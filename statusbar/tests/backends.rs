@@ -0,0 +1,294 @@
+// Widgets and clients against mock system services
+//
+// A private D-Bus bus stands in for both the system and the session bus,
+// with mock NetworkManager and MPRIS services on it, and a fake socket stands
+// in for niri. The tests fail without `dbus-daemon`; the ones running the
+// whole bar are skipped when `broadwayd` is not installed.
+
+mod support;
+
+use futures_util::StreamExt;
+use nm_dbus::{EapConfig, EapMethod, NetworkManagerClient, Phase2Auth, Security};
+use std::pin::pin;
+use support::mpris::{self, Track};
+use support::network_manager::{self, AccessPoint, Network};
+use support::niri::{self, FakeNiri};
+use support::{PrivateBus, Scratch, block_on};
+
+/// Connected to the strongest of two access points of `Home`
+fn home_network() -> Network {
+    Network {
+        access_points: vec![
+            AccessPoint::new("Cafe", 40, Security::Open),
+            AccessPoint::new("Home", 82, Security::Wpa2Psk),
+            AccessPoint::new("Home", 55, Security::Wpa2Psk),
+            AccessPoint::new("Neighbour", 67, Security::Wpa3Sae),
+        ],
+        active: Some(1),
+        ip_address: Some("192.168.1.23".to_string()),
+        ..Network::default()
+    }
+}
+
+fn private_bus(scratch: &Scratch) -> PrivateBus {
+    PrivateBus::start(&scratch.dir).expect("dbus-daemon is needed for the backend tests")
+}
+
+/// A client on the private bus; the connection lives on the runtime it was made
+/// on, so use it within the same `block_on`
+async fn client(bus: &PrivateBus) -> NetworkManagerClient {
+    let connection = bus.connect().await.expect("connect to the private bus");
+    NetworkManagerClient::with_connection(connection)
+}
+
+#[test]
+fn scan_keeps_the_strongest_of_each_network_strongest_first() {
+    let scratch = Scratch::new("nm-scan");
+    let bus = private_bus(&scratch);
+    let _network_manager = network_manager::start(&bus, home_network());
+
+    let access_points = block_on(async { client(&bus).await.scan_networks().await }).expect("scan");
+    let found: Vec<(&str, u8, Security)> = access_points
+        .iter()
+        .map(|ap| (ap.ssid.as_str(), ap.strength, ap.security))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("Home", 82, Security::Wpa2Psk),
+            ("Neighbour", 67, Security::Wpa3Sae),
+            ("Cafe", 40, Security::Open)
+        ]
+    );
+}
+
+#[test]
+fn scan_tells_the_security_of_each_network_from_its_flags() {
+    let scratch = Scratch::new("nm-security");
+    let bus = private_bus(&scratch);
+    let securities = [
+        Security::Open,
+        Security::Wep,
+        Security::WpaPsk,
+        Security::Wpa2Psk,
+        Security::Wpa3Sae,
+        Security::Enterprise,
+    ];
+    let network = Network {
+        access_points: securities
+            .iter()
+            .enumerate()
+            .map(|(i, &security)| AccessPoint::new(security.label(), 90 - i as u8, security))
+            .collect(),
+        ..Network::default()
+    };
+    let _network_manager = network_manager::start(&bus, network);
+
+    let access_points = block_on(async { client(&bus).await.scan_networks().await }).expect("scan");
+    let found: Vec<Security> = access_points.iter().map(|ap| ap.security).collect();
+    assert_eq!(found, securities);
+}
+
+#[test]
+fn enterprise_connections_carry_the_eap_settings() {
+    let scratch = Scratch::new("nm-enterprise");
+    let bus = private_bus(&scratch);
+    let network_manager = network_manager::start(&bus, home_network());
+
+    let eap = EapConfig {
+        method: EapMethod::Ttls,
+        phase2: Phase2Auth::Pap,
+        identity: "alice".to_string(),
+        anonymous_identity: "anonymous@example.org".to_string(),
+        password: "hunter2".to_string(),
+        ca_cert: None,
+    };
+    let added = block_on(async {
+        client(&bus)
+            .await
+            .connect_enterprise("Campus", &eap)
+            .await?;
+        network_manager::added_connections(&network_manager).await
+    })
+    .expect("connect");
+    assert_eq!(added.len(), 1);
+
+    let text = |group: &str, key: &str| -> String {
+        let value = &added[0][group][key];
+        value.downcast_ref::<&str>().expect("string").to_string()
+    };
+    assert_eq!(text("connection", "id"), "Campus");
+    assert_eq!(text("802-11-wireless-security", "key-mgmt"), "wpa-eap");
+    assert_eq!(text("802-1x", "phase2-auth"), "pap");
+    assert_eq!(text("802-1x", "identity"), "alice");
+    assert_eq!(
+        text("802-1x", "anonymous-identity"),
+        "anonymous@example.org"
+    );
+    assert_eq!(text("802-1x", "password"), "hunter2");
+
+    let methods = Vec::<String>::try_from(added[0]["802-1x"]["eap"].try_clone().unwrap())
+        .expect("string array");
+    assert_eq!(methods, ["ttls"]);
+    let system_ca_certs = added[0]["802-1x"]["system-ca-certs"].downcast_ref::<bool>();
+    assert_eq!(system_ca_certs, Ok(true));
+}
+
+#[test]
+fn enterprise_connections_reuse_the_saved_profile() {
+    let scratch = Scratch::new("nm-enterprise-saved");
+    let bus = private_bus(&scratch);
+    let network_manager = network_manager::start(
+        &bus,
+        Network {
+            saved: vec!["Home".to_string(), "Campus".to_string()],
+            ..home_network()
+        },
+    );
+
+    let eap = EapConfig {
+        method: EapMethod::Peap,
+        phase2: Phase2Auth::Mschapv2,
+        identity: "bob".to_string(),
+        anonymous_identity: String::new(),
+        password: "correct horse".to_string(),
+        ca_cert: None,
+    };
+    let (added, activated, saved) = block_on(async {
+        client(&bus)
+            .await
+            .connect_enterprise("Campus", &eap)
+            .await?;
+        Ok::<_, zbus::Error>((
+            network_manager::added_connections(&network_manager).await?,
+            network_manager::activated_connections(&network_manager).await?,
+            network_manager::saved_settings(&network_manager, 1).await?,
+        ))
+    })
+    .expect("connect");
+    assert!(added.is_empty());
+    assert_eq!(activated.len(), 1);
+    assert!(activated[0].as_str().ends_with("/Saved2"));
+
+    let text = |group: &str, key: &str| -> String {
+        let value = &saved[group][key];
+        value.downcast_ref::<&str>().expect("string").to_string()
+    };
+    assert_eq!(text("connection", "uuid"), "saved-2");
+    assert_eq!(text("ipv4", "method"), "manual");
+    assert_eq!(text("802-11-wireless-security", "key-mgmt"), "wpa-eap");
+    assert_eq!(text("802-1x", "identity"), "bob");
+    assert_eq!(text("802-1x", "password"), "correct horse");
+}
+
+#[test]
+fn active_connection_reports_the_connected_access_point() {
+    let scratch = Scratch::new("nm-active");
+    let bus = private_bus(&scratch);
+    let _network_manager = network_manager::start(&bus, home_network());
+
+    let connection = block_on(async { client(&bus).await.get_active_connection().await })
+        .expect("query")
+        .expect("connected");
+    assert_eq!(connection.ssid, "Home");
+    assert_eq!(connection.strength, 82);
+    assert_eq!(connection.interface, "wlan0");
+    assert_eq!(connection.ip_address, "192.168.1.23");
+}
+
+#[test]
+fn strength_changes_of_the_connected_access_point_are_streamed() {
+    let scratch = Scratch::new("nm-strength");
+    let bus = private_bus(&scratch);
+    let network_manager = network_manager::start(&bus, home_network());
+
+    let strengths = block_on(async {
+        let client = client(&bus).await;
+        let connection = client.get_active_connection().await?.expect("connected");
+        let changes = client.watch_strength(&connection.access_point).await?;
+        let mut changes = pin!(changes);
+
+        let mut strengths = vec![changes.next().await.expect("current strength")];
+        network_manager::set_strength(&network_manager, 1, 47).await?;
+        strengths.push(changes.next().await.expect("changed strength"));
+        zbus::Result::Ok(strengths)
+    })
+    .expect("watch");
+    assert_eq!(strengths, [82, 47]);
+}
+
+#[test]
+fn no_active_connection_while_disconnected() {
+    let scratch = Scratch::new("nm-disconnected");
+    let bus = private_bus(&scratch);
+    let network = Network {
+        active: None,
+        ip_address: None,
+        ..home_network()
+    };
+    let _network_manager = network_manager::start(&bus, network);
+
+    let connection =
+        block_on(async { client(&bus).await.get_active_connection().await }).expect("query");
+    assert!(connection.is_none());
+}
+
+#[test]
+fn wifi_widget_shows_the_mock_connection() {
+    let scratch = Scratch::new("wifi");
+    let bus = private_bus(&scratch);
+    let _network_manager = network_manager::start(&bus, home_network());
+
+    let Some(state) = scratch.run_headless(44, "", &bus.env()) else {
+        return;
+    };
+    assert_eq!(state["wifi"]["is_connected"], true);
+    assert_eq!(state["wifi"]["ssid"], "Home");
+    assert_eq!(state["wifi"]["signal_strength"], 82);
+    assert_eq!(state["wifi"]["ip_address"], "192.168.1.23");
+}
+
+#[test]
+fn media_player_shows_the_playing_track() {
+    let scratch = Scratch::new("mpris");
+    let bus = private_bus(&scratch);
+    let track = Track::new("Windowlicker", "Aphex Twin", "Windowlicker EP");
+    let _player = mpris::start(&bus, "Mock", track);
+
+    let Some(state) = scratch.run_headless(45, "", &bus.env()) else {
+        return;
+    };
+    assert_eq!(state["media_player"]["title"], "Windowlicker");
+    assert_eq!(state["media_player"]["artist"], "Aphex Twin");
+    assert_eq!(state["media_player"]["album"], "Windowlicker EP");
+    assert_eq!(state["media_player"]["is_playing"], true);
+}
+
+#[test]
+fn workspaces_and_title_come_from_the_niri_socket() {
+    let scratch = Scratch::new("niri");
+    let fake = FakeNiri::start(
+        &scratch.dir,
+        niri::State {
+            workspaces: vec![
+                niri::workspace(1, 1, true, Some(10)),
+                niri::workspace(2, 2, false, None),
+            ],
+            windows: vec![niri::window(10, 1, "org.gnome.Nautilus", "Files", true)],
+            keyboard_layouts: vec!["English (US)".to_string()],
+        },
+    );
+
+    let Some(state) = scratch.run_headless(46, "", &fake.env()) else {
+        return;
+    };
+    let workspaces = state["workspace"]["workspaces"]
+        .as_array()
+        .expect("workspace list");
+    assert_eq!(workspaces.len(), 2);
+    assert_eq!(workspaces[0]["workspace"]["is_focused"], true);
+    assert_eq!(workspaces[0]["window_count"], 1);
+
+    assert_eq!(state["window_title"]["title"], "Files");
+    assert_eq!(state["window_title"]["app_id"], "org.gnome.Nautilus");
+}
@@ -0,0 +1,56 @@
+// End-to-end tests running the whole bar under `--headless-test`
+//
+// The bar renders to a broadway display with a mock compositor, prints the
+// state of every widget once they settle and exits. The tests are skipped
+// when `broadwayd` is not installed.
+
+mod support;
+
+use support::run_headless;
+
+#[test]
+fn renders_mock_workspaces_and_windows() {
+    let Some(state) = run_headless("workspaces", 41, "[workspace]\nbadges = \"count\"\n") else {
+        return;
+    };
+
+    let workspaces = state["workspace"]["workspaces"]
+        .as_array()
+        .expect("workspace list");
+    assert_eq!(workspaces.len(), 3);
+    assert_eq!(workspaces[0]["workspace"]["is_focused"], true);
+    assert_eq!(workspaces[0]["window_count"], 2);
+    assert_eq!(workspaces[1]["window_count"], 1);
+
+    assert_eq!(state["window_title"]["title"], "~/statusbar");
+    assert_eq!(state["window_title"]["app_id"], "Alacritty");
+}
+
+#[test]
+fn taskbar_lists_windows_on_the_focused_workspace() {
+    let Some(state) = run_headless("taskbar", 42, "[taskbar]\nenabled = true\n") else {
+        return;
+    };
+
+    let windows = state["taskbar"]["windows"].as_array().expect("window list");
+    let app_ids: Vec<&str> = windows
+        .iter()
+        .filter_map(|window| window["app_id"].as_str())
+        .collect();
+    assert_eq!(app_ids, ["Alacritty", "firefox"]);
+}
+
+#[test]
+fn title_rules_rewrite_the_focused_title() {
+    let config = r#"
+[[window_title.rules]]
+app_id = "Alacritty"
+pattern = "^~/(.*)$"
+replacement = "term: $1"
+"#;
+    let Some(state) = run_headless("title-rules", 43, config) else {
+        return;
+    };
+
+    assert_eq!(state["window_title"]["display_title"], "term: statusbar");
+}
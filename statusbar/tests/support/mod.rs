@@ -0,0 +1,249 @@
+// Shared pieces of the integration tests
+//
+// A scratch environment to run the bar in, a broadway display to render to
+// and a private D-Bus bus with mock services on it. Tests skip themselves when
+// `broadwayd` or `dbus-daemon` is not installed.
+#![allow(dead_code)]
+
+pub mod mpris;
+pub mod network_manager;
+pub mod niri;
+
+use serde_json::Value;
+use std::fs;
+use std::future::Future;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// A broadway display server, killed when dropped
+struct Broadway {
+    process: Child,
+    display: String,
+}
+
+impl Broadway {
+    fn start(runtime_dir: &Path, display: u32) -> Option<Self> {
+        let display = format!(":{}", display);
+        let process = Command::new("broadwayd")
+            .arg(&display)
+            .env("XDG_RUNTIME_DIR", runtime_dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        // Give it a moment to create its socket
+        thread::sleep(Duration::from_millis(500));
+        Some(Self { process, display })
+    }
+}
+
+impl Drop for Broadway {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Scratch XDG directories for one test, removed when dropped
+pub struct Scratch {
+    pub dir: PathBuf,
+    name: String,
+}
+
+impl Scratch {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("statusbar-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for sub in ["config/statusbar", "cache", "data", "runtime"] {
+            fs::create_dir_all(dir.join(sub)).expect("create scratch directory");
+        }
+        Self {
+            dir,
+            name: name.to_string(),
+        }
+    }
+
+    /// Run the bar with `config` and extra environment variables, returning
+    /// the state it printed
+    pub fn run_headless(&self, display: u32, config: &str, env: &[(&str, &str)]) -> Option<Value> {
+        fs::write(self.dir.join("config/statusbar/config.toml"), config).expect("write config");
+
+        let Some(broadway) = Broadway::start(&self.dir.join("runtime"), display) else {
+            eprintln!("broadwayd not found, skipping {}", self.name);
+            return None;
+        };
+
+        let output = Command::new(env!("CARGO_BIN_EXE_statusbar"))
+            .arg("--headless-test")
+            .env("GDK_BACKEND", "broadway")
+            .env("BROADWAY_DISPLAY", &broadway.display)
+            .env("XDG_CONFIG_HOME", self.dir.join("config"))
+            .env("XDG_CACHE_HOME", self.dir.join("cache"))
+            .env("XDG_DATA_HOME", self.dir.join("data"))
+            .env("XDG_RUNTIME_DIR", self.dir.join("runtime"))
+            .envs(env.iter().copied())
+            .output()
+            .expect("run statusbar");
+        drop(broadway);
+
+        assert!(
+            output.status.success(),
+            "statusbar failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Some(serde_json::from_slice(&output.stdout).expect("state is JSON"))
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Run the bar with `config` in a fresh scratch environment
+pub fn run_headless(name: &str, display: u32, config: &str) -> Option<Value> {
+    Scratch::new(name).run_headless(display, config, &[])
+}
+
+/// Lets everyone on the bus own any name and talk to anyone
+const BUS_CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:dir={dir}</listen>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#;
+
+/// A dbus-daemon of our own, standing in for both the system and the
+/// session bus; killed when dropped
+pub struct PrivateBus {
+    process: Child,
+    pub address: String,
+}
+
+impl PrivateBus {
+    pub fn start(dir: &Path) -> Option<Self> {
+        let config = dir.join("bus.conf");
+        let contents = BUS_CONFIG.replace("{dir}", &dir.display().to_string());
+        fs::write(&config, contents).expect("write bus config");
+
+        let mut process = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        // The address is printed once the bus accepts connections
+        let mut address = String::new();
+        let stdout = process.stdout.take().expect("piped stdout");
+        BufReader::new(stdout)
+            .read_line(&mut address)
+            .expect("read bus address");
+        Some(Self {
+            process,
+            address: address.trim().to_string(),
+        })
+    }
+
+    /// Environment pointing the bar's system and session connections here
+    pub fn env(&self) -> [(&str, &str); 2] {
+        [
+            ("DBUS_SYSTEM_BUS_ADDRESS", self.address.as_str()),
+            ("DBUS_SESSION_BUS_ADDRESS", self.address.as_str()),
+        ]
+    }
+
+    /// A connection of the test's own, for talking to the mocks directly
+    pub async fn connect(&self) -> zbus::Result<zbus::Connection> {
+        zbus::connection::Builder::address(self.address.as_str())?
+            .build()
+            .await
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// A mock service running on a thread of its own until dropped
+pub struct Mock {
+    /// The service's own connection, for changing what it serves
+    pub connection: zbus::Connection,
+    stop: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Mock {
+    /// Start the service `serve` sets up, keeping its connection open
+    pub fn start<F, Fut>(serve: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = zbus::Result<zbus::Connection>>,
+    {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (stop, stopped) = oneshot::channel();
+        let thread = thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("mock runtime");
+            runtime.block_on(async move {
+                match serve().await {
+                    Ok(connection) => {
+                        let _ = ready_tx.send(Ok(connection));
+                        let _ = stopped.await;
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                    }
+                }
+            });
+        });
+
+        let connection = match ready_rx.recv().expect("mock thread") {
+            Ok(connection) => connection,
+            Err(e) => panic!("Failed to start mock: {}", e),
+        };
+        Self {
+            connection,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Run `future` to completion on a throwaway runtime
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("test runtime")
+        .block_on(future)
+}
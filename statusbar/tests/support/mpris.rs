@@ -0,0 +1,233 @@
+// Mock MPRIS media player
+//
+// Plays a single track and ignores every command; enough for the media player
+// widget to find it and show what it plays.
+use std::collections::HashMap;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::{connection, interface};
+
+use super::{Mock, PrivateBus};
+
+const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub length_us: i64,
+}
+
+impl Track {
+    pub fn new(title: &str, artist: &str, album: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            length_us: 180_000_000,
+        }
+    }
+}
+
+fn owned(value: Value<'_>) -> OwnedValue {
+    OwnedValue::try_from(value).expect("plain value")
+}
+
+struct Root {
+    identity: String,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        self.identity.clone()
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        self.identity.to_lowercase()
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct Player {
+    track: Track,
+    playing: bool,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {}
+
+    fn previous(&self) {}
+
+    fn pause(&self) {}
+
+    fn play_pause(&self) {}
+
+    fn stop(&self) {}
+
+    fn play(&self) {}
+
+    fn seek(&self, _offset: i64) {}
+
+    fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+    fn open_uri(&self, _uri: String) {}
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        match self.playing {
+            true => "Playing".to_string(),
+            false => "Paused".to_string(),
+        }
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> String {
+        "None".to_string()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let track_id = ObjectPath::try_from("/org/mpris/MediaPlayer2/Track/1").expect("valid path");
+        HashMap::from([
+            ("mpris:trackid".to_string(), owned(Value::from(track_id))),
+            (
+                "mpris:length".to_string(),
+                owned(Value::from(self.track.length_us)),
+            ),
+            (
+                "xesam:title".to_string(),
+                owned(Value::from(self.track.title.as_str())),
+            ),
+            (
+                "xesam:artist".to_string(),
+                owned(Value::from(vec![self.track.artist.as_str()])),
+            ),
+            (
+                "xesam:album".to_string(),
+                owned(Value::from(self.track.album.as_str())),
+            ),
+        ])
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn position(&self) -> i64 {
+        0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// Serve a player called `identity` playing `track` on the bus at `address`
+pub async fn serve(
+    address: &str,
+    identity: &str,
+    track: Track,
+    playing: bool,
+) -> zbus::Result<zbus::Connection> {
+    connection::Builder::address(address)?
+        .name(format!(
+            "org.mpris.MediaPlayer2.{}",
+            identity.to_lowercase()
+        ))?
+        .serve_at(
+            PLAYER_PATH,
+            Root {
+                identity: identity.to_string(),
+            },
+        )?
+        .serve_at(PLAYER_PATH, Player { track, playing })?
+        .build()
+        .await
+}
+
+/// Run a playing mock player on `bus` until dropped
+pub fn start(bus: &PrivateBus, identity: &str, track: Track) -> Mock {
+    let address = bus.address.clone();
+    let identity = identity.to_string();
+    Mock::start(move || async move { serve(&address, &identity, track, true).await })
+}
@@ -0,0 +1,410 @@
+// Mock NetworkManager with a single Wi-Fi device
+//
+// Serves the parts of the API nm-dbus reads: the device list, the wireless
+// device and its access points, the IPv4 address of the connection and the
+// saved profiles. Connections added or activated through it are recorded,
+// not brought up.
+use nm_dbus::Security;
+use std::collections::HashMap;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{connection, interface};
+
+use super::{Mock, PrivateBus};
+
+const MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
+const DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/1";
+const IP4_CONFIG_PATH: &str = "/org/freedesktop/NetworkManager/IP4Config/1";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+
+/// `NM_802_11_AP_FLAGS_PRIVACY`, set on every network that is not open
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+/// `NM_802_11_AP_SEC_KEY_MGMT_PSK` with TKIP ciphers, as a WPA1 network has
+const WPA_FLAGS_PSK: u32 = 0x144;
+/// `NM_802_11_AP_SEC_KEY_MGMT_PSK` with CCMP ciphers, as a WPA2 network has
+const RSN_FLAGS_WPA2: u32 = 0x188;
+/// `NM_802_11_AP_SEC_KEY_MGMT_SAE` with CCMP ciphers
+const RSN_FLAGS_SAE: u32 = 0x488;
+/// `NM_802_11_AP_SEC_KEY_MGMT_802_1X` with CCMP ciphers
+const RSN_FLAGS_EAP: u32 = 0x288;
+
+/// Settings of a connection, by group and key
+pub type Settings = HashMap<String, HashMap<String, OwnedValue>>;
+
+#[derive(Debug, Clone)]
+pub struct AccessPoint {
+    pub ssid: String,
+    /// Signal quality in percent
+    pub strength: u8,
+    pub security: Security,
+}
+
+impl AccessPoint {
+    pub fn new(ssid: &str, strength: u8, security: Security) -> Self {
+        Self {
+            ssid: ssid.to_string(),
+            strength,
+            security,
+        }
+    }
+
+    /// The `Flags`, `WpaFlags` and `RsnFlags` an access point with this
+    /// security advertises
+    fn flags(&self) -> (u32, u32, u32) {
+        match self.security {
+            Security::Open => (0, 0, 0),
+            Security::Wep => (AP_FLAGS_PRIVACY, 0, 0),
+            Security::WpaPsk => (AP_FLAGS_PRIVACY, WPA_FLAGS_PSK, 0),
+            Security::Wpa2Psk => (AP_FLAGS_PRIVACY, 0, RSN_FLAGS_WPA2),
+            Security::Wpa3Sae => (AP_FLAGS_PRIVACY, 0, RSN_FLAGS_SAE),
+            Security::Enterprise => (AP_FLAGS_PRIVACY, 0, RSN_FLAGS_EAP),
+        }
+    }
+}
+
+/// What the mock reports
+#[derive(Debug, Clone)]
+pub struct Network {
+    pub interface: String,
+    pub access_points: Vec<AccessPoint>,
+    /// Index into `access_points` of the one connected to
+    pub active: Option<usize>,
+    pub ip_address: Option<String>,
+    /// SSIDs with a saved profile
+    pub saved: Vec<String>,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            interface: "wlan0".to_string(),
+            access_points: Vec::new(),
+            active: None,
+            ip_address: None,
+            saved: Vec::new(),
+        }
+    }
+}
+
+fn object_path(path: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).expect("valid object path")
+}
+
+fn access_point_path(index: usize) -> OwnedObjectPath {
+    object_path(&format!(
+        "/org/freedesktop/NetworkManager/AccessPoint/{}",
+        index + 1
+    ))
+}
+
+fn saved_connection_path(index: usize) -> OwnedObjectPath {
+    object_path(&format!("{}/Saved{}", SETTINGS_PATH, index + 1))
+}
+
+fn clone_settings(settings: &Settings) -> zbus::Result<Settings> {
+    settings
+        .iter()
+        .map(|(group, values)| {
+            let values = values
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), value.try_clone()?)))
+                .collect::<zbus::Result<_>>()?;
+            Ok((group.clone(), values))
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Manager {
+    added: Vec<Settings>,
+    activated: Vec<OwnedObjectPath>,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager")]
+impl Manager {
+    fn get_devices(&self) -> Vec<OwnedObjectPath> {
+        vec![object_path(DEVICE_PATH)]
+    }
+
+    #[zbus(property)]
+    fn active_connections(&self) -> Vec<OwnedObjectPath> {
+        Vec::new()
+    }
+
+    fn add_and_activate_connection(
+        &mut self,
+        connection: Settings,
+        _device: OwnedObjectPath,
+        _specific_object: OwnedObjectPath,
+    ) -> (OwnedObjectPath, OwnedObjectPath) {
+        self.added.push(connection);
+        let index = self.added.len();
+        (
+            object_path(&format!("/org/freedesktop/NetworkManager/Settings/{index}")),
+            object_path(&format!(
+                "/org/freedesktop/NetworkManager/ActiveConnection/{index}"
+            )),
+        )
+    }
+
+    fn activate_connection(
+        &mut self,
+        connection: OwnedObjectPath,
+        _device: OwnedObjectPath,
+        _specific_object: OwnedObjectPath,
+    ) -> OwnedObjectPath {
+        self.activated.push(connection);
+        object_path("/org/freedesktop/NetworkManager/ActiveConnection/saved")
+    }
+}
+
+struct SavedConnections {
+    count: usize,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Settings")]
+impl SavedConnections {
+    fn list_connections(&self) -> Vec<OwnedObjectPath> {
+        (0..self.count).map(saved_connection_path).collect()
+    }
+}
+
+/// A saved profile, with manual addressing to tell whether an update kept it
+struct SavedConnection {
+    settings: Settings,
+}
+
+impl SavedConnection {
+    fn new(ssid: &str, index: usize) -> Self {
+        let value = |value: Value| OwnedValue::try_from(value).expect("plain value");
+        let group = |values: Vec<(&str, OwnedValue)>| {
+            values
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect()
+        };
+        let settings = HashMap::from([
+            (
+                "connection".to_string(),
+                group(vec![
+                    ("id", value(Value::from(ssid))),
+                    ("uuid", value(Value::from(format!("saved-{}", index + 1)))),
+                    ("type", value(Value::from("802-11-wireless"))),
+                ]),
+            ),
+            (
+                "802-11-wireless".to_string(),
+                group(vec![("ssid", value(Value::from(ssid.as_bytes())))]),
+            ),
+            (
+                "ipv4".to_string(),
+                group(vec![("method", value(Value::from("manual")))]),
+            ),
+        ]);
+        Self { settings }
+    }
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Settings.Connection")]
+impl SavedConnection {
+    fn get_settings(&self) -> zbus::fdo::Result<Settings> {
+        clone_settings(&self.settings).map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    fn update(&mut self, properties: Settings) {
+        self.settings = properties;
+    }
+}
+
+struct Device {
+    interface: String,
+    has_address: bool,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device")]
+impl Device {
+    #[zbus(property)]
+    fn interface(&self) -> String {
+        self.interface.clone()
+    }
+
+    /// `NM_DEVICE_TYPE_WIFI`
+    #[zbus(property)]
+    fn device_type(&self) -> u32 {
+        2
+    }
+
+    #[zbus(property)]
+    fn ip4_config(&self) -> OwnedObjectPath {
+        match self.has_address {
+            true => object_path(IP4_CONFIG_PATH),
+            false => object_path("/"),
+        }
+    }
+
+    fn disconnect(&self) {}
+}
+
+struct Wireless {
+    access_points: usize,
+    active: Option<usize>,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+impl Wireless {
+    fn get_access_points(&self) -> Vec<OwnedObjectPath> {
+        (0..self.access_points).map(access_point_path).collect()
+    }
+
+    fn request_scan(&self, _options: HashMap<String, OwnedValue>) {}
+
+    #[zbus(property)]
+    fn active_access_point(&self) -> OwnedObjectPath {
+        match self.active {
+            Some(index) => access_point_path(index),
+            None => object_path("/"),
+        }
+    }
+}
+
+struct AccessPointObject(AccessPoint);
+
+#[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+impl AccessPointObject {
+    #[zbus(property)]
+    fn ssid(&self) -> Vec<u8> {
+        self.0.ssid.as_bytes().to_vec()
+    }
+
+    #[zbus(property)]
+    fn strength(&self) -> u8 {
+        self.0.strength
+    }
+
+    #[zbus(property)]
+    fn flags(&self) -> u32 {
+        self.0.flags().0
+    }
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> u32 {
+        self.0.flags().1
+    }
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> u32 {
+        self.0.flags().2
+    }
+}
+
+struct Ip4Config {
+    address: String,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.IP4Config")]
+impl Ip4Config {
+    #[zbus(property)]
+    fn address_data(&self) -> Vec<HashMap<String, OwnedValue>> {
+        let mut address = HashMap::new();
+        address.insert(
+            "address".to_string(),
+            OwnedValue::try_from(Value::from(self.address.as_str())).expect("plain value"),
+        );
+        address.insert("prefix".to_string(), OwnedValue::from(24u32));
+        vec![address]
+    }
+}
+
+/// Serve `network` on the bus at `address`
+pub async fn serve(address: &str, network: Network) -> zbus::Result<zbus::Connection> {
+    let mut builder = connection::Builder::address(address)?
+        .name("org.freedesktop.NetworkManager")?
+        .serve_at(MANAGER_PATH, Manager::default())?
+        .serve_at(
+            DEVICE_PATH,
+            Device {
+                interface: network.interface,
+                has_address: network.ip_address.is_some(),
+            },
+        )?
+        .serve_at(
+            DEVICE_PATH,
+            Wireless {
+                access_points: network.access_points.len(),
+                active: network.active,
+            },
+        )?;
+    if let Some(address) = network.ip_address {
+        builder = builder.serve_at(IP4_CONFIG_PATH, Ip4Config { address })?;
+    }
+    builder = builder.serve_at(
+        SETTINGS_PATH,
+        SavedConnections {
+            count: network.saved.len(),
+        },
+    )?;
+    for (index, ssid) in network.saved.iter().enumerate() {
+        builder = builder.serve_at(
+            saved_connection_path(index),
+            SavedConnection::new(ssid, index),
+        )?;
+    }
+    for (index, access_point) in network.access_points.into_iter().enumerate() {
+        builder = builder.serve_at(access_point_path(index), AccessPointObject(access_point))?;
+    }
+    builder.build().await
+}
+
+/// Change the signal strength of the access point at `index`, telling
+/// everyone watching
+pub async fn set_strength(mock: &Mock, index: usize, strength: u8) -> zbus::Result<()> {
+    let access_point = mock
+        .connection
+        .object_server()
+        .interface::<_, AccessPointObject>(access_point_path(index))
+        .await?;
+    access_point.get_mut().await.0.strength = strength;
+    access_point
+        .get()
+        .await
+        .strength_changed(access_point.signal_emitter())
+        .await
+}
+
+/// Settings of the connections added so far, oldest first
+pub async fn added_connections(mock: &Mock) -> zbus::Result<Vec<Settings>> {
+    let manager = mock
+        .connection
+        .object_server()
+        .interface::<_, Manager>(MANAGER_PATH)
+        .await?;
+    let manager = manager.get().await;
+    manager.added.iter().map(clone_settings).collect()
+}
+
+/// Saved profiles activated so far, oldest first
+pub async fn activated_connections(mock: &Mock) -> zbus::Result<Vec<OwnedObjectPath>> {
+    let manager = mock
+        .connection
+        .object_server()
+        .interface::<_, Manager>(MANAGER_PATH)
+        .await?;
+    let activated = manager.get().await.activated.clone();
+    Ok(activated)
+}
+
+/// Settings of the profile saved for the `index`th SSID in `Network::saved`
+pub async fn saved_settings(mock: &Mock, index: usize) -> zbus::Result<Settings> {
+    let saved = mock
+        .connection
+        .object_server()
+        .interface::<_, SavedConnection>(saved_connection_path(index))
+        .await?;
+    let saved = saved.get().await;
+    clone_settings(&saved.settings)
+}
+
+/// Run the mock on `bus` until dropped
+pub fn start(bus: &PrivateBus, network: Network) -> Mock {
+    let address = bus.address.clone();
+    Mock::start(move || async move { serve(&address, network).await })
+}
@@ -0,0 +1,182 @@
+// Fake niri IPC socket
+//
+// Answers state requests and opens event streams with the workspaces and
+// windows it was given, which is what niri sends a new stream first. Actions
+// are accepted and ignored.
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// What the fake compositor reports, in niri's IPC format
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    pub workspaces: Vec<Value>,
+    pub windows: Vec<Value>,
+    pub keyboard_layouts: Vec<String>,
+}
+
+impl State {
+    fn reply(&self, request: &Value) -> Value {
+        let response = match request.as_str() {
+            Some("Workspaces") => json!({ "Workspaces": self.workspaces }),
+            Some("Windows") => json!({ "Windows": self.windows }),
+            Some("Outputs") => json!({ "Outputs": {} }),
+            Some("FocusedWindow") => json!({ "FocusedWindow": self.focused_window() }),
+            Some("KeyboardLayouts") => json!({ "KeyboardLayouts": self.layouts() }),
+            Some("EventStream") => json!("Handled"),
+            Some(other) => return json!({ "Err": format!("{} is not faked", other) }),
+            // Actions and the like
+            None => json!("Handled"),
+        };
+        json!({ "Ok": response })
+    }
+
+    fn focused_window(&self) -> Option<&Value> {
+        self.windows
+            .iter()
+            .find(|window| window["is_focused"] == true)
+    }
+
+    fn layouts(&self) -> Value {
+        json!({ "names": self.keyboard_layouts, "current_idx": 0 })
+    }
+
+    /// Events starting a stream, carrying the full state
+    fn initial_events(&self) -> Vec<Value> {
+        vec![
+            json!({ "WorkspacesChanged": { "workspaces": self.workspaces } }),
+            json!({ "WindowsChanged": { "windows": self.windows } }),
+            json!({ "KeyboardLayoutsChanged": { "keyboard_layouts": self.layouts() } }),
+        ]
+    }
+}
+
+/// A workspace as niri reports it
+pub fn workspace(id: u64, idx: u8, focused: bool, active_window_id: Option<u64>) -> Value {
+    json!({
+        "id": id,
+        "idx": idx,
+        "name": null,
+        "output": "eDP-1",
+        "is_urgent": false,
+        "is_active": focused,
+        "is_focused": focused,
+        "active_window_id": active_window_id,
+    })
+}
+
+/// A tiled window as niri reports it
+pub fn window(id: u64, workspace_id: u64, app_id: &str, title: &str, focused: bool) -> Value {
+    json!({
+        "id": id,
+        "title": title,
+        "app_id": app_id,
+        "pid": null,
+        "workspace_id": workspace_id,
+        "is_focused": focused,
+        "is_floating": false,
+        "is_urgent": false,
+        "layout": {
+            "pos_in_scrolling_layout": [1, 1],
+            "tile_size": [800.0, 600.0],
+            "window_size": [800, 600],
+            "tile_pos_in_workspace_view": null,
+            "window_offset_in_tile": [0.0, 0.0],
+        },
+    })
+}
+
+/// The listening socket, removed when dropped
+pub struct FakeNiri {
+    pub socket: PathBuf,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FakeNiri {
+    pub fn start(dir: &Path, state: State) -> Self {
+        let socket = dir.join("niri.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).expect("bind niri socket");
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread_stopped = stopped.clone();
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let state = state.clone();
+                let stopped = thread_stopped.clone();
+                thread::spawn(move || serve(stream, &state, &stopped));
+            }
+        });
+
+        Self {
+            socket,
+            stopped,
+            thread: Some(thread),
+        }
+    }
+
+    /// Environment pointing the bar at this socket
+    pub fn env(&self) -> [(&str, &str); 2] {
+        [
+            ("NIRI_SOCKET", self.socket.to_str().expect("UTF-8 path")),
+            // Headless mode fakes the compositor itself unless told not to
+            ("STATUSBAR_HEADLESS_COMPOSITOR", "live"),
+        ]
+    }
+}
+
+impl Drop for FakeNiri {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wake the accept loop up so it sees the flag
+        let _ = UnixStream::connect(&self.socket);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+/// Answer the requests of one connection, each on a line of its own
+fn serve(stream: UnixStream, state: &State, stopped: &AtomicBool) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    let mut send = |value: &Value| writeln!(writer, "{}", value).and_then(|()| writer.flush());
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        let request: Value = serde_json::from_str(&line).unwrap_or(Value::Null);
+        if send(&state.reply(&request)).is_err() {
+            return;
+        }
+
+        if request == "EventStream" {
+            for event in state.initial_events() {
+                if send(&event).is_err() {
+                    return;
+                }
+            }
+            // Nothing else ever happens; hold the stream open until dropped
+            while !stopped.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(50));
+            }
+            return;
+        }
+    }
+}